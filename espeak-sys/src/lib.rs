@@ -54,6 +54,7 @@ pub enum espeak_EVENT_TYPE {
 #[repr(C)]
 pub struct espeak_EVENT {
 	pub event_type: espeak_EVENT_TYPE,
+	pub unique_identifier: c_uint,
 	pub text_position: c_int,
 	pub length: c_int,
 	pub audio_position: c_int,
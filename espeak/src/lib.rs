@@ -16,6 +16,24 @@ pub struct ESpeak {
     dict: HashMap<String, String>,
     /// additional cleanup characters
     cleanup: Vec<char>,
+    /// sample rate of synthesized speech
+    sample_rate: u32,
+}
+// ----------------------------------------------------------------------------
+/// synthesized speech (16 bit mono) with timings of all phonemes
+pub struct SynthesizedSpeech {
+    pub sample_rate: u32,
+    pub samples: Vec<i16>,
+    pub phonemes: Vec<SynthesizedPhoneme>,
+}
+// ----------------------------------------------------------------------------
+/// phoneme event of the synthesizer (timings in ms)
+#[derive(Debug)]
+pub struct SynthesizedPhoneme {
+    pub phoneme: String,
+    pub word_start: bool,
+    pub start: u32,
+    pub end: u32,
 }
 
 // ----------------------------------------------------------------------------
@@ -23,14 +41,17 @@ pub struct ESpeak {
 // ----------------------------------------------------------------------------
 use std::collections::HashMap;
 
-use libc::{c_char, c_int};
+use libc::{c_char, c_int, c_short, c_void};
 use log::{debug, info};
 use std::ffi::{CStr, CString};
+use std::iter;
 use std::ptr;
+use std::slice;
+use std::sync::Mutex;
 
 use bindings::{
     espeakCHARS_UTF8, espeakINITIALIZE_PHONEME_EVENTS, espeakINITIALIZE_PHONEME_IPA,
-    espeak_AUDIO_OUTPUT, espeak_ERROR,
+    espeak_AUDIO_OUTPUT, espeak_ERROR, espeak_EVENT, espeak_EVENT_TYPE, espeak_POSITION_TYPE,
 };
 
 use lazy_static::lazy_static;
@@ -42,10 +63,11 @@ impl ESpeak {
             datadir: datadir.to_owned(),
             dict: HashMap::default(),
             cleanup: Vec::default(),
+            sample_rate: 0,
         }
     }
     // ------------------------------------------------------------------------
-    pub fn init(&self) -> Result<(), String> {
+    pub fn init(&mut self) -> Result<(), String> {
         let data_dir = CString::new(self.datadir.clone()).unwrap();
 
         let res = unsafe {
@@ -61,6 +83,8 @@ impl ESpeak {
         if res == espeak_ERROR::EE_INTERNAL_ERROR as i32 {
             Err("eSpeak: failed to initialize.".to_owned())
         } else {
+            // synchronous mode returns the sample rate of the synthesizer
+            self.sample_rate = res as u32;
             unsafe {
                 // TODO make input parameter
                 // value=0  No phoneme output (default)
@@ -157,11 +181,7 @@ impl ESpeak {
     }
     // ------------------------------------------------------------------------
     pub fn convert_to_phonemes(&self, text: &str, use_separator: bool) -> Result<String, String> {
-        let input = text
-            .to_lowercase()
-            .replace(self.cleanup.as_slice(), " ");
-
-        let s = CString::new(input).unwrap();
+        let s = CString::new(self.cleaned_input(text)).unwrap();
 
         #[cfg(windows)]
         let phonememode = {
@@ -283,6 +303,89 @@ impl ESpeak {
         })
     }
     // ------------------------------------------------------------------------
+    /// synthesizes speech for text (with the same cleanup as the phoneme
+    /// conversion). phonemes are timed by the phoneme events of the synthesizer
+    /// and end at the next phoneme or pause.
+    pub fn synthesize(&self, text: &str) -> Result<SynthesizedSpeech, String> {
+        let s = CString::new(self.cleaned_input(text)).unwrap();
+
+        // callback collects into the (global) buffer as espeak itself has only
+        // one global synthesizer
+        *SYNTH_BUFFER.lock().map_err(|_| "eSpeak: synth buffer poisoned.".to_owned())? =
+            SynthBuffer::default();
+
+        let result = unsafe {
+            bindings::espeak_SetSynthCallback(synth_callback);
+            bindings::espeak_Synth(
+                s.as_ptr() as *const c_void,
+                s.as_bytes_with_nul().len(),
+                0,
+                espeak_POSITION_TYPE::POS_CHARACTER,
+                0,
+                espeakCHARS_UTF8,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+        if !matches!(result, espeak_ERROR::EE_OK) {
+            return Err("eSpeak: failed to synthesize text.".to_owned());
+        }
+
+        let buffer = std::mem::take(
+            &mut *SYNTH_BUFFER.lock().map_err(|_| "eSpeak: synth buffer poisoned.".to_owned())?,
+        );
+
+        let duration = (buffer.samples.len() as u64 * 1000 / self.sample_rate.max(1) as u64) as u32;
+
+        // every phoneme ends with the next phoneme, pause or sentence end
+        let mut timeline = Vec::with_capacity(buffer.events.len());
+        let mut word_start = false;
+        for event in buffer.events {
+            match event {
+                SynthEvent::Word => word_start = true,
+                // pauses are named by underscores
+                SynthEvent::Phoneme(phoneme, start)
+                    if !phoneme.is_empty() && !phoneme.starts_with('_') =>
+                {
+                    timeline.push((Some((phoneme, word_start)), start));
+                    word_start = false;
+                }
+                SynthEvent::Phoneme(_, start) | SynthEvent::End(start) => {
+                    timeline.push((None, start))
+                }
+            }
+        }
+        let ends = timeline
+            .iter()
+            .skip(1)
+            .map(|(_, start)| *start)
+            .chain(iter::once(duration))
+            .collect::<Vec<_>>();
+
+        let phonemes = timeline
+            .into_iter()
+            .zip(ends)
+            .filter_map(|((phoneme, start), end)| {
+                phoneme.map(|(phoneme, word_start)| SynthesizedPhoneme {
+                    phoneme,
+                    word_start,
+                    start,
+                    end: end.max(start),
+                })
+            })
+            .collect();
+
+        Ok(SynthesizedSpeech {
+            sample_rate: self.sample_rate,
+            samples: buffer.samples,
+            phonemes,
+        })
+    }
+    // ------------------------------------------------------------------------
+    fn cleaned_input(&self, text: &str) -> String {
+        text.to_lowercase().replace(self.cleanup.as_slice(), " ")
+    }
+    // ------------------------------------------------------------------------
 }
 // ----------------------------------------------------------------------------
 impl Drop for ESpeak {
@@ -297,5 +400,54 @@ use regex::Regex;
 
 lazy_static! {
     static ref REGEXP_LANG: Regex = Regex::new("_?\\([^()]+\\)_?").unwrap();
+    static ref SYNTH_BUFFER: Mutex<SynthBuffer> = Mutex::new(SynthBuffer::default());
+}
+// ----------------------------------------------------------------------------
+enum SynthEvent {
+    Word,
+    /// phoneme (or pause) name and start in ms
+    Phoneme(String, u32),
+    /// end of sentence or message in ms
+    End(u32),
+}
+// ----------------------------------------------------------------------------
+#[derive(Default)]
+struct SynthBuffer {
+    samples: Vec<i16>,
+    events: Vec<SynthEvent>,
+}
+// ----------------------------------------------------------------------------
+extern "C" fn synth_callback(wav: *mut c_short, numsamples: c_int, events: *mut espeak_EVENT) -> c_int {
+    if let Ok(mut buffer) = SYNTH_BUFFER.lock() {
+        if !wav.is_null() && numsamples > 0 {
+            buffer
+                .samples
+                .extend_from_slice(unsafe { slice::from_raw_parts(wav, numsamples as usize) });
+        }
+        let mut event = events;
+        while !event.is_null() {
+            let e = unsafe { &*event };
+            let position = e.audio_position.max(0) as u32;
+            match e.event_type {
+                espeak_EVENT_TYPE::espeakEVENT_LIST_TERMINATED => break,
+                espeak_EVENT_TYPE::espeakEVENT_WORD => buffer.events.push(SynthEvent::Word),
+                espeak_EVENT_TYPE::espeakEVENT_PHONEME => {
+                    // phoneme names are stored as zero terminated string in the
+                    // (8 byte) id union
+                    let name = e.id.to_ne_bytes();
+                    let len = name.iter().position(|b| *b == 0).unwrap_or(name.len());
+                    let phoneme = String::from_utf8_lossy(&name[..len]).into_owned();
+                    buffer.events.push(SynthEvent::Phoneme(phoneme, position));
+                }
+                espeak_EVENT_TYPE::espeakEVENT_END | espeak_EVENT_TYPE::espeakEVENT_MSG_TERMINATED => {
+                    buffer.events.push(SynthEvent::End(position))
+                }
+                _ => {}
+            }
+            event = unsafe { event.add(1) };
+        }
+    }
+    // continue synthesis
+    0
 }
 // ----------------------------------------------------------------------------
@@ -5,6 +5,9 @@ use super::AudioWidgets;
 use self::waveform::WaveForm;
 use self::timeline::{Timeline, Block};
 
+pub use self::peaks::{PeakLevel, PeakPyramid};
+
+mod peaks;
 mod waveform;
 mod timeline;
 
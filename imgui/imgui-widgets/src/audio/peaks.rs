@@ -0,0 +1,204 @@
+use std::cmp;
+
+/// decimation factor between two consecutive levels
+const LEVEL_FACTOR: usize = 4;
+/// levels with less buckets than this are not created
+const MIN_LEVEL_BUCKETS: usize = 64;
+
+/// min/max peaks of samples with multiple decimation levels. level n contains
+/// one (min, max) pair for every LEVEL_FACTOR^(n + 1) samples.
+#[derive(Default)]
+pub struct PeakPyramid {
+    levels: Vec<PeakLevel>,
+}
+
+pub struct PeakLevel {
+    bucket_size: usize,
+    peaks: Vec<(i16, i16)>,
+}
+
+impl PeakPyramid {
+    pub fn new(values: &[i16]) -> Self {
+        let mut levels: Vec<PeakLevel> = Vec::new();
+
+        loop {
+            let next = match levels.last() {
+                // first level is built from raw samples
+                None => PeakLevel {
+                    bucket_size: LEVEL_FACTOR,
+                    peaks: values
+                        .chunks(LEVEL_FACTOR)
+                        .map(|chunk| {
+                            chunk.iter().fold((i16::MAX, i16::MIN), |(min, max), v| {
+                                (cmp::min(min, *v), cmp::max(max, *v))
+                            })
+                        })
+                        .collect(),
+                },
+                Some(prev) => PeakLevel {
+                    bucket_size: prev.bucket_size * LEVEL_FACTOR,
+                    peaks: prev.peaks.chunks(LEVEL_FACTOR).map(merge).collect(),
+                },
+            };
+            if next.peaks.len() < MIN_LEVEL_BUCKETS {
+                break;
+            }
+            levels.push(next);
+        }
+
+        PeakPyramid { levels }
+    }
+
+    /// coarsest level with buckets not larger than the given number of samples.
+    /// None if raw samples should be used.
+    pub fn level(&self, samples_per_point: usize) -> Option<&PeakLevel> {
+        self.levels
+            .iter()
+            .rev()
+            .find(|level| level.bucket_size <= samples_per_point)
+    }
+}
+
+impl PeakLevel {
+    #[inline]
+    pub fn bucket_size(&self) -> usize {
+        self.bucket_size
+    }
+
+    /// min and max of samples in range [start, end)
+    pub fn peaks(&self, start: usize, end: usize) -> Option<(i16, i16)> {
+        let first = start / self.bucket_size;
+        let last = cmp::min(
+            self.peaks.len(),
+            (end + self.bucket_size - 1) / self.bucket_size,
+        );
+
+        if first < last {
+            Some(merge(&self.peaks[first..last]))
+        } else {
+            None
+        }
+    }
+}
+
+#[inline]
+fn merge(peaks: &[(i16, i16)]) -> (i16, i16) {
+    peaks
+        .iter()
+        .fold((i16::MAX, i16::MIN), |(min, max), (pmin, pmax)| {
+            (cmp::min(min, *pmin), cmp::max(max, *pmax))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PeakPyramid;
+
+    /// deterministic signal without periodicity within a bucket
+    fn signal(len: usize) -> Vec<i16> {
+        (0..len)
+            .map(|i| ((i * 7919) % 2001) as i16 - 1000)
+            .collect()
+    }
+
+    /// min and max of raw samples of all buckets touched by [start, end)
+    fn expected(
+        values: &[i16],
+        bucket_size: usize,
+        start: usize,
+        end: usize,
+    ) -> Option<(i16, i16)> {
+        let first = start / bucket_size * bucket_size;
+        let last = values.len().min(end.div_ceil(bucket_size) * bucket_size);
+
+        values
+            .get(first..last)
+            .filter(|v| !v.is_empty())
+            .map(|v| (*v.iter().min().unwrap(), *v.iter().max().unwrap()))
+    }
+
+    #[test]
+    fn test_level_selection() {
+        assert!(PeakPyramid::new(&signal(252)).level(1000).is_none());
+
+        // 258 buckets of 4 and 65 buckets of 16 samples (last ones partial)
+        let pyramid = PeakPyramid::new(&signal(1030));
+        let bucket_size = |samples| pyramid.level(samples).map(|l| l.bucket_size());
+
+        assert_eq!(bucket_size(1), None);
+        assert_eq!(bucket_size(3), None);
+        assert_eq!(bucket_size(4), Some(4));
+        assert_eq!(bucket_size(15), Some(4));
+        assert_eq!(bucket_size(16), Some(16));
+        assert_eq!(bucket_size(100_000), Some(16));
+    }
+
+    #[test]
+    fn test_bucket_peaks() {
+        let values = signal(1030);
+        let pyramid = PeakPyramid::new(&values);
+
+        for bucket_size in [4, 16].iter().copied() {
+            let level = pyramid.level(bucket_size).unwrap();
+            assert_eq!(level.bucket_size(), bucket_size);
+
+            for start in (0..values.len()).step_by(bucket_size) {
+                let end = (start + bucket_size).min(values.len());
+                let samples = &values[start..end];
+                assert_eq!(
+                    level.peaks(start, end),
+                    Some((
+                        *samples.iter().min().unwrap(),
+                        *samples.iter().max().unwrap()
+                    )),
+                    "{} {}",
+                    bucket_size,
+                    start
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_range_peaks() {
+        let values = signal(1030);
+        let pyramid = PeakPyramid::new(&values);
+
+        // partial buckets, ranges across bucket and level bucket boundaries,
+        // ranges exceeding the samples
+        let ranges = [
+            (5, 6),
+            (3, 5),
+            (15, 17),
+            (63, 65),
+            (100, 613),
+            (0, 1030),
+            (1020, 2000),
+            (1029, 1030),
+        ];
+        for bucket_size in [4, 16].iter().copied() {
+            let level = pyramid.level(bucket_size).unwrap();
+
+            for (start, end) in ranges.iter().copied() {
+                assert_eq!(
+                    level.peaks(start, end),
+                    expected(&values, bucket_size, start, end),
+                    "{} {}..{}",
+                    bucket_size,
+                    start,
+                    end
+                );
+            }
+        }
+
+        // coarser level covers the same range with the complete buckets
+        let (fine, coarse) = (pyramid.level(4).unwrap(), pyramid.level(16).unwrap());
+        assert_eq!(fine.peaks(16, 48), coarse.peaks(16, 48));
+        assert_eq!(fine.peaks(16, 48), coarse.peaks(20, 40));
+
+        let level = pyramid.level(4).unwrap();
+        assert_eq!(level.peaks(8, 8), None);
+        assert_eq!(level.peaks(1032, 1100), None);
+        assert_eq!(level.peaks(5000, 6000), None);
+    }
+}
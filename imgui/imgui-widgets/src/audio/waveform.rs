@@ -2,6 +2,7 @@ use std::cmp;
 
 use imgui::{ImStr, ImVec2, Ui};
 
+use super::PeakPyramid;
 use {Interaction, MouseInteraction};
 
 #[must_use]
@@ -9,6 +10,7 @@ pub struct WaveForm<'ui, 'p> {
     ui: &'ui Ui<'ui>,
     label: &'p ImStr,
     values: &'p [i16],
+    peaks: Option<&'p PeakPyramid>,
     values_offset: usize,
     visible_range: &'p mut (usize, usize),
     max_value: i16,
@@ -30,6 +32,7 @@ impl<'ui, 'p> WaveForm<'ui, 'p> {
             ui,
             label,
             values,
+            peaks: None,
             values_offset: 0,
             visible_range,
             max_value: i16::MAX,
@@ -47,6 +50,13 @@ impl<'ui, 'p> WaveForm<'ui, 'p> {
         self
     }
 
+    /// precalculated peaks of values used for rendering if zoomed out
+    #[inline]
+    pub fn peaks(mut self, peaks: &'p PeakPyramid) -> Self {
+        self.peaks = Some(peaks);
+        self
+    }
+
     #[inline]
     pub fn offset(mut self, sample: usize) -> Self {
         self.values_offset = sample;
@@ -146,33 +156,62 @@ impl<'ui, 'p> WaveForm<'ui, 'p> {
             let win_end = cmp::min(len, win_start.saturating_add(win_size as usize));
             *self.visible_range = (win_start, win_end);
 
-            let mut p0 = ImVec2::zero();
-            let mut p1;
             let scale_y = self.graph_size.y * 0.5 / f32::from(self.max_value);
             let base_y = screen_pos.y + 0.5 * self.graph_size.y;
 
-            for i in 0..sampling_points as usize {
-                let index = win_start + i * sampling_step;
+            // if zoomed out render min/max peaks of every sampling step
+            let peak_level = self.peaks.and_then(|peaks| peaks.level(sampling_step));
 
-                if index < win_end {
-                    let v = f32::from(self.values[index]) * scale_y;
+            if let Some(level) = peak_level {
+                let max_y = self.graph_size.y * 0.5;
 
-                    p1 = ImVec2::new(
-                        screen_pos.x + scale_x * (i * sampling_step) as f32,
-                        f32::min(self.graph_size.y, v),
-                    );
+                for i in 0..sampling_points as usize {
+                    let index = win_start + i * sampling_step;
+                    if index >= win_end {
+                        break;
+                    }
+                    if let Some((min, max)) = level.peaks(index, index + sampling_step) {
+                        let x = screen_pos.x + scale_x * (i * sampling_step) as f32;
+                        let y_max = (f32::from(max) * scale_y).clamp(-max_y, max_y);
+                        let y_min = (f32::from(min) * scale_y).clamp(-max_y, max_y);
 
-                    if i > 0 {
                         ImDrawList_AddLine(
                             drawlist,
-                            ImVec2::new(p0.x, base_y - p0.y),
-                            ImVec2::new(p1.x, base_y - p1.y),
+                            ImVec2::new(x, base_y - y_max),
+                            // at least one pixel for silence
+                            ImVec2::new(x, base_y - y_min + 1.0),
                             col_line,
                             1.0,
                         );
-                        // ImDrawList_AddLine(drawlist, ImVec2::new(p0.x, base_y + p0.y), ImVec2::new(p1.x, base_y + p1.y), col_line, 1.0);
                     }
-                    p0 = p1;
+                }
+            } else {
+                let mut p0 = ImVec2::zero();
+                let mut p1;
+
+                for i in 0..sampling_points as usize {
+                    let index = win_start + i * sampling_step;
+
+                    if index < win_end {
+                        let v = f32::from(self.values[index]) * scale_y;
+
+                        p1 = ImVec2::new(
+                            screen_pos.x + scale_x * (i * sampling_step) as f32,
+                            f32::min(self.graph_size.y, v),
+                        );
+
+                        if i > 0 {
+                            ImDrawList_AddLine(
+                                drawlist,
+                                ImVec2::new(p0.x, base_y - p0.y),
+                                ImVec2::new(p1.x, base_y - p1.y),
+                                col_line,
+                                1.0,
+                            );
+                            // ImDrawList_AddLine(drawlist, ImVec2::new(p0.x, base_y + p0.y), ImVec2::new(p1.x, base_y + p1.y), col_line, 1.0);
+                        }
+                        p0 = p1;
+                    }
                 }
             }
 
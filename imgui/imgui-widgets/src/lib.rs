@@ -5,6 +5,7 @@ use imgui::ImVec2;
 
 pub mod audio;
 pub mod graph;
+pub mod matrix;
 // ----------------------------------------------------------------------------
 pub trait AudioWidgets {
     fn audio(&self) -> audio::Widgets;
@@ -13,6 +14,10 @@ pub trait AudioWidgets {
 pub trait GraphWidgets {
     fn graph(&self) -> graph::Widgets;
 }
+
+pub trait MatrixWidgets {
+    fn matrix(&self) -> matrix::Widgets;
+}
 // ----------------------------------------------------------------------------
 #[derive(Clone, Copy, Debug)]
 pub struct Rectangle {
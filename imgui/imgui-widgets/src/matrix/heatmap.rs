@@ -0,0 +1,335 @@
+use std::ptr;
+
+use imgui::{ImStr, ImString, ImVec2, ImVec4, Ui};
+
+use {Interaction, MouseInteraction};
+
+#[must_use]
+pub struct HeatMap<'ui, 'p> {
+    ui: &'ui Ui<'ui>,
+    label: &'p ImStr,
+    columns: usize,
+    rows: usize,
+    values: &'p [f32],
+    hovered_cell: &'p mut Option<(usize, usize)>,
+
+    value_range: (f32, f32),
+    cell_size: f32,
+    column_labels: &'p [ImString],
+    row_labels: &'p [ImString],
+    path: &'p [(usize, usize)],
+    highlighted: &'p [(usize, usize)],
+
+    color_low: ImVec4,
+    color_high: ImVec4,
+}
+
+impl<'ui, 'p> HeatMap<'ui, 'p> {
+    pub fn new(
+        ui: &'ui Ui<'ui>,
+        label: &'p ImStr,
+        dimension: (usize, usize),
+        values: &'p [f32],
+        hovered_cell: &'p mut Option<(usize, usize)>,
+    ) -> Self {
+        HeatMap {
+            ui,
+            label,
+            columns: dimension.0,
+            rows: dimension.1,
+            values,
+            hovered_cell,
+            value_range: (0.0, 1.0),
+            cell_size: 16.0,
+            column_labels: &[],
+            row_labels: &[],
+            path: &[],
+            highlighted: &[],
+            color_low: ImVec4::new(0.05, 0.1, 0.35, 1.0),
+            color_high: ImVec4::new(1.0, 0.85, 0.2, 1.0),
+        }
+    }
+
+    /// values outside of the range are not colored
+    #[inline]
+    pub fn value_range(mut self, min: f32, max: f32) -> Self {
+        if min < max {
+            self.value_range = (min, max);
+        }
+        self
+    }
+
+    #[inline]
+    pub fn cell_size(mut self, size: f32) -> Self {
+        self.cell_size = size.max(2.0);
+        self
+    }
+
+    /// labels of the last columns (e.g. if the first column has no label)
+    #[inline]
+    pub fn column_labels(mut self, labels: &'p [ImString]) -> Self {
+        self.column_labels = labels;
+        self
+    }
+
+    /// labels of the last rows (e.g. if the first row has no label)
+    #[inline]
+    pub fn row_labels(mut self, labels: &'p [ImString]) -> Self {
+        self.row_labels = labels;
+        self
+    }
+
+    /// cells (column, row) connected by a line
+    #[inline]
+    pub fn path(mut self, cells: &'p [(usize, usize)]) -> Self {
+        self.path = cells;
+        self
+    }
+
+    /// cells (column, row) with a highlighted border
+    #[inline]
+    pub fn highlighted(mut self, cells: &'p [(usize, usize)]) -> Self {
+        self.highlighted = cells;
+        self
+    }
+
+    #[inline]
+    pub fn colors<C: Into<ImVec4>>(mut self, low: C, high: C) -> Self {
+        self.color_low = low.into();
+        self.color_high = high.into();
+        self
+    }
+
+    pub fn build(self) -> Option<Interaction<(usize, usize)>> {
+        use sys::*;
+
+        unsafe {
+            let col_frame = igGetColorU32(ImGuiCol::FrameBg, 1.0);
+            let col_text = igGetColorU32(ImGuiCol::Text, 1.0);
+            let col_path = igGetColorU32(ImGuiCol::PlotLinesHovered, 1.0);
+            let col_highlight = igGetColorU32(ImGuiCol::PlotHistogram, 1.0);
+
+            let mut screen_pos = ImVec2::zero();
+            igGetCursorScreenPos(&mut screen_pos);
+
+            // space for labels on top and left
+            let label_width = Self::max_label_width(self.row_labels) + 4.0;
+            let label_height = if self.column_labels.is_empty() {
+                0.0
+            } else {
+                igGetTextLineHeight() + 4.0
+            };
+
+            let grid_pos = ImVec2::new(screen_pos.x + label_width, screen_pos.y + label_height);
+            let grid_size = ImVec2::new(
+                self.cell_size * self.columns as f32,
+                self.cell_size * self.rows as f32,
+            );
+
+            let bb = ImRect {
+                min: screen_pos,
+                max: grid_pos + grid_size,
+            };
+
+            igItemSize(bb, 0.0);
+            if !igItemAdd(bb, igGetIDStr(self.label.as_ptr())) {
+                return None;
+            }
+
+            let drawlist = igGetWindowDrawList();
+
+            // background rectangle
+            ImDrawList_AddRectFilled(drawlist, grid_pos, grid_pos + grid_size, col_frame, 0.0, 0);
+
+            let cell_size = self.cell_size;
+            let cell_min = |column: usize, row: usize| {
+                ImVec2::new(
+                    grid_pos.x + cell_size * column as f32,
+                    grid_pos.y + cell_size * row as f32,
+                )
+            };
+            let cell_max = |column: usize, row: usize| {
+                ImVec2::new(
+                    grid_pos.x + cell_size * (column + 1) as f32,
+                    grid_pos.y + cell_size * (row + 1) as f32,
+                )
+            };
+            let cell_center = |column: usize, row: usize| {
+                ImVec2::new(
+                    grid_pos.x + cell_size * (column as f32 + 0.5),
+                    grid_pos.y + cell_size * (row as f32 + 0.5),
+                )
+            };
+
+            // -- cells
+            let (min, max) = self.value_range;
+            for row in 0..self.rows {
+                for column in 0..self.columns {
+                    let value = match self.values.get(row * self.columns + column) {
+                        Some(value) if *value >= min && *value <= max => *value,
+                        _ => continue,
+                    };
+                    let col = self.color((value - min) / (max - min));
+
+                    ImDrawList_AddRectFilled(
+                        drawlist,
+                        cell_min(column, row),
+                        cell_max(column, row),
+                        col,
+                        0.0,
+                        0,
+                    );
+                }
+            }
+
+            // -- labels
+            let columnlabel_start = self.columns.saturating_sub(self.column_labels.len());
+            for (i, label) in self.column_labels.iter().take(self.columns).enumerate() {
+                let center = cell_center(columnlabel_start + i, 0);
+                let size = Self::text_size(label);
+                Self::add_text(
+                    drawlist,
+                    ImVec2::new(center.x - size.x * 0.5, screen_pos.y),
+                    col_text,
+                    label,
+                );
+            }
+
+            let rowlabel_start = self.rows.saturating_sub(self.row_labels.len());
+            for (i, label) in self.row_labels.iter().take(self.rows).enumerate() {
+                let center = cell_center(0, rowlabel_start + i);
+                let size = Self::text_size(label);
+                Self::add_text(
+                    drawlist,
+                    ImVec2::new(screen_pos.x, center.y - size.y * 0.5),
+                    col_text,
+                    label,
+                );
+            }
+
+            // -- path
+            let path = self
+                .path
+                .iter()
+                .filter(|(column, row)| *column < self.columns && *row < self.rows)
+                .collect::<Vec<_>>();
+
+            for &&(column, row) in &path {
+                ImDrawList_AddRect(
+                    drawlist,
+                    cell_min(column, row),
+                    cell_max(column, row),
+                    col_path,
+                    0.0,
+                    0,
+                    1.0,
+                );
+            }
+            for pair in path.windows(2) {
+                ImDrawList_AddLine(
+                    drawlist,
+                    cell_center(pair[0].0, pair[0].1),
+                    cell_center(pair[1].0, pair[1].1),
+                    col_path,
+                    2.0,
+                );
+            }
+
+            for &(column, row) in self.highlighted {
+                if column < self.columns && row < self.rows {
+                    ImDrawList_AddRect(
+                        drawlist,
+                        cell_min(column, row),
+                        cell_max(column, row),
+                        col_highlight,
+                        0.0,
+                        0,
+                        2.0,
+                    );
+                }
+            }
+
+            // -- interaction
+            *self.hovered_cell = None;
+
+            if igIsItemHovered(ImGuiHoveredFlags::empty()) {
+                let mouse_pos: ImVec2 = self.ui.imgui().mouse_pos().into();
+                let canvas_pos = mouse_pos - screen_pos;
+                let grid_offset = mouse_pos - grid_pos;
+
+                if grid_offset.x < 0.0
+                    || grid_offset.y < 0.0
+                    || grid_offset.x >= grid_size.x
+                    || grid_offset.y >= grid_size.y
+                {
+                    return None;
+                }
+
+                let cell = (
+                    (grid_offset.x / self.cell_size) as usize,
+                    (grid_offset.y / self.cell_size) as usize,
+                );
+                *self.hovered_cell = Some(cell);
+
+                ImDrawList_AddRect(
+                    drawlist,
+                    cell_min(cell.0, cell.1),
+                    cell_max(cell.0, cell.1),
+                    col_text,
+                    0.0,
+                    0,
+                    1.0,
+                );
+
+                if igIsItemClicked(0) {
+                    Some(MouseInteraction::Clicked(0))
+                } else if igIsItemClicked(1) {
+                    Some(MouseInteraction::Clicked(1))
+                } else if igIsItemClicked(2) {
+                    Some(MouseInteraction::Clicked(2))
+                } else {
+                    None
+                }.map(|mouse_interaction| {
+                    Interaction::new(canvas_pos, cell, mouse_interaction)
+                })
+            } else {
+                None
+            }
+        }
+    }
+
+    /// linear interpolation between low and high color
+    fn color(&self, ratio: f32) -> sys::ImU32 {
+        let ratio = ratio.clamp(0.0, 1.0);
+        let lerp = |low: f32, high: f32| low + (high - low) * ratio;
+
+        unsafe {
+            sys::igColorConvertFloat4ToU32(ImVec4::new(
+                lerp(self.color_low.x, self.color_high.x),
+                lerp(self.color_low.y, self.color_high.y),
+                lerp(self.color_low.z, self.color_high.z),
+                lerp(self.color_low.w, self.color_high.w),
+            ))
+        }
+    }
+
+    fn max_label_width(labels: &[ImString]) -> f32 {
+        labels
+            .iter()
+            .map(|label| Self::text_size(label).x)
+            .fold(0.0, f32::max)
+    }
+
+    fn text_size(text: &ImStr) -> ImVec2 {
+        let mut size = ImVec2::zero();
+        unsafe {
+            sys::igCalcTextSize(&mut size, text.as_ptr(), ptr::null(), false, -1.0);
+        }
+        size
+    }
+
+    unsafe fn add_text(drawlist: *mut sys::ImDrawList, pos: ImVec2, col: sys::ImU32, text: &ImStr) {
+        let start = text.as_ptr();
+        sys::ImDrawList_AddText(drawlist, pos, col, start, start.add(text.to_str().len()));
+    }
+}
@@ -0,0 +1,34 @@
+use imgui::{ImStr, Ui};
+
+use super::MatrixWidgets;
+
+use self::heatmap::HeatMap;
+
+mod heatmap;
+
+impl MatrixWidgets for Ui<'_> {
+    fn matrix(&self) -> Widgets {
+        Widgets::new(self)
+    }
+}
+
+pub struct Widgets<'ui> {
+    ui: &'ui Ui<'ui>,
+}
+
+impl<'ui> Widgets<'ui> {
+    fn new(ui: &'ui Ui) -> Widgets<'ui> {
+        Widgets { ui }
+    }
+
+    /// values are stored row by row (index: row * columns + column)
+    pub fn heatmap<'p>(
+        &self,
+        label: &'p ImStr,
+        dimension: (usize, usize),
+        values: &'p [f32],
+        hovered_cell: &'p mut Option<(usize, usize)>,
+    ) -> HeatMap<'ui, 'p> {
+        HeatMap::new(self.ui, label, dimension, values, hovered_cell)
+    }
+}
@@ -436,7 +436,31 @@ impl Decoder {
         LogMath::from_decoder(self)
     }
 
-    // ps_update_mllr
+    /// Adapt the current acoustic model using a linear transform (e.g. MLLR) read from a file.
+    ///
+    /// The transform replaces any previously applied transform.
+    ///
+    /// # Arguments
+    /// - `mllrfile` - Path to the transform file (e.g. created by sphinxtrain `mllr_solve`).
+    pub fn update_mllr(&mut self, mllrfile: &str) -> Result<(), Box<dyn Error>> {
+        let c_mllrfile = std::ffi::CString::new(mllrfile)?;
+
+        let mllr = unsafe { pocketsphinx_sys::ps_mllr_read(c_mllrfile.as_ptr()) };
+        if mllr.is_null() {
+            return Err("Failed to read linear transform".into());
+        }
+
+        let result = unsafe { pocketsphinx_sys::ps_update_mllr(self.inner, mllr) };
+
+        // the decoder retains the transform
+        unsafe { pocketsphinx_sys::ps_mllr_free(mllr) };
+
+        if result.is_null() {
+            Err("Failed to update linear transform".into())
+        } else {
+            Ok(())
+        }
+    }
 
     /// Reload the pronunciation dictionary from a file.
     /// This function replaces the current pronunciation dictionary with the one stored in the given dictionary. This also causes the active search module(s) to be reinitialized, in the same manner as calling add_word() with update=true.
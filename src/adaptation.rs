@@ -0,0 +1,267 @@
+//
+// per actor speaker adaptation of the audio phoneme extractor
+//
+
+// ----------------------------------------------------------------------------
+// external interface
+// ----------------------------------------------------------------------------
+/// min number of approved lines required to adapt the model to an actor
+pub const MIN_ADAPTATION_LINES: usize = 20;
+// ----------------------------------------------------------------------------
+impl<S> Processor<S>
+where
+    S: StringsProvider,
+{
+    // ------------------------------------------------------------------------
+    /// adapts the audio phoneme extractor to every actor with enough approved
+    /// (edited and without errors) phoneme tracks in inputdir. returns number
+    /// of adapted and failed actors.
+    pub fn adapt_speakers(&mut self, inputdir: PathBuf) -> Result<(usize, usize), String> {
+        let mut scanner = FilesScanner::new(inputdir)?;
+
+        let mut audiofiles = HashMap::new();
+        let mut phonemefiles = Vec::new();
+        for file in scanner.scan()? {
+            match file {
+                FileInfo::Audio(lineid, audiofile, _) => {
+                    audiofiles.insert(lineid, audiofile);
+                }
+                FileInfo::Phonemes(lineid, phonemefile) => {
+                    phonemefiles.push((lineid, phonemefile));
+                }
+                FileInfo::UnlinkedAudio(_) | FileInfo::Alignment(_, _) => {}
+            }
+        }
+
+        // approved lines grouped by actor
+        let mapping = self.matcher.phoneme_pairing_alternatives();
+        let mut actors = BTreeMap::<String, Vec<AdaptationLine>>::new();
+        for (lineid, phonemefile) in phonemefiles {
+            let track = match phonemes::load(lineid, &phonemefile) {
+                Ok(track) => track,
+                Err(why) => {
+                    warn!("id {lineid:010}: {why} skipping...");
+                    continue;
+                }
+            };
+            if track.assessed_quality() != QualityAssessment::EditedOk {
+                continue;
+            }
+            let Some(actor) = self.strings.get_actor(lineid) else {
+                continue;
+            };
+            let Some(audiofile) = audiofiles.get(&lineid) else {
+                warn!("id {lineid:010}: no audiofile found. skipping...");
+                continue;
+            };
+            let Some(transcription) = transcription(&track, mapping) else {
+                warn!("id {lineid:010}: no extractor phonemes for all segments. skipping...");
+                continue;
+            };
+
+            actors
+                .entry(actor.to_owned())
+                .or_default()
+                .push(AdaptationLine {
+                    lineid,
+                    audiofile: audiofile.to_owned(),
+                    transcription,
+                });
+        }
+
+        // per run so concurrent runs never share corpus files
+        let workdir = std::env::temp_dir().join(format!(
+            "w3phonemes-adaptation-{}-{}",
+            process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|time| time.as_nanos())
+                .unwrap_or_default()
+        ));
+
+        let mut adapted = 0;
+        let mut failed = 0;
+        for (actor, lines) in &actors {
+            if lines.len() < MIN_ADAPTATION_LINES {
+                info!(
+                    "{actor}: skipping adaptation. only #{} of required {MIN_ADAPTATION_LINES} approved lines found.",
+                    lines.len()
+                );
+                continue;
+            }
+            info!(
+                "{actor}: adapting model to #{} approved lines...",
+                lines.len()
+            );
+
+            match self.adapt_actor(&workdir, actor, lines) {
+                Ok(transform) => {
+                    info!("{actor}: > stored adaptation in [{}]", transform.display());
+                    adapted += 1;
+                }
+                Err(why) => {
+                    error!("{actor}: > {why}");
+                    failed += 1;
+                }
+            }
+        }
+        if workdir.exists() {
+            if let Err(err) = fs::remove_dir_all(&workdir) {
+                warn!("failed to remove adaptation workdir: {err}");
+            }
+        }
+        Ok((adapted, failed))
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+// internals
+// ----------------------------------------------------------------------------
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use audio::AudioWriter;
+use extractor::{speaker_filename, AdaptationCorpus};
+use file_scanner::{FileInfo, FilesScanner};
+use phonemes::{self, PhonemeSegment, PhonemeTrack, QualityAssessment};
+
+use super::{DataProvider, Processor, StringsProvider};
+// ----------------------------------------------------------------------------
+struct AdaptationLine {
+    lineid: u32,
+    audiofile: String,
+    /// extractor phonemes of the approved track
+    transcription: String,
+}
+// ----------------------------------------------------------------------------
+/// extractor phonemes of all active segments based on the mapping of ipa to
+/// extractor phonemes. None if at least one phoneme has no mapping.
+fn transcription(
+    track: &PhonemeTrack<PhonemeSegment>,
+    mapping: &HashMap<String, String>,
+) -> Option<String> {
+    let mut phonemes = Vec::new();
+    for segment in track.phonemes().iter().filter(|s| s.active) {
+        match segment.phoneme.as_str() {
+            "_" => phonemes.push("SIL"),
+            ipa => phonemes.push(mapping.get(ipa)?.as_str()),
+        }
+    }
+    Some(phonemes.join(" "))
+}
+// ----------------------------------------------------------------------------
+impl<S> Processor<S>
+where
+    S: StringsProvider,
+{
+    // ------------------------------------------------------------------------
+    /// prepares the adaptation corpus in a subdirectory of workdir and adapts
+    /// the extractor
+    fn adapt_actor(
+        &mut self,
+        workdir: &Path,
+        actor: &str,
+        lines: &[AdaptationLine],
+    ) -> Result<PathBuf, String> {
+        let dir = workdir.join(speaker_filename(actor));
+
+        fs::create_dir_all(&dir)
+            .map_err(|err| format!("failed to create adaptation workdir: {err}"))?;
+
+        let result = self
+            .prepare_corpus(&dir, lines)
+            .and_then(|corpus| self.extractor.adapt_speaker(actor, &corpus));
+
+        if let Err(err) = fs::remove_dir_all(&dir) {
+            warn!("failed to remove adaptation workdir: {err}");
+        }
+        result
+    }
+    // ------------------------------------------------------------------------
+    fn prepare_corpus(
+        &self,
+        dir: &Path,
+        lines: &[AdaptationLine],
+    ) -> Result<AdaptationCorpus, String> {
+        let mut fileids = String::new();
+        let mut transcription = String::new();
+
+        for line in lines {
+            let mut dataprovider = DataProvider::new(&line.audiofile, &self.datadir);
+            dataprovider.load()?;
+
+            // same preprocessing as for extraction
+            let audiodata = dataprovider.get_rawaudio(16000, true)?;
+            let fileid = format!("{:010}", line.lineid);
+            AudioWriter::write_wav(&dir.join(format!("{fileid}.wav")), &audiodata, 16000)?;
+
+            fileids.push_str(&format!("{fileid}\n"));
+            transcription.push_str(&format!("<s> {} </s> ({fileid})\n", line.transcription));
+        }
+
+        let corpus = AdaptationCorpus {
+            dir: dir.to_owned(),
+            fileids: dir.join("adaptation.fileids"),
+            transcription: dir.join("adaptation.transcription"),
+            lines: lines.len(),
+        };
+        fs::write(&corpus.fileids, fileids)
+            .map_err(|err| format!("failed to write adaptation fileids: {err}"))?;
+        fs::write(&corpus.transcription, transcription)
+            .map_err(|err| format!("failed to write adaptation transcription: {err}"))?;
+
+        Ok(corpus)
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+// ----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use phonemes::{PhonemeSegment, PhonemeTrack};
+
+    use super::transcription;
+
+    fn track(phonemes: &[(&str, bool)]) -> PhonemeTrack<PhonemeSegment> {
+        let segments = phonemes
+            .iter()
+            .map(|(phoneme, active)| PhonemeSegment {
+                phoneme: phoneme.to_string(),
+                active: *active,
+                ..Default::default()
+            })
+            .collect();
+        PhonemeTrack::new(1, "en", "hi", "", None, None, segments)
+    }
+
+    #[test]
+    fn test_transcription() {
+        let mapping = [("h", "HH"), ("aɪ", "AY")]
+            .iter()
+            .map(|(ipa, phoneme)| (ipa.to_string(), phoneme.to_string()))
+            .collect::<HashMap<_, _>>();
+
+        assert_eq!(
+            transcription(&track(&[("_", true), ("h", true), ("aɪ", true)]), &mapping),
+            Some(String::from("SIL HH AY"))
+        );
+        // inactive segments are skipped even without mapping
+        assert_eq!(
+            transcription(
+                &track(&[("h", true), ("ʔ", false), ("aɪ", true), ("_", true)]),
+                &mapping
+            ),
+            Some(String::from("HH AY SIL"))
+        );
+        assert_eq!(
+            transcription(&track(&[("h", true), ("ʔ", true), ("aɪ", true)]), &mapping),
+            None
+        );
+        assert_eq!(transcription(&track(&[]), &mapping), Some(String::new()));
+    }
+}
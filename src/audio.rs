@@ -4,6 +4,7 @@
 extern crate fon;
 extern crate hound;
 extern crate lewton;
+extern crate vorbis_rs;
 
 // ----------------------------------------------------------------------------
 // external interface
@@ -14,7 +15,23 @@ pub struct AudioData {
 }
 // ----------------------------------------------------------------------------
 pub struct AudioLoader;
+pub struct AudioWriter;
 pub struct AudioResampler;
+pub struct AudioAnalyzer;
+// ----------------------------------------------------------------------------
+/// measured health indicators of a (mono) audio clip
+pub struct AudioHealth {
+    /// number of runs of consecutive samples at full scale
+    pub clipped_runs: u32,
+    /// max peak level in dBFS
+    pub peak_db: f32,
+    /// estimated signal to noise ratio in dB (loud vs quiet frames)
+    pub snr_db: f32,
+    /// dc offset in percent of full scale
+    pub dc_offset: f32,
+    pub truncated_start: bool,
+    pub truncated_end: bool,
+}
 // ----------------------------------------------------------------------------
 // internals
 // ----------------------------------------------------------------------------
@@ -47,7 +64,13 @@ impl AudioData {
 // ----------------------------------------------------------------------------
 impl AudioLoader {
     // ------------------------------------------------------------------------
-    pub fn load(file: &str, target_samplerate: u32) -> Result<AudioData, String> {
+    /// wem files require the packed vorbis codebook library in
+    /// wem_codebooks_dir
+    pub fn load(
+        file: &str,
+        target_samplerate: u32,
+        wem_codebooks_dir: &Path,
+    ) -> Result<AudioData, String> {
         let (format, mut values): (_, Vec<i16>) = match file {
             s if s.ends_with(".wav") => {
                 let decoder = WavDecoder::new(file)?;
@@ -57,9 +80,13 @@ impl AudioLoader {
                 let decoder = OggDecoder::new(file)?;
                 (decoder.format(), decoder.collect())
             }
+            s if s.ends_with(".wem") => {
+                let decoder = WemDecoder::new(file, wem_codebooks_dir)?;
+                (decoder.format(), decoder.collect())
+            }
             _ => {
                 return Err(String::from(
-                    "audioloader: found unsupported audio format (supported: wav, ogg)",
+                    "audioloader: found unsupported audio format (supported: wav, ogg, wem)",
                 ))
             }
         };
@@ -91,6 +118,68 @@ impl AudioLoader {
     // ------------------------------------------------------------------------
 }
 // ----------------------------------------------------------------------------
+impl AudioWriter {
+    // ------------------------------------------------------------------------
+    /// writes values as 16 bit mono wav
+    pub fn write_wav(file: &Path, values: &[i16], sample_rate: u32) -> Result<(), String> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let error = |e: hound::Error| format!("WavWriter: {}: {}", file.display(), e);
+
+        let mut writer = hound::WavWriter::create(file, spec).map_err(error)?;
+        for value in values {
+            writer.write_sample(*value).map_err(error)?;
+        }
+        writer.finalize().map_err(error)
+    }
+    // ------------------------------------------------------------------------
+    /// encodes values as mono ogg vorbis with quality (vbr) in range -0.1..1.0
+    pub fn write_ogg(
+        file: &Path,
+        values: &[i16],
+        sample_rate: u32,
+        quality: f32,
+    ) -> Result<(), String> {
+        use self::vorbis_rs::{VorbisBitrateManagementStrategy, VorbisEncoderBuilder};
+        use std::io::BufWriter;
+        use std::num::{NonZeroU32, NonZeroU8};
+
+        let error = |e: vorbis_rs::VorbisError| format!("OggWriter: {}: {}", file.display(), e);
+
+        let sample_rate =
+            NonZeroU32::new(sample_rate).ok_or_else(|| String::from("OggWriter: samplerate 0"))?;
+        let output =
+            File::create(file).map_err(|e| format!("OggWriter: {}: {}", file.display(), e))?;
+
+        let mut encoder =
+            VorbisEncoderBuilder::new(sample_rate, NonZeroU8::MIN, BufWriter::new(output))
+                .map_err(error)?
+                .bitrate_management_strategy(VorbisBitrateManagementStrategy::QualityVbr {
+                    target_quality: quality,
+                })
+                .build()
+                .map_err(error)?;
+
+        let samples = values
+            .iter()
+            .map(|v| *v as f32 / -(i16::MIN as f32))
+            .collect::<Vec<_>>();
+
+        // encode in blocks of 1s
+        for block in samples.chunks(sample_rate.get() as usize) {
+            encoder.encode_audio_block([block]).map_err(error)?;
+        }
+        encoder.finish().map_err(error)?;
+
+        Ok(())
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
 impl AudioResampler {
     // ------------------------------------------------------------------------
     pub fn resample(
@@ -128,6 +217,319 @@ impl AudioResampler {
     // ------------------------------------------------------------------------
 }
 // ----------------------------------------------------------------------------
+// samples at (or very close to) full scale are considered clipped if they
+// occur in runs
+const CLIPPING_LEVEL: i16 = i16::MAX - 1;
+const CLIPPING_MIN_RUN: usize = 3;
+// analysis frame length 10ms
+const FRAME_MS: u32 = 10;
+// frames at start/end of clip considered for truncation check
+const EDGE_FRAMES: usize = 2;
+// edge frames louder than (speech level - margin) are probably cut-off speech
+const TRUNCATION_MARGIN_DB: f32 = 12.0;
+// ignore edges below this level regardless of speech level
+const TRUNCATION_MIN_LEVEL_DB: f32 = -40.0;
+// frames within this range below the loudest frame are considered speech
+const SPEECH_RANGE_DB: f32 = 40.0;
+// frames below this level are never considered speech
+const SPEECH_MIN_LEVEL_DB: f32 = -55.0;
+// loudness measurement (ITU-R BS.1770): 400ms blocks with 75% overlap
+const LOUDNESS_BLOCK_MS: u32 = 400;
+const LOUDNESS_STEP_MS: u32 = 100;
+const LOUDNESS_ABSOLUTE_GATE: f64 = -70.0;
+const LOUDNESS_RELATIVE_GATE: f64 = -10.0;
+// spectral change: frequency bands (Hz) and compared context before/after
+// every ms
+const CHANGE_BANDS: &[(f64, f64)] = &[
+    (80.0, 500.0),
+    (500.0, 2000.0),
+    (2000.0, 5000.0),
+    (5000.0, 10000.0),
+];
+const CHANGE_CONTEXT_MS: usize = 5;
+// ----------------------------------------------------------------------------
+impl AudioAnalyzer {
+    // ------------------------------------------------------------------------
+    pub fn analyze(values: &[i16], sample_rate: u32) -> Option<AudioHealth> {
+        let frame_size = (sample_rate * FRAME_MS / 1000) as usize;
+
+        if values.len() < frame_size * EDGE_FRAMES * 2 || frame_size == 0 {
+            return None;
+        }
+
+        let mean = values.iter().map(|v| *v as f64).sum::<f64>() / values.len() as f64;
+        let peak = values
+            .iter()
+            .map(|v| (*v as i32).abs())
+            .max()
+            .unwrap_or_default();
+
+        // count runs of clipped samples
+        let mut clipped_runs = 0;
+        let mut run = 0;
+        for v in values {
+            if *v >= CLIPPING_LEVEL || *v <= -CLIPPING_LEVEL {
+                run += 1;
+                if run == CLIPPING_MIN_RUN {
+                    clipped_runs += 1;
+                }
+            } else {
+                run = 0;
+            }
+        }
+
+        // rms level per frame (without dc offset)
+        let frames = values
+            .chunks(frame_size)
+            .map(|frame| {
+                let sum = frame
+                    .iter()
+                    .map(|v| (*v as f64 - mean).powi(2))
+                    .sum::<f64>();
+                to_db((sum / frame.len() as f64).sqrt() as f32)
+            })
+            .collect::<Vec<_>>();
+
+        let mut sorted = frames.clone();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+        // quiet frames as noise floor estimation, loud frames as speech level
+        let noise_db = sorted[sorted.len() / 10];
+        let speech_db = sorted[(sorted.len() * 9) / 10];
+
+        let edge_level = |frames: &[f32]| frames.iter().sum::<f32>() / frames.len() as f32;
+        let is_truncated = |level: f32| {
+            level > TRUNCATION_MIN_LEVEL_DB && level > speech_db - TRUNCATION_MARGIN_DB
+        };
+
+        Some(AudioHealth {
+            clipped_runs,
+            peak_db: to_db(peak as f32),
+            snr_db: speech_db - noise_db,
+            dc_offset: (mean / i16::MAX as f64 * 100.0) as f32,
+            truncated_start: is_truncated(edge_level(&frames[..EDGE_FRAMES])),
+            truncated_end: is_truncated(edge_level(&frames[frames.len() - EDGE_FRAMES..])),
+        })
+    }
+    // ------------------------------------------------------------------------
+    /// sample range from first to last frame containing speech
+    pub fn speech_bounds(values: &[i16], sample_rate: u32) -> Option<(usize, usize)> {
+        let frame_size = (sample_rate * FRAME_MS / 1000) as usize;
+
+        if values.is_empty() || frame_size == 0 {
+            return None;
+        }
+
+        let frames = values
+            .chunks(frame_size)
+            .map(|frame| {
+                let sum = frame.iter().map(|v| (*v as f64).powi(2)).sum::<f64>();
+                to_db((sum / frame.len() as f64).sqrt() as f32)
+            })
+            .collect::<Vec<_>>();
+
+        let max_db = frames.iter().copied().fold(f32::MIN, f32::max);
+        let threshold = f32::max(SPEECH_MIN_LEVEL_DB, max_db - SPEECH_RANGE_DB);
+
+        let first = frames.iter().position(|level| *level > threshold)?;
+        let last = frames.iter().rposition(|level| *level > threshold)?;
+
+        Some((
+            first * frame_size,
+            ((last + 1) * frame_size).min(values.len()),
+        ))
+    }
+    // ------------------------------------------------------------------------
+    /// integrated (gated) loudness in LUFS as defined by ITU-R BS.1770 for a
+    /// mono signal. None for digital silence.
+    pub fn loudness(values: &[i16], sample_rate: u32) -> Option<f32> {
+        let block_size = (sample_rate * LOUDNESS_BLOCK_MS / 1000) as usize;
+        let step_size = (sample_rate * LOUDNESS_STEP_MS / 1000) as usize;
+
+        if values.is_empty() || step_size == 0 {
+            return None;
+        }
+
+        // k-weighting: high shelf (head effects) followed by rlb high pass
+        let mut shelf = Biquad::k_weighting_shelf(sample_rate as f64);
+        let mut highpass = Biquad::k_weighting_high_pass(sample_rate as f64);
+
+        let filtered = values
+            .iter()
+            .map(|v| highpass.process(shelf.process(*v as f64 / -(i16::MIN as f64))))
+            .collect::<Vec<_>>();
+
+        // very short clips are measured as one block
+        let block_size = block_size.min(filtered.len());
+        let blocks = (0..=(filtered.len() - block_size) / step_size)
+            .map(|i| {
+                let block = &filtered[i * step_size..i * step_size + block_size];
+                block.iter().map(|v| v * v).sum::<f64>() / block_size as f64
+            })
+            .collect::<Vec<_>>();
+
+        let to_lufs = |power: f64| -0.691 + 10.0 * power.log10();
+        let gated_mean = |gate: f64| {
+            let gated = blocks
+                .iter()
+                .copied()
+                .filter(|power| *power > 0.0 && to_lufs(*power) > gate)
+                .collect::<Vec<_>>();
+            if gated.is_empty() {
+                None
+            } else {
+                Some(gated.iter().sum::<f64>() / gated.len() as f64)
+            }
+        };
+
+        let relative_gate = to_lufs(gated_mean(LOUDNESS_ABSOLUTE_GATE)?) + LOUDNESS_RELATIVE_GATE;
+
+        gated_mean(relative_gate.max(LOUDNESS_ABSOLUTE_GATE)).map(|power| to_lufs(power) as f32)
+    }
+    // ------------------------------------------------------------------------
+    /// strength of spectral and energy change (in dB) for every ms of audio.
+    /// compares the mean band levels of a short context before and after
+    /// each ms, i.e. value n rates a boundary at n ms.
+    pub fn spectral_change(values: &[i16], sample_rate: u32) -> Vec<f32> {
+        let block_size = (sample_rate / 1000) as usize;
+
+        if values.is_empty() || block_size == 0 {
+            return Vec::new();
+        }
+        let blocks = values.len() / block_size;
+        let nyquist = sample_rate as f64 / 2.0;
+
+        let mut change = vec![0.0; blocks + 1];
+        let mut bands = 0;
+        for (low, high) in CHANGE_BANDS.iter().filter(|(low, _)| *low < nyquist) {
+            let mut highpass = Biquad::high_pass(sample_rate as f64, *low, 0.707);
+            let mut lowpass = Biquad::low_pass(sample_rate as f64, high.min(nyquist * 0.9), 0.707);
+
+            // running sum of band level per ms block
+            let mut levels = vec![0.0; blocks + 1];
+            for (i, block) in values.chunks_exact(block_size).enumerate() {
+                let power = block
+                    .iter()
+                    .map(|v| lowpass.process(highpass.process(*v as f64)).powi(2))
+                    .sum::<f64>()
+                    / block_size as f64;
+                levels[i + 1] = levels[i] + to_db(power.sqrt() as f32) as f64;
+            }
+
+            for (pos, value) in change.iter_mut().enumerate() {
+                let context = CHANGE_CONTEXT_MS.min(pos).min(blocks - pos);
+                if context > 0 {
+                    let before = levels[pos] - levels[pos - context];
+                    let after = levels[pos + context] - levels[pos];
+                    *value += ((after - before) / context as f64).abs() as f32;
+                }
+            }
+            bands += 1;
+        }
+
+        for value in &mut change {
+            *value /= bands.max(1) as f32;
+        }
+        change
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+/// second order iir filter (direct form I), coefficients normalized by a0
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+// ----------------------------------------------------------------------------
+impl Biquad {
+    // ------------------------------------------------------------------------
+    fn new(b: [f64; 3], a: [f64; 3]) -> Biquad {
+        Biquad {
+            b: [b[0] / a[0], b[1] / a[0], b[2] / a[0]],
+            a: [a[1] / a[0], a[2] / a[0]],
+            x: [0.0; 2],
+            y: [0.0; 2],
+        }
+    }
+    // ------------------------------------------------------------------------
+    /// high shelf stage of the bs.1770 k-weighting. the parameters reproduce
+    /// the reference coefficients at 48 kHz (see libebur128) and differ from a
+    /// generic shelving filter.
+    fn k_weighting_shelf(sample_rate: f64) -> Biquad {
+        let k = (std::f64::consts::PI * 1_681.974_450_955_533 / sample_rate).tan();
+        let q = 0.707_175_236_955_419_6;
+        let vh = 10f64.powf(3.999_843_853_973_347 / 20.0);
+        let vb = vh.powf(0.499_666_774_154_541_6);
+
+        Biquad::new(
+            [
+                vh + vb * k / q + k * k,
+                2.0 * (k * k - vh),
+                vh - vb * k / q + k * k,
+            ],
+            [
+                1.0 + k / q + k * k,
+                2.0 * (k * k - 1.0),
+                1.0 - k / q + k * k,
+            ],
+        )
+    }
+    // ------------------------------------------------------------------------
+    /// rlb high pass stage of the bs.1770 k-weighting (unity gain numerator)
+    fn k_weighting_high_pass(sample_rate: f64) -> Biquad {
+        let k = (std::f64::consts::PI * 38.135_470_876_024_44 / sample_rate).tan();
+        let q = 0.500_327_037_323_877_3;
+        let a0 = 1.0 + k / q + k * k;
+
+        Biquad::new(
+            [a0, -2.0 * a0, a0],
+            [a0, 2.0 * (k * k - 1.0), 1.0 - k / q + k * k],
+        )
+    }
+    // ------------------------------------------------------------------------
+    fn high_pass(sample_rate: f64, freq: f64, q: f64) -> Biquad {
+        let w0 = 2.0 * std::f64::consts::PI * freq / sample_rate;
+        let alpha = w0.sin() / (2.0 * q);
+        let cos = w0.cos();
+
+        Biquad::new(
+            [(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0],
+            [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        )
+    }
+    // ------------------------------------------------------------------------
+    fn low_pass(sample_rate: f64, freq: f64, q: f64) -> Biquad {
+        let w0 = 2.0 * std::f64::consts::PI * freq / sample_rate;
+        let alpha = w0.sin() / (2.0 * q);
+        let cos = w0.cos();
+
+        Biquad::new(
+            [(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0],
+            [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        )
+    }
+    // ------------------------------------------------------------------------
+    #[inline]
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0]
+            - self.a[1] * self.y[1];
+
+        self.x = [x, self.x[0]];
+        self.y = [y, self.y[0]];
+        y
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+#[inline]
+fn to_db(amplitude: f32) -> f32 {
+    // clamp to -96 dBFS (16 bit) to prevent -inf for digital silence
+    20.0 * f32::max(amplitude / i16::MAX as f32, 1.0 / 65536.0).log10()
+}
+// ----------------------------------------------------------------------------
 // inspired by rodios decoder implementations
 // ----------------------------------------------------------------------------
 // Wav
@@ -260,3 +662,973 @@ impl Iterator for OggDecoder {
     // ------------------------------------------------------------------------
 }
 // ----------------------------------------------------------------------------
+// Wwise Vorbis (wem)
+// ----------------------------------------------------------------------------
+// wem files are riff containers with stripped down vorbis packets: ident and
+// comment header are missing, the setup header references codebooks of an
+// external (packed) codebook library and audio packets lack the packet type
+// and window flags. all of it is rebuilt into standard vorbis packets (same
+// approach as ww2ogg) which are decoded by lewton.
+use self::lewton::audio::{read_audio_packet, PreviousWindowRight};
+use self::lewton::header::{read_header_ident, read_header_setup, IdentHeader, SetupHeader};
+use std::path::Path;
+
+pub const WEM_CODEBOOKS_FILE: &str = "packed_codebooks_aoTuV_603.bin";
+// ----------------------------------------------------------------------------
+struct WemDecoder {
+    ident: IdentHeader,
+    setup: SetupHeader,
+    packets: vec::IntoIter<Vec<u8>>,
+    pwr: PreviousWindowRight,
+    packet_data: vec::IntoIter<i16>,
+    remaining_samples: usize,
+}
+// ----------------------------------------------------------------------------
+struct WemFile<'data> {
+    data: &'data [u8],
+    channels: u8,
+    sample_rate: u32,
+    avg_bytes_per_second: u32,
+    sample_count: u32,
+    data_offset: usize,
+    data_size: usize,
+    setup_packet_offset: usize,
+    first_audio_packet_offset: usize,
+    no_granule: bool,
+    mod_packets: bool,
+    blocksize_0_pow: u8,
+    blocksize_1_pow: u8,
+}
+// ----------------------------------------------------------------------------
+struct CodebookLibrary {
+    data: Vec<u8>,
+    offsets: Vec<usize>,
+}
+// ----------------------------------------------------------------------------
+impl WemDecoder {
+    // ------------------------------------------------------------------------
+    fn new(filename: &str, codebooks_dir: &Path) -> Result<WemDecoder, String> {
+        let data = std::fs::read(filename).map_err(|e| format!("WemLoader: {}", e))?;
+        let wem = WemFile::parse(&data).map_err(|e| format!("WemLoader: {}", e))?;
+
+        let codebooks = CodebookLibrary::load(codebooks_dir)?;
+
+        let ident = read_header_ident(&wem.ident_packet())
+            .map_err(|e| format!("WemLoader: ident header: {:?}", e))?;
+
+        let (setup_packet, mode_blockflags) = wem
+            .setup_packet(&codebooks)
+            .map_err(|e| format!("WemLoader: setup header: {}", e))?;
+
+        let setup = read_header_setup(
+            &setup_packet,
+            ident.audio_channels,
+            (ident.blocksize_0, ident.blocksize_1),
+        )
+        .map_err(|e| format!("WemLoader: setup header: {:?}", e))?;
+
+        let packets = wem
+            .audio_packets(&mode_blockflags)
+            .map_err(|e| format!("WemLoader: audio packets: {}", e))?;
+
+        let channels = ident.audio_channels;
+        if channels > 1 {
+            warn!("{filename} contains {channels} channels. decoding only channel #{channels}!");
+        }
+
+        Ok(WemDecoder {
+            ident,
+            setup,
+            packets: packets.into_iter(),
+            pwr: PreviousWindowRight::new(),
+            packet_data: Vec::default().into_iter(),
+            remaining_samples: wem.sample_count as usize,
+        })
+    }
+    // ------------------------------------------------------------------------
+    fn format(&self) -> FormatSpec {
+        FormatSpec {
+            channels: self.ident.audio_channels,
+            sample_rate: self.ident.audio_sample_rate,
+            bits: 16,
+        }
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl Iterator for WemDecoder {
+    type Item = i16;
+    // ------------------------------------------------------------------------
+    #[inline]
+    fn next(&mut self) -> Option<i16> {
+        // last packet is padded: stop at sample count from header
+        if self.remaining_samples == 0 {
+            return None;
+        }
+        loop {
+            if let Some(value) = self.packet_data.next() {
+                self.remaining_samples -= 1;
+                return Some(value);
+            }
+            // read next packet (first packet decodes to zero samples)
+            let packet = self.packets.next()?;
+            self.packet_data =
+                match read_audio_packet(&self.ident, &self.setup, &packet, &mut self.pwr) {
+                    Err(msg) => {
+                        error!("WemLoader: {:?}", msg);
+                        return None;
+                    }
+                    Ok(mut data) => data.drain(..).last().unwrap_or_default().into_iter(),
+                };
+        }
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl<'data> WemFile<'data> {
+    // ------------------------------------------------------------------------
+    fn parse(data: &'data [u8]) -> Result<WemFile<'data>, String> {
+        if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WAVE" {
+            return Err(String::from("not a (little endian) riff wave file"));
+        }
+
+        let mut fmt = None;
+        let mut vorb = None;
+        let mut datachunk = None;
+
+        let mut offset = 12;
+        while offset + 8 <= data.len() {
+            let size = read_u32(data, offset + 4)? as usize;
+            let chunk = Some((offset + 8, size));
+            match &data[offset..offset + 4] {
+                b"fmt " => fmt = chunk,
+                b"vorb" => vorb = chunk,
+                b"data" => datachunk = chunk,
+                _ => {}
+            }
+            offset += 8 + size;
+        }
+
+        let (fmt_offset, fmt_size) = fmt.ok_or("missing fmt chunk")?;
+        let (data_offset, data_size) = datachunk.ok_or("missing data chunk")?;
+
+        if read_u16(data, fmt_offset)? != 0xFFFF {
+            return Err(String::from("unsupported codec (expected wwise vorbis)"));
+        }
+
+        // vorb info is either a separate chunk or embedded in an extended fmt chunk
+        let (vorb_offset, vorb_size) = match vorb {
+            Some((offset, size)) => (offset, Some(size)),
+            None if fmt_size == 0x42 => (fmt_offset + 0x18, None),
+            None => return Err(String::from("missing vorb chunk")),
+        };
+
+        let compact = match vorb_size {
+            None | Some(0x2A) => true,
+            Some(0x32) | Some(0x34) => false,
+            Some(0x28) | Some(0x2C) => {
+                return Err(String::from(
+                    "unsupported legacy format (vorbis header triad)",
+                ))
+            }
+            Some(size) => return Err(format!("unsupported vorb chunk size {size:#x}")),
+        };
+
+        let (no_granule, mod_packets, offsets_pos, info_pos) = if compact {
+            let mod_signal = read_u32(data, vorb_offset + 0x04)?;
+            let unmodified = matches!(mod_signal, 0x4A | 0x4B | 0x69 | 0x70);
+            (true, !unmodified, vorb_offset + 0x10, vorb_offset + 0x24)
+        } else {
+            (false, false, vorb_offset + 0x18, vorb_offset + 0x2C)
+        };
+
+        Ok(WemFile {
+            data,
+            channels: read_u16(data, fmt_offset + 0x02)? as u8,
+            sample_rate: read_u32(data, fmt_offset + 0x04)?,
+            avg_bytes_per_second: read_u32(data, fmt_offset + 0x08)?,
+            sample_count: read_u32(data, vorb_offset)?,
+            data_offset,
+            data_size: usize::min(data_size, data.len().saturating_sub(data_offset)),
+            setup_packet_offset: read_u32(data, offsets_pos)? as usize,
+            first_audio_packet_offset: read_u32(data, offsets_pos + 4)? as usize,
+            no_granule,
+            mod_packets,
+            // info_pos + 0 is the uid
+            blocksize_0_pow: read_u8(data, info_pos + 4)?,
+            blocksize_1_pow: read_u8(data, info_pos + 5)?,
+        })
+    }
+    // ------------------------------------------------------------------------
+    /// returns start and size of packet payload at (absolute) offset
+    fn packet_at(&self, offset: usize) -> Result<(usize, usize), String> {
+        let header_size = if self.no_granule { 2 } else { 6 };
+        let size = read_u16(self.data, offset)? as usize;
+        let start = offset + header_size;
+
+        if start + size > self.data.len() {
+            Err(format!("packet at {offset:#x} exceeds file size"))
+        } else {
+            Ok((start, size))
+        }
+    }
+    // ------------------------------------------------------------------------
+    fn ident_packet(&self) -> Vec<u8> {
+        let mut packet = Vec::with_capacity(30);
+        packet.push(1);
+        packet.extend_from_slice(b"vorbis");
+        packet.extend_from_slice(&0u32.to_le_bytes());
+        packet.push(self.channels);
+        packet.extend_from_slice(&self.sample_rate.to_le_bytes());
+        // bitrate max, nominal, min
+        packet.extend_from_slice(&0u32.to_le_bytes());
+        packet.extend_from_slice(&(self.avg_bytes_per_second * 8).to_le_bytes());
+        packet.extend_from_slice(&0u32.to_le_bytes());
+        packet.push(self.blocksize_0_pow | (self.blocksize_1_pow << 4));
+        // framing
+        packet.push(1);
+        packet
+    }
+    // ------------------------------------------------------------------------
+    /// rebuilds the full setup header. returns packet and blockflags of all modes
+    fn setup_packet(&self, codebooks: &CodebookLibrary) -> Result<(Vec<u8>, Vec<bool>), String> {
+        let (start, size) = self.packet_at(self.data_offset + self.setup_packet_offset)?;
+        let mut input = BitReader::new(&self.data[start..start + size]);
+        let mut out = BitWriter::default();
+
+        out.write(5, 8);
+        for c in b"vorbis" {
+            out.write(*c as u32, 8);
+        }
+
+        // codebooks are referenced by id
+        let codebook_count = out.copy(&mut input, 8)? + 1;
+        for _ in 0..codebook_count {
+            let id = input.read(10)?;
+            codebooks.rebuild(id as usize, &mut out)?;
+        }
+        let check_codebook = |book: u32| {
+            if book < codebook_count {
+                Ok(())
+            } else {
+                Err(format!("invalid codebook id {book}"))
+            }
+        };
+
+        // time domain transforms placeholder
+        out.write(0, 6);
+        out.write(0, 16);
+
+        // floors (always type 1)
+        let floor_count = out.copy(&mut input, 6)? + 1;
+        for _ in 0..floor_count {
+            out.write(1, 16);
+
+            let partitions = out.copy(&mut input, 5)?;
+            let mut partition_classes = Vec::with_capacity(partitions as usize);
+            for _ in 0..partitions {
+                partition_classes.push(out.copy(&mut input, 4)? as usize);
+            }
+            let max_class = partition_classes.iter().copied().max().unwrap_or_default();
+
+            let mut class_dimensions = Vec::with_capacity(max_class + 1);
+            for _ in 0..=max_class {
+                class_dimensions.push(out.copy(&mut input, 3)? + 1);
+
+                let subclasses = out.copy(&mut input, 2)?;
+                if subclasses != 0 {
+                    check_codebook(out.copy(&mut input, 8)?)?;
+                }
+                for _ in 0..(1 << subclasses) {
+                    let book = out.copy(&mut input, 8)?;
+                    if book > 0 {
+                        check_codebook(book - 1)?;
+                    }
+                }
+            }
+
+            // multiplier
+            out.copy(&mut input, 2)?;
+            let rangebits = out.copy(&mut input, 4)?;
+            for class in partition_classes {
+                for _ in 0..class_dimensions[class] {
+                    out.copy(&mut input, rangebits)?;
+                }
+            }
+        }
+
+        // residues
+        let residue_count = out.copy(&mut input, 6)? + 1;
+        for _ in 0..residue_count {
+            let residue_type = input.read(2)?;
+            if residue_type > 2 {
+                return Err(format!("invalid residue type {residue_type}"));
+            }
+            out.write(residue_type, 16);
+
+            // begin, end, partition size
+            out.copy(&mut input, 24)?;
+            out.copy(&mut input, 24)?;
+            out.copy(&mut input, 24)?;
+            let classifications = out.copy(&mut input, 6)? + 1;
+            check_codebook(out.copy(&mut input, 8)?)?;
+
+            let mut cascade = Vec::with_capacity(classifications as usize);
+            for _ in 0..classifications {
+                let low_bits = out.copy(&mut input, 3)?;
+                let high_bits = if out.copy(&mut input, 1)? == 1 {
+                    out.copy(&mut input, 5)?
+                } else {
+                    0
+                };
+                cascade.push(high_bits * 8 + low_bits);
+            }
+            for bits in cascade {
+                for k in 0..8 {
+                    if bits & (1 << k) != 0 {
+                        check_codebook(out.copy(&mut input, 8)?)?;
+                    }
+                }
+            }
+        }
+
+        // mappings (always type 0)
+        let mapping_count = out.copy(&mut input, 6)? + 1;
+        let channels = self.channels as u32;
+        for _ in 0..mapping_count {
+            out.write(0, 16);
+
+            let submaps = if out.copy(&mut input, 1)? == 1 {
+                out.copy(&mut input, 4)? + 1
+            } else {
+                1
+            };
+
+            if out.copy(&mut input, 1)? == 1 {
+                let coupling_steps = out.copy(&mut input, 8)? + 1;
+                let bits = ilog(channels.saturating_sub(1));
+                for _ in 0..coupling_steps {
+                    let magnitude = out.copy(&mut input, bits)?;
+                    let angle = out.copy(&mut input, bits)?;
+                    if magnitude == angle || magnitude >= channels || angle >= channels {
+                        return Err(String::from("invalid channel coupling"));
+                    }
+                }
+            }
+
+            if out.copy(&mut input, 2)? != 0 {
+                return Err(String::from("mapping reserved field not zero"));
+            }
+
+            if submaps > 1 {
+                for _ in 0..channels {
+                    if out.copy(&mut input, 4)? >= submaps {
+                        return Err(String::from("invalid mapping mux"));
+                    }
+                }
+            }
+
+            for _ in 0..submaps {
+                // time config, floor, residue
+                out.copy(&mut input, 8)?;
+                if out.copy(&mut input, 8)? >= floor_count {
+                    return Err(String::from("invalid floor mapping"));
+                }
+                if out.copy(&mut input, 8)? >= residue_count {
+                    return Err(String::from("invalid residue mapping"));
+                }
+            }
+        }
+
+        // modes
+        let mode_count = out.copy(&mut input, 6)? + 1;
+        let mut mode_blockflags = Vec::with_capacity(mode_count as usize);
+        for _ in 0..mode_count {
+            mode_blockflags.push(out.copy(&mut input, 1)? == 1);
+            // window type, transform type
+            out.write(0, 16);
+            out.write(0, 16);
+            if out.copy(&mut input, 8)? >= mapping_count {
+                return Err(String::from("invalid mode mapping"));
+            }
+        }
+
+        // framing
+        out.write(1, 1);
+
+        Ok((out.into_bytes(), mode_blockflags))
+    }
+    // ------------------------------------------------------------------------
+    fn audio_packets(&self, mode_blockflags: &[bool]) -> Result<Vec<Vec<u8>>, String> {
+        let mode_bits = ilog(mode_blockflags.len().saturating_sub(1) as u32);
+        let data_end = self.data_offset + self.data_size;
+
+        let blockflag = |packet: &[u8]| -> Result<bool, String> {
+            let mode = BitReader::new(packet).read(mode_bits)? as usize;
+            mode_blockflags
+                .get(mode)
+                .copied()
+                .ok_or_else(|| format!("invalid mode {mode} in audio packet"))
+        };
+
+        let mut packets = Vec::new();
+        let mut prev_blockflag = false;
+        let mut offset = self.data_offset + self.first_audio_packet_offset;
+
+        while offset < data_end {
+            let (start, size) = self.packet_at(offset)?;
+            let next_offset = start + size;
+            let packet = &self.data[start..next_offset];
+
+            if self.mod_packets && !packet.is_empty() {
+                // restore packet type and window flags of long blocks
+                let mut input = BitReader::new(packet);
+                let mut out = BitWriter::default();
+
+                let current_blockflag = blockflag(packet)?;
+
+                out.write(0, 1);
+                out.copy(&mut input, mode_bits)?;
+
+                if current_blockflag {
+                    let next_blockflag = match self.packet_at(next_offset) {
+                        Ok((next_start, next_size)) if next_offset < data_end && next_size > 0 => {
+                            blockflag(&self.data[next_start..next_start + next_size])?
+                        }
+                        _ => false,
+                    };
+                    out.write(prev_blockflag as u32, 1);
+                    out.write(next_blockflag as u32, 1);
+                }
+                prev_blockflag = current_blockflag;
+
+                out.copy(&mut input, 8 - mode_bits)?;
+                for value in &packet[1..] {
+                    out.write(*value as u32, 8);
+                }
+                packets.push(out.into_bytes());
+            } else {
+                packets.push(packet.to_vec());
+            }
+            offset = next_offset;
+        }
+        Ok(packets)
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl CodebookLibrary {
+    // ------------------------------------------------------------------------
+    fn load(dir: &Path) -> Result<CodebookLibrary, String> {
+        let file = dir.join(WEM_CODEBOOKS_FILE);
+
+        let data = std::fs::read(&file).map_err(|e| {
+            format!(
+                "WemLoader: could not read vorbis codebooks from {}: {}",
+                file.display(),
+                e
+            )
+        })?;
+
+        // last u32 is the offset of the codebook offsets table
+        let table_offset = read_u32(&data, data.len().saturating_sub(4))? as usize;
+        if table_offset > data.len() - 4 {
+            return Err(format!(
+                "WemLoader: invalid codebook library {}",
+                file.display()
+            ));
+        }
+        let offsets = (table_offset..data.len())
+            .step_by(4)
+            .map(|pos| read_u32(&data, pos).map(|o| o as usize))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(CodebookLibrary { data, offsets })
+    }
+    // ------------------------------------------------------------------------
+    /// rebuilds the full vorbis codebook from the packed codebook with id
+    fn rebuild(&self, id: usize, out: &mut BitWriter) -> Result<(), String> {
+        // last offset is the table itself
+        if id + 1 >= self.offsets.len() {
+            return Err(format!("invalid codebook id {id}"));
+        }
+        let mut input = BitReader::new(
+            self.data
+                .get(self.offsets[id]..self.offsets[id + 1])
+                .ok_or_else(|| format!("invalid codebook id {id}"))?,
+        );
+
+        let dimensions = input.read(4)?;
+        let entries = input.read(14)?;
+
+        // sync pattern "BCV"
+        out.write(0x564342, 24);
+        out.write(dimensions, 16);
+        out.write(entries, 24);
+
+        let ordered = out.copy(&mut input, 1)?;
+        if ordered == 1 {
+            // initial length
+            out.copy(&mut input, 5)?;
+
+            let mut current_entry = 0;
+            while current_entry < entries {
+                current_entry += out.copy(&mut input, ilog(entries - current_entry))?;
+            }
+            if current_entry > entries {
+                return Err(format!("codebook #{id}: entry count mismatch"));
+            }
+        } else {
+            let codeword_length_length = input.read(3)?;
+            let sparse = out.copy(&mut input, 1)? == 1;
+
+            if codeword_length_length == 0 || codeword_length_length > 5 {
+                return Err(format!("codebook #{id}: invalid codeword length length"));
+            }
+            for _ in 0..entries {
+                if !sparse || out.copy(&mut input, 1)? == 1 {
+                    let codeword_length = input.read(codeword_length_length)?;
+                    out.write(codeword_length, 5);
+                }
+            }
+        }
+
+        let lookup_type = input.read(1)?;
+        out.write(lookup_type, 4);
+
+        if lookup_type == 1 {
+            // min, max
+            out.copy(&mut input, 32)?;
+            out.copy(&mut input, 32)?;
+            let value_length = out.copy(&mut input, 4)? + 1;
+            // sequence flag
+            out.copy(&mut input, 1)?;
+
+            for _ in 0..maptype1_quantvals(entries, dimensions) {
+                out.copy(&mut input, value_length)?;
+            }
+        }
+        Ok(())
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+// vorbis bitstream helper (lsb first)
+// ----------------------------------------------------------------------------
+struct BitReader<'data> {
+    data: &'data [u8],
+    pos: usize,
+}
+// ----------------------------------------------------------------------------
+#[derive(Default)]
+struct BitWriter {
+    data: Vec<u8>,
+    pos: usize,
+}
+// ----------------------------------------------------------------------------
+impl<'data> BitReader<'data> {
+    // ------------------------------------------------------------------------
+    fn new(data: &'data [u8]) -> BitReader<'data> {
+        BitReader { data, pos: 0 }
+    }
+    // ------------------------------------------------------------------------
+    fn read(&mut self, bits: u32) -> Result<u32, String> {
+        let mut value = 0;
+        for i in 0..bits {
+            let byte = self
+                .data
+                .get(self.pos / 8)
+                .ok_or("unexpected end of bitstream")?;
+            value |= (((byte >> (self.pos % 8)) & 1) as u32) << i;
+            self.pos += 1;
+        }
+        Ok(value)
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl BitWriter {
+    // ------------------------------------------------------------------------
+    fn write(&mut self, value: u32, bits: u32) {
+        for i in 0..bits {
+            if self.pos % 8 == 0 {
+                self.data.push(0);
+            }
+            if (value >> i) & 1 == 1 {
+                if let Some(byte) = self.data.last_mut() {
+                    *byte |= 1 << (self.pos % 8);
+                }
+            }
+            self.pos += 1;
+        }
+    }
+    // ------------------------------------------------------------------------
+    /// copies bits from input and returns the copied value
+    fn copy(&mut self, input: &mut BitReader, bits: u32) -> Result<u32, String> {
+        let value = input.read(bits)?;
+        self.write(value, bits);
+        Ok(value)
+    }
+    // ------------------------------------------------------------------------
+    fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+#[inline]
+fn ilog(mut value: u32) -> u32 {
+    let mut bits = 0;
+    while value != 0 {
+        bits += 1;
+        value >>= 1;
+    }
+    bits
+}
+// ----------------------------------------------------------------------------
+fn maptype1_quantvals(entries: u32, dimensions: u32) -> u32 {
+    if dimensions == 0 {
+        return 0;
+    }
+    // see vorbis spec: greatest integer value for which vals^dimensions <= entries
+    let bits = ilog(entries);
+    let mut vals = entries >> (bits.saturating_sub(1) * (dimensions - 1) / dimensions);
+    loop {
+        let acc = (0..dimensions).fold(1u64, |acc, _| acc.saturating_mul(vals as u64));
+        let acc1 = (0..dimensions).fold(1u64, |acc, _| acc.saturating_mul(vals as u64 + 1));
+
+        if acc <= entries as u64 && acc1 > entries as u64 {
+            return vals;
+        } else if acc > entries as u64 {
+            vals -= 1;
+        } else {
+            vals += 1;
+        }
+    }
+}
+// ----------------------------------------------------------------------------
+#[inline]
+fn read_u8(data: &[u8], pos: usize) -> Result<u8, String> {
+    data.get(pos)
+        .copied()
+        .ok_or_else(|| String::from("unexpected end of file"))
+}
+// ----------------------------------------------------------------------------
+#[inline]
+fn read_u16(data: &[u8], pos: usize) -> Result<u16, String> {
+    Ok(u16::from_le_bytes([
+        read_u8(data, pos)?,
+        read_u8(data, pos + 1)?,
+    ]))
+}
+// ----------------------------------------------------------------------------
+#[inline]
+fn read_u32(data: &[u8], pos: usize) -> Result<u32, String> {
+    Ok(u32::from_le_bytes([
+        read_u8(data, pos)?,
+        read_u8(data, pos + 1)?,
+        read_u8(data, pos + 2)?,
+        read_u8(data, pos + 3)?,
+    ]))
+}
+// ----------------------------------------------------------------------------
+// ----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::{ilog, maptype1_quantvals, AudioAnalyzer, BitReader, BitWriter, WemFile};
+
+    const SAMPLE_RATE: u32 = 16000;
+
+    /// 220 Hz tone framed by silence (durations in ms). amplitudes beyond full
+    /// scale are clipped.
+    fn tone(silence_start: u32, tone: u32, silence_end: u32, amplitude: f32) -> Vec<i16> {
+        let samples = |ms: u32| (SAMPLE_RATE * ms / 1000) as usize;
+
+        let mut values = vec![0; samples(silence_start)];
+        values.extend((0..samples(tone)).map(|i| {
+            let v = (i as f32 * 2.0 * PI * 220.0 / SAMPLE_RATE as f32).sin() * amplitude;
+            v.clamp(i16::MIN as f32, i16::MAX as f32) as i16
+        }));
+        values.resize(values.len() + samples(silence_end), 0);
+        values
+    }
+
+    /// 1 kHz sine with amplitude relative to full scale at sample_rate
+    fn sine(sample_rate: u32, ms: u32, amplitude: f32) -> Vec<i16> {
+        (0..sample_rate * ms / 1000)
+            .map(|i| {
+                let v = (i as f32 * 2.0 * PI * 1000.0 / sample_rate as f32).sin();
+                (v * amplitude * i16::MAX as f32) as i16
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_analyze_clean_audio() {
+        let health = AudioAnalyzer::analyze(&tone(100, 500, 100, 8000.0), SAMPLE_RATE).unwrap();
+
+        assert_eq!(health.clipped_runs, 0);
+        assert!(
+            (health.peak_db + 12.2).abs() < 0.1,
+            "peak {}",
+            health.peak_db
+        );
+        assert!(health.snr_db > 60.0, "snr {}", health.snr_db);
+        assert!(
+            health.dc_offset.abs() < 0.1,
+            "dc offset {}",
+            health.dc_offset
+        );
+        assert!(!health.truncated_start);
+        assert!(!health.truncated_end);
+    }
+
+    #[test]
+    fn test_analyze_clipping_and_low_level() {
+        let health = AudioAnalyzer::analyze(&tone(100, 500, 100, 40000.0), SAMPLE_RATE).unwrap();
+        assert!(health.clipped_runs > 0, "clipped sine peaks");
+
+        let health = AudioAnalyzer::analyze(&tone(100, 500, 100, 500.0), SAMPLE_RATE).unwrap();
+        assert_eq!(health.clipped_runs, 0);
+        assert!(health.peak_db < -30.0, "peak {}", health.peak_db);
+    }
+
+    #[test]
+    fn test_analyze_dc_offset() {
+        let values = tone(100, 500, 100, 8000.0)
+            .iter()
+            .map(|v| v + 2000)
+            .collect::<Vec<_>>();
+        let health = AudioAnalyzer::analyze(&values, SAMPLE_RATE).unwrap();
+
+        assert!(
+            (health.dc_offset - 6.1).abs() < 0.1,
+            "dc offset {}",
+            health.dc_offset
+        );
+        assert!(health.snr_db > 60.0, "dc offset is removed from levels");
+    }
+
+    #[test]
+    fn test_analyze_truncation() {
+        let health = AudioAnalyzer::analyze(&tone(100, 500, 0, 8000.0), SAMPLE_RATE).unwrap();
+        assert!(!health.truncated_start);
+        assert!(health.truncated_end, "speech level at end");
+
+        let health = AudioAnalyzer::analyze(&tone(0, 500, 100, 8000.0), SAMPLE_RATE).unwrap();
+        assert!(health.truncated_start, "speech level at start");
+        assert!(!health.truncated_end);
+
+        // quiet edges are no truncated speech
+        let mut values = tone(0, 600, 0, 8000.0);
+        for v in values.iter_mut().take(320) {
+            *v /= 100;
+        }
+        let health = AudioAnalyzer::analyze(&values, SAMPLE_RATE).unwrap();
+        assert!(!health.truncated_start);
+        assert!(health.truncated_end);
+    }
+
+    #[test]
+    fn test_analyze_too_short() {
+        // less than 2 edge frames at start and end
+        assert!(AudioAnalyzer::analyze(&tone(0, 30, 0, 8000.0), SAMPLE_RATE).is_none());
+        assert!(AudioAnalyzer::analyze(&tone(0, 40, 0, 8000.0), SAMPLE_RATE).is_some());
+    }
+
+    #[test]
+    fn test_bitstream_roundtrip() {
+        let values = [
+            (1, 1),
+            (5, 3),
+            (0, 2),
+            (0x2AB, 10),
+            (0xDEADBEEF, 32),
+            (3, 2),
+        ];
+
+        let mut out = BitWriter::default();
+        for &(value, bits) in &values {
+            out.write(value, bits);
+        }
+        let data = out.into_bytes();
+        // 50 bits, lsb first
+        assert_eq!(data.len(), 7);
+        assert_eq!(data[0] & 0b1111, 0b1011);
+
+        let mut input = BitReader::new(&data);
+        for &(value, bits) in &values {
+            assert_eq!(input.read(bits), Ok(value));
+        }
+        // padding of the last byte
+        assert_eq!(input.read(6), Ok(0));
+        assert!(input.read(1).is_err(), "end of bitstream");
+
+        // copies are identical
+        let mut input = BitReader::new(&data);
+        let mut out = BitWriter::default();
+        for &(value, bits) in &values {
+            assert_eq!(out.copy(&mut input, bits), Ok(value));
+        }
+        assert_eq!(out.into_bytes(), data);
+    }
+
+    #[test]
+    fn test_ilog() {
+        let values = [0, 1, 2, 3, 4, 7, 8, 255, 256, u32::MAX];
+        assert_eq!(
+            values.iter().map(|v| ilog(*v)).collect::<Vec<_>>(),
+            [0, 1, 2, 2, 3, 3, 4, 8, 9, 32]
+        );
+    }
+
+    #[test]
+    fn test_maptype1_quantvals() {
+        // greatest value for which value^dimensions <= entries
+        assert_eq!(maptype1_quantvals(81, 4), 3);
+        assert_eq!(maptype1_quantvals(80, 4), 2);
+        assert_eq!(maptype1_quantvals(256, 2), 16);
+        assert_eq!(maptype1_quantvals(255, 2), 15);
+        assert_eq!(maptype1_quantvals(1000, 3), 10);
+        assert_eq!(maptype1_quantvals(17, 1), 17);
+        assert_eq!(maptype1_quantvals(1, 8), 1);
+        assert_eq!(maptype1_quantvals(10, 0), 0);
+    }
+
+    /// riff wave container with the chunks (id, content)
+    fn riff(chunks: &[(&[u8], Vec<u8>)]) -> Vec<u8> {
+        let mut content = b"WAVE".to_vec();
+        for (id, data) in chunks {
+            content.extend_from_slice(id);
+            content.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            content.extend_from_slice(data);
+        }
+        let mut file = b"RIFF".to_vec();
+        file.extend_from_slice(&(content.len() as u32).to_le_bytes());
+        file.extend(content);
+        file
+    }
+
+    /// extended wwise vorbis fmt chunk with embedded vorb info
+    fn wem_fmt() -> Vec<u8> {
+        let mut fmt = vec![0; 0x42];
+        let mut set = |pos: usize, bytes: &[u8]| fmt[pos..pos + bytes.len()].copy_from_slice(bytes);
+        set(0x00, &0xFFFFu16.to_le_bytes());
+        set(0x02, &1u16.to_le_bytes());
+        set(0x04, &44100u32.to_le_bytes());
+        set(0x08, &16000u32.to_le_bytes());
+        // vorb: sample count, mod signal, packet offsets, blocksizes
+        set(0x18, &22050u32.to_le_bytes());
+        set(0x1C, &0x4Au32.to_le_bytes());
+        set(0x28, &0u32.to_le_bytes());
+        set(0x2C, &0x20u32.to_le_bytes());
+        set(0x40, &[8, 11]);
+        fmt
+    }
+
+    #[test]
+    fn test_wem_parse() {
+        let data = riff(&[(b"fmt ", wem_fmt()), (b"data", vec![0; 0x40])]);
+        let wem = WemFile::parse(&data).unwrap();
+
+        assert_eq!((wem.channels, wem.sample_rate), (1, 44100));
+        assert_eq!(wem.sample_count, 22050);
+        assert_eq!((wem.data_offset, wem.data_size), (12 + 8 + 0x42 + 8, 0x40));
+        assert_eq!(wem.first_audio_packet_offset, 0x20);
+        assert_eq!((wem.blocksize_0_pow, wem.blocksize_1_pow), (8, 11));
+        assert!(wem.no_granule && !wem.mod_packets);
+    }
+
+    #[test]
+    fn test_wem_parse_invalid() {
+        let error = |data: &[u8]| WemFile::parse(data).err();
+
+        let data = riff(&[(b"fmt ", wem_fmt()), (b"data", vec![0; 0x40])]);
+        assert!(error(&data[..8]).is_some(), "truncated riff header");
+
+        let mut rifx = data.clone();
+        rifx[..4].copy_from_slice(b"RIFX");
+        assert!(error(&rifx).is_some(), "big endian riff");
+
+        let mut codec = data.clone();
+        codec[20..22].copy_from_slice(&1u16.to_le_bytes());
+        assert!(error(&codec).is_some(), "pcm codec");
+
+        let data = riff(&[(b"fmt ", wem_fmt())]);
+        assert_eq!(error(&data).as_deref(), Some("missing data chunk"));
+
+        let data = riff(&[(b"data", vec![0; 0x40])]);
+        assert_eq!(error(&data).as_deref(), Some("missing fmt chunk"));
+
+        // fmt chunk cut off within the vorb info
+        let data = riff(&[(b"data", vec![0; 0x40]), (b"fmt ", wem_fmt())]);
+        assert_eq!(
+            error(&data[..data.len() - 0x20]).as_deref(),
+            Some("unexpected end of file")
+        );
+    }
+
+    #[test]
+    fn test_loudness() {
+        assert_eq!(AudioAnalyzer::loudness(&[], 48000), None);
+        assert_eq!(AudioAnalyzer::loudness(&[0; 48000], 48000), None, "silence");
+
+        // 1 kHz sine at -20 dBFS peak is -23 LUFS (rms of sine is -3 dB)
+        let values = sine(48000, 2000, 0.1);
+        let loudness = AudioAnalyzer::loudness(&values, 48000).unwrap();
+        assert!((loudness + 23.0).abs() < 0.1, "loudness {}", loudness);
+
+        let values = sine(48000, 2000, 0.2);
+        let louder = AudioAnalyzer::loudness(&values, 48000).unwrap();
+        assert!(
+            (louder - loudness - 6.02).abs() < 0.05,
+            "double amplitude {}",
+            louder
+        );
+
+        // 1s leading and trailing silence: silent blocks are gated, the 3
+        // blocks at each edge with 25%, 50% and 75% of the tone are not
+        let mut framed = vec![0; 48000];
+        framed.extend(sine(48000, 2000, 0.1));
+        framed.resize(framed.len() + 48000, 0);
+        let gated = AudioAnalyzer::loudness(&framed, 48000).unwrap();
+        let expected = loudness + 10.0 * (20.0f32 / 23.0).log10();
+        assert!((gated - expected).abs() < 0.05, "with silence {}", gated);
+    }
+
+    #[test]
+    fn test_speech_bounds() {
+        assert_eq!(AudioAnalyzer::speech_bounds(&[], SAMPLE_RATE), None);
+        assert_eq!(
+            AudioAnalyzer::speech_bounds(&[0; 16000], SAMPLE_RATE),
+            None,
+            "silence"
+        );
+
+        let values = tone(0, 500, 0, 8000.0);
+        assert_eq!(
+            AudioAnalyzer::speech_bounds(&values, SAMPLE_RATE),
+            Some((0, 8000))
+        );
+
+        // 200ms leading and 300ms trailing silence
+        let values = tone(200, 500, 300, 8000.0);
+        assert_eq!(
+            AudioAnalyzer::speech_bounds(&values, SAMPLE_RATE),
+            Some((3200, 11200))
+        );
+
+        // low level noise floor is no speech
+        let values = tone(200, 500, 300, 8000.0)
+            .iter()
+            .enumerate()
+            .map(|(i, v)| if i % 2 == 0 { v + 20 } else { v - 20 })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            AudioAnalyzer::speech_bounds(&values, SAMPLE_RATE),
+            Some((3200, 11200))
+        );
+    }
+}
@@ -12,7 +12,9 @@ use std::path::{Path, PathBuf};
 
 use logger::LevelFilter;
 
-use w3phonemetools::{ActorMapping ,ProcessingQueue};
+use w3phonemetools::{
+    ActorMapping, ConditioningSettings, ExtractionMethod, ProcessingQueue, WordQualityReport,
+};
 
 use w3phonemetools::gui;
 // ----------------------------------------------------------------------------
@@ -20,6 +22,8 @@ const VERSION: Option<&'static str> = option_env!("CARGO_PKG_VERSION");
 const NAME: &str = "w3speech phonemes extractor";
 const HELPFILE: &str = "./help-phoneme-extractor.txt";
 
+const DEFAULT_SIMILARITY_BLEND: f32 = 0.5;
+
 const LIBRARIES: &str =
     "This program uses the CMU Pocketsphinx library (https://github.com/cmusphinx/pocketsphinx),\
      \nthe eSpeak Library (http://espeak.sourceforge.net).";
@@ -30,18 +34,34 @@ enum OpMode {
     Extract,
     LogMissing,
     Generate,
+    Split,
+    Condition,
+    Adapt,
+    Learn,
+    Tune,
+    Evaluate,
+    Synthesize,
+    Bootstrap,
 }
 
 struct CliArgs {
     mode: OpMode,
     language: String,
     force_rename: bool,
+    extraction_method: Option<ExtractionMethod>,
+    realign_words: bool,
+    refine_boundaries: bool,
     input: Option<PathBuf>,
+    reference: Option<PathBuf>,
     strings_file: Option<PathBuf>,
     mappings_file: Option<PathBuf>,
     worker: Option<usize>,
     datadir: PathBuf,
     outdir: Option<PathBuf>,
+    split_actor: Option<String>,
+    split_ids: Option<(u32, u32)>,
+    conditioning: ConditioningSettings,
+    similarity_blend: f32,
     loglevel: LevelFilter,
 }
 // ----------------------------------------------------------------------------
@@ -56,8 +76,10 @@ fn setup_option() -> Options {
         "e",
         "extract",
         "non interactive (batch-mode without gui) extraction \
-         of timed phoneme information from <id>*.ogg and <id>*.wav file(s) found \
-         in DIRECTORY. results are saved as <id>.phonemes file(s) in DIRECTORY.\
+         of timed phoneme information from <id>*.ogg, <id>*.wav and <id>*.wem file(s) found \
+         in DIRECTORY. results are saved as <id>.phonemes file(s) in DIRECTORY. \
+         words with low alignment confidence of all phonemes files are listed \
+         (worst first) in phonemes.word-quality.txt in DIRECTORY. \
          requires a strings-file with matching text lines for every <id>. \
          if no --strings-file parameter is given the first found csv-file \
          (*.csv|*.strings-csv) in DIRECTORY or its parent directory is used.",
@@ -100,13 +122,192 @@ fn setup_option() -> Options {
         "DIRECTORY",
     );
 
+    // optional mode
+    opts.optopt(
+        "",
+        "split-recording",
+        "segments a long recording (e.g. a full scene) into utterances and \
+         assigns them to the best matching textlines of the strings-file. every \
+         assigned utterance is saved as <id>[<duration>]<actor><texthint>.wav \
+         clip. low confidence, unmatched and alternative takes are listed in a \
+         <recording>.split-review.csv. if no --strings-file parameter is given \
+         it is searched as in batch-mode.",
+        "FILE",
+    );
+
+    opts.optopt(
+        "",
+        "split-actor",
+        "restricts matched textlines in --split-recording mode to the actor.",
+        "ACTOR",
+    );
+
+    opts.optopt(
+        "",
+        "split-ids",
+        "restricts matched textlines in --split-recording mode to the (inclusive) \
+         id range.",
+        "FROM-TO",
+    );
+
+    // optional mode
+    opts.optopt(
+        "",
+        "condition-audio",
+        "batch conditioning of all <id>* audio files found in DIRECTORY for \
+         delivery: loudness normalization, trimming/padding of silence before \
+         and after speech, conversion to mono 44.1kHz ogg vorbis. results are \
+         saved as <id>[<duration>]<remaining filename>.ogg in the output-dir \
+         (default is DIRECTORY/conditioned). NOTE: existing phonemes files \
+         are not valid for conditioned audio as timings change.",
+        "DIRECTORY",
+    );
+
+    // optional mode
+    opts.optopt(
+        "",
+        "adapt-speakers",
+        "estimates a speaker adaptation (MLLR transform) of the pocketsphinx \
+         acoustic model for every actor with enough approved (edited and without \
+         errors) <id>.phonemes files and matching audio in DIRECTORY. transforms \
+         are saved in data/pocketsphinx/<LANGUAGE>/adaptation and used \
+         automatically for extraction of lines of the actor. requires the \
+         sphinxtrain tools sphinx_fe, bw and mllr_solve (see ADAPTATION-TOOLS in \
+         the pocketsphinx cfg). if no --strings-file parameter is given it is \
+         searched as in batch-mode.",
+        "DIRECTORY",
+    );
+
+    // optional mode
+    opts.optopt(
+        "",
+        "learn-similarity",
+        "learns phoneme similarity scores from all edited (manually corrected) \
+         <id>.phonemes files in DIRECTORY by aligning their stored audio \
+         hypothesis with the corrected phonemes. the learned scores are blended \
+         with the current <LANGUAGE>.phoneme.similarity.csv and saved as \
+         <LANGUAGE>.phoneme.similarity.learned.csv together with a report of all \
+         changed scores (<LANGUAGE>.phoneme.similarity.learned-diff.csv) in the \
+         output-dir.",
+        "DIRECTORY",
+    );
+
+    // optional mode
+    opts.optopt(
+        "",
+        "tune-scoring",
+        "tunes the alignment scoring profile (gap penalties, merge/split biases \
+         and similarity scaling) to maximise the accuracy of phoneme boundaries \
+         of all edited (manually corrected) <id>.phonemes files in DIRECTORY. \
+         the recognized audio phonemes stored in the files are realigned offline \
+         and every 5th line is held out for evaluation. the best profile is saved \
+         as <LANGUAGE>.phoneme.scoring.tuned.cfg together with a metrics report \
+         (<LANGUAGE>.phoneme.scoring.tuned-report.csv) in the output-dir.",
+        "DIRECTORY",
+    );
+
+    // optional mode
+    opts.optopt(
+        "",
+        "evaluate",
+        "compares all <id>.phonemes files in DIRECTORY with the reference \
+         alignments of the same ids in the --reference directory and reports \
+         mean/median boundary errors, the share of boundaries within 20/50 ms and \
+         the phoneme insertion/deletion rates in total, per actor and per \
+         phoneme. the report is saved as phonemes.evaluation.txt, .csv and .json \
+         in the output-dir (default is DIRECTORY).",
+        "DIRECTORY",
+    );
+
+    opts.optopt(
+        "",
+        "reference",
+        "directory with reference alignments for --evaluate: edited <id>.phonemes \
+         files, praat <id>.TextGrid files (long text format, tier named \"phones\") \
+         or <id>.alignment.json files with a list of {\"phoneme\", \"start\", \"end\"} \
+         objects (timings in ms).",
+        "DIRECTORY",
+    );
+
+    // optional mode
+    opts.optflag(
+        "",
+        "synthesize-corpus",
+        "generates a synthetic benchmark corpus with eSpeak: audio (<id>.wav) \
+         for every line in strings-file together with the exact phoneme timings \
+         of the synthesizer as reference alignment (<id>.alignment.json). files \
+         are saved in the output-dir (default is data/<LANGUAGE>.synthetic). the \
+         corpus can be extracted with --extract and checked with --evaluate \
+         (using the same directory as --reference). requires --strings-file.",
+    );
+
+    // optional mode
+    opts.optflag(
+        "",
+        "bootstrap-language",
+        "generates starter data files for a new language from the eSpeak \
+         translations of all lines in strings-file and the PHONEMES of the \
+         <LANGUAGE>.pocketsphinx.cfg in the data directory: a similarity matrix \
+         seeded from articulatory features, a pocketsphinx cfg with generated \
+         character mappings, empty custom dictionaries and a coverage report \
+         (<LANGUAGE>.bootstrap-coverage.csv). files are saved in the output-dir \
+         (default is data/<LANGUAGE>.bootstrap). requires --strings-file and \
+         --language.",
+    );
+
+    opts.optopt(
+        "",
+        "similarity-blend",
+        &format!(
+            "weight of learned scores in --learn-similarity mode (0: keep current \
+             scores, 1: learned scores only). scores of pairs never observed for a \
+             frequent text phoneme are pulled toward -1, even if they were \
+             hand-tuned. default is {DEFAULT_SIMILARITY_BLEND}."
+        ),
+        "WEIGHT",
+    );
+
+    opts.optopt(
+        "",
+        "target-loudness",
+        &format!(
+            "target integrated loudness for --condition-audio. default is {}.",
+            w3phonemetools::DEFAULT_TARGET_LOUDNESS
+        ),
+        "LUFS",
+    );
+
+    opts.optopt(
+        "",
+        "head-silence",
+        &format!(
+            "silence before start of speech for --condition-audio. default is {}.",
+            w3phonemetools::DEFAULT_HEAD_MS
+        ),
+        "MILLISECONDS",
+    );
+
+    opts.optopt(
+        "",
+        "tail-silence",
+        &format!(
+            "silence after end of speech for --condition-audio. default is {}.",
+            w3phonemetools::DEFAULT_TAIL_MS
+        ),
+        "MILLISECONDS",
+    );
+
     // output directory
     opts.optopt(
         "o",
         "output-dir",
-        "defines the output directory for generated phonemes. valid only in \
-         combination with --generate-from-text-only. default is directory \
-         of the strings csv files.",
+        "defines the output directory for generated phonemes, split clips or \
+         conditioned audio. valid only in combination with \
+         --generate-from-text-only, --split-recording or --condition-audio. \
+         default is directory of the strings csv files (respectively the \
+         directory of the recording). also valid for --learn-similarity, \
+         --tune-scoring (default is data directory), --evaluate, \
+         --synthesize-corpus and --bootstrap-language.",
         "DIRECTORY",
     );
 
@@ -129,6 +330,38 @@ fn setup_option() -> Options {
          actor will only be added if it can be extracted from the strings-csv.",
     );
 
+    // extraction method
+    opts.optopt(
+        "",
+        "extraction-method",
+        "overrides the language specific method for extraction of phoneme timings \
+         in --extract mode: \"allphone\" (grammar constrained phoneme recognition), \
+         \"forced\" (forced alignment of the dictionary transcription with \
+         automatic fallback to allphone) or \"external\" (external aligner defined \
+         in the pocketsphinx cfg). default is defined in the language specific \
+         pocketsphinx cfg.",
+        "METHOD",
+    );
+
+    // second pass for flagged words
+    opts.optflag(
+        "",
+        "realign-words",
+        "re-aligns every word flagged by the quality assessment (gaps, inactive \
+         segments, low scores) in a second pass within a short audio window around \
+         the word in --extract mode. refined timings are kept only if they reduce \
+         the warnings of the word.",
+    );
+
+    // boundary refinement
+    opts.optflag(
+        "",
+        "no-boundary-refinement",
+        "keeps the phoneme boundaries of the recognizer (10ms frames) in --extract \
+         mode instead of moving them to the nearest strong spectral or energy \
+         change of the full resolution audio.",
+    );
+
     // language
     opts.optopt(
         "l",
@@ -201,10 +434,28 @@ fn parse_arguments(found: Matches) -> Result<CliArgs, String> {
     let param_extract_dir = found.opt_str("e");
     let param_generated_only = found.opt_present("generate-from-text-only");
     let param_force_rename = found.opt_present("force-rename");
+    let param_extraction_method = found.opt_str("extraction-method");
+    let param_realign_words = found.opt_present("realign-words");
+    let param_no_refinement = found.opt_present("no-boundary-refinement");
     let param_log_missing = found.opt_present("log-missing-audio");
     let param_audio_dir = found.opt_str("a");
     let param_worker = found.opt_str("w");
     let param_mappings = found.opt_str("actor-mappings");
+    let param_split_recording = found.opt_str("split-recording");
+    let param_split_actor = found.opt_str("split-actor");
+    let param_split_ids = found.opt_str("split-ids");
+    let param_condition_dir = found.opt_str("condition-audio");
+    let param_adapt_dir = found.opt_str("adapt-speakers");
+    let param_learn_dir = found.opt_str("learn-similarity");
+    let param_tune_dir = found.opt_str("tune-scoring");
+    let param_evaluate_dir = found.opt_str("evaluate");
+    let param_reference_dir = found.opt_str("reference");
+    let param_similarity_blend = found.opt_str("similarity-blend");
+    let param_synthesize = found.opt_present("synthesize-corpus");
+    let param_bootstrap = found.opt_present("bootstrap-language");
+    let param_target_loudness = found.opt_str("target-loudness");
+    let param_head_silence = found.opt_str("head-silence");
+    let param_tail_silence = found.opt_str("tail-silence");
 
     // dirs
     let param_data_dir = found.opt_str("d");
@@ -224,26 +475,108 @@ fn parse_arguments(found: Matches) -> Result<CliArgs, String> {
     if param_log_missing {
         modes += 1;
     }
+    if param_split_recording.is_some() {
+        modes += 1;
+    }
+    if param_condition_dir.is_some() {
+        modes += 1;
+    }
+    if param_adapt_dir.is_some() {
+        modes += 1;
+    }
+    if param_learn_dir.is_some() {
+        modes += 1;
+    }
+    if param_tune_dir.is_some() {
+        modes += 1;
+    }
+    if param_evaluate_dir.is_some() {
+        modes += 1;
+    }
+    if param_synthesize {
+        modes += 1;
+    }
+    if param_bootstrap {
+        modes += 1;
+    }
 
     if modes > 1 {
         return Err("invalid combination of options: choose either \
-                    --extract, --generate-from-text-only or --log-missing-audio"
+                    --extract, --generate-from-text-only, --log-missing-audio, \
+                    --split-recording, --condition-audio, --adapt-speakers, \
+                    --learn-similarity, --tune-scoring, --evaluate, \
+                    --synthesize-corpus or --bootstrap-language"
             .to_string());
     }
-    if param_audio_dir.is_some() && (param_generated_only || param_extract_dir.is_some()) {
+    if param_audio_dir.is_some()
+        && (param_generated_only
+            || param_extract_dir.is_some()
+            || param_split_recording.is_some()
+            || param_condition_dir.is_some()
+            || param_adapt_dir.is_some()
+            || param_learn_dir.is_some()
+            || param_tune_dir.is_some()
+            || param_evaluate_dir.is_some()
+            || param_synthesize
+            || param_bootstrap)
+    {
         return Err("invalid combination of options: audio-dir option is only \
                     valid for interactive gui mode or --log-missing-audio \
-                    (not in --extract or --generate-from-text-only batch mode"
+                    (not in --extract, --generate-from-text-only, \
+                    --split-recording, --condition-audio, --adapt-speakers, \
+                    --learn-similarity, --tune-scoring, --evaluate, \
+                    --synthesize-corpus or --bootstrap-language batch mode"
             .to_string());
     }
-    if param_out_dir.is_some() && !param_generated_only {
+    if param_out_dir.is_some()
+        && !param_generated_only
+        && param_split_recording.is_none()
+        && param_condition_dir.is_none()
+        && param_learn_dir.is_none()
+        && param_tune_dir.is_none()
+        && param_evaluate_dir.is_none()
+        && !param_synthesize
+        && !param_bootstrap
+    {
         return Err("invalid combination of options: output-dir option is only \
-                    valid for --generate-from-text-only batch mode"
+                    valid for --generate-from-text-only, --split-recording, \
+                    --condition-audio, --learn-similarity, --tune-scoring, \
+                    --evaluate, --synthesize-corpus or --bootstrap-language batch \
+                    mode"
             .to_string());
     }
+    if param_evaluate_dir.is_some() != param_reference_dir.is_some() {
+        return Err("--evaluate requires a --reference parameter (and vice versa)".to_string());
+    }
+    if param_similarity_blend.is_some() && param_learn_dir.is_none() {
+        return Err("--similarity-blend is only valid in --learn-similarity mode".to_string());
+    }
+    if (param_target_loudness.is_some()
+        || param_head_silence.is_some()
+        || param_tail_silence.is_some())
+        && param_condition_dir.is_none()
+    {
+        return Err(
+            "loudness and silence options are only valid in --condition-audio mode".to_string(),
+        );
+    }
+    if (param_split_actor.is_some() || param_split_ids.is_some()) && param_split_recording.is_none()
+    {
+        return Err(
+            "--split-actor and --split-ids are only valid in --split-recording mode".to_string(),
+        );
+    }
     if param_generated_only && param_strings_file.is_none() {
         return Err("--generate-from-text-only requires a --strings-file parameter".to_string());
     }
+    if param_synthesize && param_strings_file.is_none() {
+        return Err("--synthesize-corpus requires a --strings-file parameter".to_string());
+    }
+    if param_bootstrap && (param_strings_file.is_none() || !found.opt_present("l")) {
+        return Err(
+            "--bootstrap-language requires a --strings-file and a --language parameter".to_string(),
+        );
+    }
 
     // -- set options or defaults
     let (extract_dir, extract_dir_lang) = match param_extract_dir {
@@ -256,6 +589,59 @@ fn parse_arguments(found: Matches) -> Result<CliArgs, String> {
         None => (None, None),
     };
 
+    let (adapt_dir, adapt_dir_lang) = match param_adapt_dir {
+        Some(dir) => {
+            let audio_dir = check_dir(dir.as_str(), "audio directory")?;
+            debug!("audio directory provided. extracting language prefix...");
+            let lang = w3phonemetools::extract_language_info(&audio_dir);
+            (Some(audio_dir), lang)
+        }
+        None => (None, None),
+    };
+
+    let (learn_dir, learn_dir_lang) = match param_learn_dir {
+        Some(dir) => {
+            let phonemes_dir = check_dir(dir.as_str(), "phonemes directory")?;
+            debug!("phonemes directory provided. extracting language prefix...");
+            let lang = w3phonemetools::extract_language_info(&phonemes_dir);
+            (Some(phonemes_dir), lang)
+        }
+        None => (None, None),
+    };
+
+    let (tune_dir, tune_dir_lang) = match param_tune_dir {
+        Some(dir) => {
+            let phonemes_dir = check_dir(dir.as_str(), "phonemes directory")?;
+            debug!("phonemes directory provided. extracting language prefix...");
+            let lang = w3phonemetools::extract_language_info(&phonemes_dir);
+            (Some(phonemes_dir), lang)
+        }
+        None => (None, None),
+    };
+
+    let evaluate_dir = match param_evaluate_dir {
+        Some(dir) => Some(check_dir(dir.as_str(), "phonemes directory")?),
+        None => None,
+    };
+
+    let reference_dir = match param_reference_dir {
+        Some(dir) => Some(check_dir(dir.as_str(), "reference directory")?),
+        None => None,
+    };
+
+    let similarity_blend = match param_similarity_blend {
+        Some(value) => {
+            let blend = value
+                .parse::<f32>()
+                .map_err(|e| format!("could not parse similarity-blend parameter: {}", e))?;
+            if !(0.0..=1.0).contains(&blend) {
+                return Err("similarity-blend must be within [0;1]".to_string());
+            }
+            blend
+        }
+        None => DEFAULT_SIMILARITY_BLEND,
+    };
+
     let (audio_dir, audio_dir_lang) = match param_audio_dir {
         Some(audio_dir) => {
             let audio_dir = check_dir(audio_dir.as_str(), "audio directory")?;
@@ -266,6 +652,48 @@ fn parse_arguments(found: Matches) -> Result<CliArgs, String> {
         None => (None, None),
     };
 
+    let split_recording = match param_split_recording {
+        Some(ref file) => Some(check_file(file, "recording")?),
+        None => None,
+    };
+
+    let split_ids = match param_split_ids {
+        Some(ref range) => {
+            let parse_id = |id: &str| {
+                id.trim()
+                    .parse::<u32>()
+                    .map_err(|e| format!("could not parse split-ids parameter: {}", e))
+            };
+            let (from, to) = range
+                .split_once('-')
+                .ok_or_else(|| "split-ids parameter must be a range FROM-TO".to_string())?;
+            Some((parse_id(from)?, parse_id(to)?))
+        }
+        None => None,
+    };
+
+    let condition_dir = match param_condition_dir {
+        Some(dir) => Some(check_dir(dir.as_str(), "audio directory")?),
+        None => None,
+    };
+
+    let mut conditioning = ConditioningSettings::default();
+    if let Some(value) = param_target_loudness {
+        conditioning.target_loudness = value
+            .parse::<f32>()
+            .map_err(|e| format!("could not parse target-loudness parameter: {}", e))?;
+    }
+    if let Some(value) = param_head_silence {
+        conditioning.head_ms = value
+            .parse::<u32>()
+            .map_err(|e| format!("could not parse head-silence parameter: {}", e))?;
+    }
+    if let Some(value) = param_tail_silence {
+        conditioning.tail_ms = value
+            .parse::<u32>()
+            .map_err(|e| format!("could not parse tail-silence parameter: {}", e))?;
+    }
+
     let outdir = match param_out_dir {
         Some(dir) => Some(check_dir(dir.as_str(), "output directory")?),
         None => None,
@@ -284,7 +712,13 @@ fn parse_arguments(found: Matches) -> Result<CliArgs, String> {
     let language = match found.opt_str("l") {
         Some(lang_code) => lang_code.to_lowercase(),
         None => {
-            match extract_dir_lang.or(audio_dir_lang).take() {
+            match extract_dir_lang
+                .or(adapt_dir_lang)
+                .or(learn_dir_lang)
+                .or(tune_dir_lang)
+                .or(audio_dir_lang)
+                .take()
+            {
                 Some(lang) => {
                     debug!("setting language to detected: {lang}");
                     lang
@@ -325,6 +759,22 @@ fn parse_arguments(found: Matches) -> Result<CliArgs, String> {
         (OpMode::Extract, extract_dir)
     } else if param_log_missing {
         (OpMode::LogMissing, audio_dir)
+    } else if split_recording.is_some() {
+        (OpMode::Split, split_recording)
+    } else if condition_dir.is_some() {
+        (OpMode::Condition, condition_dir)
+    } else if adapt_dir.is_some() {
+        (OpMode::Adapt, adapt_dir)
+    } else if learn_dir.is_some() {
+        (OpMode::Learn, learn_dir)
+    } else if tune_dir.is_some() {
+        (OpMode::Tune, tune_dir)
+    } else if evaluate_dir.is_some() {
+        (OpMode::Evaluate, evaluate_dir)
+    } else if param_synthesize {
+        (OpMode::Synthesize, None)
+    } else if param_bootstrap {
+        (OpMode::Bootstrap, None)
     } else {
         (OpMode::Interactive, audio_dir)
     };
@@ -337,16 +787,38 @@ fn parse_arguments(found: Matches) -> Result<CliArgs, String> {
         return Err("--force-rename only valid in --extract mode".to_string());
     };
 
+    if param_extraction_method.is_some() && mode != OpMode::Extract {
+        return Err("--extraction-method only valid in --extract mode".to_string());
+    };
+    if param_realign_words && mode != OpMode::Extract {
+        return Err("--realign-words only valid in --extract mode".to_string());
+    };
+    if param_no_refinement && mode != OpMode::Extract {
+        return Err("--no-boundary-refinement only valid in --extract mode".to_string());
+    };
+    let extraction_method = match param_extraction_method {
+        Some(method) => Some(method.parse::<ExtractionMethod>()?),
+        None => None,
+    };
+
     Ok(CliArgs {
         mode,
         language,
         force_rename: param_force_rename,
+        extraction_method,
+        realign_words: param_realign_words,
+        refine_boundaries: !param_no_refinement,
         input,
+        reference: reference_dir,
         strings_file,
         mappings_file,
         worker,
         datadir,
         outdir,
+        split_actor: param_split_actor,
+        split_ids,
+        conditioning,
+        similarity_blend,
         loglevel,
     })
 }
@@ -356,6 +828,7 @@ fn print_usage(program: &str, opts: Options) {
     print!("{}", opts.usage(&brief));
 }
 // ----------------------------------------------------------------------------
+#[allow(clippy::too_many_arguments)]
 fn extract_phonemes(
     inputdir: PathBuf,
     stringsfile: Option<PathBuf>,
@@ -363,6 +836,9 @@ fn extract_phonemes(
     language: String,
     actor_mappings_file: Option<&Path>,
     force_rename: bool,
+    extraction_method: Option<ExtractionMethod>,
+    realign_words: bool,
+    refine_boundaries: bool,
     loglevel: LevelFilter,
 ) -> Result<(), String> {
     info!(
@@ -376,13 +852,31 @@ fn extract_phonemes(
     let stringsfile =
         stringsfile.map_or_else(|| w3phonemetools::search_strings_file(&inputdir), Ok)?;
 
-    let mut processor =
-        w3phonemetools::init_phoneme_extraction(&language, &stringsfile, &datadir, loglevel)?;
+    if let Some(method) = extraction_method {
+        info!("> using {method} phoneme extraction method.");
+    }
+
+    let mut processor = w3phonemetools::init_phoneme_extraction(
+        &language,
+        &stringsfile,
+        &datadir,
+        extraction_method,
+        loglevel,
+    )?;
+
+    if realign_words {
+        info!("> flagged words will be re-aligned in a second pass.");
+        processor.set_word_realignment(true);
+    }
+    if !refine_boundaries {
+        info!("> recognized phoneme boundaries will not be refined.");
+        processor.set_boundary_refinement(false);
+    }
 
     // init mapping actor
     let mut actor_mappings = ActorMapping::load(&datadir, actor_mappings_file)?;
 
-    let mut tasks = ProcessingQueue::new_from_directory(inputdir, force_rename)?;
+    let mut tasks = ProcessingQueue::new_from_directory(&inputdir, force_rename)?;
 
     while let Some(task) = tasks.take_waiting() {
         info!("id {:>10}: file: {}", task.lineid(), task.audiofile());
@@ -402,6 +896,16 @@ fn extract_phonemes(
     //     Err(format!("failed for #{} strings. see log for details.", tasks.failed()))
     // }
 
+    let mut report = WordQualityReport::default();
+    report.add_queue(&tasks);
+    for line in report.summary() {
+        info!("{line}");
+    }
+    // no csv extension: would be found as strings-file on next run
+    let report_file = inputdir.join("phonemes.word-quality.txt");
+    report.store(&report_file)?;
+    info!("stored low confidence words in {}", report_file.display());
+
     if let Some(actor_mappings_file) = actor_mappings_file {
         store_actor_mappings(actor_mappings_file, &mut actor_mappings)?;
     }
@@ -524,6 +1028,364 @@ fn generate_phonemes(
     Ok(())
 }
 // ----------------------------------------------------------------------------
+#[allow(clippy::too_many_arguments)]
+fn split_recording(
+    recording: PathBuf,
+    stringsfile: Option<PathBuf>,
+    datadir: PathBuf,
+    language: String,
+    actor: Option<String>,
+    ids: Option<(u32, u32)>,
+    outputdir: Option<PathBuf>,
+    loglevel: LevelFilter,
+) -> Result<(), String> {
+    info!("SPLITTING RECORDING {}", recording.display());
+
+    let mut recording_dir = recording.clone();
+    recording_dir.pop();
+
+    let stringsfile =
+        stringsfile.map_or_else(|| w3phonemetools::search_strings_file(&recording_dir), Ok)?;
+    let outputdir = outputdir.unwrap_or(recording_dir);
+
+    let mut processor =
+        w3phonemetools::init_phoneme_extraction(&language, &stringsfile, &datadir, None, loglevel)?;
+
+    let clips = processor.split_recording(&recording, actor.as_deref(), ids, &outputdir)?;
+
+    info!("finished splitting recording into #{clips} clips.");
+    Ok(())
+}
+// ----------------------------------------------------------------------------
+fn condition_audio(
+    inputdir: PathBuf,
+    outputdir: Option<PathBuf>,
+    datadir: PathBuf,
+    settings: ConditioningSettings,
+) -> Result<(), String> {
+    info!(
+        "CONDITIONING AUDIO: SCANNING {} for audio",
+        inputdir.display()
+    );
+    info!(
+        "> target loudness {} LUFS, head {}ms, tail {}ms",
+        settings.target_loudness, settings.head_ms, settings.tail_ms
+    );
+
+    let outputdir = outputdir.unwrap_or_else(|| inputdir.join("conditioned"));
+    if outputdir == inputdir {
+        return Err("output-dir for conditioned audio must differ from input \
+                    directory (would create duplicate ids)"
+            .to_string());
+    }
+    std::fs::create_dir_all(&outputdir).map_err(|e| {
+        format!(
+            "failed to create output directory {}: {}",
+            outputdir.display(),
+            e
+        )
+    })?;
+
+    let conditioner = w3phonemetools::AudioConditioner::new(settings, outputdir, datadir);
+    let (conditioned, failed) = conditioner.condition_all(inputdir)?;
+
+    info!("finished conditioning of #{conditioned} audio files.");
+    if failed > 0 {
+        warn!("failed for #{failed} audio files. see log for details.");
+    }
+    Ok(())
+}
+// ----------------------------------------------------------------------------
+fn adapt_speakers(
+    inputdir: PathBuf,
+    stringsfile: Option<PathBuf>,
+    datadir: PathBuf,
+    language: String,
+    loglevel: LevelFilter,
+) -> Result<(), String> {
+    info!(
+        "ADAPTING SPEAKERS: SCANNING {} for approved phonemes",
+        inputdir.display()
+    );
+
+    let stringsfile =
+        stringsfile.map_or_else(|| w3phonemetools::search_strings_file(&inputdir), Ok)?;
+
+    let mut processor =
+        w3phonemetools::init_phoneme_extraction(&language, &stringsfile, &datadir, None, loglevel)?;
+
+    let (adapted, failed) = processor.adapt_speakers(inputdir)?;
+
+    info!("finished adaptation for #{adapted} actors.");
+    if failed > 0 {
+        warn!("failed for #{failed} actors. see log for details.");
+    }
+    Ok(())
+}
+// ----------------------------------------------------------------------------
+fn learn_similarity(
+    inputdir: PathBuf,
+    datadir: PathBuf,
+    language: String,
+    blend: f32,
+    outputdir: Option<PathBuf>,
+) -> Result<(), String> {
+    use w3phonemetools::{load_similarity_matrix, SimilarityTrainer};
+
+    info!(
+        "LEARNING PHONEME SIMILARITY: SCANNING {} for edited phonemes",
+        inputdir.display()
+    );
+
+    let similarity_file = datadir.join(format!("{language}.phoneme.similarity.csv"));
+    let similarity_matrix = load_similarity_matrix(&datadir, &language)?;
+
+    let mut trainer = SimilarityTrainer::new(similarity_matrix);
+    let (used, skipped) = trainer.add_tracks(inputdir)?;
+
+    info!(
+        "collected #{} phoneme pairs from #{used} edited phonemes files.",
+        trainer.pairs()
+    );
+    if skipped > 0 {
+        warn!("skipped #{skipped} phonemes files. see log for details.");
+    }
+    if used == 0 {
+        return Err("no edited phonemes files found.".to_string());
+    }
+
+    let (learned, changes) = trainer.learn(blend);
+
+    let outputdir = outputdir.unwrap_or(datadir);
+    let learned_file = outputdir.join(format!("{language}.phoneme.similarity.learned.csv"));
+    let report_file = outputdir.join(format!("{language}.phoneme.similarity.learned-diff.csv"));
+
+    learned.store(
+        &learned_file,
+        &[
+            format!("similarity matrix learned from #{used} edited phonemes files"),
+            format!(
+                "based on {} (blend weight {blend})",
+                similarity_file.display()
+            ),
+            String::from("first row Arpabet phonemes"),
+            String::from("first col IPA phonemes"),
+        ],
+    )?;
+    SimilarityTrainer::store_report(&changes, &report_file)?;
+
+    info!(
+        "stored learned similarity matrix with #{} changed scores in {}",
+        changes.len(),
+        learned_file.display()
+    );
+    info!(
+        "stored report of changed scores in {}",
+        report_file.display()
+    );
+    Ok(())
+}
+// ----------------------------------------------------------------------------
+fn tune_scoring(
+    inputdir: PathBuf,
+    datadir: PathBuf,
+    language: String,
+    outputdir: Option<PathBuf>,
+) -> Result<(), String> {
+    use w3phonemetools::{ScoringTuner, BOUNDARY_TOLERANCE_MS};
+
+    info!(
+        "TUNING ALIGNMENT SCORING: SCANNING {} for edited phonemes",
+        inputdir.display()
+    );
+
+    let mut tuner = w3phonemetools::init_scoring_tuner(&language, &datadir)?;
+    let (used, skipped) = tuner.add_tracks(inputdir)?;
+
+    let (training, heldout) = tuner.lines();
+    info!(
+        "collected #{training} training and #{heldout} held-out lines from #{used} edited \
+         phonemes files."
+    );
+    if skipped > 0 {
+        warn!("skipped #{skipped} phonemes files. see log for details.");
+    }
+    if used == 0 {
+        return Err("no edited phonemes files found.".to_string());
+    }
+
+    let result = tuner.tune()?;
+
+    let outputdir = outputdir.unwrap_or(datadir);
+    let tuned_file = outputdir.join(format!("{language}.phoneme.scoring.tuned.cfg"));
+    let report_file = outputdir.join(format!("{language}.phoneme.scoring.tuned-report.csv"));
+
+    let (initial, tuned) = &result.heldout;
+    result.tuned.store(
+        &tuned_file,
+        &[
+            format!("alignment scoring profile tuned on #{training} edited phonemes files"),
+            format!(
+                "held-out boundary accuracy (tolerance {BOUNDARY_TOLERANCE_MS} ms): \
+                 {:.1}% -> {:.1}%",
+                initial.accuracy() * 100.0,
+                tuned.accuracy() * 100.0
+            ),
+        ],
+    )?;
+    ScoringTuner::store_report(&result, &report_file)?;
+
+    info!(
+        "held-out boundary accuracy: {:.1}% -> {:.1}% (mean error {:.1} ms -> {:.1} ms)",
+        initial.accuracy() * 100.0,
+        tuned.accuracy() * 100.0,
+        initial.mean_error(),
+        tuned.mean_error()
+    );
+    info!("stored tuned scoring profile in {}", tuned_file.display());
+    info!("stored metrics report in {}", report_file.display());
+    Ok(())
+}
+// ----------------------------------------------------------------------------
+fn evaluate(
+    inputdir: PathBuf,
+    referencedir: PathBuf,
+    outputdir: Option<PathBuf>,
+) -> Result<(), String> {
+    use w3phonemetools::Evaluation;
+
+    info!(
+        "EVALUATING PHONEMES: COMPARING {} with reference alignments in {}",
+        inputdir.display(),
+        referencedir.display()
+    );
+
+    let outputdir = outputdir.unwrap_or_else(|| inputdir.clone());
+
+    let mut evaluation = Evaluation::default();
+    let (evaluated, skipped) = evaluation.add_directories(inputdir, referencedir)?;
+
+    if skipped > 0 {
+        warn!("skipped #{skipped} lines. see log for details.");
+    }
+    if evaluated == 0 {
+        return Err("no phonemes files with reference alignment found.".to_string());
+    }
+
+    for line in evaluation.summary() {
+        info!("{line}");
+    }
+
+    let text_file = outputdir.join("phonemes.evaluation.txt");
+    let csv_file = outputdir.join("phonemes.evaluation.csv");
+    let json_file = outputdir.join("phonemes.evaluation.json");
+
+    evaluation.store_text(&text_file)?;
+    evaluation.store_csv(&csv_file)?;
+    evaluation.store_json(&json_file)?;
+
+    info!(
+        "stored evaluation report of #{evaluated} lines in {}, {} and {}",
+        text_file.display(),
+        csv_file.display(),
+        json_file.display()
+    );
+    Ok(())
+}
+// ----------------------------------------------------------------------------
+fn synthesize_corpus(
+    stringsfile: PathBuf,
+    datadir: PathBuf,
+    language: String,
+    outputdir: Option<PathBuf>,
+) -> Result<(), String> {
+    info!(
+        "SYNTHESIZING CORPUS from strings file {}",
+        stringsfile.display()
+    );
+
+    let outputdir = outputdir.unwrap_or_else(|| datadir.join(format!("{language}.synthetic")));
+    std::fs::create_dir_all(&outputdir).map_err(|e| {
+        format!(
+            "failed to create output directory {}: {}",
+            outputdir.display(),
+            e
+        )
+    })?;
+
+    let (corpus, strings) =
+        w3phonemetools::init_corpus_synthesis(&language, &stringsfile, &datadir, &outputdir)?;
+
+    let mut generated = 0;
+    let mut failed = 0;
+    for (id, (text, _actor)) in strings.get_all_lines().iter() {
+        match corpus.generate(*id, text) {
+            Ok(phonemes) => {
+                debug!("id {id:>10}: synthesized #{phonemes} phonemes");
+                generated += 1;
+            }
+            Err(why) => {
+                error!("{why} skipping id [{id:>10}]...");
+                failed += 1;
+            }
+        }
+    }
+    info!(
+        "finished synthesis of #{generated} lines in {}.",
+        outputdir.display()
+    );
+    if failed > 0 {
+        warn!("failed for #{failed} strings. see log for details.");
+    }
+    Ok(())
+}
+// ----------------------------------------------------------------------------
+fn bootstrap_language(
+    stringsfile: PathBuf,
+    datadir: PathBuf,
+    language: String,
+    outputdir: Option<PathBuf>,
+) -> Result<(), String> {
+    info!(
+        "BOOTSTRAPPING LANGUAGE {language} from strings file {}",
+        stringsfile.display()
+    );
+
+    let outputdir = outputdir.unwrap_or_else(|| datadir.join(format!("{language}.bootstrap")));
+    let (bootstrap, strings) =
+        w3phonemetools::init_language_bootstrap(&language, &stringsfile, &datadir)?;
+
+    let summary = bootstrap.generate(strings.as_ref(), &outputdir)?;
+
+    info!(
+        "finished bootstrap of #{} text phonemes and #{} audio phonemes from #{} lines.",
+        summary.text_phonemes, summary.audio_phonemes, summary.lines.0
+    );
+    if summary.lines.1 > 0 {
+        warn!(
+            "translation failed for #{} lines. see log for details.",
+            summary.lines.1
+        );
+    }
+    if !summary.unmatched.is_empty() {
+        warn!(
+            "no similar audio phoneme found for text phonemes: {}",
+            summary.unmatched.join(" ")
+        );
+    }
+    if !summary.unmapped_characters.is_empty() {
+        warn!(
+            "no mapping generated for characters: {}",
+            summary.unmapped_characters.iter().collect::<String>()
+        );
+    }
+    for file in &summary.files {
+        info!("> {}", file.display());
+    }
+    info!("review all generated files before copying them to the data directory.");
+    Ok(())
+}
+// ----------------------------------------------------------------------------
 fn store_actor_mappings(path: &Path, mappings: &mut ActorMapping) -> Result<(), String> {
     info!(
         "updating actor mapping based on processed lines and extracted actor names in: {}",
@@ -601,6 +1463,9 @@ fn start_main() -> Result<(), i32> {
                     args.language,
                     args.mappings_file.as_deref(),
                     args.force_rename,
+                    args.extraction_method,
+                    args.realign_words,
+                    args.refine_boundaries,
                     args.loglevel,
                 ),
                 OpMode::LogMissing => log_missing_audio(
@@ -615,6 +1480,59 @@ fn start_main() -> Result<(), i32> {
                     args.mappings_file.as_deref(),
                     args.outdir,
                 ),
+                OpMode::Split => split_recording(
+                    args.input.expect("recording missing"),
+                    args.strings_file,
+                    args.datadir,
+                    args.language,
+                    args.split_actor,
+                    args.split_ids,
+                    args.outdir,
+                    args.loglevel,
+                ),
+                OpMode::Condition => condition_audio(
+                    args.input.expect("audio dir missing"),
+                    args.outdir,
+                    args.datadir,
+                    args.conditioning,
+                ),
+                OpMode::Adapt => adapt_speakers(
+                    args.input.expect("audio dir missing"),
+                    args.strings_file,
+                    args.datadir,
+                    args.language,
+                    args.loglevel,
+                ),
+                OpMode::Learn => learn_similarity(
+                    args.input.expect("phonemes dir missing"),
+                    args.datadir,
+                    args.language,
+                    args.similarity_blend,
+                    args.outdir,
+                ),
+                OpMode::Tune => tune_scoring(
+                    args.input.expect("phonemes dir missing"),
+                    args.datadir,
+                    args.language,
+                    args.outdir,
+                ),
+                OpMode::Evaluate => evaluate(
+                    args.input.expect("phonemes dir missing"),
+                    args.reference.expect("reference dir missing"),
+                    args.outdir,
+                ),
+                OpMode::Synthesize => synthesize_corpus(
+                    args.strings_file.expect("strings-file missing"),
+                    args.datadir,
+                    args.language,
+                    args.outdir,
+                ),
+                OpMode::Bootstrap => bootstrap_language(
+                    args.strings_file.expect("strings-file missing"),
+                    args.datadir,
+                    args.language,
+                    args.outdir,
+                ),
                 OpMode::Interactive => interactive_mode(
                     args.input,
                     args.strings_file,
@@ -0,0 +1,1151 @@
+//
+// starter data files generation for new languages
+//
+
+// ----------------------------------------------------------------------------
+// external interface
+// ----------------------------------------------------------------------------
+/// generates starter files (similarity matrix and character mapping) for a new
+/// language from the eSpeak translations of all lines and the phoneme set of the
+/// pocketsphinx model. the custom dictionaries are created empty.
+pub struct LanguageBootstrap {
+    language: String,
+    translator: TextPhonemeConverter,
+    /// pocketsphinx language config defining the audio phoneme set
+    cfg_file: PathBuf,
+    audio_phonemes: Vec<String>,
+}
+// ----------------------------------------------------------------------------
+pub struct BootstrapSummary {
+    /// number of translated and failed lines
+    pub lines: (usize, usize),
+    /// number of different eSpeak (text) phonemes
+    pub text_phonemes: usize,
+    /// number of pocketsphinx (audio) phonemes
+    pub audio_phonemes: usize,
+    /// text phonemes without any similar audio phoneme
+    pub unmatched: Vec<String>,
+    /// characters without generated mapping
+    pub unmapped_characters: Vec<char>,
+    pub files: Vec<PathBuf>,
+}
+// ----------------------------------------------------------------------------
+impl LanguageBootstrap {
+    // ------------------------------------------------------------------------
+    pub fn new(
+        language: &str,
+        translator: TextPhonemeConverter,
+        cfg_file: &Path,
+    ) -> Result<LanguageBootstrap, String> {
+        let audio_phonemes = PocketSphinx::phoneme_set(cfg_file)?;
+
+        Ok(LanguageBootstrap {
+            language: language.to_owned(),
+            translator,
+            cfg_file: cfg_file.to_owned(),
+            audio_phonemes,
+        })
+    }
+    // ------------------------------------------------------------------------
+    /// translates all lines and writes the starter files and a coverage report
+    /// into outputdir. existing files are never overwritten. on failure all
+    /// written files are removed.
+    pub fn generate(
+        &self,
+        strings: &dyn StringsProvider,
+        outputdir: &Path,
+    ) -> Result<BootstrapSummary, String> {
+        info!(
+            "collecting phoneme inventory of #{} lines...",
+            strings.line_count()
+        );
+        let inventory = self.collect_inventory(strings);
+
+        if inventory.phonemes.is_empty() {
+            return Err(String::from("eSpeak translation yielded no phonemes."));
+        }
+        info!(
+            "> found #{} different phonemes in #{} lines ({} failed)",
+            inventory.phonemes.len(),
+            inventory.lines,
+            inventory.failed
+        );
+
+        let scores = PhonemeScores::calculate(&inventory, &self.audio_phonemes);
+        let mapping = scores.character_mapping(&inventory);
+
+        let files = BootstrapFiles::new(&self.language, outputdir)?;
+        fs::create_dir_all(outputdir)
+            .map_err(|err| format!("failed to create {}: {err}", outputdir.display()))?;
+
+        // none of the files existed before: remove all of them on failure so
+        // the bootstrap can be rerun
+        if let Err(why) = self.store_files(&inventory, &scores, &mapping, &files) {
+            files.remove();
+            return Err(why);
+        }
+
+        Ok(BootstrapSummary {
+            lines: (inventory.lines, inventory.failed),
+            text_phonemes: scores.text_phonemes.len(),
+            audio_phonemes: self.audio_phonemes.len(),
+            unmatched: (0..scores.text_phonemes.len())
+                .filter(|t| scores.best_match(*t).is_none())
+                .map(|t| scores.text_phonemes[t].clone())
+                .collect(),
+            unmapped_characters: inventory
+                .characters
+                .keys()
+                .filter(|c| !mapping.contains_key(c))
+                .copied()
+                .collect(),
+            files: files.into_vec(),
+        })
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+// internals
+// ----------------------------------------------------------------------------
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use espeak::TextPhonemeTranslator;
+use pocketsphinx::PocketSphinx;
+use similarity_matrix::SimilarityMatrix;
+use text::{CsvWriter, SimpleCsvWriter};
+
+use super::{StringsProvider, TextPhonemeConverter, REGEXP_CLEANUP};
+// ----------------------------------------------------------------------------
+/// min share of the character observations for an audio phoneme to be added as
+/// alternative to the character mapping
+const CHARACTER_MAPPING_MIN_SHARE: f32 = 0.05;
+const CHARACTER_MAPPING_MAX_ALTERNATIVES: usize = 8;
+/// min score of an audio phoneme to count for the character mapping
+const CHARACTER_MAPPING_MIN_SCORE: f32 = 1.0;
+/// min articulatory similarity (0..1) for a score in the similarity matrix
+const MIN_FEATURE_SIMILARITY: f32 = 0.3;
+/// min score of the best audio phoneme for a text phoneme to be considered
+/// well covered
+const COVERAGE_MIN_SCORE: f32 = 1.5;
+// ----------------------------------------------------------------------------
+#[derive(Default)]
+struct PhonemeInventory {
+    /// number of translated and failed lines
+    lines: usize,
+    failed: usize,
+    /// eSpeak phonemes with their number of occurrences
+    phonemes: BTreeMap<String, usize>,
+    /// (lowercased) alphabetic characters with their number of occurrences
+    characters: BTreeMap<char, usize>,
+    /// co-occurrences of characters and eSpeak phonemes of the same word
+    character_phonemes: BTreeMap<char, BTreeMap<String, usize>>,
+}
+// ----------------------------------------------------------------------------
+struct PhonemeScores {
+    /// text phonemes ordered by number of occurrences
+    text_phonemes: Vec<String>,
+    audio_phonemes: Vec<String>,
+    /// one row of scores per text phoneme
+    rows: Vec<Vec<Option<f32>>>,
+}
+// ----------------------------------------------------------------------------
+struct BootstrapFiles {
+    similarity: PathBuf,
+    config: PathBuf,
+    pocketsphinx_dict: PathBuf,
+    espeak_dict: PathBuf,
+    coverage: PathBuf,
+}
+// ----------------------------------------------------------------------------
+impl LanguageBootstrap {
+    // ------------------------------------------------------------------------
+    fn collect_inventory(&self, strings: &dyn StringsProvider) -> PhonemeInventory {
+        let mut inventory = PhonemeInventory::default();
+
+        for (lineid, (text, _actor)) in strings.get_all_lines() {
+            // remove all non-spoken textual hints framed by *
+            let text = &*REGEXP_CLEANUP.replace_all(text, "");
+
+            let translation = match self.translator.translate(text) {
+                Ok(translation) => translation,
+                Err(why) => {
+                    warn!("id {lineid:010}: translation failed: {why}. skipping...");
+                    inventory.failed += 1;
+                    continue;
+                }
+            };
+            inventory.lines += 1;
+
+            let mut phoneme_words: Vec<Vec<&str>> = Vec::new();
+            for segment in &translation.phonemes {
+                if segment.phoneme.is_empty() {
+                    continue;
+                }
+                *inventory
+                    .phonemes
+                    .entry(segment.phoneme.clone())
+                    .or_default() += 1;
+
+                match phoneme_words.last_mut() {
+                    Some(word) if !segment.word_start => word.push(segment.phoneme.as_str()),
+                    _ => phoneme_words.push(vec![segment.phoneme.as_str()]),
+                }
+            }
+
+            let words = text
+                .split_whitespace()
+                .map(|word| {
+                    word.chars()
+                        .filter(|c| c.is_alphabetic())
+                        .flat_map(char::to_lowercase)
+                        .collect::<Vec<_>>()
+                })
+                .filter(|word| !word.is_empty())
+                .collect::<Vec<_>>();
+
+            for c in words.iter().flatten() {
+                *inventory.characters.entry(*c).or_default() += 1;
+            }
+
+            // without a 1:1 relation of words (e.g. numbers or abbreviations
+            // expanded by eSpeak) characters cannot be assigned reliably
+            if words.len() != phoneme_words.len() {
+                trace!(
+                    "id {lineid:010}: word count differs from translation. skipping characters."
+                );
+                continue;
+            }
+            for (chars, phonemes) in words.iter().zip(&phoneme_words) {
+                // characters are assigned to the proportional range of phonemes
+                let (nc, np) = (chars.len(), phonemes.len());
+                for (i, c) in chars.iter().enumerate() {
+                    let first = i * np / nc;
+                    let last = ((i + 1) * np).div_ceil(nc).max(first + 1);
+                    let cooccurrences = inventory.character_phonemes.entry(*c).or_default();
+                    for phoneme in &phonemes[first..last] {
+                        *cooccurrences.entry((*phoneme).to_owned()).or_default() += 1;
+                    }
+                }
+            }
+        }
+        inventory
+    }
+    // ------------------------------------------------------------------------
+    fn store_files(
+        &self,
+        inventory: &PhonemeInventory,
+        scores: &PhonemeScores,
+        mapping: &BTreeMap<char, Vec<String>>,
+        files: &BootstrapFiles,
+    ) -> Result<(), String> {
+        self.store_similarity(scores, &files.similarity, inventory.lines)?;
+        self.store_config(mapping, &files.config)?;
+        self.store_dictionaries(&files.pocketsphinx_dict, &files.espeak_dict)?;
+        self.store_coverage(inventory, scores, mapping, &files.coverage)
+    }
+    // ------------------------------------------------------------------------
+    fn store_similarity(
+        &self,
+        scores: &PhonemeScores,
+        file: &Path,
+        lines: usize,
+    ) -> Result<(), String> {
+        let matrix = SimilarityMatrix::new(
+            scores.audio_phonemes.clone(),
+            scores.text_phonemes.clone(),
+            scores.rows.clone(),
+        )?;
+        matrix.store(
+            file,
+            &[
+                format!(
+                    "similarity matrix for pocketsphinx {} to eSpeak IPA phoneme matching",
+                    self.language
+                ),
+                format!("generated from articulatory features of eSpeak translations of #{lines} lines."),
+                String::from("review and adapt all scores!"),
+                String::from("first row pocketsphinx phonemes"),
+                String::from("first col IPA phonemes"),
+            ],
+        )?;
+        info!("> stored similarity matrix in [{}]", file.display());
+        Ok(())
+    }
+    // ------------------------------------------------------------------------
+    /// copies the language config and replaces all character mappings with the
+    /// generated ones
+    fn store_config(
+        &self,
+        mapping: &BTreeMap<char, Vec<String>>,
+        file: &Path,
+    ) -> Result<(), String> {
+        let conf = fs::read_to_string(&self.cfg_file)
+            .map_err(|err| format!("failed to load {}: {err}", self.cfg_file.display()))?;
+
+        fs::write(file, replace_character_mappings(&conf, mapping))
+            .map_err(|err| format!("failed to write {}: {err}", file.display()))?;
+        info!("> stored pocketsphinx config in [{}]", file.display());
+        Ok(())
+    }
+    // ------------------------------------------------------------------------
+    /// empty custom dictionaries (the pocketsphinx one is required to load the
+    /// language config)
+    fn store_dictionaries(
+        &self,
+        pocketsphinx_dict: &Path,
+        espeak_dict: &Path,
+    ) -> Result<(), String> {
+        let language = &self.language;
+        for (file, description) in [
+            (
+                pocketsphinx_dict,
+                "Custom dictionary to map (lowercased) words to a sequence of phonemes.",
+            ),
+            (espeak_dict, "Custom search and replace for translations."),
+        ] {
+            let content = format!(
+                ";\n; {description}\n;\n; empty starter file for language {language}. see the en\n; dictionary for the file format and examples.\n;\n"
+            );
+            fs::write(file, content)
+                .map_err(|err| format!("failed to write {}: {err}", file.display()))?;
+            info!("> stored custom dictionary in [{}]", file.display());
+        }
+        Ok(())
+    }
+    // ------------------------------------------------------------------------
+    fn store_coverage(
+        &self,
+        inventory: &PhonemeInventory,
+        scores: &PhonemeScores,
+        mapping: &BTreeMap<char, Vec<String>>,
+        file: &Path,
+    ) -> Result<(), String> {
+        let mut writer = SimpleCsvWriter::create(&file.to_path_buf())
+            .map_err(|err| format!("failed to create {}: {err}", file.display()))?;
+
+        writer.write_comment(&format!(" phoneme coverage for language {}", self.language));
+        writer.write_comment("");
+        writer.write_comment(" text phonemes");
+        writer.write_comment(" ipa|occurrences|best audio phoneme|score|status");
+        for (t, text) in scores.text_phonemes.iter().enumerate() {
+            let (audio, score, status) = match scores.best_match(t) {
+                _ if features(text).is_none() => ("", String::new(), "no features"),
+                Some((a, score)) => (
+                    scores.audio_phonemes[a].as_str(),
+                    format!("{score:.2}"),
+                    if score >= COVERAGE_MIN_SCORE {
+                        "ok"
+                    } else {
+                        "weak"
+                    },
+                ),
+                None => ("", String::new(), "unmatched"),
+            };
+            writer.writeln(&format!(
+                "{text}|{}|{audio}|{score}|{status}",
+                inventory.phonemes.get(text).unwrap_or(&0)
+            ));
+        }
+
+        writer.write_comment("");
+        writer.write_comment(" audio phonemes");
+        writer.write_comment(" phoneme|ipa|matched text phonemes|status");
+        for (a, audio) in scores.audio_phonemes.iter().enumerate() {
+            let ipa = audio_ipa(audio);
+            let matched = (0..scores.text_phonemes.len())
+                .filter(|t| scores.best_match(*t).is_some_and(|(best, _)| best == a))
+                .map(|t| scores.text_phonemes[t].as_str())
+                .collect::<Vec<_>>();
+            let status = if features(&ipa).is_none() {
+                "no features"
+            } else if matched.is_empty() {
+                "unused"
+            } else {
+                "ok"
+            };
+            writer.writeln(&format!("{audio}|{ipa}|{}|{status}", matched.join(" ")));
+        }
+
+        writer.write_comment("");
+        writer.write_comment(" characters");
+        writer.write_comment(" character|occurrences|mapping");
+        for (c, count) in &inventory.characters {
+            let alternatives = mapping
+                .get(c)
+                .map(|alternatives| alternatives.join(" "))
+                .unwrap_or_else(|| String::from("unmapped"));
+            writer.writeln(&format!("{c}|{count}|{alternatives}"));
+        }
+
+        info!("> stored coverage report in [{}]", file.display());
+        Ok(())
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl PhonemeScores {
+    // ------------------------------------------------------------------------
+    fn calculate(inventory: &PhonemeInventory, audio_phonemes: &[String]) -> PhonemeScores {
+        let mut text_phonemes = inventory.phonemes.iter().collect::<Vec<_>>();
+        text_phonemes.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        let text_phonemes = text_phonemes
+            .into_iter()
+            .map(|(phoneme, _)| phoneme.to_owned())
+            .collect::<Vec<_>>();
+
+        let audio_features = audio_phonemes
+            .iter()
+            .map(|audio| features(&audio_ipa(audio)))
+            .collect::<Vec<_>>();
+
+        let rows = text_phonemes
+            .iter()
+            .map(|text| {
+                let text = features(text);
+                audio_features
+                    .iter()
+                    .map(|audio| match (&text, audio) {
+                        (Some(text), Some(audio)) => score(sequence_similarity(text, audio)),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .collect();
+
+        PhonemeScores {
+            text_phonemes,
+            audio_phonemes: audio_phonemes.to_vec(),
+            rows,
+        }
+    }
+    // ------------------------------------------------------------------------
+    /// audio phoneme with the highest score for the text phoneme
+    fn best_match(&self, text: usize) -> Option<(usize, f32)> {
+        self.rows[text]
+            .iter()
+            .enumerate()
+            .filter_map(|(a, score)| score.map(|score| (a, score)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+    // ------------------------------------------------------------------------
+    /// audio phoneme alternatives for every character weighted by the scores of
+    /// all co-occurring text phonemes
+    fn character_mapping(&self, inventory: &PhonemeInventory) -> BTreeMap<char, Vec<String>> {
+        let text_idx = self
+            .text_phonemes
+            .iter()
+            .enumerate()
+            .map(|(i, phoneme)| (phoneme.as_str(), i))
+            .collect::<HashMap<_, _>>();
+
+        let mut mapping = BTreeMap::new();
+        for (c, cooccurrences) in &inventory.character_phonemes {
+            let mut weights = vec![0.0f32; self.audio_phonemes.len()];
+            let mut total = 0.0;
+            for (phoneme, count) in cooccurrences {
+                let Some(t) = text_idx.get(phoneme.as_str()) else {
+                    continue;
+                };
+                for (a, score) in self.rows[*t].iter().enumerate() {
+                    if let Some(score) = score.filter(|s| *s >= CHARACTER_MAPPING_MIN_SCORE) {
+                        weights[a] += *count as f32 * score;
+                    }
+                }
+                total += *count as f32 * 2.0;
+            }
+            if total == 0.0 {
+                continue;
+            }
+
+            let mut alternatives = weights
+                .iter()
+                .enumerate()
+                .filter(|(_, weight)| **weight / total >= CHARACTER_MAPPING_MIN_SHARE)
+                .collect::<Vec<_>>();
+            alternatives.sort_by(|a, b| b.1.total_cmp(a.1));
+
+            let alternatives = alternatives
+                .into_iter()
+                .take(CHARACTER_MAPPING_MAX_ALTERNATIVES)
+                .map(|(a, _)| self.audio_phonemes[a].clone())
+                .collect::<Vec<_>>();
+
+            if !alternatives.is_empty() {
+                mapping.insert(*c, alternatives);
+            }
+        }
+        mapping
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl BootstrapFiles {
+    // ------------------------------------------------------------------------
+    fn new(language: &str, outputdir: &Path) -> Result<BootstrapFiles, String> {
+        let files = BootstrapFiles {
+            similarity: outputdir.join(format!("{language}.phoneme.similarity.csv")),
+            config: outputdir.join(format!("{language}.pocketsphinx.cfg")),
+            pocketsphinx_dict: outputdir.join(format!("{language}.pocketsphinx.custom.dict")),
+            espeak_dict: outputdir.join(format!("{language}.espeak.custom.dict")),
+            coverage: outputdir.join(format!("{language}.bootstrap-coverage.csv")),
+        };
+        // prevent overwriting of maintained files (e.g. outputdir == datadir)
+        for file in files.as_slice() {
+            if file.exists() {
+                return Err(format!(
+                    "{} already exists. remove it or use another output directory.",
+                    file.display()
+                ));
+            }
+        }
+        Ok(files)
+    }
+    // ------------------------------------------------------------------------
+    fn as_slice(&self) -> [&PathBuf; 5] {
+        [
+            &self.similarity,
+            &self.config,
+            &self.pocketsphinx_dict,
+            &self.espeak_dict,
+            &self.coverage,
+        ]
+    }
+    // ------------------------------------------------------------------------
+    /// removes all (partially) written files
+    fn remove(&self) {
+        for file in self.as_slice().iter().filter(|file| file.exists()) {
+            if let Err(err) = fs::remove_file(file) {
+                warn!("failed to remove {}: {err}", file.display());
+            }
+        }
+    }
+    // ------------------------------------------------------------------------
+    fn into_vec(self) -> Vec<PathBuf> {
+        vec![
+            self.similarity,
+            self.config,
+            self.pocketsphinx_dict,
+            self.espeak_dict,
+            self.coverage,
+        ]
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+/// replaces all character mappings of a language config with the generated ones
+fn replace_character_mappings(conf: &str, mapping: &BTreeMap<char, Vec<String>>) -> String {
+    let mut generated = vec![
+        String::from("; generated mappings for all (lowercased) alphabetic characters used in"),
+        String::from("; textlines. review and adapt!"),
+        String::from(";"),
+    ];
+    generated.extend(
+        mapping
+            .iter()
+            .map(|(c, alternatives)| format!("{c} = {}", alternatives.join(" "))),
+    );
+
+    let mut result = Vec::new();
+    let mut generated = Some(generated);
+    for line in conf.lines() {
+        let is_mapping = !line.starts_with(';')
+            && line
+                .split_once('=')
+                .is_some_and(|(key, _)| key.trim().chars().count() == 1);
+
+        if is_mapping {
+            // generated block replaces the first mapping
+            if let Some(generated) = generated.take() {
+                result.extend(generated);
+            }
+        } else {
+            result.push(line.to_owned());
+        }
+    }
+    if let Some(generated) = generated {
+        result.push(String::new());
+        result.extend(generated);
+    }
+    result.join("\n") + "\n"
+}
+// ----------------------------------------------------------------------------
+// articulatory features
+// ----------------------------------------------------------------------------
+/// place of articulation ordered from front to back
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Place {
+    Bilabial,
+    Labiodental,
+    Dental,
+    Alveolar,
+    Postalveolar,
+    Retroflex,
+    Palatal,
+    Velar,
+    Uvular,
+    Pharyngeal,
+    Glottal,
+}
+// ----------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Manner {
+    Plosive,
+    Fricative,
+    LateralFricative,
+    Nasal,
+    Trill,
+    Tap,
+    Approximant,
+    LateralApproximant,
+}
+// ----------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Articulation {
+    /// height: 0 (close) .. 6 (open), backness: 0 (front) .. 2 (back)
+    Vowel {
+        height: u8,
+        backness: u8,
+        rounded: bool,
+    },
+    Consonant {
+        place: Place,
+        manner: Manner,
+        voiced: bool,
+    },
+}
+// ----------------------------------------------------------------------------
+/// IPA of the common ARPAbet phonemes. other audio phonemes are interpreted as
+/// IPA.
+fn audio_ipa(phoneme: &str) -> String {
+    let ipa = match phoneme.to_uppercase().as_str() {
+        "AA" => "ɑ",
+        "AE" => "æ",
+        "AH" => "ʌ",
+        "AO" => "ɔ",
+        "AW" => "aʊ",
+        "AX" => "ə",
+        "AXR" => "ɚ",
+        "AY" => "aɪ",
+        "EH" => "ɛ",
+        "ER" => "ɝ",
+        "EY" => "eɪ",
+        "IH" => "ɪ",
+        "IX" => "ɨ",
+        "IY" => "i",
+        "OW" => "oʊ",
+        "OY" => "ɔɪ",
+        "UH" => "ʊ",
+        "UW" => "u",
+        "UX" => "ʉ",
+        "B" => "b",
+        "CH" => "tʃ",
+        "D" => "d",
+        "DH" => "ð",
+        "DX" => "ɾ",
+        "EL" => "l",
+        "EM" => "m",
+        "EN" => "n",
+        "F" => "f",
+        "G" => "ɡ",
+        "HH" => "h",
+        "JH" => "dʒ",
+        "K" => "k",
+        "L" => "l",
+        "M" => "m",
+        "N" => "n",
+        "NG" => "ŋ",
+        "P" => "p",
+        "Q" => "ʔ",
+        "R" => "ɹ",
+        "S" => "s",
+        "SH" => "ʃ",
+        "T" => "t",
+        "TH" => "θ",
+        "V" => "v",
+        "W" => "w",
+        "WH" => "ʍ",
+        "Y" => "j",
+        "Z" => "z",
+        "ZH" => "ʒ",
+        _ => phoneme,
+    };
+    ipa.to_owned()
+}
+// ----------------------------------------------------------------------------
+/// articulations of all segments of an IPA phoneme (e.g. diphthongs or
+/// affricates). diacritics and unknown symbols are ignored. None if no segment
+/// is known.
+fn features(ipa: &str) -> Option<Vec<Articulation>> {
+    let segments = ipa.chars().filter_map(articulation).collect::<Vec<_>>();
+    if segments.is_empty() {
+        None
+    } else {
+        Some(segments)
+    }
+}
+// ----------------------------------------------------------------------------
+fn articulation(symbol: char) -> Option<Articulation> {
+    use self::Articulation::*;
+    use self::Manner::*;
+    use self::Place::*;
+
+    let vowel = |height, backness, rounded| Vowel {
+        height,
+        backness,
+        rounded,
+    };
+    let consonant = |place, manner, voiced| Consonant {
+        place,
+        manner,
+        voiced,
+    };
+
+    let articulation = match symbol {
+        // vowels
+        'i' => vowel(0, 0, false),
+        'y' => vowel(0, 0, true),
+        'ɨ' => vowel(0, 1, false),
+        'ʉ' => vowel(0, 1, true),
+        'ɯ' => vowel(0, 2, false),
+        'u' => vowel(0, 2, true),
+        'ɪ' => vowel(1, 0, false),
+        'ʏ' => vowel(1, 0, true),
+        'ᵻ' => vowel(1, 1, false),
+        'ᵿ' => vowel(1, 1, true),
+        'ʊ' => vowel(1, 2, true),
+        'e' => vowel(2, 0, false),
+        'ø' => vowel(2, 0, true),
+        'ɘ' => vowel(2, 1, false),
+        'ɵ' => vowel(2, 1, true),
+        'ɤ' => vowel(2, 2, false),
+        'o' => vowel(2, 2, true),
+        'ə' | 'ɚ' => vowel(3, 1, false),
+        'ɛ' => vowel(4, 0, false),
+        'œ' => vowel(4, 0, true),
+        'ɜ' | 'ɝ' => vowel(4, 1, false),
+        'ɞ' => vowel(4, 1, true),
+        'ʌ' => vowel(4, 2, false),
+        'ɔ' => vowel(4, 2, true),
+        'æ' => vowel(5, 0, false),
+        'ɐ' => vowel(5, 1, false),
+        'a' => vowel(6, 0, false),
+        'ɶ' => vowel(6, 0, true),
+        'ɑ' => vowel(6, 2, false),
+        'ɒ' => vowel(6, 2, true),
+
+        // plosives
+        'p' => consonant(Bilabial, Plosive, false),
+        'b' => consonant(Bilabial, Plosive, true),
+        't' => consonant(Alveolar, Plosive, false),
+        'd' => consonant(Alveolar, Plosive, true),
+        'ʈ' => consonant(Retroflex, Plosive, false),
+        'ɖ' => consonant(Retroflex, Plosive, true),
+        'c' => consonant(Palatal, Plosive, false),
+        'ɟ' => consonant(Palatal, Plosive, true),
+        'k' => consonant(Velar, Plosive, false),
+        'g' | 'ɡ' => consonant(Velar, Plosive, true),
+        'q' => consonant(Uvular, Plosive, false),
+        'ɢ' => consonant(Uvular, Plosive, true),
+        'ʔ' => consonant(Glottal, Plosive, false),
+
+        // nasals
+        'm' => consonant(Bilabial, Nasal, true),
+        'ɱ' => consonant(Labiodental, Nasal, true),
+        'n' => consonant(Alveolar, Nasal, true),
+        'ɳ' => consonant(Retroflex, Nasal, true),
+        'ɲ' => consonant(Palatal, Nasal, true),
+        'ŋ' => consonant(Velar, Nasal, true),
+        'ɴ' => consonant(Uvular, Nasal, true),
+
+        // trills and taps
+        'ʙ' => consonant(Bilabial, Trill, true),
+        'r' => consonant(Alveolar, Trill, true),
+        'ʀ' => consonant(Uvular, Trill, true),
+        'ɾ' => consonant(Alveolar, Tap, true),
+        'ɽ' => consonant(Retroflex, Tap, true),
+
+        // fricatives
+        'ɸ' => consonant(Bilabial, Fricative, false),
+        'β' => consonant(Bilabial, Fricative, true),
+        'f' => consonant(Labiodental, Fricative, false),
+        'v' => consonant(Labiodental, Fricative, true),
+        'θ' => consonant(Dental, Fricative, false),
+        'ð' => consonant(Dental, Fricative, true),
+        's' => consonant(Alveolar, Fricative, false),
+        'z' => consonant(Alveolar, Fricative, true),
+        'ʃ' => consonant(Postalveolar, Fricative, false),
+        'ʒ' => consonant(Postalveolar, Fricative, true),
+        'ʂ' => consonant(Retroflex, Fricative, false),
+        'ʐ' => consonant(Retroflex, Fricative, true),
+        'ç' => consonant(Palatal, Fricative, false),
+        'ʝ' => consonant(Palatal, Fricative, true),
+        'x' => consonant(Velar, Fricative, false),
+        'ɣ' => consonant(Velar, Fricative, true),
+        'χ' => consonant(Uvular, Fricative, false),
+        'ʁ' => consonant(Uvular, Fricative, true),
+        'ħ' => consonant(Pharyngeal, Fricative, false),
+        'ʕ' => consonant(Pharyngeal, Fricative, true),
+        'h' => consonant(Glottal, Fricative, false),
+        'ɦ' => consonant(Glottal, Fricative, true),
+        'ɬ' => consonant(Alveolar, LateralFricative, false),
+        'ɮ' => consonant(Alveolar, LateralFricative, true),
+
+        // approximants
+        'ʋ' => consonant(Labiodental, Approximant, true),
+        'ɹ' => consonant(Alveolar, Approximant, true),
+        'ɻ' => consonant(Retroflex, Approximant, true),
+        'j' => consonant(Palatal, Approximant, true),
+        'ɰ' | 'w' => consonant(Velar, Approximant, true),
+        'ʍ' => consonant(Velar, Approximant, false),
+        'l' | 'ɫ' => consonant(Alveolar, LateralApproximant, true),
+        'ɭ' => consonant(Retroflex, LateralApproximant, true),
+        'ʎ' => consonant(Palatal, LateralApproximant, true),
+        'ʟ' => consonant(Velar, LateralApproximant, true),
+
+        _ => return None,
+    };
+    Some(articulation)
+}
+// ----------------------------------------------------------------------------
+/// similarity (0..1) of two phonemes. sequences of different length (e.g.
+/// diphthong vs monophthong) are compared by their best matching boundary
+/// segment.
+fn sequence_similarity(a: &[Articulation], b: &[Articulation]) -> f32 {
+    if a.len() == b.len() {
+        a.iter().zip(b).map(|(a, b)| similarity(a, b)).sum::<f32>() / a.len() as f32
+    } else {
+        let first = similarity(&a[0], &b[0]);
+        let last = similarity(&a[a.len() - 1], &b[b.len() - 1]);
+        0.8 * first.max(last)
+    }
+}
+// ----------------------------------------------------------------------------
+fn similarity(a: &Articulation, b: &Articulation) -> f32 {
+    use self::Articulation::*;
+
+    match (*a, *b) {
+        (
+            Vowel {
+                height: h1,
+                backness: b1,
+                rounded: r1,
+            },
+            Vowel {
+                height: h2,
+                backness: b2,
+                rounded: r2,
+            },
+        ) => {
+            let similarity = 1.0
+                - 0.1 * h1.abs_diff(h2) as f32
+                - 0.2 * b1.abs_diff(b2) as f32
+                - if r1 != r2 { 0.15 } else { 0.0 };
+            similarity.max(0.0)
+        }
+        (
+            Consonant {
+                place: p1,
+                manner: m1,
+                voiced: v1,
+            },
+            Consonant {
+                place: p2,
+                manner: m2,
+                voiced: v2,
+            },
+        ) => {
+            let place = (1.0 - 0.2 * (p1 as u8).abs_diff(p2 as u8) as f32).max(0.2);
+            let voicing = if v1 != v2 { 0.75 } else { 1.0 };
+            manner_similarity(m1, m2) * place * voicing
+        }
+        (Vowel { .. }, Consonant { .. }) => semivowel_similarity(b, a),
+        (Consonant { .. }, Vowel { .. }) => semivowel_similarity(a, b),
+    }
+}
+// ----------------------------------------------------------------------------
+fn manner_similarity(a: Manner, b: Manner) -> f32 {
+    use self::Manner::*;
+
+    if a == b {
+        return 1.0;
+    }
+    match (a, b) {
+        (Trill, Tap) | (Tap, Trill) => 0.8,
+        (Fricative, LateralFricative)
+        | (LateralFricative, Fricative)
+        | (Approximant, LateralApproximant)
+        | (LateralApproximant, Approximant) => 0.7,
+        (Tap, Plosive)
+        | (Plosive, Tap)
+        | (Tap, Approximant)
+        | (Approximant, Tap)
+        | (Trill, Approximant)
+        | (Approximant, Trill)
+        | (LateralFricative, LateralApproximant)
+        | (LateralApproximant, LateralFricative) => 0.5,
+        (Plosive, Fricative)
+        | (Fricative, Plosive)
+        | (Fricative, Approximant)
+        | (Approximant, Fricative) => 0.4,
+        (Nasal, Plosive) | (Plosive, Nasal) => 0.3,
+        _ => 0.1,
+    }
+}
+// ----------------------------------------------------------------------------
+/// semivowels j and w are similar to close front and close back rounded vowels
+fn semivowel_similarity(consonant: &Articulation, vowel: &Articulation) -> f32 {
+    match (*consonant, *vowel) {
+        (
+            Articulation::Consonant {
+                place: Place::Palatal,
+                manner: Manner::Approximant,
+                ..
+            },
+            Articulation::Vowel {
+                height: 0..=1,
+                backness: 0,
+                ..
+            },
+        )
+        | (
+            Articulation::Consonant {
+                place: Place::Velar,
+                manner: Manner::Approximant,
+                ..
+            },
+            Articulation::Vowel {
+                height: 0..=1,
+                backness: 2,
+                rounded: true,
+            },
+        ) => 0.5,
+        _ => 0.0,
+    }
+}
+// ----------------------------------------------------------------------------
+/// similarity score in the range of the similarity matrix (0..2) rounded to
+/// 0.05 steps. None for dissimilar phonemes.
+fn score(similarity: f32) -> Option<f32> {
+    if similarity < MIN_FEATURE_SIMILARITY {
+        None
+    } else {
+        Some((2.0 * similarity * 20.0).round() / 20.0)
+    }
+}
+// ----------------------------------------------------------------------------
+// ----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::env;
+    use std::fs;
+
+    use super::Articulation::*;
+    use super::Manner::*;
+    use super::Place::*;
+    use super::{
+        articulation, audio_ipa, features, replace_character_mappings, score, sequence_similarity,
+        BootstrapFiles, PhonemeInventory, PhonemeScores,
+    };
+
+    fn similarity(a: &str, b: &str) -> f32 {
+        sequence_similarity(&features(a).unwrap(), &features(b).unwrap())
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| (*v).to_owned()).collect()
+    }
+
+    #[test]
+    fn test_feature_table() {
+        let vowel = |height, backness, rounded| Vowel {
+            height,
+            backness,
+            rounded,
+        };
+        let consonant = |place, manner, voiced| Consonant {
+            place,
+            manner,
+            voiced,
+        };
+
+        assert_eq!(articulation('i'), Some(vowel(0, 0, false)));
+        assert_eq!(articulation('ɔ'), Some(vowel(4, 2, true)));
+        assert_eq!(articulation('ɚ'), articulation('ə'));
+        assert_eq!(
+            articulation('ʃ'),
+            Some(consonant(Postalveolar, Fricative, false))
+        );
+        assert_eq!(articulation('ŋ'), Some(consonant(Velar, Nasal, true)));
+        assert_eq!(articulation('ɡ'), articulation('g'));
+        assert_eq!(articulation('ɾ'), Some(consonant(Alveolar, Tap, true)));
+        assert_eq!(articulation('ˈ'), None);
+
+        // diacritics are ignored, segments of diphthongs and affricates kept
+        assert_eq!(
+            features("ˈaɪ"),
+            Some(vec![vowel(6, 0, false), vowel(1, 0, false)])
+        );
+        assert_eq!(features("tʃ").map(|f| f.len()), Some(2));
+        assert_eq!(features("ːˌ"), None);
+
+        assert_eq!(audio_ipa("CH"), "tʃ");
+        assert_eq!(audio_ipa("ng"), "ŋ");
+        assert_eq!(audio_ipa("ɲ"), "ɲ");
+
+        // every phoneme of the en model has features
+        let phonemes = "AA AE AH AO AW AY CH DH EH ER EY IH IY NG OW OY SH TH UH UW ZH B D F G K \
+                        L M N P R S T V W Y Z JH HH";
+        for phoneme in phonemes.split_whitespace() {
+            assert!(features(&audio_ipa(phoneme)).is_some(), "{}", phoneme);
+        }
+    }
+
+    #[test]
+    fn test_sequence_similarity() {
+        let expected = [
+            ("s", "s", 1.0),
+            // voicing
+            ("s", "z", 0.75),
+            // height
+            ("i", "ɪ", 0.9),
+            // backness and rounding
+            ("i", "u", 0.45),
+            // manner
+            ("m", "b", 0.3),
+            // place (lower bound)
+            ("p", "ʔ", 0.2),
+            // best matching boundary segment of different lengths
+            ("aɪ", "a", 0.8),
+            ("tʃ", "ʃ", 0.8),
+            // semivowels
+            ("j", "i", 0.5),
+            ("w", "u", 0.5),
+            ("w", "i", 0.0),
+            ("s", "a", 0.0),
+        ];
+        for (a, b, expected) in expected.iter() {
+            let result = similarity(a, b);
+            assert!((result - expected).abs() < 1e-6, "{} {}: {}", a, b, result);
+            let result = similarity(b, a);
+            assert!((result - expected).abs() < 1e-6, "{} {}: {}", b, a, result);
+        }
+
+        assert_eq!(score(0.29), None);
+        assert_eq!(score(0.3), Some(0.6));
+        assert_eq!(score(0.32), Some(0.65));
+        assert_eq!(score(1.0), Some(2.0));
+    }
+
+    #[test]
+    fn test_calculate_scores() {
+        let mut inventory = PhonemeInventory::default();
+        inventory.phonemes.insert(String::from("s"), 1);
+        inventory.phonemes.insert(String::from("ʃ"), 3);
+        inventory.phonemes.insert(String::from("+"), 2);
+
+        let scores = PhonemeScores::calculate(&inventory, &strings(&["S", "Z", "AA"]));
+
+        // ordered by number of occurrences
+        assert_eq!(scores.text_phonemes, ["ʃ", "+", "s"]);
+        assert_eq!(scores.rows[0], [Some(1.6), Some(1.2), None]);
+        assert_eq!(scores.rows[1], [None, None, None]);
+        assert_eq!(scores.rows[2], [Some(2.0), Some(1.5), None]);
+
+        assert_eq!(scores.best_match(0), Some((0, 1.6)));
+        assert_eq!(scores.best_match(1), None);
+    }
+
+    #[test]
+    fn test_character_mapping() {
+        let scores = PhonemeScores {
+            text_phonemes: strings(&["k", "s"]),
+            audio_phonemes: strings(&["K", "S", "G"]),
+            rows: vec![
+                vec![Some(2.0), None, Some(1.5)],
+                vec![None, Some(2.0), Some(0.5)],
+            ],
+        };
+
+        let mut inventory = PhonemeInventory::default();
+        let mut cooccurrences = |c: char, phonemes: &[(&str, usize)]| {
+            inventory.character_phonemes.insert(
+                c,
+                phonemes
+                    .iter()
+                    .map(|(phoneme, count)| ((*phoneme).to_owned(), *count))
+                    .collect(),
+            );
+        };
+        cooccurrences('c', &[("k", 3), ("s", 1)]);
+        cooccurrences('z', &[("s", 1)]);
+        // share of k scores below min share
+        cooccurrences('q', &[("k", 1), ("s", 30)]);
+        // unknown text phonemes
+        cooccurrences('x', &[("ʔ", 2)]);
+
+        let mapping = scores.character_mapping(&inventory);
+
+        // ordered by weight, scores below min score are ignored
+        assert_eq!(mapping.get(&'c'), Some(&strings(&["K", "G", "S"])));
+        assert_eq!(mapping.get(&'z'), Some(&strings(&["S"])));
+        assert_eq!(mapping.get(&'q'), Some(&strings(&["S"])));
+        assert_eq!(mapping.get(&'x'), None);
+    }
+
+    #[test]
+    fn test_replace_character_mappings() {
+        let mut mapping = BTreeMap::new();
+        mapping.insert('a', strings(&["AA", "AH"]));
+        mapping.insert('b', strings(&["B"]));
+
+        let generated = "; generated mappings for all (lowercased) alphabetic characters used in\n\
+                         ; textlines. review and adapt!\n\
+                         ;\n\
+                         a = AA AH\n\
+                         b = B\n";
+
+        // generated block replaces the first mapping, other mappings are removed
+        let conf = "; header\nPHONEMES = AA B\n\n; mappings\na = AA\nc = K\n;d = D\nCLEANUP = ?!\n";
+        assert_eq!(
+            replace_character_mappings(conf, &mapping),
+            format!("; header\nPHONEMES = AA B\n\n; mappings\n{generated};d = D\nCLEANUP = ?!\n")
+        );
+
+        // appended if there are no mappings
+        assert_eq!(
+            replace_character_mappings("PHONEMES = AA B\n", &mapping),
+            format!("PHONEMES = AA B\n\n{generated}")
+        );
+
+        let conf =
+            fs::read_to_string([env!("CARGO_MANIFEST_DIR"), "data/en.pocketsphinx.cfg"].join("/"))
+                .unwrap();
+        let result = replace_character_mappings(&conf, &mapping);
+        assert!(result.contains("\nPHONEMES = AA AE AH"));
+        assert!(result.contains("\na = AA AH\nb = B\n"));
+        assert!(!result.contains("\nc = "), "all mappings replaced");
+    }
+
+    #[test]
+    fn test_bootstrap_files_cleanup() {
+        let outputdir = env::temp_dir().join("w3phonemetools-test-bootstrap");
+        fs::remove_dir_all(&outputdir).ok();
+        fs::create_dir_all(&outputdir).unwrap();
+
+        let files = BootstrapFiles::new("xx", &outputdir).unwrap();
+        fs::write(&files.similarity, "partial").unwrap();
+        assert!(
+            BootstrapFiles::new("xx", &outputdir).is_err(),
+            "existing file"
+        );
+
+        files.remove();
+        assert!(!files.similarity.exists());
+        assert!(BootstrapFiles::new("xx", &outputdir).is_ok(), "rerun");
+
+        fs::remove_dir_all(&outputdir).ok();
+    }
+}
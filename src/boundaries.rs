@@ -0,0 +1,277 @@
+//
+// sub-frame refinement of phoneme boundaries
+//
+
+// ----------------------------------------------------------------------------
+// external interface
+// ----------------------------------------------------------------------------
+/// moves every boundary of active segments within a small window to the
+/// nearest strong spectral or energy change of the (original samplerate)
+/// audio. adjacent inactive segments follow the moved boundaries. adjustments
+/// are appended to the matching info of the segments. returns number of moved
+/// boundaries.
+pub(super) fn refine_boundaries(
+    track: &mut PhonemeTrack<PhonemeSegment>,
+    values: &[i16],
+    sample_rate: u32,
+) -> usize {
+    let change = AudioAnalyzer::spectral_change(values, sample_rate);
+    move_boundaries(track.phonemes_mut(), &change)
+}
+// ----------------------------------------------------------------------------
+// internals
+// ----------------------------------------------------------------------------
+use audio::AudioAnalyzer;
+use phonemes::{PhonemeSegment, PhonemeTrack};
+// ----------------------------------------------------------------------------
+/// max distance (ms) a boundary is moved (recognizer frames are 10ms)
+const REFINE_WINDOW_MS: u32 = 15;
+/// refined segments are not shorter than this (ms)
+const MIN_SEGMENT_MS: u32 = 15;
+/// min spectral change (dB) considered a boundary
+const MIN_CHANGE_DB: f32 = 6.0;
+/// min improvement (dB) compared to the current boundary position
+const MIN_IMPROVEMENT_DB: f32 = 1.0;
+/// prefers nearer positions for changes of similar strength (dB per ms)
+const DISTANCE_PENALTY_DB: f32 = 0.1;
+// ----------------------------------------------------------------------------
+/// moves the boundaries of active segments to the strongest nearby change of
+/// the change curve (one value per ms). returns number of moved boundaries.
+fn move_boundaries(phonemes: &mut [PhonemeSegment], change: &[f32]) -> usize {
+    if change.is_empty() {
+        return 0;
+    }
+    let duration = (change.len() - 1) as u32;
+
+    let active = phonemes
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.active)
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+
+    let mut moved = 0;
+    for (k, slot) in active.iter().copied().enumerate() {
+        let prev = k.checked_sub(1).map(|k| active[k]);
+        let next = active.get(k + 1).copied();
+
+        // start boundary (shared with previous segment if they touch)
+        let start = phonemes[slot].start;
+        let shared = prev.filter(|prev| phonemes[*prev].end >= start);
+        let lower = match prev {
+            Some(prev) if shared.is_some() => phonemes[prev].start + MIN_SEGMENT_MS,
+            Some(prev) => phonemes[prev].end,
+            None => 0,
+        };
+        let upper = phonemes[slot].end.saturating_sub(MIN_SEGMENT_MS);
+
+        if let Some(refined) = strongest_change(change, start, lower, upper) {
+            let delta = refined as i64 - start as i64;
+            phonemes[slot].start = refined;
+            add_note(&mut phonemes[slot], "start", delta);
+            follow_start(phonemes, slot, start, refined);
+            if let Some(prev) = shared {
+                let delta = refined as i64 - phonemes[prev].end as i64;
+                phonemes[prev].end = refined;
+                add_note(&mut phonemes[prev], "end", delta);
+            }
+            moved += 1;
+        }
+
+        // end boundary only if not shared with next segment
+        let end = phonemes[slot].end;
+        let upper = match next {
+            Some(next) if phonemes[next].start <= end => continue,
+            Some(next) => phonemes[next].start,
+            None => duration,
+        };
+        let lower = phonemes[slot].start + MIN_SEGMENT_MS;
+
+        if let Some(refined) = strongest_change(change, end, lower, upper) {
+            phonemes[slot].end = refined;
+            add_note(&mut phonemes[slot], "end", refined as i64 - end as i64);
+            follow_end(phonemes, slot, end, refined);
+            moved += 1;
+        }
+    }
+    moved
+}
+// ----------------------------------------------------------------------------
+/// position of the strongest change within the window around pos limited to
+/// [lower, upper]. None if there is no clearly stronger change than at pos.
+fn strongest_change(change: &[f32], pos: u32, lower: u32, upper: u32) -> Option<u32> {
+    let last = change.len() as u32 - 1;
+    let from = pos.saturating_sub(REFINE_WINDOW_MS).max(lower);
+    let to = (pos + REFINE_WINDOW_MS).min(upper).min(last);
+
+    if from > to || pos > last {
+        return None;
+    }
+
+    let rated = |p: u32| change[p as usize] - DISTANCE_PENALTY_DB * p.abs_diff(pos) as f32;
+    let best = (from..=to).max_by(|a, b| rated(*a).total_cmp(&rated(*b)))?;
+
+    let strength = change[best as usize];
+    if best != pos
+        && strength >= MIN_CHANGE_DB
+        && strength - change[pos as usize] >= MIN_IMPROVEMENT_DB
+    {
+        Some(best)
+    } else {
+        None
+    }
+}
+// ----------------------------------------------------------------------------
+/// inactive segments before slot ending at (or after) the moved start boundary
+/// end at the new position
+fn follow_start(phonemes: &mut [PhonemeSegment], slot: usize, old: u32, new: u32) {
+    for segment in phonemes[..slot].iter_mut().rev().take_while(|s| !s.active) {
+        if segment.end == old || segment.end > new {
+            segment.end = new;
+            segment.start = segment.start.min(new);
+        }
+    }
+}
+// ----------------------------------------------------------------------------
+/// inactive segments after slot starting at (or before) the moved end boundary
+/// start at the new position
+fn follow_end(phonemes: &mut [PhonemeSegment], slot: usize, old: u32, new: u32) {
+    for segment in phonemes[slot + 1..].iter_mut().take_while(|s| !s.active) {
+        if segment.start == old || segment.start < new {
+            segment.start = new;
+            segment.end = segment.end.max(new);
+        }
+    }
+}
+// ----------------------------------------------------------------------------
+fn add_note(segment: &mut PhonemeSegment, boundary: &str, delta: i64) {
+    let info = segment.matching_info.get_or_insert_with(String::new);
+    info.push_str(&format!(" [{boundary} {delta:+}ms]"));
+}
+// ----------------------------------------------------------------------------
+// ----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::super::phonemes::PhonemeSegment;
+    use super::{move_boundaries, strongest_change};
+
+    /// change curve (one value per ms) with peaks (position, dB)
+    fn change_curve(duration: usize, peaks: &[(usize, f32)]) -> Vec<f32> {
+        let mut change = vec![1.0; duration + 1];
+        for &(pos, db) in peaks {
+            change[pos] = db;
+        }
+        change
+    }
+
+    fn segment(phoneme: &str, start: u32, end: u32, active: bool) -> PhonemeSegment {
+        PhonemeSegment {
+            phoneme: phoneme.to_owned(),
+            word_start: false,
+            start,
+            end,
+            weight: 1.0,
+            score: 1.0,
+            matching_info: None,
+            traceback: None,
+            active,
+            warnings: Vec::default(),
+        }
+    }
+
+    #[test]
+    fn test_strongest_change() {
+        let change = change_curve(300, &[(108, 10.0), (120, 20.0)]);
+        // stronger but farther change is outside the window
+        assert_eq!(strongest_change(&change, 100, 0, 300), Some(108));
+        assert_eq!(strongest_change(&change, 108, 0, 300), Some(120));
+        assert_eq!(
+            strongest_change(&change, 100, 0, 105),
+            None,
+            "limited range"
+        );
+
+        let change = change_curve(300, &[(108, 5.0)]);
+        assert_eq!(strongest_change(&change, 100, 0, 300), None, "weak change");
+
+        let change = change_curve(300, &[(100, 8.0), (104, 8.5)]);
+        assert_eq!(
+            strongest_change(&change, 100, 0, 300),
+            None,
+            "no improvement"
+        );
+    }
+
+    fn timings(phonemes: &[PhonemeSegment]) -> Vec<(&str, u32, u32)> {
+        phonemes
+            .iter()
+            .map(|p| (p.phoneme.as_str(), p.start, p.end))
+            .collect()
+    }
+
+    fn is_contiguous(phonemes: &[PhonemeSegment]) -> bool {
+        phonemes
+            .windows(2)
+            .all(|pair| pair[0].end == pair[1].start && pair[0].start <= pair[0].end)
+    }
+
+    #[test]
+    fn test_move_boundaries() {
+        let mut phonemes = vec![
+            segment("a", 0, 100, true),
+            segment("b", 100, 200, true),
+            segment("_", 200, 250, false),
+            segment("c", 250, 300, true),
+            segment("_", 300, 320, false),
+        ];
+        let change = change_curve(320, &[(110, 12.0), (207, 9.0), (240, 9.0), (290, 9.0)]);
+
+        assert_eq!(move_boundaries(&mut phonemes, &change), 4);
+        // shared boundary moves both segments, inactive segments follow
+        assert_eq!(
+            timings(&phonemes),
+            [
+                ("a", 0, 110),
+                ("b", 110, 207),
+                ("_", 207, 240),
+                ("c", 240, 290),
+                ("_", 290, 320)
+            ]
+        );
+        assert!(is_contiguous(&phonemes), "{:?}", timings(&phonemes));
+        assert_eq!(phonemes[0].matching_info.as_deref(), Some(" [end +10ms]"));
+        assert_eq!(
+            phonemes[1].matching_info.as_deref(),
+            Some(" [start +10ms] [end +7ms]")
+        );
+        assert_eq!(phonemes[2].matching_info, None, "inactive segment");
+
+        // inactive segments shorter than the move are clamped
+        let mut phonemes = vec![
+            segment("a", 0, 100, true),
+            segment("_", 100, 105, false),
+            segment("_", 105, 150, false),
+            segment("b", 150, 200, true),
+        ];
+        let change = change_curve(200, &[(110, 12.0)]);
+
+        assert_eq!(move_boundaries(&mut phonemes, &change), 1);
+        assert_eq!(
+            timings(&phonemes),
+            [
+                ("a", 0, 110),
+                ("_", 110, 110),
+                ("_", 110, 150),
+                ("b", 150, 200)
+            ]
+        );
+        assert!(is_contiguous(&phonemes), "{:?}", timings(&phonemes));
+
+        // segments keep a min duration
+        let mut phonemes = vec![segment("a", 0, 20, true), segment("b", 20, 100, true)];
+        let change = change_curve(100, &[(10, 12.0)]);
+        assert_eq!(move_boundaries(&mut phonemes, &change), 0);
+
+        assert_eq!(move_boundaries(&mut phonemes, &[]), 0);
+    }
+}
@@ -0,0 +1,241 @@
+//
+// batch audio conditioning
+//
+
+// ----------------------------------------------------------------------------
+// external interface
+// ----------------------------------------------------------------------------
+pub struct ConditioningSettings {
+    /// target integrated loudness in LUFS
+    pub target_loudness: f32,
+    /// silence before start of speech in ms
+    pub head_ms: u32,
+    /// silence after end of speech in ms
+    pub tail_ms: u32,
+    /// ogg vorbis vbr quality (-0.1..1.0)
+    pub quality: f32,
+}
+// ----------------------------------------------------------------------------
+pub struct AudioConditioner {
+    settings: ConditioningSettings,
+    outputdir: PathBuf,
+    /// data directory with the codebook library for wem decoding
+    datadir: PathBuf,
+}
+// ----------------------------------------------------------------------------
+pub const DEFAULT_TARGET_LOUDNESS: f32 = -23.0;
+pub const DEFAULT_HEAD_MS: u32 = 100;
+pub const DEFAULT_TAIL_MS: u32 = 200;
+pub const DEFAULT_OGG_QUALITY: f32 = 0.6;
+// ----------------------------------------------------------------------------
+impl Default for ConditioningSettings {
+    // ------------------------------------------------------------------------
+    fn default() -> ConditioningSettings {
+        ConditioningSettings {
+            target_loudness: DEFAULT_TARGET_LOUDNESS,
+            head_ms: DEFAULT_HEAD_MS,
+            tail_ms: DEFAULT_TAIL_MS,
+            quality: DEFAULT_OGG_QUALITY,
+        }
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl AudioConditioner {
+    // ------------------------------------------------------------------------
+    pub fn new(
+        settings: ConditioningSettings,
+        outputdir: PathBuf,
+        datadir: PathBuf,
+    ) -> AudioConditioner {
+        AudioConditioner {
+            settings,
+            outputdir,
+            datadir,
+        }
+    }
+    // ------------------------------------------------------------------------
+    /// conditions all <id>* audiofiles found in inputdir. returns number of
+    /// conditioned and failed files.
+    pub fn condition_all(&self, inputdir: PathBuf) -> Result<(usize, usize), String> {
+        let mut scanner = FilesScanner::new(inputdir)?;
+
+        let mut conditioned = 0;
+        let mut failed = 0;
+        for file in scanner.scan()? {
+            match file {
+                FileInfo::Audio(lineid, ref audiofile, _) => {
+                    match self.condition(lineid, audiofile) {
+                        Ok(new_audiofile) => {
+                            info!("id {lineid:010}: stored as {new_audiofile}");
+                            conditioned += 1;
+                        }
+                        Err(why) => {
+                            error!("{why} skipping id [{lineid:>10}]...");
+                            failed += 1;
+                        }
+                    }
+                }
+                FileInfo::UnlinkedAudio(ref audiofile) => {
+                    warn!("skipping audiofile without id: {audiofile}");
+                }
+                FileInfo::Phonemes(_, _) | FileInfo::Alignment(_, _) => {}
+            }
+        }
+        Ok((conditioned, failed))
+    }
+    // ------------------------------------------------------------------------
+    /// normalizes loudness, trims/pads silence at head and tail and stores
+    /// the result as mono ogg vorbis with updated duration tag in outputdir.
+    /// returns the new audiofile.
+    pub fn condition(&self, lineid: u32, audiofile: &str) -> Result<String, String> {
+        let mut dataprovider = DataProvider::new(audiofile, &self.datadir);
+        // loader always provides mono audio with the required samplerate
+        dataprovider.load()?;
+        let audiodata = dataprovider.get_rawaudio(REQUIRED_SAMPLE_RATE, false)?;
+
+        let (start, end) = AudioAnalyzer::speech_bounds(&audiodata, REQUIRED_SAMPLE_RATE)
+            .ok_or_else(|| format!("id {lineid:010}: no speech found in audio."))?;
+
+        let samples_per_ms = REQUIRED_SAMPLE_RATE as usize / 1000;
+        let head = self.settings.head_ms as usize * samples_per_ms;
+        let tail = self.settings.tail_ms as usize * samples_per_ms;
+
+        // keep available recorded silence and pad only the missing part
+        let from = start.saturating_sub(head);
+        let to = (end + tail).min(audiodata.len());
+
+        let mut values = Vec::with_capacity(head + (end - start) + tail);
+        values.resize(head - (start - from), 0);
+        values.extend_from_slice(&audiodata[from..to]);
+        values.resize(values.len() + tail - (to - end), 0);
+
+        let gain_db = self.loudness_gain(lineid, &values)?;
+        let gain = 10f32.powf(gain_db / 20.0);
+        for value in values.iter_mut() {
+            *value = (*value as f32 * gain)
+                .round()
+                .clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+        }
+
+        let duration = values.len() as f32 / REQUIRED_SAMPLE_RATE as f32;
+        let new_audiofile = self.outputdir.join(conditioned_filename(
+            lineid,
+            duration,
+            Path::new(audiofile),
+        )?);
+
+        debug!(
+            "id {lineid:010}: > trimmed to {:.4}s, gain {:+.1} dB",
+            duration, gain_db
+        );
+        AudioWriter::write_ogg(
+            &new_audiofile,
+            &values,
+            REQUIRED_SAMPLE_RATE,
+            self.settings.quality,
+        )?;
+
+        Ok(new_audiofile.to_string_lossy().to_string())
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+// internals
+// ----------------------------------------------------------------------------
+use std::path::{Path, PathBuf};
+
+use audio::{AudioAnalyzer, AudioWriter};
+use file_scanner::{FileInfo, FilesScanner};
+
+use super::{audiofile_name, DataProvider, REQUIRED_SAMPLE_RATE};
+// ----------------------------------------------------------------------------
+// gain is limited to prevent clipping of peaks
+const MAX_PEAK_DB: f32 = -1.0;
+// ----------------------------------------------------------------------------
+impl AudioConditioner {
+    // ------------------------------------------------------------------------
+    fn loudness_gain(&self, lineid: u32, values: &[i16]) -> Result<f32, String> {
+        let loudness = AudioAnalyzer::loudness(values, REQUIRED_SAMPLE_RATE)
+            .ok_or_else(|| format!("id {lineid:010}: failed to measure loudness."))?;
+
+        let peak = values
+            .iter()
+            .map(|v| (*v as i32).abs())
+            .max()
+            .unwrap_or_default();
+        let peak_db = 20.0 * (peak as f32 / i16::MAX as f32).log10();
+
+        let gain_db = self.settings.target_loudness - loudness;
+        let max_gain_db = MAX_PEAK_DB - peak_db;
+
+        if gain_db > max_gain_db {
+            warn!(
+                "id {lineid:010}: > target loudness not reachable without clipping: \
+                 {:.1} LUFS instead of {:.1} LUFS",
+                loudness + max_gain_db,
+                self.settings.target_loudness
+            );
+            Ok(max_gain_db)
+        } else {
+            Ok(gain_db)
+        }
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+/// <id>[<duration>]<remaining filename>.ogg
+fn conditioned_filename(lineid: u32, duration: f32, audiofile: &Path) -> Result<String, String> {
+    let stem = audiofile
+        .file_stem()
+        .ok_or_else(|| format!("id {lineid:010}: could not extract filename."))?
+        .to_string_lossy();
+
+    // remove max-10 digit prefix and a previous duration tag
+    let remaining = stem
+        .chars()
+        .skip_while(char::is_ascii_digit)
+        .collect::<String>();
+    let remaining = match remaining
+        .strip_prefix('[')
+        .and_then(|tagged| tagged.split_once(']'))
+    {
+        Some((_, remaining)) => remaining.to_owned(),
+        None => remaining,
+    };
+
+    audiofile_name(lineid, duration, None, &format!("{remaining}.ogg"))
+}
+// ----------------------------------------------------------------------------
+// ----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::conditioned_filename;
+
+    #[test]
+    fn test_conditioned_filename() {
+        let filename = |file: &str| conditioned_filename(123, 2.5, Path::new(file));
+
+        assert_eq!(
+            filename("audio/0000000123[3.1000]-geralt-hello_world.wav"),
+            Ok(String::from("0000000123[2.5000]-geralt-hello_world.ogg")),
+            "previous duration tag replaced"
+        );
+        assert_eq!(
+            filename("audio/123 take 2.ogg"),
+            Ok(String::from("0000000123[2.5000] take 2.ogg"))
+        );
+        assert_eq!(
+            filename("0000000123.wem"),
+            Ok(String::from("0000000123[2.5000].ogg"))
+        );
+        // incomplete tag is part of the filename
+        assert_eq!(
+            filename("0000000123[3.1-hello.wav"),
+            Ok(String::from("0000000123[2.5000][3.1-hello.ogg"))
+        );
+        assert!(filename("").is_err());
+    }
+}
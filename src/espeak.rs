@@ -7,7 +7,7 @@
 // ----------------------------------------------------------------------------
 extern crate espeak;
 
-pub use self::espeak::ESpeak;
+pub use self::espeak::{ESpeak, SynthesizedPhoneme, SynthesizedSpeech};
 // ----------------------------------------------------------------------------
 pub trait TextPhonemeTranslator {
     // ------------------------------------------------------------------------
@@ -0,0 +1,947 @@
+//
+// evaluation of extracted phoneme tracks against reference alignments
+//
+
+// ----------------------------------------------------------------------------
+// external interface
+// ----------------------------------------------------------------------------
+/// compares extracted phoneme tracks with reference alignments (edited phonemes
+/// files, praat TextGrids or json) and collects boundary errors and sequence
+/// differences in total, per actor and per phoneme
+#[derive(Default)]
+pub struct Evaluation {
+    lines: usize,
+    total: ErrorStats,
+    actors: BTreeMap<String, ErrorStats>,
+    phonemes: BTreeMap<String, ErrorStats>,
+}
+// ----------------------------------------------------------------------------
+/// boundary errors and sequence differences of a set of phonemes
+#[derive(Clone, Debug, Default)]
+pub struct ErrorStats {
+    /// absolute errors of all compared boundaries in ms
+    errors: Vec<u32>,
+    /// number of reference phonemes
+    pub reference: usize,
+    /// extracted phonemes without reference phoneme
+    pub insertions: usize,
+    /// reference phonemes without extracted phoneme
+    pub deletions: usize,
+    /// extracted phonemes paired with a different reference phoneme
+    pub substitutions: usize,
+}
+// ----------------------------------------------------------------------------
+/// phoneme with timing (in ms) of a reference alignment
+#[derive(Clone, Debug)]
+pub struct ReferenceSegment {
+    pub phoneme: String,
+    pub start: u32,
+    pub end: u32,
+}
+// ----------------------------------------------------------------------------
+/// boundary error thresholds of the reports in ms
+pub const EVALUATION_TOLERANCES_MS: [u32; 2] = [20, 50];
+// ----------------------------------------------------------------------------
+impl Evaluation {
+    // ------------------------------------------------------------------------
+    /// evaluates all phonemes files in extracteddir which have a reference
+    /// alignment with the same id in referencedir. TextGrid and json
+    /// alignments (<id>.alignment.json) take precedence over phonemes files
+    /// (so extracted tracks may be stored next to their references). returns
+    /// number of evaluated and skipped lines.
+    pub fn add_directories(
+        &mut self,
+        extracteddir: PathBuf,
+        referencedir: PathBuf,
+    ) -> Result<(usize, usize), String> {
+        let mut references = BTreeMap::new();
+        let mut phonemefiles = Vec::new();
+        for file in FilesScanner::new(referencedir)?.scan()? {
+            match file {
+                FileInfo::Alignment(lineid, file) => {
+                    if let Some(prev) = references.insert(lineid, file) {
+                        warn!("id {lineid:010}: found duplicate reference. ignoring {prev}");
+                    }
+                }
+                FileInfo::Phonemes(lineid, file) => phonemefiles.push((lineid, file)),
+                FileInfo::Audio(_, _, _) | FileInfo::UnlinkedAudio(_) => {}
+            }
+        }
+        for (lineid, file) in phonemefiles {
+            references.entry(lineid).or_insert(file);
+        }
+
+        let mut evaluated = 0;
+        let mut skipped = 0;
+        for file in FilesScanner::new(extracteddir)?.scan()? {
+            let FileInfo::Phonemes(lineid, phonemefile) = file else {
+                continue;
+            };
+            let Some(referencefile) = references.get(&lineid) else {
+                debug!("id {lineid:010}: no reference alignment found. skipping...");
+                continue;
+            };
+            let result = phonemes::load(lineid, &phonemefile).and_then(|track| {
+                load_reference(lineid, referencefile).map(|reference| (track, reference))
+            });
+            match result {
+                Ok((track, reference)) => {
+                    self.add_line(&track, &reference);
+                    evaluated += 1;
+                }
+                Err(why) => {
+                    warn!("id {lineid:010}: {why} skipping...");
+                    skipped += 1;
+                }
+            }
+        }
+        Ok((evaluated, skipped))
+    }
+    // ------------------------------------------------------------------------
+    /// pairs the aligned (active) phonemes of the track with the reference
+    /// phonemes (min edit distance) and collects the errors of all pairs
+    pub fn add_line(
+        &mut self,
+        track: &PhonemeTrack<PhonemeSegment>,
+        reference: &[ReferenceSegment],
+    ) {
+        let extracted = track
+            .phonemes()
+            .iter()
+            .filter(|p| p.active && p.phoneme != "_")
+            .collect::<Vec<_>>();
+
+        let actor = track
+            .actor()
+            .cloned()
+            .unwrap_or_else(|| String::from("unknown"));
+
+        let mut line = LineStats::default();
+
+        for pairing in Self::pair_phonemes(&extracted, reference) {
+            match pairing {
+                Pairing::Pair(e, r) => {
+                    let (extracted, reference) = (extracted[e], &reference[r]);
+                    let errors = [
+                        extracted.start.abs_diff(reference.start),
+                        extracted.end.abs_diff(reference.end),
+                    ];
+                    let substituted =
+                        extracted.phoneme.to_lowercase() != reference.phoneme.to_lowercase();
+
+                    line.add(&reference.phoneme, |stats| {
+                        stats.reference += 1;
+                        stats.errors.extend_from_slice(&errors);
+                        if substituted {
+                            stats.substitutions += 1;
+                        }
+                    });
+                }
+                Pairing::Insertion(e) => {
+                    line.add(&extracted[e].phoneme, |stats| stats.insertions += 1);
+                }
+                Pairing::Deletion(r) => {
+                    line.add(&reference[r].phoneme, |stats| {
+                        stats.reference += 1;
+                        stats.deletions += 1;
+                    });
+                }
+            }
+        }
+
+        for (phoneme, stats) in line.phonemes {
+            self.total.merge(&stats);
+            self.actors.entry(actor.clone()).or_default().merge(&stats);
+            self.phonemes.entry(phoneme).or_default().merge(&stats);
+        }
+        self.lines += 1;
+    }
+    // ------------------------------------------------------------------------
+    /// number of evaluated lines
+    pub fn lines(&self) -> usize {
+        self.lines
+    }
+    // ------------------------------------------------------------------------
+    pub fn total(&self) -> &ErrorStats {
+        &self.total
+    }
+    // ------------------------------------------------------------------------
+    /// human readable summary with per actor breakdown
+    pub fn summary(&self) -> Vec<String> {
+        let total = &self.total;
+        let mut summary = vec![
+            format!(
+                "evaluated lines: {}, reference phonemes: {}, compared boundaries: {}",
+                self.lines,
+                total.reference,
+                total.boundaries()
+            ),
+            format!(
+                "boundary error: mean {:.1} ms, median {} ms",
+                total.mean_error(),
+                total.median_error()
+            ),
+        ];
+        summary.push(
+            EVALUATION_TOLERANCES_MS
+                .iter()
+                .map(|ms| format!("within {ms} ms: {:.1}%", total.within(*ms) * 100.0))
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+        summary.push(format!(
+            "insertion rate: {:.1}%, deletion rate: {:.1}%, substitution rate: {:.1}%",
+            total.insertion_rate() * 100.0,
+            total.deletion_rate() * 100.0,
+            total.substitution_rate() * 100.0
+        ));
+        summary.push(String::from("per actor:"));
+        for (actor, stats) in &self.actors {
+            summary.push(format!(
+                "  {actor}: phonemes {}, mean {:.1} ms, median {} ms, within {} ms {:.1}%, \
+                 insertions {:.1}%, deletions {:.1}%",
+                stats.reference,
+                stats.mean_error(),
+                stats.median_error(),
+                EVALUATION_TOLERANCES_MS[0],
+                stats.within(EVALUATION_TOLERANCES_MS[0]) * 100.0,
+                stats.insertion_rate() * 100.0,
+                stats.deletion_rate() * 100.0
+            ));
+        }
+        summary
+    }
+    // ------------------------------------------------------------------------
+    /// stores the summary and the per phoneme breakdown as plain text
+    pub fn store_text(&self, file: &Path) -> Result<(), String> {
+        let mut report = self.summary();
+        report.push(String::from("per phoneme:"));
+        for (phoneme, stats) in &self.phonemes {
+            report.push(format!(
+                "  {phoneme}: phonemes {}, mean {:.1} ms, median {} ms, within {} ms {:.1}%, \
+                 insertions {}, deletions {}, substitutions {}",
+                stats.reference,
+                stats.mean_error(),
+                stats.median_error(),
+                EVALUATION_TOLERANCES_MS[0],
+                stats.within(EVALUATION_TOLERANCES_MS[0]) * 100.0,
+                stats.insertions,
+                stats.deletions,
+                stats.substitutions
+            ));
+        }
+        report.push(String::new());
+
+        fs::write(file, report.join("\n"))
+            .map_err(|err| format!("failed to write {}: {err}", file.display()))
+    }
+    // ------------------------------------------------------------------------
+    /// stores total, per actor and per phoneme metrics as csv
+    pub fn store_csv(&self, file: &Path) -> Result<(), String> {
+        let mut writer = SimpleCsvWriter::create(&file.to_path_buf())
+            .map_err(|err| format!("failed to create {}: {err}", file.display()))?;
+
+        writer.write_comment(&format!(
+            " boundary errors of #{} evaluated lines",
+            self.lines
+        ));
+        writer.write_comment(&format!(
+            " group|name|phonemes|boundaries|mean error (ms)|median error (ms)|{}|\
+             insertions|deletions|substitutions",
+            EVALUATION_TOLERANCES_MS
+                .iter()
+                .map(|ms| format!("within {ms} ms"))
+                .collect::<Vec<_>>()
+                .join("|")
+        ));
+
+        let rows = iter::once(("total", "all", &self.total))
+            .chain(self.actors.iter().map(|(a, s)| ("actor", a.as_str(), s)))
+            .chain(
+                self.phonemes
+                    .iter()
+                    .map(|(p, s)| ("phoneme", p.as_str(), s)),
+            );
+
+        for (group, name, stats) in rows {
+            writer.writeln(&format!(
+                "{group}|{name}|{}|{}|{:.1}|{}|{}|{}|{}|{}",
+                stats.reference,
+                stats.boundaries(),
+                stats.mean_error(),
+                stats.median_error(),
+                EVALUATION_TOLERANCES_MS
+                    .iter()
+                    .map(|ms| format!("{:.3}", stats.within(*ms)))
+                    .collect::<Vec<_>>()
+                    .join("|"),
+                stats.insertions,
+                stats.deletions,
+                stats.substitutions
+            ));
+        }
+        Ok(())
+    }
+    // ------------------------------------------------------------------------
+    /// stores total, per actor and per phoneme metrics as json
+    pub fn store_json(&self, file: &Path) -> Result<(), String> {
+        let group = |stats: &BTreeMap<String, ErrorStats>| {
+            stats
+                .iter()
+                .map(|(name, stats)| format!("    {}: {}", json_string(name), stats.to_json()))
+                .collect::<Vec<_>>()
+                .join(",\n")
+        };
+        let json = format!(
+            "{{\n  \"lines\": {},\n  \"total\": {},\n  \"actors\": {{\n{}\n  }},\n  \
+             \"phonemes\": {{\n{}\n  }}\n}}\n",
+            self.lines,
+            self.total.to_json(),
+            group(&self.actors),
+            group(&self.phonemes)
+        );
+
+        fs::write(file, json).map_err(|err| format!("failed to write {}: {err}", file.display()))
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl ErrorStats {
+    // ------------------------------------------------------------------------
+    /// number of compared boundaries (start and end of every paired phoneme)
+    pub fn boundaries(&self) -> usize {
+        self.errors.len()
+    }
+    // ------------------------------------------------------------------------
+    /// mean absolute boundary error in ms
+    pub fn mean_error(&self) -> f32 {
+        if self.errors.is_empty() {
+            0.0
+        } else {
+            self.errors.iter().map(|e| *e as f32).sum::<f32>() / self.errors.len() as f32
+        }
+    }
+    // ------------------------------------------------------------------------
+    /// median absolute boundary error in ms (mean of the two middle errors on an
+    /// even number of boundaries)
+    pub fn median_error(&self) -> u32 {
+        let mut errors = self.errors.clone();
+        errors.sort_unstable();
+        let middle = errors.len() / 2;
+        match errors.len() {
+            0 => 0,
+            len if len % 2 == 0 => (errors[middle - 1] + errors[middle]) / 2,
+            _ => errors[middle],
+        }
+    }
+    // ------------------------------------------------------------------------
+    /// share of boundaries with an error of at most ms
+    pub fn within(&self, ms: u32) -> f32 {
+        Self::rate(
+            self.errors.iter().filter(|e| **e <= ms).count(),
+            self.errors.len(),
+        )
+    }
+    // ------------------------------------------------------------------------
+    /// inserted phonemes relative to the number of reference phonemes
+    pub fn insertion_rate(&self) -> f32 {
+        Self::rate(self.insertions, self.reference)
+    }
+    // ------------------------------------------------------------------------
+    pub fn deletion_rate(&self) -> f32 {
+        Self::rate(self.deletions, self.reference)
+    }
+    // ------------------------------------------------------------------------
+    pub fn substitution_rate(&self) -> f32 {
+        Self::rate(self.substitutions, self.reference)
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+/// loads the phonemes of a reference alignment: active phonemes of an edited
+/// phonemes file, the phone tier of a praat TextGrid (long text format) or a
+/// json list of objects with "phoneme", "start" and "end" (in ms).
+pub fn load_reference(lineid: u32, file: &str) -> Result<Vec<ReferenceSegment>, String> {
+    let segments = if file.ends_with(".phonemes") {
+        let track = phonemes::load(lineid, file)?;
+        if track.version() <= 1 {
+            return Err(format!("reference {file} is not edited."));
+        }
+        track
+            .phonemes()
+            .iter()
+            .filter(|p| p.active && p.phoneme != "_")
+            .map(|p| ReferenceSegment {
+                phoneme: p.phoneme.clone(),
+                start: p.start,
+                end: p.end,
+            })
+            .collect()
+    } else {
+        let content =
+            fs::read_to_string(file).map_err(|err| format!("failed to load {file}: {err}"))?;
+
+        if file.ends_with(".TextGrid") {
+            parse_textgrid(&content).map_err(|why| format!("{file}: {why}"))?
+        } else {
+            parse_json(&content).map_err(|why| format!("{file}: {why}"))?
+        }
+    };
+
+    if segments.is_empty() {
+        Err(format!("reference {file} contains no phonemes."))
+    } else {
+        Ok(segments)
+    }
+}
+// ----------------------------------------------------------------------------
+/// stores phonemes as json reference alignment (format of load_reference)
+pub fn store_reference(file: &Path, segments: &[ReferenceSegment]) -> Result<(), String> {
+    let phonemes = segments
+        .iter()
+        .map(|s| {
+            format!(
+                "    {{\"phoneme\": {}, \"start\": {}, \"end\": {}}}",
+                json_string(&s.phoneme),
+                s.start,
+                s.end
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",\n");
+
+    fs::write(
+        file,
+        format!("{{\n  \"phonemes\": [\n{phonemes}\n  ]\n}}\n"),
+    )
+    .map_err(|err| format!("failed to write {}: {err}", file.display()))
+}
+// ----------------------------------------------------------------------------
+// internals
+// ----------------------------------------------------------------------------
+use std::collections::BTreeMap;
+use std::fs;
+use std::iter;
+use std::path::{Path, PathBuf};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use file_scanner::{FileInfo, FilesScanner};
+use phonemes::{self, PhonemeSegment, PhonemeTrack};
+use text::{CsvWriter, SimpleCsvWriter};
+// ----------------------------------------------------------------------------
+/// names of TextGrid tiers with phoneme intervals
+const TEXTGRID_PHONEME_TIERS: &[&str] = &["phones", "phonemes", "phone", "phoneme"];
+/// TextGrid labels of silences and pauses
+const TEXTGRID_SILENCE_LABELS: &[&str] = &["", "sil", "sp", "spn", "<sil>"];
+
+lazy_static! {
+    // innermost json object
+    static ref REGEXP_JSON_OBJECT: Regex = Regex::new(r"\{[^{}]*\}").unwrap();
+    static ref REGEXP_JSON_PHONEME: Regex =
+        Regex::new(r#""phoneme"\s*:\s*"((?:[^"\\]|\\.)*)""#).unwrap();
+    static ref REGEXP_JSON_START: Regex = Regex::new(r#""start"\s*:\s*([0-9.]+)"#).unwrap();
+    static ref REGEXP_JSON_END: Regex = Regex::new(r#""end"\s*:\s*([0-9.]+)"#).unwrap();
+}
+// ----------------------------------------------------------------------------
+enum Pairing {
+    /// extracted and reference phoneme
+    Pair(usize, usize),
+    Insertion(usize),
+    Deletion(usize),
+}
+// ----------------------------------------------------------------------------
+/// stats of one line per phoneme
+#[derive(Default)]
+struct LineStats {
+    phonemes: BTreeMap<String, ErrorStats>,
+}
+// ----------------------------------------------------------------------------
+impl LineStats {
+    // ------------------------------------------------------------------------
+    fn add<F: FnOnce(&mut ErrorStats)>(&mut self, phoneme: &str, update: F) {
+        update(self.phonemes.entry(phoneme.to_owned()).or_default());
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl Evaluation {
+    // ------------------------------------------------------------------------
+    /// min edit distance alignment of the phoneme labels. pairs are preferred
+    /// to insertions and deletions on equal costs.
+    fn pair_phonemes(
+        extracted: &[&PhonemeSegment],
+        reference: &[ReferenceSegment],
+    ) -> Vec<Pairing> {
+        let (rows, cols) = (extracted.len() + 1, reference.len() + 1);
+        let mut costs = vec![0u32; rows * cols];
+        let is_same = |e: usize, r: usize| {
+            extracted[e].phoneme.to_lowercase() == reference[r].phoneme.to_lowercase()
+        };
+
+        for e in 0..rows {
+            for r in 0..cols {
+                costs[e * cols + r] = match (e, r) {
+                    (0, _) => r as u32,
+                    (_, 0) => e as u32,
+                    _ => {
+                        let pair =
+                            costs[(e - 1) * cols + r - 1] + u32::from(!is_same(e - 1, r - 1));
+                        let insertion = costs[(e - 1) * cols + r] + 1;
+                        let deletion = costs[e * cols + r - 1] + 1;
+                        pair.min(insertion).min(deletion)
+                    }
+                };
+            }
+        }
+
+        let mut pairings = Vec::with_capacity(rows.max(cols));
+        let (mut e, mut r) = (extracted.len(), reference.len());
+        while e > 0 || r > 0 {
+            let cost = costs[e * cols + r];
+            if e > 0
+                && r > 0
+                && cost == costs[(e - 1) * cols + r - 1] + u32::from(!is_same(e - 1, r - 1))
+            {
+                e -= 1;
+                r -= 1;
+                pairings.push(Pairing::Pair(e, r));
+            } else if e > 0 && cost == costs[(e - 1) * cols + r] + 1 {
+                e -= 1;
+                pairings.push(Pairing::Insertion(e));
+            } else {
+                r -= 1;
+                pairings.push(Pairing::Deletion(r));
+            }
+        }
+        pairings.reverse();
+        pairings
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl ErrorStats {
+    // ------------------------------------------------------------------------
+    fn merge(&mut self, other: &ErrorStats) {
+        self.errors.extend_from_slice(&other.errors);
+        self.reference += other.reference;
+        self.insertions += other.insertions;
+        self.deletions += other.deletions;
+        self.substitutions += other.substitutions;
+    }
+    // ------------------------------------------------------------------------
+    fn rate(count: usize, total: usize) -> f32 {
+        if total > 0 {
+            count as f32 / total as f32
+        } else {
+            0.0
+        }
+    }
+    // ------------------------------------------------------------------------
+    fn to_json(&self) -> String {
+        format!(
+            "{{\"phonemes\": {}, \"boundaries\": {}, \"mean_error_ms\": {:.1}, \
+             \"median_error_ms\": {}, {}, \"insertions\": {}, \"deletions\": {}, \
+             \"substitutions\": {}}}",
+            self.reference,
+            self.boundaries(),
+            self.mean_error(),
+            self.median_error(),
+            EVALUATION_TOLERANCES_MS
+                .iter()
+                .map(|ms| format!("\"within_{ms}ms\": {:.3}", self.within(*ms)))
+                .collect::<Vec<_>>()
+                .join(", "),
+            self.insertions,
+            self.deletions,
+            self.substitutions
+        )
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+// ----------------------------------------------------------------------------
+/// decodes the escape sequences of a json string (without quotes). returns
+/// None on invalid escapes.
+fn json_unescape(value: &str) -> Option<String> {
+    let hex4 = |s: &str| {
+        s.get(..4)
+            .filter(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()))
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+    };
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        let c = match chars.next()? {
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'u' => {
+                let mut code = hex4(chars.as_str())?;
+                chars.nth(3);
+                // surrogate pair for chars outside the basic multilingual plane
+                if (0xd800..0xdc00).contains(&code) {
+                    let low = hex4(chars.as_str().strip_prefix("\\u")?)?;
+                    if !(0xdc00..0xe000).contains(&low) {
+                        return None;
+                    }
+                    chars.nth(5);
+                    code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                }
+                char::from_u32(code)?
+            }
+            c @ ('"' | '\\' | '/') => c,
+            _ => return None,
+        };
+        unescaped.push(c);
+    }
+    Some(unescaped)
+}
+// ----------------------------------------------------------------------------
+fn seconds_to_ms(value: &str) -> Option<u32> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .map(|secs| (secs * 1000.0).round() as u32)
+}
+// ----------------------------------------------------------------------------
+/// intervals of the phoneme tier (or the only interval tier) without silences
+fn parse_textgrid(content: &str) -> Result<Vec<ReferenceSegment>, String> {
+    // (tier name, intervals)
+    let mut tiers: Vec<(String, Vec<ReferenceSegment>)> = Vec::new();
+    let mut interval: Option<(Option<u32>, Option<u32>)> = None;
+
+    for line in content.lines().map(str::trim) {
+        let Some((key, value)) = line.split_once('=') else {
+            if line.starts_with("item [") && !line.starts_with("item []") {
+                tiers.push((String::new(), Vec::new()));
+            }
+            if line.starts_with("intervals [") {
+                interval = Some((None, None));
+            }
+            continue;
+        };
+        let value = value.trim();
+        match (key.trim(), interval.as_mut(), tiers.last_mut()) {
+            ("name", None, Some(tier)) => {
+                tier.0 = value.trim_matches('"').to_owned();
+            }
+            ("xmin", Some(interval), _) => interval.0 = seconds_to_ms(value),
+            ("xmax", Some(interval), _) => interval.1 = seconds_to_ms(value),
+            ("text", Some((start, end)), Some(tier)) => {
+                let label = value
+                    .strip_prefix('"')
+                    .and_then(|v| v.strip_suffix('"'))
+                    .unwrap_or(value)
+                    .replace("\"\"", "\"");
+
+                if let (Some(start), Some(end)) = (*start, *end) {
+                    tier.1.push(ReferenceSegment {
+                        phoneme: label.trim().to_owned(),
+                        start,
+                        end,
+                    });
+                }
+                interval = None;
+            }
+            _ => {}
+        }
+    }
+
+    if tiers.is_empty() {
+        return Err(String::from(
+            "no interval tiers found (only TextGrids in long text format are supported).",
+        ));
+    }
+    let tier = match tiers
+        .iter()
+        .position(|(name, _)| TEXTGRID_PHONEME_TIERS.contains(&name.to_lowercase().as_str()))
+    {
+        Some(i) => tiers.swap_remove(i),
+        None if tiers.len() == 1 => tiers.swap_remove(0),
+        None => {
+            return Err(format!(
+                "no phoneme tier found (expected one of: {}).",
+                TEXTGRID_PHONEME_TIERS.join(", ")
+            ))
+        }
+    };
+
+    Ok(tier
+        .1
+        .into_iter()
+        .filter(|s| !TEXTGRID_SILENCE_LABELS.contains(&s.phoneme.to_lowercase().as_str()))
+        .collect())
+}
+// ----------------------------------------------------------------------------
+/// all objects with phoneme, start and end (in ms). fails for the first object
+/// with a missing or invalid field.
+fn parse_json(content: &str) -> Result<Vec<ReferenceSegment>, String> {
+    REGEXP_JSON_OBJECT
+        .find_iter(content)
+        .enumerate()
+        .map(|(i, object)| {
+            let object = object.as_str();
+            let invalid = |field: &str| {
+                format!(
+                    "missing or invalid {field} in phoneme object #{}: {object}",
+                    i + 1
+                )
+            };
+            let timing = |regex: &Regex| {
+                regex
+                    .captures(object)
+                    .and_then(|captures| captures[1].parse::<f64>().ok())
+            };
+
+            let phoneme = REGEXP_JSON_PHONEME
+                .captures(object)
+                .and_then(|captures| json_unescape(&captures[1]))
+                .ok_or_else(|| invalid("phoneme"))?;
+            let start = timing(&REGEXP_JSON_START).ok_or_else(|| invalid("start"))?;
+            let end = timing(&REGEXP_JSON_END).ok_or_else(|| invalid("end"))?;
+
+            Ok(ReferenceSegment {
+                phoneme,
+                start: start.round() as u32,
+                end: end.round() as u32,
+            })
+        })
+        .collect()
+}
+// ----------------------------------------------------------------------------
+// ----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::super::phonemes::PhonemeSegment;
+    use super::{
+        json_unescape, parse_json, parse_textgrid, ErrorStats, Evaluation, Pairing,
+        ReferenceSegment,
+    };
+
+    fn to_tuples(segments: &[ReferenceSegment]) -> Vec<(&str, u32, u32)> {
+        segments
+            .iter()
+            .map(|s| (s.phoneme.as_str(), s.start, s.end))
+            .collect()
+    }
+
+    fn reference(phonemes: &[&str]) -> Vec<ReferenceSegment> {
+        phonemes
+            .iter()
+            .map(|p| ReferenceSegment {
+                phoneme: (*p).to_owned(),
+                start: 0,
+                end: 0,
+            })
+            .collect()
+    }
+
+    fn extracted(phonemes: &[&str]) -> Vec<PhonemeSegment> {
+        phonemes
+            .iter()
+            .map(|p| PhonemeSegment {
+                phoneme: (*p).to_owned(),
+                word_start: false,
+                start: 0,
+                end: 0,
+                weight: 1.0,
+                score: 1.0,
+                matching_info: None,
+                traceback: None,
+                active: true,
+                warnings: Vec::default(),
+            })
+            .collect()
+    }
+
+    fn pairings_to_string(pairings: &[Pairing]) -> String {
+        pairings
+            .iter()
+            .map(|p| match p {
+                Pairing::Pair(e, r) => format!("{}{}", e, r),
+                Pairing::Insertion(e) => format!("+{}", e),
+                Pairing::Deletion(r) => format!("-{}", r),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn test_parse_textgrid() {
+        let textgrid = r#"File type = "ooTextFile"
+Object class = "TextGrid"
+
+xmin = 0
+xmax = 0.5
+tiers? <exists>
+size = 2
+item []:
+    item [1]:
+        class = "IntervalTier"
+        name = "words"
+        xmin = 0
+        xmax = 0.5
+        intervals: size = 1
+        intervals [1]:
+            xmin = 0
+            xmax = 0.5
+            text = "hi"
+    item [2]:
+        class = "IntervalTier"
+        name = "phones"
+        xmin = 0
+        xmax = 0.5
+        intervals: size = 4
+        intervals [1]:
+            xmin = 0
+            xmax = 0.1
+            text = "sil"
+        intervals [2]:
+            xmin = 0.1
+            xmax = 0.25
+            text = "h"
+        intervals [3]:
+            xmin = 0.25
+            xmax = 0.4805
+            text = "a""ɪ"
+        intervals [4]:
+            xmin = 0.4805
+            xmax = 0.5
+            text = ""
+"#;
+        let segments = parse_textgrid(textgrid).unwrap();
+        assert_eq!(
+            to_tuples(&segments),
+            [("h", 100, 250), ("a\"ɪ", 250, 481)],
+            "phone tier without silences"
+        );
+
+        let no_phoneme_tier = textgrid.replace("\"phones\"", "\"syllables\"");
+        assert!(parse_textgrid(&no_phoneme_tier).is_err());
+        assert!(parse_textgrid("File type = \"ooTextFile short\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let json = r#"{
+  "phonemes": [
+    {"phoneme": "h", "start": 0, "end": 150},
+    {"start": 150, "end": 479.6, "phoneme": "a\u026a"},
+    {"phoneme": "\"\\\/", "start": 480, "end": 500},
+    {"phoneme": "\ud83d\ude00", "start": 500, "end": 510}
+  ]
+}"#;
+        assert_eq!(
+            to_tuples(&parse_json(json).unwrap()),
+            [
+                ("h", 0, 150),
+                ("aɪ", 150, 480),
+                ("\"\\/", 480, 500),
+                ("\u{1f600}", 500, 510)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_json_invalid_objects() {
+        let error = |object: &str| {
+            let json = format!(r#"[{{"phoneme": "h", "start": 0, "end": 150}}, {object}]"#);
+            parse_json(&json).map(|segments| segments.len())
+        };
+
+        assert_eq!(
+            error(r#"{"phoneme": "no timing"}"#),
+            Err(String::from(
+                r#"missing or invalid start in phoneme object #2: {"phoneme": "no timing"}"#
+            ))
+        );
+        assert_eq!(
+            error(r#"{"phoneme": "\x", "start": 150, "end": 200}"#),
+            Err(String::from(
+                r#"missing or invalid phoneme in phoneme object #2: {"phoneme": "\x", "start": 150, "end": 200}"#
+            )),
+            "invalid escape"
+        );
+        assert!(error(r#"{"start": 150, "end": 200}"#).is_err());
+        assert!(error(r#"{"phoneme": "a", "start": 150, "end": 1.2.3}"#).is_err());
+    }
+
+    #[test]
+    fn test_json_unescape() {
+        assert_eq!(
+            json_unescape(r#"\b\f\n\r\t\u00e6"#),
+            Some(String::from("\u{8}\u{c}\n\r\tæ"))
+        );
+        assert_eq!(json_unescape(r#"\"#), None, "unterminated escape");
+        assert_eq!(json_unescape(r#"\u00g0"#), None, "invalid hex digits");
+        assert_eq!(json_unescape(r#"\u+0e6"#), None, "sign is no hex digit");
+        assert_eq!(json_unescape(r#"\ud83d"#), None, "missing low surrogate");
+        assert_eq!(
+            json_unescape(r#"\ud83d\u0041"#),
+            None,
+            "invalid low surrogate"
+        );
+    }
+
+    #[test]
+    fn test_pair_phonemes() {
+        let e = extracted(&["h", "A", "x", "l", "o"]);
+        let e = e.iter().collect::<Vec<_>>();
+        let r = reference(&["h", "a", "l", "l", "o"]);
+
+        let pairings = Evaluation::pair_phonemes(&e, &r);
+        assert_eq!(
+            pairings_to_string(&pairings),
+            "00 11 22 33 44",
+            "substitution is preferred to insertion and deletion"
+        );
+
+        let e = extracted(&["h", "a", "x", "l", "o"]);
+        let e = e.iter().collect::<Vec<_>>();
+        let r = reference(&["h", "a", "l", "o", "u"]);
+
+        let pairings = Evaluation::pair_phonemes(&e, &r);
+        assert_eq!(pairings_to_string(&pairings), "00 11 +2 32 43 -4");
+
+        let r = reference(&["a"]);
+        assert_eq!(
+            pairings_to_string(&Evaluation::pair_phonemes(&[], &r)),
+            "-0"
+        );
+    }
+
+    #[test]
+    fn test_median_error() {
+        let mut stats = ErrorStats::default();
+        assert_eq!(stats.median_error(), 0);
+
+        stats.errors = vec![30, 10, 20];
+        assert_eq!(stats.median_error(), 20);
+
+        stats.errors = vec![40, 10, 30, 0];
+        assert_eq!(stats.median_error(), 20, "mean of the middle errors");
+    }
+}
@@ -0,0 +1,532 @@
+//
+// audio phoneme extractor
+//
+
+// ----------------------------------------------------------------------------
+// external interface
+// ----------------------------------------------------------------------------
+pub trait AudioPhonemeExtractor {
+    // ------------------------------------------------------------------------
+    /// extracts timed phonemes of the spoken text from (16kHz) audio
+    fn extract_phonemes(
+        &mut self,
+        lineid: u32,
+        raw_audio_data: &[i16],
+        text: &str,
+        phonetizer: &WordPhonetizer,
+    ) -> Result<PhonemeResult, String>;
+    // ------------------------------------------------------------------------
+    /// extracts up to max alternative timed phoneme hypotheses of the spoken
+    /// text. the first one is the best hypothesis of the extractor.
+    fn extract_phoneme_alternatives(
+        &mut self,
+        lineid: u32,
+        raw_audio_data: &[i16],
+        text: &str,
+        phonetizer: &WordPhonetizer,
+        _max: usize,
+    ) -> Result<Vec<PhonemeResult>, String> {
+        Ok(vec![self.extract_phonemes(
+            lineid,
+            raw_audio_data,
+            text,
+            phonetizer,
+        )?])
+    }
+    // ------------------------------------------------------------------------
+    /// recognizes timed phonemes from (16kHz) audio without any text
+    /// constraint
+    fn recognize_phonemes(
+        &mut self,
+        lineid: u32,
+        raw_audio_data: &[i16],
+    ) -> Result<PhonemeResult, String>;
+    // ------------------------------------------------------------------------
+    /// re-decodes a short (16kHz) audio window with a grammar that accepts only
+    /// the given sequence of extractor phonemes (e.g. a single word) with
+    /// optional surrounding silence
+    fn realign_phonemes(
+        &mut self,
+        lineid: u32,
+        raw_audio_data: &[i16],
+        phonemes: &str,
+    ) -> Result<PhonemeResult, String>;
+    // ------------------------------------------------------------------------
+    /// enables (default) marking of word starts for hypotheses without them
+    /// (may require an additional decoding)
+    fn set_word_start_anchoring(&mut self, _enabled: bool) {}
+    // ------------------------------------------------------------------------
+    /// selects the speaker specific adaptation (if available) for following
+    /// extractions. None resets to the speaker independent model.
+    fn set_speaker(&mut self, _actor: Option<&str>) -> Result<(), String> {
+        Ok(())
+    }
+    // ------------------------------------------------------------------------
+    /// estimates a speaker specific adaptation of the acoustic model from the
+    /// corpus. returns the stored adaptation file.
+    fn adapt_speaker(
+        &mut self,
+        actor: &str,
+        _corpus: &AdaptationCorpus,
+    ) -> Result<PathBuf, String> {
+        Err(format!(
+            "speaker adaptation for {actor} not supported by extractor."
+        ))
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+/// (16kHz) audio and corrected transcriptions of lines of one speaker
+pub struct AdaptationCorpus {
+    /// directory with <lineid>.wav audiofiles
+    pub dir: PathBuf,
+    /// list of line ids (one per line)
+    pub fileids: PathBuf,
+    /// extractor phoneme transcriptions as <s> phonemes </s> (<lineid>)
+    pub transcription: PathBuf,
+    pub lines: usize,
+}
+// ----------------------------------------------------------------------------
+/// filesystem safe name for files of an actor: the lowercased alphanumerics of
+/// the name followed by a (stable) hash of the full name so different actors
+/// never share files
+pub fn speaker_filename(actor: &str) -> String {
+    // fnv-1a
+    let hash = actor.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    let name = actor
+        .chars()
+        .map(|c| match c {
+            c if c.is_alphanumeric() => c.to_ascii_lowercase(),
+            _ => '_',
+        })
+        .collect::<String>();
+
+    format!("{name}-{hash:016x}")
+}
+// ----------------------------------------------------------------------------
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ExtractionMethod {
+    /// allphone decoding constrained by a jsgf grammar generated from text
+    #[default]
+    Allphone,
+    /// forced alignment of the dictionary transcription of text
+    Forced,
+    /// external aligner defined in language config
+    External,
+}
+// ----------------------------------------------------------------------------
+/// creates the audio phoneme extractor for language. method overrides the
+/// extraction method defined in language config. overrides is an optional
+/// (project specific) config with decoder settings.
+pub fn create_extractor(
+    datadir: &Path,
+    language: &str,
+    method: Option<ExtractionMethod>,
+    overrides: Option<&Path>,
+    loglevel: LevelFilter,
+) -> Result<Box<dyn AudioPhonemeExtractor>, String> {
+    let mut pocketsphinx =
+        PocketSphinx::new(&datadir.to_string_lossy(), language, overrides, loglevel)?;
+
+    let method = method.unwrap_or_else(|| pocketsphinx.extraction_method());
+
+    match method {
+        ExtractionMethod::External => {
+            let command = pocketsphinx
+                .external_aligner()
+                .map(str::to_owned)
+                .ok_or_else(|| {
+                    format!("extraction method external requires EXTERNAL-ALIGNER setting in {language}.pocketsphinx.cfg")
+                })?;
+
+            info!("> using external aligner: {command}");
+            Ok(Box::new(ExternalAligner::new(command, pocketsphinx)))
+        }
+        _ => {
+            pocketsphinx.set_extraction_method(method);
+            Ok(Box::new(pocketsphinx))
+        }
+    }
+}
+// ----------------------------------------------------------------------------
+// internals
+// ----------------------------------------------------------------------------
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::slice;
+use std::str::FromStr;
+
+use logger::LevelFilter;
+
+use audio::AudioWriter;
+use phonemes::{PhonemeResult, PhonemeSegment};
+use pocketsphinx::PocketSphinx;
+
+use super::WordPhonetizer;
+// ----------------------------------------------------------------------------
+/// runs an external aligner for every line. the commandline may contain the
+/// placeholders {audio}, {text}, {output} and {lineid}. the aligner is
+/// expected to write one phoneme per line into {output}:
+///     phoneme|start ms|end ms[|confidence 0..1]
+/// phonemes must be ordered and must not overlap. lines starting with ; are
+/// ignored.
+struct ExternalAligner {
+    command: String,
+    workdir: PathBuf,
+    /// used if the external aligner fails and for unconstrained recognition
+    fallback: PocketSphinx,
+}
+// ----------------------------------------------------------------------------
+impl FromStr for ExtractionMethod {
+    type Err = String;
+    // ------------------------------------------------------------------------
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "allphone" => Ok(ExtractionMethod::Allphone),
+            "forced" => Ok(ExtractionMethod::Forced),
+            "external" => Ok(ExtractionMethod::External),
+            _ => Err(format!(
+                "unsupported extraction method: {s}. expected allphone, forced or external"
+            )),
+        }
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl fmt::Display for ExtractionMethod {
+    // ------------------------------------------------------------------------
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExtractionMethod::Allphone => write!(f, "allphone"),
+            ExtractionMethod::Forced => write!(f, "forced"),
+            ExtractionMethod::External => write!(f, "external"),
+        }
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl ExternalAligner {
+    // ------------------------------------------------------------------------
+    fn new(command: String, fallback: PocketSphinx) -> ExternalAligner {
+        ExternalAligner {
+            command,
+            workdir: std::env::temp_dir().join("w3phonemes-external"),
+            fallback,
+        }
+    }
+    // ------------------------------------------------------------------------
+    fn align(
+        &self,
+        lineid: u32,
+        raw_audio_data: &[i16],
+        text: &str,
+    ) -> Result<PhonemeResult, String> {
+        fs::create_dir_all(&self.workdir)
+            .map_err(|err| format!("external aligner: failed to create workdir: {err}"))?;
+
+        let audiofile = self.workdir.join(format!("{lineid:010}.wav"));
+        let textfile = self.workdir.join(format!("{lineid:010}.txt"));
+        let outputfile = self.workdir.join(format!("{lineid:010}.phonemes"));
+
+        AudioWriter::write_wav(&audiofile, raw_audio_data, 16000)?;
+        fs::write(&textfile, text)
+            .map_err(|err| format!("external aligner: failed to write text: {err}"))?;
+        // prevent picking up results from a previous run
+        let _ = fs::remove_file(&outputfile);
+
+        let result = self.run(lineid, &audiofile, &textfile, &outputfile);
+
+        let _ = fs::remove_file(&audiofile);
+        let _ = fs::remove_file(&textfile);
+        let _ = fs::remove_file(&outputfile);
+
+        result
+    }
+    // ------------------------------------------------------------------------
+    fn run(
+        &self,
+        lineid: u32,
+        audiofile: &Path,
+        textfile: &Path,
+        outputfile: &Path,
+    ) -> Result<PhonemeResult, String> {
+        let args = self
+            .command
+            .split_whitespace()
+            .map(|arg| {
+                arg.replace("{audio}", &audiofile.to_string_lossy())
+                    .replace("{text}", &textfile.to_string_lossy())
+                    .replace("{output}", &outputfile.to_string_lossy())
+                    .replace("{lineid}", &lineid.to_string())
+            })
+            .collect::<Vec<_>>();
+
+        let (program, args) = args
+            .split_first()
+            .ok_or_else(|| String::from("external aligner: empty commandline"))?;
+
+        trace!(">> running {program} {}", args.join(" "));
+        let output = Command::new(program)
+            .args(args)
+            .output()
+            .map_err(|err| format!("external aligner: failed to run {program}: {err}"))?;
+
+        if !output.status.success() {
+            return Err(format!(
+                "external aligner: {program} failed ({}): {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        let content = fs::read_to_string(outputfile)
+            .map_err(|err| format!("external aligner: failed to read result: {err}"))?;
+
+        let phonemes = parse_alignment(&content)?;
+        if phonemes.is_empty() {
+            return Err(String::from("external aligner: returned no phonemes."));
+        }
+
+        Ok(PhonemeResult {
+            hypothesis: Some(
+                phonemes
+                    .iter()
+                    .map(|p| p.phoneme.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ),
+            phonemes,
+        })
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl AudioPhonemeExtractor for ExternalAligner {
+    // ------------------------------------------------------------------------
+    fn extract_phonemes(
+        &mut self,
+        lineid: u32,
+        raw_audio_data: &[i16],
+        text: &str,
+        phonetizer: &WordPhonetizer,
+    ) -> Result<PhonemeResult, String> {
+        trace!("> external aligner: aligning phonemes...");
+
+        match self.align(lineid, raw_audio_data, text) {
+            Ok(mut result) => {
+                // external phoneme alignments provide no word boundaries
+                self.fallback.anchor_word_starts(
+                    lineid,
+                    raw_audio_data,
+                    text,
+                    phonetizer,
+                    slice::from_mut(&mut result),
+                );
+                Ok(result)
+            }
+            Err(why) => {
+                warn!("{lineid:010}: {why} falling back to pocketsphinx...");
+                self.fallback
+                    .extract_phonemes(lineid, raw_audio_data, text, phonetizer)
+            }
+        }
+    }
+    // ------------------------------------------------------------------------
+    fn recognize_phonemes(
+        &mut self,
+        lineid: u32,
+        raw_audio_data: &[i16],
+    ) -> Result<PhonemeResult, String> {
+        self.fallback.recognize_phonemes(lineid, raw_audio_data)
+    }
+    // ------------------------------------------------------------------------
+    fn realign_phonemes(
+        &mut self,
+        lineid: u32,
+        raw_audio_data: &[i16],
+        phonemes: &str,
+    ) -> Result<PhonemeResult, String> {
+        self.fallback
+            .realign_phonemes(lineid, raw_audio_data, phonemes)
+    }
+    // ------------------------------------------------------------------------
+    fn set_word_start_anchoring(&mut self, enabled: bool) {
+        self.fallback.set_word_start_anchoring(enabled);
+    }
+    // ------------------------------------------------------------------------
+    fn set_speaker(&mut self, actor: Option<&str>) -> Result<(), String> {
+        // used only for fallback extraction
+        self.fallback.set_speaker(actor)
+    }
+    // ------------------------------------------------------------------------
+    fn adapt_speaker(&mut self, actor: &str, corpus: &AdaptationCorpus) -> Result<PathBuf, String> {
+        self.fallback.adapt_speaker(actor, corpus)
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+fn parse_alignment(content: &str) -> Result<Vec<PhonemeSegment>, String> {
+    let mut phonemes: Vec<PhonemeSegment> = Vec::new();
+
+    for (i, line) in content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i, line.trim()))
+        .filter(|(_i, line)| !line.is_empty() && !line.starts_with(';'))
+    {
+        let err = || {
+            format!(
+                "external aligner: failed to parse result line {}: {line}",
+                i + 1
+            )
+        };
+
+        let mut fields = line.split('|').map(str::trim);
+        let phoneme = fields.next().filter(|p| !p.is_empty()).ok_or_else(err)?;
+        let start = fields
+            .next()
+            .and_then(|v| v.parse::<u32>().ok())
+            .ok_or_else(err)?;
+        let end = fields
+            .next()
+            .and_then(|v| v.parse::<u32>().ok())
+            .filter(|end| *end >= start)
+            .ok_or_else(err)?;
+        if phonemes.last().is_some_and(|prev| start < prev.end) {
+            return Err(format!(
+                "external aligner: phoneme in result line {} starts before the end of \
+                 the previous phoneme: {line}",
+                i + 1
+            ));
+        }
+        let score = match fields.next() {
+            Some(score) => {
+                let score = score.parse::<f32>().map_err(|_| err())?;
+                if !(0.0..=1.0).contains(&score) {
+                    return Err(format!(
+                        "external aligner: confidence in result line {} outside of 0..1: \
+                         {line}",
+                        i + 1
+                    ));
+                }
+                score
+            }
+            // no acoustic confidence available
+            None => 1.0,
+        };
+
+        phonemes.push(PhonemeSegment {
+            phoneme: phoneme.to_uppercase(),
+            // set from word level alignment of fallback (if available)
+            word_start: false,
+            start,
+            end,
+            weight: 1.0,
+            score,
+            matching_info: None,
+            traceback: None,
+            active: true,
+            warnings: Vec::default(),
+        });
+    }
+    Ok(phonemes)
+}
+// ----------------------------------------------------------------------------
+// ----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::{parse_alignment, speaker_filename};
+
+    #[test]
+    fn test_speaker_filename() {
+        assert_eq!(speaker_filename("Geralt"), "geralt-fb4135209671c8e0");
+        assert_eq!(speaker_filename("geralt"), "geralt-bc536211a4350e00");
+        assert_eq!(
+            speaker_filename("Emhyr var Emreis"),
+            "emhyr_var_emreis-5f8e299f20850bf6"
+        );
+        assert_eq!(
+            speaker_filename("Emhyr_var_Emreis"),
+            "emhyr_var_emreis-1c2250e8f1597a16"
+        );
+        assert_eq!(speaker_filename(""), "-cbf29ce484222325");
+    }
+
+    #[test]
+    fn test_parse_alignment() {
+        let content = "; phoneme|start|end|confidence\n\
+                       \n\
+                       sil|0|120|0.9\n\
+                       hh | 120 | 180 | 0.75\n\
+                       ay|180|350|1\n\
+                       sil|400|500|0\n";
+        let phonemes = parse_alignment(content).unwrap();
+
+        assert_eq!(
+            phonemes
+                .iter()
+                .map(|p| (p.phoneme.as_str(), p.start, p.end, p.score))
+                .collect::<Vec<_>>(),
+            [
+                ("SIL", 0, 120, 0.9),
+                ("HH", 120, 180, 0.75),
+                ("AY", 180, 350, 1.0),
+                ("SIL", 400, 500, 0.0),
+            ]
+        );
+        assert!(phonemes.iter().all(|p| p.active && !p.word_start));
+        assert_eq!(parse_alignment("; empty\n").map(|p| p.len()), Ok(0));
+    }
+
+    #[test]
+    fn test_parse_alignment_without_score() {
+        let phonemes = parse_alignment("hh|0|50\nay|50|200|0.5\n").unwrap();
+
+        assert_eq!(phonemes[0].score, 1.0, "no acoustic confidence");
+        assert_eq!(phonemes[1].score, 0.5);
+    }
+
+    #[test]
+    fn test_parse_alignment_malformed_lines() {
+        for line in [
+            "|0|50",
+            "hh",
+            "hh|0",
+            "hh|a|50",
+            "hh|0|-50",
+            "hh|100|50",
+            "hh|0|50|high",
+        ] {
+            let result = parse_alignment(&format!("ay|0|0\n{line}\n"));
+            assert_eq!(
+                result.map(|p| p.len()),
+                Err(format!(
+                    "external aligner: failed to parse result line 2: {line}"
+                )),
+                "{}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_alignment_overlaps() {
+        // out of order
+        assert!(parse_alignment("hh|100|200\nay|0|100\n").is_err());
+        // overlapping
+        assert!(parse_alignment("hh|0|120\nay|100|200\n").is_err());
+        // gaps between phonemes are allowed
+        assert!(parse_alignment("hh|0|100\nay|150|200\n").is_ok());
+    }
+
+    #[test]
+    fn test_parse_alignment_score_range() {
+        assert!(parse_alignment("hh|0|100|1.5\n").is_err());
+        assert!(parse_alignment("hh|0|100|-0.1\n").is_err());
+        assert!(parse_alignment("hh|0|100|NaN\n").is_err());
+    }
+}
@@ -14,6 +14,8 @@ pub enum FileInfo {
     UnlinkedAudio(String),
     Audio(u32, String, Option<f32>),
     Phonemes(u32, String),
+    /// reference alignment (praat <id>.TextGrid or <id>.alignment.json)
+    Alignment(u32, String),
 }
 // ----------------------------------------------------------------------------
 // internals
@@ -126,7 +128,9 @@ impl FilesScanner {
 
         for entry in glob(&self.input_wildcard).map_err(|e| format!("{}", e))? {
             if let Some((filename, filepath)) = Self::extract_path_components(entry) {
-                let is_audiofile = filename.ends_with(".wav") || filename.ends_with(".ogg");
+                let is_audiofile = filename.ends_with(".wav")
+                    || filename.ends_with(".ogg")
+                    || filename.ends_with(".wem");
                 match Self::extract_metainfo(&filename) {
                     Some((id, duration)) => {
                         if is_audiofile {
@@ -137,6 +141,12 @@ impl FilesScanner {
                             debug!("found phoneme file: {} [id: {}]", filepath, id);
 
                             files.push(FileInfo::Phonemes(id, filepath));
+                        } else if filename.ends_with(".TextGrid")
+                            || filename.ends_with(".alignment.json")
+                        {
+                            debug!("found alignment file: {} [id: {}]", filepath, id);
+
+                            files.push(FileInfo::Alignment(id, filepath));
                         } else {
                             #[cfg(debug_assertions)]
                             trace!(
@@ -176,9 +176,37 @@ pub(super) fn force_renaming(queue: &mut queue::AudioQueue) -> Result<(), String
     Ok(())
 }
 // ----------------------------------------------------------------------------
+pub(super) fn realign_selection(range: (u32, u32), state: &mut State) -> Result<(), String> {
+    state.audioqueue.realign_selected(range)?;
+    state.audioqueue.refresh_captions();
+
+    // track will be replaced by the re-aligned one
+    state.editor_data.reset();
+    Ok(())
+}
+// ----------------------------------------------------------------------------
+pub(super) fn inspect_alignment(
+    datadir: &Path,
+    data: &mut editor::EditableData,
+) -> Result<(), String> {
+    use phonemes::{PhonemeSegment, PhonemeTrack};
+
+    let track: PhonemeTrack<PhonemeSegment> = data.phonemetrack().into();
+
+    let similarity_matrix = ::load_similarity_matrix(datadir, track.language())?;
+    let matcher = ::PhonemeSequenceMatcher::new(similarity_matrix);
+
+    let matrix = matcher
+        .calculate_track_alignment_matrix(&track)
+        .map_err(|e| format!("failed to recalculate alignment matrix: {e}"))?;
+
+    data.set_alignment(matrix);
+    Ok(())
+}
+// ----------------------------------------------------------------------------
 //
 // ----------------------------------------------------------------------------
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::thread;
 
@@ -14,16 +14,31 @@ pub(super) struct EditableData {
     start_marker: usize,
     end_marker: usize,
     dragging: Option<DragOperation>,
+    alignment: Option<AlignmentData>,
 }
 // ----------------------------------------------------------------------------
 #[derive(Default)]
 struct AudioData {
     samples: Vec<i16>,
+    /// min/max peaks for rendering long audio zoomed out
+    peaks: PeakPyramid,
     max: i16,
     rate: u32,
     duration: f32,
 }
 // ----------------------------------------------------------------------------
+/// recalculated alignment of the track for inspection
+struct AlignmentData {
+    matrix: AlignmentMatrix,
+    /// accumulated scores of all cells (row by row)
+    totals: Vec<f32>,
+    total_range: (f32, f32),
+    /// best path starting at the top left cell
+    path: Vec<(usize, usize)>,
+    audio_labels: Vec<ImString>,
+    text_labels: Vec<ImString>,
+}
+// ----------------------------------------------------------------------------
 enum DragOperation {
     Waveform(UiDragging<usize>, f32),
     PhonemeBlock(PhonemeDragging),
@@ -50,9 +65,14 @@ pub(super) enum Action {
     SetDragMode(PhonemeDragMode),
     SetActor(String),
     AutoCloseGaps,
+    ReExtractSelection,
     ActivatePhonemeSegment(usize, bool),
     SetPhonemeSegmentPos(usize, f32, f32),
     SetPhonemeSegmentWeight(usize, f32),
+    SelectPhonemeSegment(usize),
+    SelectPhonemeSegments(usize, usize),
+    ShowAlignmentMatrix,
+    CloseAlignmentMatrix,
     Timeline(TimelineAction),
 }
 // ----------------------------------------------------------------------------
@@ -116,8 +136,12 @@ pub(super) fn handle_action(
 // ----------------------------------------------------------------------------
 // internals
 // ----------------------------------------------------------------------------
+use imgui::ImString;
+use imgui_widgets::audio::PeakPyramid;
 use imgui_widgets::UiDragging;
 
+use sequence_matcher::AlignmentMatrix;
+
 use super::player;
 
 use super::phonemes::{PhonemeSegment, PhonemeTrack};
@@ -143,6 +167,7 @@ impl EditableData {
             start_marker: 0,
             end_marker: 0,
             dragging: None,
+            alignment: None,
         }
     }
     // ------------------------------------------------------------------------
@@ -155,6 +180,7 @@ impl EditableData {
         self.offset = 0;
         self.zoom = 1.0;
         self.dragging = None;
+        self.alignment = None;
     }
     // ------------------------------------------------------------------------
     pub fn set_audio(&mut self, rawaudio: Vec<i16>, sample_rate: u32) {
@@ -193,6 +219,15 @@ impl EditableData {
         self.set_offset(f32::max(0.0, new_offset) as usize);
     }
     // ------------------------------------------------------------------------
+    /// moves the visible part of the zoomed waveform if position is not visible
+    pub fn scroll_to(&mut self, pos: usize) {
+        let visible = (self.audio.samples.len() as f32 / self.zoom) as usize;
+
+        if pos < self.offset || pos >= self.offset + visible {
+            self.set_offset(pos.saturating_sub(visible / 4));
+        }
+    }
+    // ------------------------------------------------------------------------
     pub fn set_playback_start_pos(&mut self, start: usize) {
         let start = usize::min(start, self.audio.samples.len());
 
@@ -229,6 +264,16 @@ impl EditableData {
         }
     }
     // ------------------------------------------------------------------------
+    /// marked audio region in ms. None if no region is marked
+    pub fn selected_range_ms(&self) -> Option<(u32, u32)> {
+        if self.start_marker < self.end_marker {
+            let to_ms = |pos: usize| (pos as f32 / self.audio.rate as f32 * 1000.0) as u32;
+            Some((to_ms(self.start_marker), to_ms(self.end_marker)))
+        } else {
+            None
+        }
+    }
+    // ------------------------------------------------------------------------
     pub fn is_available(&self) -> bool {
         !self.audio.is_empty()
     }
@@ -250,6 +295,10 @@ impl EditableData {
         self.unmodified = self.phonemetrack.clone();
     }
     // ------------------------------------------------------------------------
+    pub fn set_alignment(&mut self, matrix: AlignmentMatrix) {
+        self.alignment = Some(AlignmentData::new(matrix));
+    }
+    // ------------------------------------------------------------------------
 }
 // ----------------------------------------------------------------------------
 impl AudioData {
@@ -265,6 +314,7 @@ impl AudioData {
             .map(|i| i16::checked_abs(*i).unwrap_or(i16::MAX))
             .max()
             .unwrap_or(0);
+        self.peaks = PeakPyramid::new(&rawaudio);
         self.samples = rawaudio;
         self.rate = rate;
         self.duration = self.samples.len() as f32 / rate as f32;
@@ -274,6 +324,48 @@ impl AudioData {
         f32::trunc(self.duration * 1000.0) as u32
     }
     // ------------------------------------------------------------------------
+    fn ms_to_sample(&self, ms: u32) -> usize {
+        (u64::from(ms) * u64::from(self.rate) / 1000) as usize
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl AlignmentData {
+    // ------------------------------------------------------------------------
+    fn new(matrix: AlignmentMatrix) -> AlignmentData {
+        let totals = (0..matrix.height())
+            .flat_map(|t| (0..matrix.width()).map(move |a| (a, t)))
+            .map(|(a, t)| matrix.total(a, t).unwrap_or(f32::MIN))
+            .collect::<Vec<_>>();
+
+        let total_range = totals
+            .iter()
+            .filter(|total| **total > f32::MIN)
+            .fold((f32::MAX, f32::MIN), |(min, max), total| {
+                (min.min(*total), max.max(*total))
+            });
+
+        let path = ::std::iter::once((0, 0))
+            .chain(matrix.path.iter().cloned())
+            .collect();
+
+        let labels = |phonemes: &[::phonemes::PhonemeSegment]| {
+            phonemes
+                .iter()
+                .map(|p| ImString::new(p.phoneme.as_str()))
+                .collect::<Vec<_>>()
+        };
+
+        AlignmentData {
+            audio_labels: labels(&matrix.audio),
+            text_labels: labels(&matrix.text),
+            totals,
+            total_range,
+            path,
+            matrix,
+        }
+    }
+    // ------------------------------------------------------------------------
 }
 // ----------------------------------------------------------------------------
 // converter
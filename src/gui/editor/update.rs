@@ -49,6 +49,12 @@ pub(super) fn handle_action(
             ::phonemes::auto_close_gaps(state.audio.duration_ms(), &mut state.phonemetrack);
         }
 
+        ReExtractSelection => {
+            result = state
+                .selected_range_ms()
+                .map(|(start, end)| Action::ReExtractSelection(start, end));
+        }
+
         ActivatePhonemeSegment(slot, activated) => {
             ::phonemes::update_timings_on_activation(
                 state.audio.duration_ms(),
@@ -83,6 +89,32 @@ pub(super) fn handle_action(
                 .take(1)
                 .for_each(|segment| segment.weight = weight);
         }
+
+        SelectPhonemeSegment(slot) => {
+            if let Some(segment) = state.phonemetrack.phonemes().get(slot) {
+                let start = state.audio.ms_to_sample(segment.start);
+                let end = state.audio.ms_to_sample(segment.end);
+
+                timeline::handle_action(SetPlaybackStart(start), settings, state, player);
+                timeline::handle_action(SetPlaybackEnd(end), settings, state, player);
+                state.scroll_to(start);
+            }
+        }
+
+        SelectPhonemeSegments(first, last) => {
+            let phonemes = state.phonemetrack.phonemes();
+            if let (Some(first), Some(last)) = (phonemes.get(first), phonemes.get(last)) {
+                let start = state.audio.ms_to_sample(first.start);
+                let end = state.audio.ms_to_sample(last.end);
+
+                timeline::handle_action(SetPlaybackStart(start), settings, state, player);
+                timeline::handle_action(SetPlaybackEnd(end), settings, state, player);
+                state.scroll_to(start);
+            }
+        }
+
+        ShowAlignmentMatrix => result = Some(Action::InspectAlignment),
+        CloseAlignmentMatrix => state.alignment = None,
     }
     result
 }
@@ -95,6 +127,7 @@ use super::{EditableData, Settings};
 
 // actions
 use super::Action as EditorAction;
+use super::TimelineAction::{SetPlaybackEnd, SetPlaybackStart};
 use gui::Action;
 
 // misc
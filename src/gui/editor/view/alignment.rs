@@ -0,0 +1,170 @@
+//
+// interactive view::alignment
+//
+
+// ----------------------------------------------------------------------------
+// external interface
+// ----------------------------------------------------------------------------
+pub(in gui) fn render(ui: &Ui<'_>, fonts: &Fonts, data: &EditableData) -> Option<EditorAction> {
+    let mut result = None;
+    let mut opened = true;
+
+    if let Some(ref alignment) = data.alignment {
+        ui.window(im_str!("Alignment Matrix"))
+            .size((700.0, 450.0), imgui::ImGuiCond::FirstUseEver)
+            .horizontal_scrollbar(true)
+            .opened(&mut opened)
+            .build(|| {
+                ui.text(im_str!(
+                    "columns: recognized audio phonemes, rows: text phonemes. \
+                     click a cell to select the phoneme segment."
+                ));
+                ui.separator();
+
+                let matrix = &alignment.matrix;
+                let highlighted = selected_cells(data, alignment);
+                let mut hovered_cell = None;
+                let mut interaction = None;
+
+                ui.with_font(fonts.phonemes(), || {
+                    interaction = ui
+                        .matrix()
+                        .heatmap(
+                            im_str!("##alignment_matrix"),
+                            (matrix.width(), matrix.height()),
+                            &alignment.totals,
+                            &mut hovered_cell,
+                        )
+                        .value_range(alignment.total_range.0, alignment.total_range.1)
+                        .cell_size(CELL_SIZE)
+                        .column_labels(&alignment.audio_labels)
+                        .row_labels(&alignment.text_labels)
+                        .path(&alignment.path)
+                        .highlighted(&highlighted)
+                        .build();
+                });
+
+                if let Some((a, t)) = hovered_cell {
+                    show_cell_info(ui, fonts, matrix, a, t);
+                }
+
+                if let Some(interaction) = interaction {
+                    if let MouseInteraction::Clicked(0) = *interaction.mouse() {
+                        let (a, t) = interaction.virtual_position();
+                        result = segment_slot(data, matrix, a, t)
+                            .map(EditorAction::SelectPhonemeSegment);
+                    }
+                }
+            });
+    }
+
+    if !opened {
+        result = Some(EditorAction::CloseAlignmentMatrix);
+    }
+    result
+}
+// ----------------------------------------------------------------------------
+// internals
+// ----------------------------------------------------------------------------
+use imgui;
+use imgui::Ui;
+use imgui_widgets::{MatrixWidgets, MouseInteraction};
+
+use sequence_matcher::{AlignmentMatrix, AlignmentOperation};
+
+// actions
+use super::EditorAction;
+
+// state
+use super::{AlignmentData, EditableData};
+
+// misc
+use super::Fonts;
+// ----------------------------------------------------------------------------
+const CELL_SIZE: f32 = 20.0;
+// ----------------------------------------------------------------------------
+/// phoneme segment of the track for a matrix cell: the aligned text phoneme or
+/// the segment containing the audio phoneme for deletes (and the first row)
+fn segment_slot(
+    data: &EditableData,
+    matrix: &AlignmentMatrix,
+    a: usize,
+    t: usize,
+) -> Option<usize> {
+    let phonemes = data.phonemetrack.phonemes();
+
+    if t > 0 && matrix.operation(a, t) != Some(AlignmentOperation::Delete) {
+        // text phonemes of the matrix are all track phonemes except gaps
+        phonemes
+            .iter()
+            .enumerate()
+            .filter(|(_, segment)| segment.phoneme != "_")
+            .nth(t - 1)
+            .map(|(slot, _)| slot)
+    } else if a > 0 {
+        let audio = &matrix.audio[a - 1];
+        let center = (audio.start + audio.end) / 2;
+
+        phonemes
+            .iter()
+            .position(|segment| segment.start <= center && center < segment.end)
+    } else {
+        None
+    }
+}
+// ----------------------------------------------------------------------------
+/// cells of the best path belonging to segments within the marked audio area
+fn selected_cells(data: &EditableData, alignment: &AlignmentData) -> Vec<(usize, usize)> {
+    let Some((start, end)) = data.selected_range_ms() else {
+        return Vec::default();
+    };
+    let phonemes = data.phonemetrack.phonemes();
+
+    alignment
+        .path
+        .iter()
+        .filter(|(a, t)| {
+            segment_slot(data, &alignment.matrix, *a, *t)
+                .and_then(|slot| phonemes.get(slot))
+                .map(|segment| segment.start < end && start < segment.end)
+                .unwrap_or(false)
+        })
+        .cloned()
+        .collect()
+}
+// ----------------------------------------------------------------------------
+// ui rendering helpers
+// ----------------------------------------------------------------------------
+#[inline]
+fn show_cell_info(ui: &Ui<'_>, fonts: &Fonts, matrix: &AlignmentMatrix, a: usize, t: usize) {
+    let chosen = matrix.operation(a, t);
+
+    ui.tooltip(|| {
+        ui.with_font(fonts.phonemes(), || {
+            match a.checked_sub(1).map(|a| &matrix.audio[a]) {
+                Some(p) => ui.text(format!("audio: {} [{} - {}]", p.phoneme, p.start, p.end)),
+                None => ui.text("audio: _"),
+            }
+            match t.checked_sub(1).map(|t| &matrix.text[t]) {
+                Some(p) => ui.text(format!("text:  {}", p.phoneme)),
+                None => ui.text("text:  _"),
+            }
+        });
+
+        match (matrix.total(a, t), chosen) {
+            (Some(total), Some(op)) => ui.text(format!("total: {:.2} ({})", total, op.name())),
+            (Some(total), None) => ui.text(format!("total: {:.2}", total)),
+            _ => ui.text("total: -"),
+        }
+
+        let scores = matrix.operation_scores(a, t);
+        if !scores.is_empty() {
+            ui.separator();
+        }
+        for (op, score) in scores {
+            let marker = if Some(op) == chosen { '*' } else { ' ' };
+            ui.text(format!("{} {: <12}{: >6.2}", marker, op.name(), score));
+        }
+    });
+}
+// ----------------------------------------------------------------------------
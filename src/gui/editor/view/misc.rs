@@ -41,6 +41,15 @@ pub(in gui) fn render(
                         show_phoneme_translation(ui, fonts, track.translation());
                     };
 
+                    if !track.audio_warnings().is_empty()
+                        && ui
+                            .collapsing_header(im_str!("audio warnings"))
+                            .default_open(true)
+                            .build()
+                    {
+                        show_audio_warnings(ui, track);
+                    };
+
                     if ui
                         .collapsing_header(im_str!("phoneme block drag mode"))
                         .build()
@@ -55,6 +64,9 @@ pub(in gui) fn render(
                     }
 
                     if ui.collapsing_header(im_str!("loaded phoneme data")).build() {
+                        if ui.small_button(im_str!("show alignment matrix")) {
+                            result = Some(EditorAction::ShowAlignmentMatrix);
+                        }
                         show_phonemes_traceback(ui, fonts, track);
                     }
                 });
@@ -99,6 +111,18 @@ fn show_phoneme_translation(ui: &Ui, fonts: &Fonts, translation: &str) {
 }
 // ----------------------------------------------------------------------------
 #[inline]
+fn show_audio_warnings(ui: &Ui, track: &PhonemeTrack<PhonemeSegment>) {
+    ui.spacing();
+    for warning in track.audio_warnings() {
+        ui.text_colored(
+            (1.0, 0.35, 0.35, 1.0),
+            &imgui::ImString::new(warning.long()),
+        );
+    }
+    ui.spacing();
+}
+// ----------------------------------------------------------------------------
+#[inline]
 fn show_drag_settings(ui: &Ui, settings: &Settings) -> Option<EditorAction> {
     let mut result = None;
     ui.spacing();
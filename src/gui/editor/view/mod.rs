@@ -5,6 +5,7 @@
 // ----------------------------------------------------------------------------
 // external interface
 // ----------------------------------------------------------------------------
+pub mod alignment;
 pub mod misc;
 pub mod table;
 pub mod timeline;
@@ -16,7 +17,7 @@ pub mod timeline;
 use super::{Action as EditorAction, TimelineAction};
 
 // state
-use super::{EditableData, PhonemeDragMode, Settings};
+use super::{AlignmentData, EditableData, PhonemeDragMode, Settings};
 
 // misc
 use super::{PhonemeSegment, PhonemeTrack};
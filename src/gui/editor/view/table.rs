@@ -25,7 +25,8 @@ pub(in gui) fn render(
         .build(|| {
             let duration = data.audio.duration_ms();
 
-            phoneme_table_header(ui, area.size.0, &mut result);
+            let has_selection = data.selected_range_ms().is_some();
+            phoneme_table_header(ui, area.size.0, has_selection, &mut result);
 
             let start_marker = (data.start_marker as f32 / data.audio.rate as f32) * 1000.0;
             let end_marker = (data.end_marker as f32 / data.audio.rate as f32) * 1000.0;
@@ -153,7 +154,12 @@ use super::{Fonts, UiArea};
 // ui rendering helpers
 // ----------------------------------------------------------------------------
 #[inline]
-fn phoneme_table_header(ui: &Ui<'_>, width: f32, action: &mut Option<EditorAction>) {
+fn phoneme_table_header(
+    ui: &Ui<'_>,
+    width: f32,
+    has_selection: bool,
+    action: &mut Option<EditorAction>,
+) {
     ui.columns(3, im_str!("phoneme_table"), false);
 
     ui.set_column_offset(1, 80.0);
@@ -169,6 +175,12 @@ fn phoneme_table_header(ui: &Ui<'_>, width: f32, action: &mut Option<EditorActio
     ui.text(im_str!("start"));
     ui.same_line(timing_col_width * 0.75 - 16.0);
     ui.text(im_str!(" end "));
+    if has_selection {
+        ui.same_line(timing_col_width - 130.0);
+        if ui.small_button(im_str!("re-extract selection")) {
+            *action = Some(EditorAction::ReExtractSelection);
+        }
+    }
 
     ui.next_column();
     ui.same_line(ui.get_column_width(2) * 0.5 - 20.0);
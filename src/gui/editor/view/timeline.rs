@@ -34,6 +34,7 @@ pub(in gui) fn render(
                 if let Some(interaction) = ui
                     .audio()
                     .waveform(im_str!("Waveform"), &data.audio.samples, &mut visible_range)
+                    .peaks(&data.audio.peaks)
                     .offset(data.offset)
                     .zoom_x(data.zoom)
                     .max_value(data.audio.max)
@@ -114,6 +115,26 @@ pub(in gui) fn render(
                 }
                 // data.hover_marker = f32::trunc(hovered_position * data.audio.rate as f32) as usize;
 
+                ui.audio()
+                    .timeline(
+                        im_str!("WordConfidence"),
+                        data.audio.duration,
+                        &mut hovered_position,
+                    )
+                    .view_start(data.offset as f32 / data.audio.rate as f32)
+                    .zoom(data.zoom)
+                    .size((waveform_width, 8.0))
+                    .build(|_pos, size, visible_timeframe| {
+                        show_word_confidence_bars(
+                            ui,
+                            size,
+                            visible_timeframe,
+                            data.phonemetrack.phonemes(),
+                            data.phonemetrack.word_confidences(),
+                            &mut result,
+                        );
+                    });
+
                 // scale position slider (max) to respect size of window
                 let max_offset = calc_window_max_start_offset(data.zoom, data.audio.samples.len());
 
@@ -144,6 +165,8 @@ use super::{EditorAction, TimelineAction};
 use super::EditableData;
 
 // misc
+use phonemes::{WordConfidence, WORD_CONFIDENCE_ERROR, WORD_CONFIDENCE_WARN};
+
 use super::PhonemeSegment;
 use super::{Fonts, UiArea};
 use super::{MAX_ZOOM, MIN_ZOOM};
@@ -225,6 +248,74 @@ fn show_phoneme_blocks(
     }
 }
 // ----------------------------------------------------------------------------
+/// colored bars below the phoneme blocks. clicking a bar selects the word.
+#[inline]
+fn show_word_confidence_bars(
+    ui: &Ui<'_>,
+    frame_size: imgui::ImVec2,
+    visible_timeframe: (f32, f32),
+    phonemes: &[PhonemeSegment],
+    confidences: &[WordConfidence],
+    result: &mut Option<EditorAction>,
+) {
+    let (start, end) = (visible_timeframe.0, visible_timeframe.1);
+
+    // phonemesegment times are ms
+    let start_ms = f32::trunc(start * 1000.0) as u32;
+    let end_ms = f32::trunc(end * 1000.0) as u32;
+
+    for (i, word) in confidences.iter().enumerate() {
+        // timings follow the (edited) segments, the confidence is updated on save
+        let Some((word_start, word_end)) = word.timings(phonemes) else {
+            continue;
+        };
+        if word_start >= end_ms || word_end <= start_ms {
+            continue;
+        }
+        if let Some(interaction) = ui
+            .audio()
+            .timeline_block(
+                im_str!("WordConfidence##{}", i),
+                im_str!(""),
+                (word_start as f32 / 1000.0, word_end as f32 / 1000.0),
+                (start, end),
+            )
+            .set_draw_framesize(frame_size)
+            .set_draw_color(confidence_color(word.confidence))
+            .set_draw_label(false)
+            .set_draw_borders(false)
+            .build()
+        {
+            if let MouseInteraction::Clicked(0) = *interaction.mouse() {
+                *result = Some(EditorAction::SelectPhonemeSegments(
+                    word.segments.0,
+                    word.segments.1,
+                ));
+            }
+        }
+        if ui.is_item_hovered() {
+            ui.tooltip(|| {
+                ui.text(format!("word confidence: {:.2}", word.confidence));
+                ui.separator();
+                ui.text(format!("score:           {:.2}", word.score));
+                ui.text(format!("usual durations: {:.0}%", word.durations * 100.0));
+                ui.text(format!("inactive:        {:.0}%", word.inactive * 100.0));
+            });
+        }
+    }
+}
+// ----------------------------------------------------------------------------
+#[inline]
+fn confidence_color(confidence: f32) -> (f32, f32, f32, f32) {
+    if confidence < WORD_CONFIDENCE_ERROR {
+        (1.0, 0.35, 0.35, 0.9)
+    } else if confidence < WORD_CONFIDENCE_WARN {
+        (1.0, 0.75, 0.2, 0.9)
+    } else {
+        (0.35, 0.8, 0.35, 0.9)
+    }
+}
+// ----------------------------------------------------------------------------
 #[inline]
 fn calc_window_max_start_offset(zoom: f32, datapoints: usize) -> i32 {
     let win_size = f32::trunc(datapoints as f32 / zoom);
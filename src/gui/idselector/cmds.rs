@@ -8,10 +8,11 @@
 // ----------------------------------------------------------------------------
 pub(in gui) fn init_selector(
     selection: Selection,
+    datadir: &Path,
     state: &mut IdSelectorState,
     player: &mut player::Player,
 ) -> Result<(), String> {
-    let mut dataprovider = DataProvider::new(selection.audiofile());
+    let mut dataprovider = DataProvider::new(selection.audiofile(), datadir);
 
     dataprovider.load()?;
     let audiodata = dataprovider.get_rawaudio(player.playback_samplerate(), false)?;
@@ -101,7 +102,7 @@ pub(super) fn refresh_search_result(
 // ----------------------------------------------------------------------------
 //
 // ----------------------------------------------------------------------------
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use imgui::ImString;
 use imgui_controls::input;
@@ -102,6 +102,9 @@ enum Action {
     Editor(editor::Action),
     OpenFileBrowser,
     RenameAllFiles,
+    ReExtractSelection(u32, u32),
+    QueueReExtraction(u32, u32),
+    InspectAlignment,
     FileBrowser(filebrowser::Selection),
     LineIdSelector(idselector::Action),
     Quit,
@@ -320,6 +323,20 @@ fn handle_action(
             cmds::force_renaming(&mut state.audioqueue)?;
             Ok(())
         }
+        Action::ReExtractSelection(start, end) => {
+            actions.include(ima_seq![
+                Action::GuardModifiedData,
+                Action::QueueReExtraction(start, end)
+            ]);
+            Ok(())
+        }
+        Action::QueueReExtraction(start, end) => {
+            cmds::realign_selection((start, end), state)?;
+            Ok(())
+        }
+        Action::InspectAlignment => {
+            cmds::inspect_alignment(&worker_pool.params.datadir, &mut state.editor_data)
+        }
 
         Action::Confirm(text, yes_actions, no_actions) => {
             actions.set_interactive(text, yes_actions, no_actions);
@@ -346,7 +363,13 @@ fn handle_action(
             Some(entry) => {
                 if entry.has_lineid() {
                     state.editor_data.reset();
-                    queue::load_data(&entry, &mut state.editor_data, &mut state.player).map(|_| {
+                    queue::load_data(
+                        &entry,
+                        &worker_pool.params.datadir,
+                        &mut state.editor_data,
+                        &mut state.player,
+                    )
+                    .map(|_| {
                         let selected_mapping =
                             if let Some(actor) = state.editor_data.unmodified().actor() {
                                 state.actor_mapping.resolve(actor)
@@ -360,6 +383,7 @@ fn handle_action(
 
                     idselector::cmds::init_selector(
                         entry,
+                        &worker_pool.params.datadir,
                         &mut state.lineid_selector,
                         &mut state.player,
                     )
@@ -38,6 +38,9 @@ impl From<&PhonemeTrack<RawPhonemeSegment>> for PhonemeTrack<PhonemeSegment> {
             phonemes,
         );
         t.set_version(track.version());
+        t.set_audio_warnings(track.audio_warnings().clone());
+        t.set_audio_alternatives(track.audio_alternatives().clone());
+        t.set_word_confidences(track.word_confidences().clone());
         t
     }
     // ------------------------------------------------------------------------
@@ -57,6 +60,8 @@ impl From<&PhonemeTrack<PhonemeSegment>> for PhonemeTrack<RawPhonemeSegment> {
             phonemes,
         );
         t.set_version(track.version());
+        t.set_audio_warnings(track.audio_warnings().clone());
+        t.set_audio_alternatives(track.audio_alternatives().clone());
         t.assess_quality();
         t
     }
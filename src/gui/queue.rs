@@ -35,12 +35,13 @@ pub struct WorkerQueue {
 // ----------------------------------------------------------------------------
 pub(super) fn load_data(
     entry: &Selection,
+    datadir: &Path,
     data: &mut editor::EditableData,
     player: &mut player::Player,
 ) -> Result<(), String> {
     data.reset();
 
-    let mut dataprovider = DataProvider::new(&entry.audiofile);
+    let mut dataprovider = DataProvider::new(&entry.audiofile, datadir);
 
     dataprovider.load()?;
     // TODO move into dataprovider?
@@ -64,6 +65,7 @@ pub(super) fn load_data(
 // ----------------------------------------------------------------------------
 use imgui::ImString;
 
+use std::path::Path;
 use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex};
 
@@ -221,6 +223,23 @@ impl AudioQueue {
         }
     }
     // ------------------------------------------------------------------------
+    /// queues re-alignment of range (in ms) for the selected entry
+    pub(in gui) fn realign_selected(&mut self, range: (u32, u32)) -> Result<(), String> {
+        match self.tasks.queue.lock() {
+            Ok(mut queue) => {
+                let slot = self
+                    .selected
+                    .ok_or_else(|| String::from("no audioqueue entry selected"))?;
+
+                queue.realign_task(slot.0, range)?;
+                self.tasks.dirty.store(true, Ordering::SeqCst);
+                self.selected = None;
+                Ok(())
+            }
+            Err(_) => Err(String::from("could not acquire lock on processing queue")),
+        }
+    }
+    // ------------------------------------------------------------------------
     #[inline]
     pub(in gui) fn info(&self) -> &str {
         &self.info
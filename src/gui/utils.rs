@@ -26,7 +26,7 @@ impl ScreenSpaceManager {
         let (width, height) = winsize;
 
         let menubar_height = 19.0;
-        let timeline_height = 220.0;
+        let timeline_height = 232.0;
 
         let queue_area_height = queue_height.unwrap_or(212.0);
         let queue_area_min_height = 150.0;
@@ -50,6 +50,10 @@ pub(super) fn show_editor(
             ) {
                 result = Some(action.into());
             }
+
+            if let Some(action) = editor::view::alignment::render(ui, fonts, &state.editor_data) {
+                result = Some(action.into());
+            }
         });
     }
     result
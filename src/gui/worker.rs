@@ -22,7 +22,7 @@ pub(super) fn start(
         info!("> starting worker thread {:?}.", id);
         let mut stop = false;
 
-        match ::init_phoneme_extraction(&language, &stringsfile, &datadir, loglevel) {
+        match ::init_phoneme_extraction(&language, &stringsfile, &datadir, None, loglevel) {
             Ok(mut processor) => {
                 while !stop {
                     while let Some(task) = tasks.take_waiting() {
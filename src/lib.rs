@@ -19,39 +19,73 @@ mod queue;
 mod utils;
 
 mod actors;
+mod adaptation;
+mod bootstrap;
+mod boundaries;
+mod conditioner;
 mod espeak;
+mod evaluation;
+mod extractor;
 mod file_scanner;
 mod matrix;
+mod phoneme_durations;
 mod phonemes;
 mod pocketsphinx;
+mod refiner;
+mod scoring_profile;
+mod scoring_tuner;
 mod sequence_matcher;
 mod similarity_matrix;
+mod similarity_trainer;
+mod splitter;
+mod synthetic_corpus;
 mod text;
+mod word_report;
 
 pub mod gui;
 // ----------------------------------------------------------------------------
+pub use bootstrap::{BootstrapSummary, LanguageBootstrap};
 pub use espeak::{ESpeak as TextPhonemeConverter, TextPhonemeTranslator};
-pub use pocketsphinx::PocketSphinx as AudioPhonemeExtractor;
-
-pub use sequence_matcher::SequenceMatcher as PhonemeSequenceMatcher;
-pub use similarity_matrix::SimilarityMatrix as PhonemeSimilarityMatrix;
+pub use evaluation::{ErrorStats, Evaluation, EVALUATION_TOLERANCES_MS};
+pub use extractor::{AdaptationCorpus, AudioPhonemeExtractor, ExtractionMethod};
+
+pub use phoneme_durations::PhonemeDurations;
+pub use scoring_profile::ScoringProfile;
+pub use scoring_tuner::{BoundaryMetrics, ScoringTuner, TuningResult, BOUNDARY_TOLERANCE_MS};
+pub use sequence_matcher::{
+    AlignmentMatrix, AlignmentOperation, SequenceMatcher as PhonemeSequenceMatcher,
+};
+pub use similarity_matrix::{ScoreChange, SimilarityMatrix as PhonemeSimilarityMatrix};
+pub use similarity_trainer::SimilarityTrainer;
+pub use synthetic_corpus::SyntheticCorpus;
 pub use text::{
     CsvLoader, CsvStringsData, CsvStringsLoader, CsvWriter, SimpleCsvWriter, StringsProvider,
 };
 
 pub use actors::ActorMapping;
+pub use conditioner::{
+    AudioConditioner, ConditioningSettings, DEFAULT_HEAD_MS, DEFAULT_TAIL_MS,
+    DEFAULT_TARGET_LOUDNESS,
+};
 pub use phonemes::store as store_phonemes;
 
 pub use phonemes::{PhonemeResult, PhonemeTrack};
 pub use queue::ProcessingQueue;
+pub use word_report::WordQualityReport;
 pub struct Processor<S>
 where
     S: StringsProvider,
 {
     strings: S,
     translator: TextPhonemeConverter,
-    extractor: AudioPhonemeExtractor,
+    extractor: Box<dyn AudioPhonemeExtractor>,
     matcher: PhonemeSequenceMatcher,
+    /// second pass re-aligning words flagged by quality assessment
+    realign_words: bool,
+    /// moves boundaries to strong signal changes of the full resolution audio
+    refine_boundaries: bool,
+    /// data directory with the codebook library for wem decoding
+    datadir: PathBuf,
 }
 // ----------------------------------------------------------------------------
 pub struct Generator {
@@ -60,10 +94,14 @@ pub struct Generator {
     outputdir: PathBuf,
 }
 // ----------------------------------------------------------------------------
+/// max number of n-best audio hypotheses rescored against the text translation
+const MAX_AUDIO_ALTERNATIVES: usize = 5;
+// ----------------------------------------------------------------------------
 use std::collections::{BTreeMap, HashMap};
 
 use lazy_static::lazy_static;
 
+use phonemes::QualityWarning;
 use queue::TaskData;
 use queue::TaskResult;
 // ----------------------------------------------------------------------------
@@ -75,14 +113,18 @@ where
     pub fn new(
         strings: S,
         translator: TextPhonemeConverter,
-        extractor: AudioPhonemeExtractor,
+        extractor: Box<dyn AudioPhonemeExtractor>,
         matcher: PhonemeSequenceMatcher,
+        datadir: &Path,
     ) -> Processor<S> {
         Processor {
             strings,
             translator,
             extractor,
             matcher,
+            realign_words: false,
+            refine_boundaries: true,
+            datadir: datadir.to_path_buf(),
         }
     }
     // ------------------------------------------------------------------------
@@ -90,6 +132,11 @@ where
         &self.strings
     }
     // ------------------------------------------------------------------------
+    /// enables (default) refinement of the recognized phoneme boundaries
+    pub fn set_boundary_refinement(&mut self, enabled: bool) {
+        self.refine_boundaries = enabled;
+    }
+    // ------------------------------------------------------------------------
     fn extract_phonemes(&mut self, task: &TaskData) -> Result<String, String> {
         let original_text = self.strings.get_line(task.lineid())?;
         let actor = self.strings.get_actor(task.lineid());
@@ -106,36 +153,61 @@ where
             .ok_or_else(|| String::from("text to phoneme translator returned empty string."))?;
         debug!("id {lineid:10}: phoneme translation: {translation}");
 
-        let mut dataprovider = DataProvider::new_from_taskdata(task);
+        let mut dataprovider = DataProvider::new_from_taskdata(task, &self.datadir);
         dataprovider.load()?;
 
+        let audio_warnings = dataprovider.analyze_audio();
+        for warning in &audio_warnings {
+            warn!("id {lineid:010}: > audio health: {}", warning.long());
+        }
+
         // pocketsphinx requires the audiodata to be 16khz
         // in addition normalize audio to improve detection for low volume audio
         let audiodata = dataprovider.get_rawaudio(16000, true)?;
 
+        // speaker adapted model if available
+        self.extractor.set_speaker(actor.map(String::as_str))?;
+
         info!("id {lineid:010}: extracting phonemes from audio... (this may take a while)");
         let phonetizer = WordPhonetizer::new(
             self.matcher.phoneme_pairing_alternatives(),
             &self.translator
         );
 
-        let audio_phonemetrail =
-            self.extractor
-                .extract_phonemes(lineid, &audiodata, text, &phonetizer)?;
+        let mut candidates = self.extractor.extract_phoneme_alternatives(
+            lineid,
+            &audiodata,
+            text,
+            &phonetizer,
+            MAX_AUDIO_ALTERNATIVES,
+        )?;
+
+        // rescore all hypotheses by their alignment with the text translation
+        let (best, phonemetrail, scores) =
+            self.matcher
+                .calculate_best_matching(task.lineid(), &candidates, &text_phonemetrail)?;
+
+        // keep rejected (but aligned) alternatives for debugging
+        let alternatives = candidates
+            .iter()
+            .zip(scores)
+            .enumerate()
+            .filter(|(i, _)| *i != best)
+            .filter_map(|(_, (candidate, score))| {
+                score.map(|score| (score, candidate.hypothesis.clone().unwrap_or_default()))
+            })
+            .collect::<Vec<_>>();
+        if best > 0 {
+            debug!("id {lineid:010}: > rescoring selected alternative hypothesis #{best}");
+        }
 
+        let audio_phonemetrail = candidates.swap_remove(best);
         let recognized = audio_phonemetrail
             .hypothesis
             .as_ref()
             .ok_or_else(|| String::from("audio phoneme extractor returned empty result."))?;
         debug!("id {lineid:010}: phonemes from audio: {recognized}");
 
-        let phonemetrail = self.matcher.calculate_matching(
-            task.lineid(),
-            &audio_phonemetrail,
-            &text_phonemetrail,
-        )?;
-        let phonemecount = phonemetrail.phonemes.len();
-
         let mut phonemetrack = PhonemeTrack::new(
             task.lineid(),
             self.strings.get_lang(),
@@ -145,17 +217,48 @@ where
             actor.cloned(),
             phonemetrail.phonemes,
         );
+        phonemetrack.set_audio_alternatives(alternatives);
 
         let duration_in_ms = f32::trunc(dataprovider.get_audio_duration() * 1000.0) as u32;
         let gaps_closed = phonemes::auto_close_gaps(duration_in_ms, &mut phonemetrack);
         if gaps_closed > 0 {
             warn!("id {lineid:010}: > auto-closed #{gaps_closed} phoneme timing gaps found within a word boundary.",);
         }
+
+        // recognizer timings are 10ms frames. use full resolution audio to
+        // move boundaries to the nearest strong signal change
+        if self.refine_boundaries {
+            let refined = boundaries::refine_boundaries(
+                &mut phonemetrack,
+                &dataprovider.get_rawaudio(REQUIRED_SAMPLE_RATE, false)?,
+                REQUIRED_SAMPLE_RATE,
+            );
+            debug!("id {lineid:010}: > refined #{refined} phoneme boundaries");
+        }
+        phonemetrack.set_audio_warnings(audio_warnings);
         phonemetrack.assess_quality();
 
+        if self.realign_words {
+            let words = refiner::flagged_words(&phonemetrack);
+            if !words.is_empty() {
+                let mut realigner = refiner::WordRealigner {
+                    extractor: &mut *self.extractor,
+                    matcher: &self.matcher,
+                    phonetizer: &phonetizer,
+                };
+                let realigned =
+                    realigner.realign(lineid, &audiodata, &mut phonemetrack, &words, false);
+                info!(
+                    "id {lineid:010}: > re-aligned #{realigned} of #{} flagged words",
+                    words.len()
+                );
+            }
+        }
+
         let mut outputdir = PathBuf::from(task.audiofile());
         outputdir.pop();
 
+        let phonemecount = phonemetrack.phonemes().len();
         let phonemefile = phonemes::store(&outputdir, phonemetrack)?;
 
         info!("id {lineid:010}: stored #{phonemecount} phoneme timings in [{phonemefile}]");
@@ -166,7 +269,7 @@ where
     fn rename_audiofile(&mut self, task: &TaskData, full_rename: bool) -> Result<String, String> {
         use std::fs;
 
-        let mut dataprovider = DataProvider::new_from_taskdata(task);
+        let mut dataprovider = DataProvider::new_from_taskdata(task, &self.datadir);
         dataprovider.load()?;
 
         debug!("extracting duration from audio...");
@@ -195,13 +298,7 @@ where
                         .collect::<String>()
                 };
                 let actor = self.strings.get_actor(task.lineid());
-                let new_audiofile = format!(
-                    "{:0>10}[{:.4}]{}{}",
-                    task.lineid(),
-                    duration,
-                    ::escape_actor(&actor.map(|a| format!("-{a}-")).unwrap_or_default())?,
-                    filename
-                );
+                let new_audiofile = audiofile_name(task.lineid(), duration, actor, &filename)?;
 
                 info!("renaming audiofile to: {}", new_audiofile);
                 let new_audiofile = old_audiofile.with_file_name(new_audiofile);
@@ -236,6 +333,13 @@ where
                     task.set_error(why)
                 }
             },
+            Realign(start, end) => match self.realign_range(&task, (start, end)) {
+                Ok(phoneme_file) => task.set_phonemefile(phoneme_file),
+                Err(why) => {
+                    error!("{} skipping id [{:>10}]...", &why, task.lineid());
+                    task.set_error(why)
+                }
+            },
         }
     }
     // ------------------------------------------------------------------------
@@ -307,24 +411,28 @@ impl<'translator, 'mapping> WordPhonetizer<'translator, 'mapping> {
     // ------------------------------------------------------------------------
     fn phonetize(&self, word: &str) -> Option<String> {
         if let Ok(trail) = self.translator.translate(word.trim()) {
-            let mut phonemes = Vec::with_capacity(trail.phonemes.len());
-            for ipa in &trail.phonemes {
-                if let Some(phoneme) = self.mapping.get(&ipa.phoneme) {
-                    phonemes.push(phoneme.as_str());
-                } else {
-                    return None;
-                }
-            }
-            Some(phonemes.join(" "))
+            self.map_phonemes(trail.phonemes.iter().map(|ipa| ipa.phoneme.as_str()))
         } else {
             None
         }
     }
     // ------------------------------------------------------------------------
+    /// maps ipa phonemes to the highest scoring audio extractor phonemes. None
+    /// if at least one phoneme has no mapping.
+    fn map_phonemes<'a>(&self, ipa: impl Iterator<Item = &'a str>) -> Option<String> {
+        let mut phonemes = Vec::new();
+        for ipa in ipa {
+            phonemes.push(self.mapping.get(ipa)?.as_str());
+        }
+        Some(phonemes.join(" "))
+    }
+    // ------------------------------------------------------------------------
 }
 // ----------------------------------------------------------------------------
 pub struct DataProvider {
     audiofile: String,
+    /// data directory with the codebook library for wem decoding
+    datadir: PathBuf,
 
     audio_modified: bool,
     audiodata: Vec<i16>,
@@ -334,22 +442,23 @@ const REQUIRED_SAMPLE_RATE: u32 = 44100;
 // ----------------------------------------------------------------------------
 impl DataProvider {
     // ------------------------------------------------------------------------
-    pub fn new(audiofile: &str) -> DataProvider {
+    pub fn new(audiofile: &str, datadir: &Path) -> DataProvider {
         DataProvider {
             audiofile: audiofile.to_owned(),
+            datadir: datadir.to_path_buf(),
             audio_modified: false,
             audiodata: Vec::default(),
         }
     }
     // ------------------------------------------------------------------------
-    pub fn new_from_taskdata(taskinfo: &TaskData) -> DataProvider {
-        DataProvider::new(taskinfo.audiofile())
+    pub fn new_from_taskdata(taskinfo: &TaskData, datadir: &Path) -> DataProvider {
+        DataProvider::new(taskinfo.audiofile(), datadir)
     }
     // ------------------------------------------------------------------------
     pub fn load(&mut self) -> Result<(), String> {
         debug!("reading audio data from {}", self.audiofile);
 
-        let data = audio::AudioLoader::load(&self.audiofile, REQUIRED_SAMPLE_RATE)?;
+        let data = audio::AudioLoader::load(&self.audiofile, REQUIRED_SAMPLE_RATE, &self.datadir)?;
         self.audiodata = data.values;
         self.audio_modified = data.resampled;
 
@@ -360,6 +469,47 @@ impl DataProvider {
         self.audiodata.len() as f32 / REQUIRED_SAMPLE_RATE as f32
     }
     // ------------------------------------------------------------------------
+    /// checks loaded audio for clipping, very low level, heavy background
+    /// noise, dc offset and truncated start/end
+    pub fn analyze_audio(&self) -> Vec<QualityWarning> {
+        use phonemes::{AUDIO_LOW_LEVEL_PEAK_DB, AUDIO_MAX_DC_OFFSET, AUDIO_MIN_SNR_DB};
+        use phonemes::QualityWarning::*;
+
+        let mut warnings = Vec::new();
+
+        let health = match audio::AudioAnalyzer::analyze(&self.audiodata, REQUIRED_SAMPLE_RATE) {
+            Some(health) => health,
+            None => return warnings,
+        };
+        trace!(
+            "> audio health: clipped #{}, peak {:.1} dBFS, snr {:.1} dB, dc offset {:.2}%",
+            health.clipped_runs,
+            health.peak_db,
+            health.snr_db,
+            health.dc_offset
+        );
+
+        if health.clipped_runs > 0 {
+            warnings.push(AudioClipping(health.clipped_runs));
+        }
+        if health.peak_db < AUDIO_LOW_LEVEL_PEAK_DB {
+            warnings.push(AudioLowLevel(health.peak_db));
+        } else if health.snr_db < AUDIO_MIN_SNR_DB {
+            // noise floor of the recording chain dominates very quiet audio
+            warnings.push(AudioNoisy(health.snr_db));
+        }
+        if health.dc_offset.abs() > AUDIO_MAX_DC_OFFSET {
+            warnings.push(AudioDcOffset(health.dc_offset));
+        }
+        if health.truncated_start {
+            warnings.push(AudioTruncatedStart);
+        }
+        if health.truncated_end {
+            warnings.push(AudioTruncatedEnd);
+        }
+        warnings
+    }
+    // ------------------------------------------------------------------------
     pub fn get_rawaudio(&self, sample_rate: u32, normalized: bool) -> Result<Vec<i16>, String> {
         if sample_rate == REQUIRED_SAMPLE_RATE {
             Ok(self.audiodata.clone())
@@ -387,10 +537,9 @@ pub fn init_phoneme_extraction(
     language: &str,
     stringsfile: &Path,
     datadir: &Path,
+    method: Option<ExtractionMethod>,
     loglevel: LevelFilter,
 ) -> Result<Processor<CsvStringsData>, String> {
-    let similarity_file = datadir.join(format!("{language}.phoneme.similarity.csv"));
-
     info!("loading strings file {}", stringsfile.display());
     let strings_provider = CsvStringsData::load_with_language(stringsfile, Some(language))
         .map_err(|e| {
@@ -408,15 +557,29 @@ pub fn init_phoneme_extraction(
         .init()
         .and(translator.set_language(language, Some(format!("{language}.espeak.custom.dict"))))?;
 
+    // optional project specific decoder settings next to the strings file
+    let overrides = stringsfile.with_file_name(format!("{language}.pocketsphinx.override.cfg"));
+    let overrides = if overrides.is_file() {
+        info!(
+            "loading pocketsphinx decoder overrides {}",
+            overrides.display()
+        );
+        Some(overrides.as_path())
+    } else {
+        None
+    };
+
     info!("initializing audio phoneme extractor (pocketsphinx)");
-    let extractor = AudioPhonemeExtractor::new(&datadir.to_string_lossy(), language, loglevel)?;
+    let mut extractor =
+        extractor::create_extractor(datadir, language, method, overrides, loglevel)?;
 
-    info!(
-        "loading phoneme similarity matrix for phoneme sequence matcher {}",
-        similarity_file.display()
-    );
+    let similarity_matrix = load_similarity_matrix(datadir, language)?;
 
-    let similarity_matrix = PhonemeSimilarityMatrix::load(&similarity_file)?;
+    // word starts are only used as alignment anchors
+    if similarity_matrix.profile().word_start_anchor == 0.0 {
+        info!("> word start anchor score is 0: disabled word level alignment");
+        extractor.set_word_start_anchoring(false);
+    }
     let matcher = PhonemeSequenceMatcher::new(similarity_matrix);
 
     Ok(Processor::new(
@@ -424,9 +587,43 @@ pub fn init_phoneme_extraction(
         translator,
         extractor,
         matcher,
+        datadir,
     ))
 }
 // ----------------------------------------------------------------------------
+/// loads the similarity matrix of the language and the optional alignment
+/// scoring profile and typical phoneme durations next to it
+pub fn load_similarity_matrix(
+    datadir: &Path,
+    language: &str,
+) -> Result<PhonemeSimilarityMatrix, String> {
+    let similarity_file = datadir.join(format!("{language}.phoneme.similarity.csv"));
+    info!(
+        "loading phoneme similarity matrix for phoneme sequence matcher {}",
+        similarity_file.display()
+    );
+    let mut similarity_matrix = PhonemeSimilarityMatrix::load(&similarity_file)?;
+
+    let profile_file = datadir.join(format!("{language}.phoneme.scoring.cfg"));
+    if profile_file.is_file() {
+        info!(
+            "loading alignment scoring profile {}",
+            profile_file.display()
+        );
+        similarity_matrix.set_profile(ScoringProfile::load(&profile_file)?);
+    }
+
+    let durations_file = datadir.join(format!("{language}.phoneme.durations.cfg"));
+    if durations_file.is_file() {
+        info!(
+            "loading typical phoneme durations {}",
+            durations_file.display()
+        );
+        similarity_matrix.set_durations(PhonemeDurations::load(&durations_file)?);
+    }
+    Ok(similarity_matrix)
+}
+// ----------------------------------------------------------------------------
 pub fn init_phoneme_generation(
     language: &str,
     stringsfile: &Path,
@@ -450,6 +647,70 @@ pub fn init_phoneme_generation(
     ))
 }
 // ----------------------------------------------------------------------------
+pub fn init_corpus_synthesis(
+    language: &str,
+    stringsfile: &Path,
+    datadir: &Path,
+    outputdir: &Path,
+) -> Result<(SyntheticCorpus, Box<dyn StringsProvider>), String> {
+    info!("loading strings file {}", stringsfile.display());
+    let strings_provider = CsvStringsData::load_with_language(stringsfile, Some(language))
+        .map_err(|e| format!("could not create string provider: {}", e))?;
+
+    info!("initializing speech synthesizer (eSpeak)");
+    let mut translator = TextPhonemeConverter::new(&datadir.to_string_lossy());
+
+    translator
+        .init()
+        .and(translator.set_language(language, Some(format!("{language}.espeak.custom.dict"))))?;
+
+    Ok((
+        SyntheticCorpus::new(translator, outputdir.to_owned()),
+        Box::new(strings_provider),
+    ))
+}
+// ----------------------------------------------------------------------------
+pub fn init_language_bootstrap(
+    language: &str,
+    stringsfile: &Path,
+    datadir: &Path,
+) -> Result<(LanguageBootstrap, Box<dyn StringsProvider>), String> {
+    info!("loading strings file {}", stringsfile.display());
+    let strings_provider = CsvStringsData::load_with_language(stringsfile, Some(language))
+        .map_err(|e| format!("could not create string provider: {}", e))?;
+
+    info!("initializing text to phoneme translator (eSpeak)");
+    let mut translator = TextPhonemeConverter::new(&datadir.to_string_lossy());
+
+    translator
+        .init()
+        .and(translator.set_language(language, Some(format!("{language}.espeak.custom.dict"))))?;
+
+    let cfg_file = datadir.join(format!("{language}.pocketsphinx.cfg"));
+    info!(
+        "loading pocketsphinx phoneme set from {}",
+        cfg_file.display()
+    );
+
+    Ok((
+        LanguageBootstrap::new(language, translator, &cfg_file)?,
+        Box::new(strings_provider),
+    ))
+}
+// ----------------------------------------------------------------------------
+pub fn init_scoring_tuner(language: &str, datadir: &Path) -> Result<ScoringTuner, String> {
+    info!("initializing text to phoneme translator (eSpeak)");
+    let mut translator = TextPhonemeConverter::new(&datadir.to_string_lossy());
+
+    translator
+        .init()
+        .and(translator.set_language(language, Some(format!("{language}.espeak.custom.dict"))))?;
+
+    let similarity_matrix = load_similarity_matrix(datadir, language)?;
+
+    Ok(ScoringTuner::new(similarity_matrix, translator))
+}
+// ----------------------------------------------------------------------------
 pub fn find_missing_audio(
     datadir: PathBuf,
     stringsfile: PathBuf,
@@ -489,7 +750,7 @@ pub fn find_missing_audio(
                     audio.insert(id, (filepath.to_owned(), duration.is_some()));
                 }
             }
-            FileInfo::Phonemes(_, _) => {}
+            FileInfo::Phonemes(_, _) | FileInfo::Alignment(_, _) => {}
         }
     }
     info!("found {} audio files", audio.len());
@@ -600,6 +861,22 @@ fn escape_textline(line: &str) -> Result<String, String> {
         .collect())
 }
 // ----------------------------------------------------------------------------
+/// <id>[<duration>]<actor><filename>
+fn audiofile_name(
+    lineid: u32,
+    duration: f32,
+    actor: Option<&String>,
+    filename: &str,
+) -> Result<String, String> {
+    Ok(format!(
+        "{:0>10}[{:.4}]{}{}",
+        lineid,
+        duration,
+        escape_actor(&actor.map(|a| format!("-{a}-")).unwrap_or_default())?,
+        filename
+    ))
+}
+// ----------------------------------------------------------------------------
 fn escape_actor(line: &str) -> Result<String, String> {
     let replacer = regex::Regex::new("[/\\?%*:|<>.$…, \"]")
         .map_err(|err| format!("failed to initialize filename escape regex: {}", err))?;
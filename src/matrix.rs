@@ -12,19 +12,37 @@ pub struct Matrix2D<T: Default> {
     pub data: Vec<T>,
 }
 // ----------------------------------------------------------------------------
-pub struct DebugMatrix2D<'a, T, U>
+/// matrix which stores only a band of consecutive columns per row. cells
+/// outside of the band are read as default value and cannot be written.
+pub struct BandedMatrix2D<T: Default> {
+    pub width: usize,
+    pub height: usize,
+    /// first and last stored column of every row
+    band: Vec<(usize, usize)>,
+    /// data index of the first stored cell of every row
+    offsets: Vec<usize>,
+    data: Vec<T>,
+    outside: T,
+}
+// ----------------------------------------------------------------------------
+pub trait Dimension2D {
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+}
+// ----------------------------------------------------------------------------
+pub struct DebugMatrix2D<'a, M, U>
 where
-    T: 'a + Default + fmt::Display,
+    M: 'a,
     U: 'a + fmt::Display,
 {
-    matrix: &'a Matrix2D<T>,
+    matrix: &'a M,
     col_names: &'a [U],
     row_names: &'a [U],
 }
 // ----------------------------------------------------------------------------
 // internals
 // ----------------------------------------------------------------------------
-const MAX_MATRIX_SIZE: usize = 500;
+pub const MAX_MATRIX_SIZE: usize = 500;
 
 use std::fmt;
 use std::ops::{Index, IndexMut};
@@ -91,18 +109,124 @@ impl<T: Default> IndexMut<(usize, usize)> for Matrix2D<T> {
     // ------------------------------------------------------------------------
 }
 // ----------------------------------------------------------------------------
+impl<T: Default> Dimension2D for Matrix2D<T> {
+    // ------------------------------------------------------------------------
+    fn width(&self) -> usize {
+        self.width
+    }
+    // ------------------------------------------------------------------------
+    fn height(&self) -> usize {
+        self.height
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+// ----------------------------------------------------------------------------
+impl<T: Default + Clone> BandedMatrix2D<T> {
+    // ------------------------------------------------------------------------
+    /// band contains the first and last column for every row (one row per
+    /// element)
+    pub fn new(width: usize, band: Vec<(usize, usize)>) -> BandedMatrix2D<T> {
+        if width == 0 || band.is_empty() {
+            fatal!(
+                "width and height for banded matrix must not be zero. got: {}x{}",
+                width,
+                band.len()
+            );
+        }
+        let mut offsets = Vec::with_capacity(band.len());
+        let mut size = 0;
+        for (row, &(first, last)) in band.iter().enumerate() {
+            if first > last || last >= width {
+                fatal!(
+                    "invalid band columns [{};{}] in row {} for banded matrix with width {}",
+                    first,
+                    last,
+                    row,
+                    width
+                );
+            }
+            offsets.push(size);
+            size += last - first + 1;
+        }
+        BandedMatrix2D {
+            width,
+            height: band.len(),
+            band,
+            offsets,
+            data: vec![T::default(); size],
+            outside: T::default(),
+        }
+    }
+    // ------------------------------------------------------------------------
+    /// all columns of all rows are stored
+    pub fn new_full(width: usize, height: usize) -> BandedMatrix2D<T> {
+        Self::new(width, vec![(0, width.saturating_sub(1)); height])
+    }
+    // ------------------------------------------------------------------------
+    /// first and last stored column of the row
+    #[inline]
+    pub fn columns(&self, row: usize) -> (usize, usize) {
+        self.band[row]
+    }
+    // ------------------------------------------------------------------------
+    #[inline]
+    pub fn is_stored(&self, index: (usize, usize)) -> bool {
+        let (first, last) = self.band[index.1];
+        first <= index.0 && index.0 <= last
+    }
+    // ------------------------------------------------------------------------
+    pub fn is_full(&self) -> bool {
+        self.data.len() == self.width * self.height
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl<T: Default + Clone> Index<(usize, usize)> for BandedMatrix2D<T> {
+    type Output = T;
+    // ------------------------------------------------------------------------
+    fn index(&self, index: (usize, usize)) -> &T {
+        assert!(index.0 < self.width, "x out of bound!");
+        assert!(index.1 < self.height, "y out of bound!");
+
+        if self.is_stored(index) {
+            &self.data[self.offsets[index.1] + index.0 - self.band[index.1].0]
+        } else {
+            &self.outside
+        }
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl<T: Default + Clone> IndexMut<(usize, usize)> for BandedMatrix2D<T> {
+    // ------------------------------------------------------------------------
+    fn index_mut(&mut self, index: (usize, usize)) -> &mut T {
+        assert!(self.is_stored(index), "cell out of band!");
+
+        &mut self.data[self.offsets[index.1] + index.0 - self.band[index.1].0]
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl<T: Default> Dimension2D for BandedMatrix2D<T> {
+    // ------------------------------------------------------------------------
+    fn width(&self) -> usize {
+        self.width
+    }
+    // ------------------------------------------------------------------------
+    fn height(&self) -> usize {
+        self.height
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
 // ----------------------------------------------------------------------------
-impl<'a, T, U> DebugMatrix2D<'a, T, U>
+impl<'a, M, U> DebugMatrix2D<'a, M, U>
 where
-    T: Default + fmt::Display,
     U: 'a + fmt::Display,
 {
     // ------------------------------------------------------------------------
-    pub fn new(
-        matrix: &'a Matrix2D<T>,
-        col_names: &'a [U],
-        row_names: &'a [U],
-    ) -> DebugMatrix2D<'a, T, U> {
+    pub fn new(matrix: &'a M, col_names: &'a [U], row_names: &'a [U]) -> DebugMatrix2D<'a, M, U> {
         DebugMatrix2D {
             matrix,
             col_names,
@@ -112,20 +236,21 @@ where
     // ------------------------------------------------------------------------
 }
 // ----------------------------------------------------------------------------
-impl<T, U> fmt::Display for DebugMatrix2D<'_, T, U>
+impl<M, U> fmt::Display for DebugMatrix2D<'_, M, U>
 where
-    T: Default + fmt::Display,
+    M: Dimension2D + Index<(usize, usize)>,
+    M::Output: fmt::Display,
     U: fmt::Display,
 {
     // ------------------------------------------------------------------------
     #[allow(unused_must_use)]
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         // scorematrix has extra first row/col without phonemes -> pad names
-        let colname_padding = self.matrix.width - self.col_names.len();
-        let rowname_padding = self.matrix.height - self.row_names.len();
+        let colname_padding = self.matrix.width() - self.col_names.len();
+        let rowname_padding = self.matrix.height() - self.row_names.len();
 
         write!(f, "\n    ");
-        for w in 0..self.matrix.width {
+        for w in 0..self.matrix.width() {
             if w >= colname_padding {
                 write!(f, "|{: ^4}", &self.col_names[w - colname_padding]);
             } else {
@@ -133,16 +258,15 @@ where
             }
         }
 
-        for h in 0..self.matrix.height {
+        for h in 0..self.matrix.height() {
             if h >= rowname_padding {
                 write!(f, "\n{: ^4}", &self.row_names[h - rowname_padding]);
             } else {
                 write!(f, "\n    ");
             }
 
-            for w in 0..self.matrix.width {
-                let s = &self.matrix.data[h * self.matrix.width + w];
-                write!(f, "|{: ^4}", s);
+            for w in 0..self.matrix.width() {
+                write!(f, "|{: ^4}", &self.matrix[(w, h)]);
             }
         }
         Ok(())
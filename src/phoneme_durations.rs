@@ -0,0 +1,191 @@
+//
+// typical phoneme durations
+//
+
+// ----------------------------------------------------------------------------
+// external interface
+// ----------------------------------------------------------------------------
+/// typical durations (in ms) of phoneme classes. text phonemes sharing a
+/// recognized audio phoneme get a part of its timing slot proportional to
+/// these durations.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhonemeDurations {
+    durations: [u32; PHONEME_CLASSES.len()],
+}
+// ----------------------------------------------------------------------------
+/// coarse articulatory class of an (IPA) phoneme
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PhonemeClass {
+    Vowel = 0,
+    Diphthong = 1,
+    Plosive = 2,
+    Affricate = 3,
+    Fricative = 4,
+    Nasal = 5,
+    Approximant = 6,
+    Tap = 7,
+    Other = 8,
+}
+// ----------------------------------------------------------------------------
+/// keys of all phoneme classes in duration files (in order of PhonemeClass)
+pub const PHONEME_CLASSES: &[&str] = &[
+    "vowel",
+    "diphthong",
+    "plosive",
+    "affricate",
+    "fricative",
+    "nasal",
+    "approximant",
+    "tap",
+    "other",
+];
+// ----------------------------------------------------------------------------
+impl PhonemeDurations {
+    // ------------------------------------------------------------------------
+    /// loads durations of phoneme classes (CLASS = ms). undefined classes use
+    /// the defaults.
+    pub fn load(file: &Path) -> Result<PhonemeDurations, String> {
+        let cfg_name = file.display();
+        let conf =
+            fs::read_to_string(file).map_err(|err| format!("failed to load {cfg_name}: {err}"))?;
+
+        let mut durations = PhonemeDurations::default();
+
+        for (i, line) in conf
+            .lines()
+            .enumerate()
+            .filter(|(_i, line)| !line.trim().is_empty() && !line.starts_with(';'))
+        {
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("{cfg_name}:{} failed to parse: {line}", i + 1))?;
+
+            let value = value
+                .trim()
+                .parse::<u32>()
+                .map_err(|err| format!("{cfg_name}:{} invalid value for {key}: {err}", i + 1))?;
+
+            durations
+                .set(&key.trim().to_lowercase(), value)
+                .map_err(|why| format!("{cfg_name}:{} {why}", i + 1))?;
+        }
+        Ok(durations)
+    }
+    // ------------------------------------------------------------------------
+    /// sets the duration of a phoneme class (lowercased key of duration files)
+    pub fn set(&mut self, key: &str, duration: u32) -> Result<(), String> {
+        let class = PHONEME_CLASSES
+            .iter()
+            .position(|class| *class == key)
+            .ok_or_else(|| format!("unknown phoneme class {}", key.to_uppercase()))?;
+
+        if duration == 0 {
+            return Err(format!("{} must be positive.", key.to_uppercase()));
+        }
+        self.durations[class] = duration;
+        Ok(())
+    }
+    // ------------------------------------------------------------------------
+    /// typical duration of a phoneme class in ms
+    pub fn get(&self, class: PhonemeClass) -> u32 {
+        self.durations[class as usize]
+    }
+    // ------------------------------------------------------------------------
+    /// typical duration of an (IPA) phoneme in ms
+    pub fn of(&self, phoneme: &str) -> u32 {
+        self.get(PhonemeClass::of(phoneme))
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl PhonemeClass {
+    // ------------------------------------------------------------------------
+    /// class of an IPA phoneme. diacritics, stress and length markers are
+    /// ignored.
+    pub fn of(phoneme: &str) -> PhonemeClass {
+        let vowels = phoneme.chars().filter(|c| VOWELS.contains(*c)).count();
+        if vowels > 1 {
+            return PhonemeClass::Diphthong;
+        } else if vowels == 1 {
+            return PhonemeClass::Vowel;
+        }
+
+        let is_fricative = |c: char| FRICATIVES.contains(c);
+
+        match phoneme.chars().find(|c| !DIACRITICS.contains(*c)) {
+            Some(c) if PLOSIVES.contains(c) && phoneme.chars().skip(1).any(is_fricative) => {
+                PhonemeClass::Affricate
+            }
+            Some(c) if PLOSIVES.contains(c) => PhonemeClass::Plosive,
+            Some(c) if is_fricative(c) => PhonemeClass::Fricative,
+            Some(c) if NASALS.contains(c) => PhonemeClass::Nasal,
+            Some(c) if APPROXIMANTS.contains(c) => PhonemeClass::Approximant,
+            Some(c) if TAPS.contains(c) => PhonemeClass::Tap,
+            _ => PhonemeClass::Other,
+        }
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+// internals
+// ----------------------------------------------------------------------------
+use std::fs;
+use std::path::Path;
+// ----------------------------------------------------------------------------
+/// mean durations of the phoneme classes in read (english) speech in ms
+const DEFAULT_DURATIONS: [u32; PHONEME_CLASSES.len()] = [80, 150, 70, 110, 95, 65, 60, 30, 80];
+// ----------------------------------------------------------------------------
+const VOWELS: &str = "iyɨʉɯuɪʏᵻᵿʊeøɘɵɤoəɚɛœɜɝɞʌɔæɐaɶɑɒ";
+const PLOSIVES: &str = "pbtdʈɖcɟkgɡqɢʔ";
+const FRICATIVES: &str = "ɸβfvθðszʃʒʂʐçʝxɣχʁħʕhɦɬɮɕʑ";
+const NASALS: &str = "mɱnɳɲŋɴ";
+/// including trills and laterals
+const APPROXIMANTS: &str = "ʋɹɻjɰwʍlɫɭʎʟrʀʙ";
+const TAPS: &str = "ɾɽ";
+const DIACRITICS: &str = "ˈˌ\"^ː-";
+// ----------------------------------------------------------------------------
+impl Default for PhonemeDurations {
+    fn default() -> PhonemeDurations {
+        PhonemeDurations {
+            durations: DEFAULT_DURATIONS,
+        }
+    }
+}
+// ----------------------------------------------------------------------------
+// ----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::PhonemeClass::*;
+    use super::{PhonemeClass, PhonemeDurations};
+
+    #[test]
+    fn test_phoneme_classes() {
+        let expected = [
+            ("ə", Vowel),
+            ("ˈaɪ", Diphthong),
+            ("iː", Vowel),
+            ("k", Plosive),
+            ("tʃ", Affricate),
+            ("dʒ", Affricate),
+            ("s", Fricative),
+            ("ŋ", Nasal),
+            ("ɹ", Approximant),
+            ("ɾ", Tap),
+            ("+spn+", Other),
+        ];
+        for (phoneme, class) in expected.iter() {
+            assert_eq!(PhonemeClass::of(phoneme), *class, "{}", phoneme);
+        }
+    }
+
+    #[test]
+    fn test_durations_set() {
+        let mut durations = PhonemeDurations::default();
+
+        assert!(durations.set("diphthong", 200).is_ok());
+        assert_eq!(durations.of("aɪ"), 200);
+        assert!(durations.set("diphthong", 0).is_err(), "zero duration");
+        assert!(durations.set("syllable", 100).is_err(), "unknown class");
+        assert_eq!(durations.of("aɪ"), 200);
+    }
+}
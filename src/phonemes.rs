@@ -17,16 +17,35 @@ pub trait PhonemeSegmentInterface {
     fn set_end(&mut self, value: u32);
 }
 // ----------------------------------------------------------------------------
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum QualityWarning {
     PhonemeGapInWord,
     UnusualDuration(u32),
     InactiveSegmentsInWord(usize),
     HighAmountOfLowScoreSegments(u32, f32),
+    // audio health (track level)
+    AudioClipping(u32),
+    AudioLowLevel(f32),
+    AudioNoisy(f32),
+    AudioDcOffset(f32),
+    AudioTruncatedStart,
+    AudioTruncatedEnd,
 }
 // ----------------------------------------------------------------------------
 const LOW_SCORE_THRESHOLD: f32 = 0.15;
 const MAX_LOW_SCORE_PERCENTAGE: u32 = 20;
+/// plausible duration (ms) of an active phoneme segment within a word
+const MIN_SEGMENT_DURATION_MS: u32 = 15;
+const MAX_SEGMENT_DURATION_MS: u32 = 500;
+
+/// words below the confidence should be checked
+pub const WORD_CONFIDENCE_WARN: f32 = 0.5;
+/// words below the confidence are probably misaligned
+pub const WORD_CONFIDENCE_ERROR: f32 = 0.25;
+
+pub const AUDIO_LOW_LEVEL_PEAK_DB: f32 = -24.0;
+pub const AUDIO_MIN_SNR_DB: f32 = 15.0;
+pub const AUDIO_MAX_DC_OFFSET: f32 = 2.0;
 // ----------------------------------------------------------------------------
 impl QualityWarning {
     // ------------------------------------------------------------------------
@@ -38,6 +57,12 @@ impl QualityWarning {
             UnusualDuration(d) => format!("suspicious duration: {d}ms"),
             InactiveSegmentsInWord(_n) => "inactive phoneme within a word".to_string(),
             HighAmountOfLowScoreSegments(_n, f) => format!("low confidence-score: {f:.2}"),
+            AudioClipping(n) => format!("clipped audio ({n}x)"),
+            AudioLowLevel(db) => format!("very low audio level: {db:.1} dBFS"),
+            AudioNoisy(db) => format!("noisy audio (SNR {db:.1} dB)"),
+            AudioDcOffset(p) => format!("audio dc offset: {p:.1}%"),
+            AudioTruncatedStart => "audio start truncated?".to_string(),
+            AudioTruncatedEnd => "audio end truncated?".to_string(),
         }
     }
     // ------------------------------------------------------------------------
@@ -58,6 +83,72 @@ impl QualityWarning {
                     (found {n}%, see phoneme data table)",
                 )
             }
+            AudioClipping(n) => format!("audio contains #{n} clipped sections"),
+            AudioLowLevel(db) => {
+                format!("audio peak level {db:.1} dBFS is below {AUDIO_LOW_LEVEL_PEAK_DB} dBFS")
+            }
+            AudioNoisy(db) => format!(
+                "heavy background noise: estimated signal to noise ratio {db:.1} dB \
+                (expected >= {AUDIO_MIN_SNR_DB} dB)"
+            ),
+            AudioDcOffset(p) => {
+                format!("audio has a dc offset of {p:.1}% (expected <= {AUDIO_MAX_DC_OFFSET}%)")
+            }
+            AudioTruncatedStart => {
+                "audio starts with speech level signal. probably cut off too early".to_string()
+            }
+            AudioTruncatedEnd => {
+                "audio ends with speech level signal. probably cut off too early".to_string()
+            }
+        }
+    }
+    // ------------------------------------------------------------------------
+    fn is_audio_warning(&self) -> bool {
+        use self::QualityWarning::*;
+
+        matches!(
+            self,
+            AudioClipping(_)
+                | AudioLowLevel(_)
+                | AudioNoisy(_)
+                | AudioDcOffset(_)
+                | AudioTruncatedStart
+                | AudioTruncatedEnd
+        )
+    }
+    // ------------------------------------------------------------------------
+    /// compact encoding of audio warnings for storing in phonemes file meta
+    fn encode(&self) -> Option<String> {
+        use self::QualityWarning::*;
+
+        match self {
+            AudioClipping(n) => Some(format!("clipping:{n}")),
+            AudioLowLevel(db) => Some(format!("low-level:{db:.1}")),
+            AudioNoisy(db) => Some(format!("noise:{db:.1}")),
+            AudioDcOffset(p) => Some(format!("dc-offset:{p:.1}")),
+            AudioTruncatedStart => Some("truncated-start".to_string()),
+            AudioTruncatedEnd => Some("truncated-end".to_string()),
+            _ => None,
+        }
+    }
+    // ------------------------------------------------------------------------
+    fn decode(value: &str) -> Result<QualityWarning, String> {
+        use self::QualityWarning::*;
+
+        let (key, value) = value.trim().split_once(':').unwrap_or((value.trim(), ""));
+        let parse_f32 =
+            |v: &str| f32::from_str(v).map_err(|e| format!("audio warning [{key}]: {e}"));
+
+        match key {
+            "clipping" => Ok(AudioClipping(
+                u32::from_str(value).map_err(|e| format!("audio warning [{key}]: {e}"))?,
+            )),
+            "low-level" => Ok(AudioLowLevel(parse_f32(value)?)),
+            "noise" => Ok(AudioNoisy(parse_f32(value)?)),
+            "dc-offset" => Ok(AudioDcOffset(parse_f32(value)?)),
+            "truncated-start" => Ok(AudioTruncatedStart),
+            "truncated-end" => Ok(AudioTruncatedEnd),
+            _ => Err(format!("found unsupported audio warning [{key}]")),
         }
     }
     // ------------------------------------------------------------------------
@@ -82,6 +173,24 @@ pub struct PhonemeResult {
     pub phonemes: Vec<PhonemeSegment>,
 }
 // ----------------------------------------------------------------------------
+/// confidence of the alignment of one word of the track (all values in [0, 1])
+#[derive(Clone, Debug, PartialEq)]
+pub struct WordConfidence {
+    /// indices of the first and last phoneme segment of the word (including
+    /// gap segments)
+    pub segments: (usize, usize),
+    /// average score of the active segments relative to the max similarity
+    /// score. segment scores combine phoneme similarity and acoustic confidence
+    /// of the matched audio phonemes
+    pub score: f32,
+    /// share of active segments with a plausible duration
+    pub durations: f32,
+    /// share of inactive segments (without gaps)
+    pub inactive: f32,
+    /// combination of all indicators
+    pub confidence: f32,
+}
+// ----------------------------------------------------------------------------
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Default)]
 pub enum QualityAssessment {
     #[default]
@@ -93,7 +202,7 @@ pub enum QualityAssessment {
     EditedWithErrors,
 }
 // ----------------------------------------------------------------------------
-#[derive(Default, Clone, Eq, PartialEq)]
+#[derive(Default, Clone, PartialEq)]
 pub struct PhonemeTrack<T> {
     id: u32,
     version: u16,
@@ -101,9 +210,14 @@ pub struct PhonemeTrack<T> {
     input_text: String,
     translation: String,
     audio_hypothesis: Option<String>,
+    /// rejected alternative audio hypotheses with alignment score per alignment
+    /// operation
+    audio_alternatives: Vec<(f32, String)>,
     actor: Option<String>,
     phonemes: Vec<T>,
+    audio_warnings: Vec<QualityWarning>,
     quality: QualityAssessment,
+    word_confidences: Vec<WordConfidence>,
 }
 // ----------------------------------------------------------------------------
 pub fn store(outputpath: &PathBuf, data: PhonemeTrack<PhonemeSegment>) -> Result<String, String> {
@@ -136,6 +250,7 @@ use std::path::{Path, PathBuf};
 use text::{CsvLoader, CsvWriter, SimpleCsvWriter};
 
 use sequence_matcher::WARN_MATCHING_SCORE_MIN;
+use similarity_matrix::SIMILARITY_SCORE_MAX;
 // ----------------------------------------------------------------------------
 impl<T> PhonemeTrack<T> {
     // ------------------------------------------------------------------------
@@ -155,9 +270,12 @@ impl<T> PhonemeTrack<T> {
             input_text: text.to_owned(),
             translation: translation.to_owned(),
             audio_hypothesis,
+            audio_alternatives: Vec::default(),
             actor,
             phonemes,
+            audio_warnings: Vec::default(),
             quality: QualityAssessment::Unknown,
+            word_confidences: Vec::default(),
         }
     }
     // ------------------------------------------------------------------------
@@ -185,6 +303,10 @@ impl<T> PhonemeTrack<T> {
         &self.audio_hypothesis
     }
     // ------------------------------------------------------------------------
+    pub fn audio_alternatives(&self) -> &Vec<(f32, String)> {
+        &self.audio_alternatives
+    }
+    // ------------------------------------------------------------------------
     pub fn actor(&self) -> Option<&String> {
         self.actor.as_ref()
     }
@@ -197,6 +319,15 @@ impl<T> PhonemeTrack<T> {
         &mut self.phonemes
     }
     // ------------------------------------------------------------------------
+    pub fn audio_warnings(&self) -> &Vec<QualityWarning> {
+        &self.audio_warnings
+    }
+    // ------------------------------------------------------------------------
+    /// confidences of all words from the last quality assessment
+    pub fn word_confidences(&self) -> &Vec<WordConfidence> {
+        &self.word_confidences
+    }
+    // ------------------------------------------------------------------------
     pub fn set_version(&mut self, version: u16) {
         self.version = version
     }
@@ -205,6 +336,21 @@ impl<T> PhonemeTrack<T> {
         self.actor = Some(actor.to_string())
     }
     // ------------------------------------------------------------------------
+    pub fn set_audio_alternatives(&mut self, alternatives: Vec<(f32, String)>) {
+        self.audio_alternatives = alternatives
+    }
+    // ------------------------------------------------------------------------
+    pub fn set_audio_warnings(&mut self, warnings: Vec<QualityWarning>) {
+        self.audio_warnings = warnings
+            .into_iter()
+            .filter(QualityWarning::is_audio_warning)
+            .collect();
+    }
+    // ------------------------------------------------------------------------
+    pub fn set_word_confidences(&mut self, confidences: Vec<WordConfidence>) {
+        self.word_confidences = confidences
+    }
+    // ------------------------------------------------------------------------
 }
 // ----------------------------------------------------------------------------
 impl PhonemeTrack<PhonemeSegment> {
@@ -222,6 +368,8 @@ impl PhonemeTrack<PhonemeSegment> {
         // - any translation word inactive -> serious problem
         // - unusually long phoneme timing -> probably a problem
         // - very low scores -> probably a problem
+        // - truncated audio -> serious problem
+        // - any other audio health warning -> probably a problem
 
         let mut words = Vec::new();
         let mut word = Vec::new();
@@ -263,7 +411,7 @@ impl PhonemeTrack<PhonemeSegment> {
                 let duration = segment.end.saturating_sub(segment.start);
 
                 if segment.active {
-                    if !(15..=500).contains(&duration) {
+                    if !(MIN_SEGMENT_DURATION_MS..=MAX_SEGMENT_DURATION_MS).contains(&duration) {
                         // very short or very long (> 0.5s) segment
                         debug!(
                             "id {lineid:010}: > detected unusually short or long segment within \
@@ -335,12 +483,74 @@ impl PhonemeTrack<PhonemeSegment> {
             }
         }
 
+        // audio problems cannot be fixed by editing the phonemes: already
+        // reviewed (edited) tracks are not downgraded
+        let is_reviewed = self.version > 1;
+        for warning in &self.audio_warnings {
+            debug!("id {lineid:010}: > audio health: {}", warning.long());
+            let assessment = match warning {
+                AudioTruncatedStart => {
+                    // make it visible on the affected segment
+                    if let Some(segment) = self.phonemes.iter_mut().find(|s| s.active) {
+                        segment.warnings.push(*warning);
+                    }
+                    NeedsCheckError
+                }
+                AudioTruncatedEnd => {
+                    if let Some(segment) = self.phonemes.iter_mut().rev().find(|s| s.active) {
+                        segment.warnings.push(*warning);
+                    }
+                    NeedsCheckError
+                }
+                _ => NeedsCheckWarn,
+            };
+            if !is_reviewed {
+                new_assessment.update(assessment);
+            }
+        }
+
+        self.word_confidences = word_confidences(&self.phonemes);
+
         self.quality = new_assessment;
         self.quality
     }
     // ------------------------------------------------------------------------
 }
 // ----------------------------------------------------------------------------
+impl WordConfidence {
+    // ------------------------------------------------------------------------
+    /// start and end (ms) of the active segments of the word in phonemes. None
+    /// if all segments are inactive.
+    pub fn timings<P: PhonemeSegmentInterface>(&self, phonemes: &[P]) -> Option<(u32, u32)> {
+        let (first, last) = self.segments;
+
+        phonemes
+            .iter()
+            .take(last + 1)
+            .skip(first)
+            .filter(|p| p.is_active())
+            .fold(None, |range, p| match range {
+                Some((start, end)) => Some((u32::min(start, p.start()), u32::max(end, p.end()))),
+                None => Some((p.start(), p.end())),
+            })
+    }
+    // ------------------------------------------------------------------------
+    /// phonemes of the word in phonemes (without gaps)
+    pub fn phonemes<P: PhonemeSegmentInterface>(&self, phonemes: &[P]) -> String {
+        let (first, last) = self.segments;
+
+        phonemes
+            .iter()
+            .take(last + 1)
+            .skip(first)
+            .map(|p| p.phoneme())
+            .filter(|p| *p != "_")
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
 impl QualityAssessment {
     // ------------------------------------------------------------------------
     fn update(&mut self, new_assessment: Self) {
@@ -465,6 +675,19 @@ impl CsvLoader<PhonemeTrack<PhonemeSegment>> for PhonemeTrack<PhonemeSegment> {
                             ("audio-hypothesis", value) => {
                                 track.audio_hypothesis = Some(value.to_owned())
                             }
+                            ("audio-alternative", value) => {
+                                let (score, hypothesis) = value
+                                    .split_once('|')
+                                    .and_then(|(score, hypothesis)| {
+                                        Some((score.trim().parse::<f32>().ok()?, hypothesis))
+                                    })
+                                    .ok_or_else(|| {
+                                        err_format("expected audio-alternative as score|hypothesis")
+                                    })?;
+                                track
+                                    .audio_alternatives
+                                    .push((score, hypothesis.to_owned()));
+                            }
                             ("version", value) => {
                                 track.version =
                                     u16::from_str(value).map_err(|e| err_format(&e.to_string()))?
@@ -472,6 +695,14 @@ impl CsvLoader<PhonemeTrack<PhonemeSegment>> for PhonemeTrack<PhonemeSegment> {
                             ("actor", value) => {
                                 track.actor = Some(value.trim().to_lowercase());
                             }
+                            ("audio-warnings", value) => {
+                                track.audio_warnings = value
+                                    .split(',')
+                                    .filter(|w| !w.trim().is_empty())
+                                    .map(QualityWarning::decode)
+                                    .collect::<Result<Vec<_>, _>>()
+                                    .map_err(|e| err_format(&e))?;
+                            }
                             (key, _) => {
                                 return Err(err_format(&format!("found unsupported meta key [{key}]")))
                             }
@@ -529,10 +760,21 @@ fn save_as_csv(filepath: &PathBuf, data: &PhonemeTrack<PhonemeSegment>) -> Resul
     }
     writer.write_meta("text", &data.input_text);
     writer.write_meta("translation", &data.translation);
+    if !data.audio_warnings.is_empty() {
+        let warnings = data
+            .audio_warnings
+            .iter()
+            .filter_map(QualityWarning::encode)
+            .collect::<Vec<_>>();
+        writer.write_meta("audio-warnings", &warnings.join(", "));
+    }
 
     // available audio hypothesis means audio + translation data available
     if let Some(ref audio_hypothesis) = data.audio_hypothesis {
         writer.write_meta("audio-hypothesis", audio_hypothesis);
+        for (score, hypothesis) in &data.audio_alternatives {
+            writer.write_meta("audio-alternative", &format!("{score:.2}|{hypothesis}"));
+        }
         writer.write_comment("");
         writer.write_comment(
             "auto-matched phoneme translation (eSpeak) with timings (pocketsphinx):",
@@ -751,6 +993,63 @@ pub fn update_timings_on_activation<P: PhonemeSegmentInterface>(
 // ----------------------------------------------------------------------------
 // helper
 // ----------------------------------------------------------------------------
+/// confidence of every word (phoneme segments up to the next word start) with
+/// at least one phoneme
+fn word_confidences(phonemes: &[PhonemeSegment]) -> Vec<WordConfidence> {
+    let mut word_starts = phonemes
+        .iter()
+        .enumerate()
+        .filter(|(i, segment)| *i > 0 && segment.word_start)
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    word_starts.push(phonemes.len());
+
+    let mut confidences = Vec::with_capacity(word_starts.len());
+    let mut first = 0;
+    for next in word_starts {
+        let word = &phonemes[first..next];
+        let segments = word.iter().filter(|s| s.phoneme != "_").count();
+
+        if segments > 0 {
+            let active = word.iter().filter(|s| s.active).collect::<Vec<_>>();
+            let inactive = word
+                .iter()
+                .filter(|s| !s.active && s.phoneme != "_")
+                .count();
+
+            let (score, durations) = if active.is_empty() {
+                (0.0, 0.0)
+            } else {
+                let plausible = active
+                    .iter()
+                    .map(|s| s.end.saturating_sub(s.start))
+                    .filter(|d| (MIN_SEGMENT_DURATION_MS..=MAX_SEGMENT_DURATION_MS).contains(d))
+                    .count();
+                let score = active.iter().map(|s| s.score).sum::<f32>() / active.len() as f32;
+
+                (
+                    (score / SIMILARITY_SCORE_MAX).clamp(0.0, 1.0),
+                    plausible as f32 / active.len() as f32,
+                )
+            };
+            let inactive = inactive as f32 / segments as f32;
+
+            // scores are the main indicator. implausible durations halve the
+            // confidence at most. inactive phonemes are missing parts of the
+            // word and almost always an alignment error
+            confidences.push(WordConfidence {
+                segments: (first, next - 1),
+                score,
+                durations,
+                inactive,
+                confidence: score * (0.5 + 0.5 * durations) * (1.0 - inactive).powi(2),
+            });
+        }
+        first = next;
+    }
+    confidences
+}
+// ----------------------------------------------------------------------------
 #[inline]
 fn clamp_ms(time: f32, granularity: f32, min: u32, max: u32) -> u32 {
     let x = (time / granularity).round() * granularity;
@@ -763,3 +1062,126 @@ fn clamp_ms(time: f32, granularity: f32, min: u32, max: u32) -> u32 {
     }
 }
 // ----------------------------------------------------------------------------
+// ----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::QualityAssessment::*;
+    use super::QualityWarning::*;
+    use super::{word_confidences, PhonemeSegment, PhonemeTrack, QualityWarning};
+
+    fn track(audio_warnings: Vec<QualityWarning>) -> PhonemeTrack<PhonemeSegment> {
+        let phonemes = [
+            ("_", 0, 100),
+            ("h", 100, 180),
+            ("aɪ", 180, 350),
+            ("_", 350, 400),
+        ]
+        .iter()
+        .map(|&(phoneme, start, end)| PhonemeSegment {
+            phoneme: phoneme.to_owned(),
+            word_start: phoneme == "h",
+            start,
+            end,
+            weight: 1.0,
+            score: 1.0,
+            matching_info: None,
+            traceback: None,
+            active: phoneme != "_",
+            warnings: Vec::default(),
+        })
+        .collect();
+
+        let mut track = PhonemeTrack::new(1, "en", "hi", "haɪ", None, None, phonemes);
+        track.set_audio_warnings(audio_warnings);
+        track
+    }
+
+    #[test]
+    fn test_assess_quality_audio_warnings() {
+        assert_eq!(track(Vec::default()).assess_quality(), Ok);
+        assert_eq!(
+            track(vec![AudioNoisy(10.0)]).assess_quality(),
+            NeedsCheckWarn
+        );
+        assert_eq!(
+            track(vec![AudioClipping(2), AudioTruncatedStart]).assess_quality(),
+            NeedsCheckError
+        );
+
+        // truncation is shown on the first/last active segment
+        let mut truncated = track(vec![AudioTruncatedStart, AudioTruncatedEnd]);
+        truncated.assess_quality();
+        let warnings = truncated
+            .phonemes()
+            .iter()
+            .map(|p| p.warnings.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            warnings,
+            [
+                vec![],
+                vec![AudioTruncatedStart],
+                vec![AudioTruncatedEnd],
+                vec![]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_assess_quality_keeps_reviewed_tracks() {
+        let mut reviewed = track(vec![AudioLowLevel(-30.0), AudioTruncatedEnd]);
+        reviewed.set_version(2);
+
+        assert_eq!(reviewed.assess_quality(), EditedOk, "audio warnings only");
+        assert_eq!(
+            reviewed.phonemes()[2].warnings,
+            [AudioTruncatedEnd],
+            "still visible"
+        );
+
+        // phoneme problems of edited tracks are still reported
+        reviewed.phonemes_mut()[2].start = 190;
+        assert_eq!(reviewed.assess_quality(), EditedWithErrors);
+    }
+
+    #[test]
+    fn test_word_confidences_of_inexact_matches() {
+        let phonemes = [
+            ("h", 100, 180, 2.0),
+            ("aɪ", 180, 350, 2.0),
+            ("j", 350, 400, 2.0),
+            ("u", 400, 550, 1.2),
+        ]
+        .iter()
+        .map(|&(phoneme, start, end, score)| PhonemeSegment {
+            phoneme: phoneme.to_owned(),
+            word_start: phoneme == "h" || phoneme == "j",
+            start,
+            end,
+            score,
+            active: true,
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+
+        let words = word_confidences(&phonemes);
+        assert_eq!(words.len(), 2);
+        assert!(words[0].confidence > 0.9, "exact matches: {:?}", words);
+        assert!(
+            words[1].confidence < words[0].confidence - 0.15,
+            "similar but wrong phoneme: {:?}",
+            words
+        );
+        assert!((words[1].score - 1.6 / 2.1).abs() < 1e-6, "{:?}", words);
+    }
+
+    #[test]
+    fn test_audio_warning_messages() {
+        assert!(AudioTruncatedEnd.long().contains("too early"));
+
+        for warning in [AudioClipping(3), AudioDcOffset(2.5), AudioTruncatedEnd].iter() {
+            let encoded = warning.encode().unwrap();
+            assert_eq!(QualityWarning::decode(&encoded), Result::Ok(*warning));
+        }
+    }
+}
@@ -11,6 +11,19 @@ extern crate pocketsphinx;
 pub struct PocketSphinx {
     decoder: pocketsphinx::Decoder,
     translator: Translator,
+    method: ExtractionMethod,
+    external_aligner: Option<String>,
+    model_dir: String,
+    phoneme_dictionary_path: String,
+    noise_dictionary_path: String,
+    /// directory of sphinxtrain tools used for speaker adaptation
+    adaptation_tools: Option<String>,
+    /// currently applied speaker transform
+    speaker_transform: Option<PathBuf>,
+    /// applied speaker transform file was replaced by a new adaptation
+    speaker_transform_replaced: bool,
+    /// marks word starts of hypotheses without them by a word level alignment
+    word_start_anchoring: bool,
 }
 // ----------------------------------------------------------------------------
 struct Translator {
@@ -29,6 +42,14 @@ struct Config {
     phoneme_set: Vec<String>,
     /// additional cleanup characters
     cleanup: Vec<char>,
+    /// default extraction method for this language
+    method: ExtractionMethod,
+    /// commandline of external aligner
+    external_aligner: Option<String>,
+    /// additional decoder settings (e.g. beam widths) overriding the defaults
+    decoder_options: Vec<(String, String)>,
+    /// directory of sphinxtrain tools (sphinx_fe, bw, mllr_solve)
+    adaptation_tools: Option<String>,
     /// mappings from IPA phonemes to a set of alternative pocketsphinx phonemes.
     /// used to translate unknown words letter by letter
     mapping: IndexMap<char, String>,
@@ -55,15 +76,21 @@ struct Config {
 }
 // ----------------------------------------------------------------------------
 use std::collections::{HashMap, HashSet};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::slice;
 
 use indexmap::IndexMap;
 use logger::LevelFilter;
 
 use super::WordPhonetizer;
 
+use extractor::{speaker_filename, AdaptationCorpus, AudioPhonemeExtractor, ExtractionMethod};
 use phonemes::{PhonemeResult, PhonemeSegment};
-use pocketsphinx::pocketsphinx::{Config as PocketSphinxConfig, SegFrames};
+use pocketsphinx::pocketsphinx::{
+    Config as PocketSphinxConfig, Endpointer, LogMath, NBestHypothesis, ParamType, SegFrames,
+    SegIter, SegProp, VADMode,
+};
 // ----------------------------------------------------------------------------
 #[cfg(target_os = "linux")]
 fn get_null_logfile() -> &'static str {
@@ -74,6 +101,49 @@ fn get_null_logfile() -> &'static str {
     "NUL"
 }
 // ----------------------------------------------------------------------------
+/// prefix of config keys passed as option to the decoder
+const DECODER_OPTION_PREFIX: &str = "decoder-";
+/// decoder options set up by the extractor itself
+const RESERVED_DECODER_OPTIONS: &[&str] = &[
+    "hmm",
+    "allphone",
+    "dict",
+    "fdict",
+    "jsgf",
+    "fsg",
+    "lm",
+    "kws",
+    "keyphrase",
+    "logfn",
+    "backtrace",
+];
+// ----------------------------------------------------------------------------
+/// subdirectory of the model directory with speaker transforms
+const ADAPTATION_DIR: &str = "adaptation";
+/// acoustic feature settings of the model forwarded to baum-welch statistics
+/// accumulation
+const ADAPTATION_FEATURE_PARAMS: &[&str] = &[
+    "feat", "agc", "cmn", "varnorm", "lda", "ldadim", "svspec", "cmninit",
+];
+// ----------------------------------------------------------------------------
+/// acoustic likelihood per frame (ln) that reduces the confidence of a segment
+/// to 1/e compared to the median segment of the utterance
+const ACOUSTIC_CONFIDENCE_SCALE: f64 = 2.0;
+// ----------------------------------------------------------------------------
+/// max distance (ms) of a recognized phoneme start to a word start of the word
+/// level alignment to be marked as word start
+const WORD_START_TOLERANCE_MS: u32 = 50;
+// ----------------------------------------------------------------------------
+/// raw recognizer scores of a segment (in logbase of the decoder)
+struct AcousticScore {
+    /// log posterior probability (0 if not computed by search)
+    posterior: i32,
+    /// acoustic model score
+    ascr: i32,
+    frames: i32,
+    silence: bool,
+}
+// ----------------------------------------------------------------------------
 impl Config {
     // ------------------------------------------------------------------------
     fn load(cfg_dir: &str, language: &str) -> Result<Self, String> {
@@ -89,6 +159,10 @@ impl Config {
         let mut model_dictionary = None;
         let mut phoneme_model = None;
         let mut noise_dictionary = "noisedict".to_string();
+        let mut method = ExtractionMethod::default();
+        let mut external_aligner = None;
+        let mut decoder_options = Vec::new();
+        let mut adaptation_tools = None;
 
         let conf = fs::read_to_string(cfg_path)
             .map_err(|err| format!("failed to load {cfg_name}: {err}"))?;
@@ -104,14 +178,7 @@ impl Config {
 
             match key.trim().to_lowercase().as_str() {
                 "phonemes" => {
-                    phonemes = value
-                        .trim()
-                        .split(' ')
-                        .map(|a| a.trim().to_string())
-                        .filter(|a| !a.is_empty())
-                        .collect::<Vec<_>>();
-                    phonemes.sort();
-                    phonemes.dedup();
+                    phonemes = parse_phoneme_set(value);
                 }
                 "cleanup" => {
                     cleanup.extend(value.trim().chars());
@@ -127,6 +194,20 @@ impl Config {
                 "noise-dictionary" => {
                     noise_dictionary = value.trim().to_string();
                 }
+                "extraction" => {
+                    method = value
+                        .parse()
+                        .map_err(|err| format!("{cfg_name}:{} {err}", i + 1))?;
+                }
+                "external-aligner" => {
+                    external_aligner = Some(value.trim().to_string());
+                }
+                "adaptation-tools" => {
+                    adaptation_tools = Some(value.trim().to_string());
+                }
+                option if option.starts_with(DECODER_OPTION_PREFIX) => {
+                    decoder_options.push(parse_decoder_option(option, value));
+                }
                 character if character.chars().count() == 1 => {
                     let alternatives = value
                         .trim()
@@ -199,6 +280,10 @@ impl Config {
             model_dir: model_dir.to_string(),
             phoneme_set: phonemes,
             cleanup,
+            method,
+            external_aligner,
+            decoder_options,
+            adaptation_tools,
             mapping,
 
             phoneme_model,
@@ -214,12 +299,56 @@ impl Config {
         })
     }
     // ------------------------------------------------------------------------
+    /// loads (project specific) decoder options overriding the options of the
+    /// language config. only DECODER-<option> settings are allowed.
+    fn load_overrides(&mut self, file: &Path) -> Result<(), String> {
+        use std::fs;
+
+        let cfg_name = file.display();
+        let conf =
+            fs::read_to_string(file).map_err(|err| format!("failed to load {cfg_name}: {err}"))?;
+
+        for (i, line) in conf
+            .lines()
+            .enumerate()
+            .filter(|(_i, line)| !line.is_empty() && !line.starts_with(';'))
+        {
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("{cfg_name}:{} failed to parse: {line}", i + 1))?;
+
+            match key.trim().to_lowercase().as_str() {
+                option if option.starts_with(DECODER_OPTION_PREFIX) => {
+                    self.decoder_options
+                        .push(parse_decoder_option(option, value));
+                }
+                _ => {
+                    return Err(format!(
+                    "{cfg_name}:{} only DECODER-<option> settings can be overriden. found {key}",
+                    i + 1
+                ))
+                }
+            }
+        }
+        Ok(())
+    }
+    // ------------------------------------------------------------------------
 }
 // ----------------------------------------------------------------------------
 impl PocketSphinx {
     // ------------------------------------------------------------------------
-    pub fn new(modeldir: &str, language: &str, loglevel: LevelFilter) -> Result<Self, String> {
-        let config = Config::load(modeldir, language)?;
+    /// overrides is an optional (project specific) config with decoder options
+    /// overriding the options of the language config
+    pub fn new(
+        modeldir: &str,
+        language: &str,
+        overrides: Option<&Path>,
+        loglevel: LevelFilter,
+    ) -> Result<Self, String> {
+        let mut config = Config::load(modeldir, language)?;
+        if let Some(file) = overrides {
+            config.load_overrides(file)?;
+        }
 
         let translator =
             Translator::new(&config, loglevel).map_err(|err| format!("pocketsphinx: {err}"))?;
@@ -228,10 +357,11 @@ impl PocketSphinx {
         debug!("> model directory:    [{}]", config.model_dir);
         debug!("> phoneme model:      [{}]", config.phoneme_model);
         debug!("> phoneme dictionary: [{}]", config.phoneme_dictionary);
+        debug!("> extraction method:  [{}]", config.method);
 
         // audio -> phoneme decoder
         // Create a config and set default acoustic model, dictionary, and language model
-        let decoder = Self::create_config(
+        let mut decoder_config = Self::create_config(
             "decoder",
             &[
                 ("hmm", &config.model_dir),
@@ -242,20 +372,125 @@ impl PocketSphinx {
                 ("bestpath", "false"),
                 ("remove_noise", "true"),
                 ("fsgusefiller", "false"),
+                // pruning of all searches (allphone, grammar constrained
+                // recognition and realignment). pocketsphinx defaults, can be
+                // tuned with DECODER-<option> settings
+                ("beam", "1e-48"),
+                ("wbeam", "7e-29"),
+                ("pbeam", "1e-48"),
                 ("fdict", &config.noise_dictionary_path),
                 ("dict", &config.phoneme_dictionary_path),
             ],
             loglevel,
-        )?
-        .init_decoder()
-        .map_err(|err| format!("failed to create pocketsphinx decoder: {err}"))?;
+        )?;
+        Self::apply_decoder_options(&mut decoder_config, &config.decoder_options)?;
+
+        for setting in ["beam", "wbeam", "pbeam"].iter() {
+            if let Ok(value) = decoder_config.get_float(setting) {
+                debug!("> decoder {:<12}[{value:e}]", format!("{setting}:"));
+            }
+        }
+
+        let decoder = decoder_config
+            .init_decoder()
+            .map_err(|err| format!("failed to create pocketsphinx decoder: {err}"))?;
 
         Ok(PocketSphinx {
             decoder,
             translator,
+            method: config.method,
+            external_aligner: config.external_aligner,
+            model_dir: config.model_dir,
+            phoneme_dictionary_path: config.phoneme_dictionary_path,
+            noise_dictionary_path: config.noise_dictionary_path,
+            adaptation_tools: config.adaptation_tools,
+            speaker_transform: None,
+            speaker_transform_replaced: false,
+            word_start_anchoring: true,
         })
     }
     // ------------------------------------------------------------------------
+    /// phoneme set of the pocketsphinx model defined in the language config
+    /// (without loading the model)
+    pub fn phoneme_set(cfg_file: &Path) -> Result<Vec<String>, String> {
+        use std::fs;
+
+        let conf = fs::read_to_string(cfg_file)
+            .map_err(|err| format!("failed to load {}: {err}", cfg_file.display()))?;
+
+        conf.lines()
+            .filter(|line| !line.starts_with(';'))
+            .filter_map(|line| line.split_once('='))
+            .find(|(key, _)| key.trim().eq_ignore_ascii_case("phonemes"))
+            .map(|(_, value)| parse_phoneme_set(value))
+            .filter(|phonemes| !phonemes.is_empty())
+            .ok_or_else(|| format!("{} defines no PHONEMES", cfg_file.display()))
+    }
+    // ------------------------------------------------------------------------
+    /// extraction method defined in language config
+    pub fn extraction_method(&self) -> ExtractionMethod {
+        self.method
+    }
+    // ------------------------------------------------------------------------
+    /// commandline of external aligner defined in language config
+    pub fn external_aligner(&self) -> Option<&str> {
+        self.external_aligner.as_deref()
+    }
+    // ------------------------------------------------------------------------
+    pub fn set_extraction_method(&mut self, method: ExtractionMethod) {
+        self.method = method;
+    }
+    // ------------------------------------------------------------------------
+    /// speaker transform file of actor (may not exist)
+    fn speaker_transform_file(&self, actor: &str) -> PathBuf {
+        Path::new(&self.model_dir)
+            .join(ADAPTATION_DIR)
+            .join(format!("{}.mllr", speaker_filename(actor)))
+    }
+    // ------------------------------------------------------------------------
+    /// acoustic feature settings of the model (feat.params) as (key, value)
+    fn feature_params(&self) -> Result<Vec<(String, String)>, String> {
+        use std::fs;
+
+        let file = Path::new(&self.model_dir).join("feat.params");
+        let content = fs::read_to_string(&file)
+            .map_err(|err| format!("failed to load {}: {err}", file.display()))?;
+
+        Ok(content
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix('-'))
+            .filter_map(|line| line.split_once(char::is_whitespace))
+            .map(|(key, value)| (key.to_owned(), value.trim().to_owned()))
+            .collect())
+    }
+    // ------------------------------------------------------------------------
+    /// runs a sphinxtrain tool from the configured tools directory (or PATH)
+    fn run_adaptation_tool(&self, tool: &str, options: &[(&str, &str)]) -> Result<(), String> {
+        let program = match &self.adaptation_tools {
+            Some(dir) => Path::new(dir).join(tool),
+            None => PathBuf::from(tool),
+        };
+
+        trace!(">> running {}", program.display());
+        let mut command = Command::new(&program);
+        for (option, value) in options {
+            trace!(">> -{option} {value}");
+            command.arg(format!("-{option}")).arg(value);
+        }
+        let output = command
+            .output()
+            .map_err(|err| format!("speaker adaptation: failed to run {tool}: {err}"))?;
+
+        if !output.status.success() {
+            return Err(format!(
+                "speaker adaptation: {tool} failed ({}): {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+    // ------------------------------------------------------------------------
     fn create_config(
         name: &str,
         options: &[(&str, &str)],
@@ -282,6 +517,59 @@ impl PocketSphinx {
         Ok(config)
     }
     // ------------------------------------------------------------------------
+    /// validates the type of every option against the decoder config before
+    /// setting it
+    fn apply_decoder_options(
+        config: &mut pocketsphinx::Config,
+        options: &[(String, String)],
+    ) -> Result<(), String> {
+        for (setting, value) in options {
+            if RESERVED_DECODER_OPTIONS.contains(&setting.as_str()) {
+                return Err(format!(
+                    "pocketsphinx: decoder setting {setting} is managed by the phoneme extractor \
+                     and cannot be changed."
+                ));
+            }
+            let (param_type, _required) = config
+                .get_typeof_param(setting)
+                .map_err(|_| format!("pocketsphinx: unknown decoder setting {setting}"))?;
+
+            let valid = match param_type {
+                ParamType::Integer => value.parse::<i64>().is_ok(),
+                ParamType::Float => value.parse::<f64>().is_ok(),
+                ParamType::Boolean => matches!(
+                    value.to_lowercase().as_str(),
+                    "yes" | "no" | "true" | "false" | "1" | "0"
+                ),
+                ParamType::String => true,
+            };
+            if !valid {
+                return Err(format!(
+                    "pocketsphinx: invalid value for decoder setting {setting}: {value}. \
+                     expected {}",
+                    match param_type {
+                        ParamType::Integer => "an integer",
+                        ParamType::Float => "a number",
+                        ParamType::Boolean => "yes or no",
+                        ParamType::String => "a string",
+                    }
+                ));
+            }
+
+            debug!("> decoder setting:    [-{setting} {value}]");
+            config.set_str(setting, value).map_err(|err| {
+                format!("pocketsphinx: failed to set decoder setting {setting}: {err}")
+            })?;
+        }
+
+        if !config.is_valid() {
+            return Err(String::from(
+                "pocketsphinx: invalid combination of decoder settings.",
+            ));
+        }
+        Ok(())
+    }
+    // ------------------------------------------------------------------------
     fn get_hypothesis(
         &mut self,
         search_id: &str,
@@ -291,6 +579,14 @@ impl PocketSphinx {
             .set_activate_search(search_id)
             .map_err(|err| format!("pocketsphinx: failed to activate search: {err}"))?;
 
+        self.decode(raw_audio_data)?;
+
+        self.decoder
+            .get_hyp()
+            .map_err(|err| format!("pocketsphinx: {err}"))
+    }
+    // ------------------------------------------------------------------------
+    fn decode(&mut self, raw_audio_data: &[i16]) -> Result<(), String> {
         self.decoder
             .start_utt()
             .map_err(|err| format!("pocketsphinx: {err}"))?;
@@ -306,19 +602,25 @@ impl PocketSphinx {
             .map_err(|err| format!("pocketsphinx: {err}"))?;
 
         trace!(">> {} frames of data searched", frames);
-
-        self.decoder
-            .get_hyp()
-            .map_err(|err| format!("pocketsphinx: {err}"))
+        Ok(())
     }
     // ------------------------------------------------------------------------
-    pub fn extract_phonemes(
+    /// phonemes of the hypothesis of the extraction method
+    fn extract_hypothesis(
         &mut self,
         lineid: u32,
         raw_audio_data: &[i16],
         text: &str,
         phonetizer: &WordPhonetizer,
     ) -> Result<PhonemeResult, String> {
+        if self.method == ExtractionMethod::Forced {
+            match self.align_phonemes(lineid, raw_audio_data, text, phonetizer) {
+                Ok(result) => return Ok(result),
+                Err(why) => {
+                    warn!("{why} falling back to allphone extraction...");
+                }
+            }
+        }
         trace!("> pocketsphinx: extracting phonemes...");
 
         let grammer = self.translator.generate_grammer(text, phonetizer);
@@ -350,18 +652,288 @@ impl PocketSphinx {
             }
         };
 
-        let mut result = PhonemeResult {
+        Ok(PhonemeResult {
             hypothesis: Some(hypothesis),
-            phonemes: Vec::new(),
-        };
+            phonemes: self.collect_phoneme_segments(lineid)?,
+        })
+    }
+    // ------------------------------------------------------------------------
+    /// forced alignment of the dictionary transcription of text
+    fn align_phonemes(
+        &mut self,
+        lineid: u32,
+        raw_audio_data: &[i16],
+        text: &str,
+        phonetizer: &WordPhonetizer,
+    ) -> Result<PhonemeResult, String> {
+        trace!("> pocketsphinx: aligning phonemes...");
+
+        let words = self
+            .translator
+            .generate_transcription(text, phonetizer)
+            .ok_or_else(|| {
+                format!(
+                    "{lineid:010}: pocketsphinx: no dictionary transcription available for all words."
+                )
+            })?;
+        let transcription = words.join(" ");
+        let word_starts = word_start_indices(&words);
+        trace!(">> transcription: {transcription}");
+
+        // every phoneme is a word in the phoneme dictionary
+        self.decoder.set_align_text(&transcription).map_err(|err| {
+            format!("{lineid:010}: pocketsphinx: failed to set align text: {err}")
+        })?;
+        self.decode(raw_audio_data)?;
+
+        // second pass for phone level alignment with scores
+        self.decoder
+            .set_alignment(None)
+            .map_err(|err| format!("{lineid:010}: pocketsphinx: failed to set alignment: {err}"))?;
+        self.decode(raw_audio_data)?;
+
+        let alignment = self.decoder.get_alignment().ok_or_else(|| {
+            format!("{lineid:010}: pocketsphinx: failed to align audio with transcription.")
+        })?;
+        let mut phonemes = Vec::new();
+        let mut scores = Vec::new();
+        // index of the phoneme within the transcription
+        let mut transcribed = 0;
+        for phone in alignment.get_phones() {
+            let seg = phone.get_seg();
+            let phoneme = match phone.get_name() {
+                "<s>" | "<sil>" | "</s>" | "(NULL)" => "SIL",
+                p => p,
+            };
+            let word_start = !is_filler(phoneme) && word_starts.contains(&transcribed);
+            if !is_filler(phoneme) {
+                transcribed += 1;
+            }
+            if seg.duration <= 0 {
+                continue;
+            }
+            phonemes.push(PhonemeSegment {
+                phoneme: phoneme.to_string(),
+                word_start,
+                // save timings as ms
+                start: seg.start as u32 * 10,
+                end: (seg.start + seg.duration) as u32 * 10,
+                weight: 1.0,
+                score: 1.0,
+                matching_info: None,
+                traceback: None,
+                active: true,
+                warnings: Vec::default(),
+            });
+            scores.push(AcousticScore {
+                posterior: 0,
+                ascr: seg.score,
+                frames: seg.duration,
+                silence: phoneme == "SIL",
+            });
+        }
+        let confidences = acoustic_confidences(&self.decoder.get_logmath(), &scores);
+        for (phoneme, confidence) in phonemes.iter_mut().zip(confidences) {
+            phoneme.score = confidence;
+        }
+
+        if phonemes.is_empty() {
+            return Err(format!(
+                "{lineid:010}: pocketsphinx: forced alignment returned no phonemes."
+            ));
+        }
+
+        Ok(PhonemeResult {
+            hypothesis: Some(transcription),
+            phonemes,
+        })
+    }
+    // ------------------------------------------------------------------------
+    /// start times (ms) of all words of text from a forced alignment of their
+    /// dictionary transcription
+    fn align_word_starts(
+        &mut self,
+        lineid: u32,
+        raw_audio_data: &[i16],
+        text: &str,
+        phonetizer: &WordPhonetizer,
+    ) -> Result<Vec<u32>, String> {
+        trace!("> pocketsphinx: aligning words...");
+
+        let words = self
+            .translator
+            .generate_transcription(text, phonetizer)
+            .ok_or_else(|| {
+                format!(
+                    "{lineid:010}: pocketsphinx: no dictionary transcription available for all words."
+                )
+            })?;
+
+        // every phoneme is a word in the phoneme dictionary
+        self.decoder
+            .set_align_text(&words.join(" "))
+            .map_err(|err| {
+                format!("{lineid:010}: pocketsphinx: failed to set align text: {err}")
+            })?;
+        self.decode(raw_audio_data)?;
+
+        let segments = self
+            .decoder
+            .get_seg_iter()
+            .ok_or_else(|| format!("{lineid:010}: pocketsphinx: failed to get word segments"))?;
 
         // WORKAROUND for duped segments
         let mut prev_start = -1;
-        for segment in self
+        let mut phoneme_starts = Vec::new();
+        let mut duplicates = 0;
+        for segment in segments {
+            let SegFrames { start, .. } = segment.get_frames();
+            if is_filler(&segment.get_word()) {
+                continue;
+            }
+            if prev_start < start {
+                prev_start = start;
+                phoneme_starts.push(start as u32 * 10);
+            } else {
+                duplicates += 1;
+            }
+        }
+
+        let word_starts = word_start_indices(&words);
+        let phonemes = words
+            .iter()
+            .map(|w| w.split_whitespace().count())
+            .sum::<usize>();
+        if phoneme_starts.len() != phonemes {
+            let why = format!(
+                "{lineid:010}: pocketsphinx: word alignment returned {} of {phonemes} phonemes.",
+                phoneme_starts.len()
+            );
+            if duplicates > 0 {
+                warn!(
+                    "{why} dropped #{duplicates} segment(s) starting on the same frame as their \
+                     predecessor. no word starts available."
+                );
+            }
+            return Err(why);
+        }
+
+        Ok(word_starts.iter().map(|i| phoneme_starts[*i]).collect())
+    }
+    // ------------------------------------------------------------------------
+    /// marks the phonemes of all candidates starting at the word starts of a
+    /// word level alignment of text. candidates with word starts (e.g. from a
+    /// forced alignment) are not changed. if the word alignment fails or word
+    /// start anchoring is disabled no word starts are available.
+    pub fn anchor_word_starts(
+        &mut self,
+        lineid: u32,
+        raw_audio_data: &[i16],
+        text: &str,
+        phonetizer: &WordPhonetizer,
+        candidates: &mut [PhonemeResult],
+    ) {
+        let has_word_starts = |c: &PhonemeResult| c.phonemes.iter().any(|p| p.word_start);
+        if !self.word_start_anchoring || candidates.iter().all(has_word_starts) {
+            return;
+        }
+
+        match self.align_word_starts(lineid, raw_audio_data, text, phonetizer) {
+            Ok(word_starts) => {
+                trace!(">> word starts: {word_starts:?}");
+                for candidate in candidates.iter_mut().filter(|c| !has_word_starts(c)) {
+                    mark_word_starts(&mut candidate.phonemes, &word_starts);
+                }
+            }
+            Err(why) => debug!("{why} no word starts available."),
+        }
+    }
+    // ------------------------------------------------------------------------
+    /// detects utterances in (16kHz) audio. returns start and end in ms
+    pub fn detect_utterances(raw_audio_data: &[i16]) -> Result<Vec<(u32, u32)>, String> {
+        let endpointer = Endpointer::new(None, None, VADMode::Loose, Some(16000), None)
+            .map_err(|err| format!("pocketsphinx: failed to init endpointer: {err}"))?;
+
+        let frame_size = endpointer.get_frame_size();
+        let to_ms = |seconds: f64| (seconds * 1000.0).round() as u32;
+
+        let mut utterances = Vec::new();
+        let mut start = None;
+
+        for frame in raw_audio_data.chunks(frame_size) {
+            let prev_in_speech = endpointer.get_in_speech();
+            let speech = if frame.len() == frame_size {
+                endpointer.process(frame).is_some()
+            } else {
+                endpointer.end_stream(frame).is_some()
+            };
+
+            if speech {
+                if !prev_in_speech {
+                    start = Some(to_ms(endpointer.get_speech_start()));
+                }
+                if !endpointer.get_in_speech() {
+                    if let Some(start) = start.take() {
+                        utterances.push((start, to_ms(endpointer.get_speech_end())));
+                    }
+                }
+            }
+        }
+        // speech until end of audio
+        if let Some(start) = start {
+            utterances.push((start, (raw_audio_data.len() as u64 * 1000 / 16000) as u32));
+        }
+
+        debug!("pocketsphinx: detected #{} utterances", utterances.len());
+        Ok(utterances)
+    }
+    // ------------------------------------------------------------------------
+    fn collect_phoneme_segments(&self, lineid: u32) -> Result<Vec<PhonemeSegment>, String> {
+        let segments = self
             .decoder
             .get_seg_iter()
-            .ok_or_else(|| format!("{lineid:010}: pocketsphinx: failed to get segments"))?
-        {
+            .ok_or_else(|| format!("{lineid:010}: pocketsphinx: failed to get segments"))?;
+
+        Ok(self.convert_segments(segments))
+    }
+    // ------------------------------------------------------------------------
+    /// alternative hypotheses of the last decoded utterance (excluding the best
+    /// hypothesis). empty if the search does not provide an n-best list.
+    fn collect_alternatives(&self, best: &PhonemeResult, max: usize) -> Vec<PhonemeResult> {
+        let Some(nbest) = self.decoder.get_nbest_iter() else {
+            return Vec::new();
+        };
+
+        let mut hypotheses = vec![best.hypothesis.clone().unwrap_or_default()];
+        let mut alternatives = Vec::new();
+
+        for entry in nbest {
+            if alternatives.len() + 1 >= max {
+                break;
+            }
+            let NBestHypothesis { hypothesis, .. } = entry.get_hyp();
+            if hypothesis.is_empty() || hypotheses.contains(&hypothesis) {
+                continue;
+            }
+            let phonemes = self.convert_segments(entry.get_seg());
+            if !phonemes.is_empty() {
+                hypotheses.push(hypothesis.clone());
+                alternatives.push(PhonemeResult {
+                    hypothesis: Some(hypothesis),
+                    phonemes,
+                });
+            }
+        }
+        alternatives
+    }
+    // ------------------------------------------------------------------------
+    fn convert_segments(&self, segments: SegIter) -> Vec<PhonemeSegment> {
+        let mut phonemes = Vec::new();
+        let mut scores = Vec::new();
+
+        // WORKAROUND for duped segments
+        let mut prev_start = -1;
+        for segment in segments {
             // remap different noise/sil phonemes
             let phoneme = match segment.get_word().as_str() {
                 "<s>" | "<sil>" | "</s>" | "(NULL)"  => "SIL".to_string(),
@@ -376,15 +948,22 @@ impl PocketSphinx {
             }
             if prev_start < start {
                 prev_start = start;
-                result.phonemes.push(PhonemeSegment {
+                let SegProp { prob, am_score, .. } = segment.get_prob();
+                scores.push(AcousticScore {
+                    posterior: prob,
+                    ascr: am_score,
+                    frames: end - start + 1,
+                    silence: phoneme == "SIL",
+                });
+                phonemes.push(PhonemeSegment {
                     phoneme: phoneme.to_owned(),
-                    // no information about word boundaries
+                    // set from word level alignment (if available)
                     word_start: false,
                     // save timings as ms
                     start: start as u32 * 10,
                     end: end as u32 * 10,
                     weight: 1.0,
-                    score: 0.0,
+                    score: 1.0,
                     matching_info: None,
                     traceback: None,
                     active: true,
@@ -392,32 +971,263 @@ impl PocketSphinx {
                 });
             }
         }
+        let confidences = acoustic_confidences(&self.decoder.get_logmath(), &scores);
+        for (phoneme, confidence) in phonemes.iter_mut().zip(confidences) {
+            phoneme.score = confidence;
+        }
         // postprocessing:
         // remove timing gaps between neighboring phonemes (except SIL) by
         // extending start & end
-        let len = result.phonemes.len();
+        let len = phonemes.len();
         if len > 2 {
             let mut is_prev_sil = true;
 
             for i in 0..len {
-                is_prev_sil = if result.phonemes[i].phoneme != "SIL" {
+                is_prev_sil = if phonemes[i].phoneme != "SIL" {
                     if !is_prev_sil {
-                        result.phonemes[i].start -= 5;
+                        phonemes[i].start -= 5;
                     }
                     false
                 } else {
                     true
                 };
 
-                if i + 1 < len && result.phonemes[i + 1].phoneme != "SIL" {
-                    result.phonemes[i].end += 5;
+                if i + 1 < len && phonemes[i + 1].phoneme != "SIL" {
+                    phonemes[i].end += 5;
                 }
             }
         }
 
+        phonemes
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl AudioPhonemeExtractor for PocketSphinx {
+    // ------------------------------------------------------------------------
+    fn extract_phonemes(
+        &mut self,
+        lineid: u32,
+        raw_audio_data: &[i16],
+        text: &str,
+        phonetizer: &WordPhonetizer,
+    ) -> Result<PhonemeResult, String> {
+        let mut result = self.extract_hypothesis(lineid, raw_audio_data, text, phonetizer)?;
+
+        self.anchor_word_starts(
+            lineid,
+            raw_audio_data,
+            text,
+            phonetizer,
+            slice::from_mut(&mut result),
+        );
         Ok(result)
     }
     // ------------------------------------------------------------------------
+    fn extract_phoneme_alternatives(
+        &mut self,
+        lineid: u32,
+        raw_audio_data: &[i16],
+        text: &str,
+        phonetizer: &WordPhonetizer,
+        max: usize,
+    ) -> Result<Vec<PhonemeResult>, String> {
+        let best = self.extract_hypothesis(lineid, raw_audio_data, text, phonetizer)?;
+
+        // forced alignment provides no n-best list and just one path
+        let alternatives = self.collect_alternatives(&best, max);
+        trace!(
+            ">> pocketsphinx: found #{} alternative hypotheses",
+            alternatives.len()
+        );
+
+        let mut candidates = vec![best];
+        candidates.extend(alternatives);
+
+        // word alignment replaces the search of the n-best list
+        self.anchor_word_starts(lineid, raw_audio_data, text, phonetizer, &mut candidates);
+        Ok(candidates)
+    }
+    // ------------------------------------------------------------------------
+    fn recognize_phonemes(
+        &mut self,
+        lineid: u32,
+        raw_audio_data: &[i16],
+    ) -> Result<PhonemeResult, String> {
+        trace!("> pocketsphinx: recognizing phonemes...");
+
+        let Some((hypothesis, _score)) = self.get_hypothesis("_default", raw_audio_data)? else {
+            return Err(format!(
+                "{lineid:010}: pocketsphinx: failed to extract hypothesis from audio."
+            ));
+        };
+
+        Ok(PhonemeResult {
+            hypothesis: Some(hypothesis),
+            phonemes: self.collect_phoneme_segments(lineid)?,
+        })
+    }
+    // ------------------------------------------------------------------------
+    fn realign_phonemes(
+        &mut self,
+        lineid: u32,
+        raw_audio_data: &[i16],
+        phonemes: &str,
+    ) -> Result<PhonemeResult, String> {
+        trace!("> pocketsphinx: realigning phonemes: {phonemes}");
+
+        let grammer = self.translator.generate_phoneme_grammer(phonemes);
+        self.decoder
+            .add_jsgf_string("realign", &grammer)
+            .map_err(|err| format!("{lineid:010}: pocketsphinx: failed to add grammer: {err}"))?;
+
+        let Some((hypothesis, _score)) = self.get_hypothesis("realign", raw_audio_data)? else {
+            return Err(format!(
+                "{lineid:010}: pocketsphinx: failed to realign phonemes [{phonemes}]."
+            ));
+        };
+
+        Ok(PhonemeResult {
+            hypothesis: Some(hypothesis),
+            phonemes: self.collect_phoneme_segments(lineid)?,
+        })
+    }
+    // ------------------------------------------------------------------------
+    /// the word level alignment is an additional full decoding of the line
+    fn set_word_start_anchoring(&mut self, enabled: bool) {
+        self.word_start_anchoring = enabled;
+    }
+    // ------------------------------------------------------------------------
+    fn set_speaker(&mut self, actor: Option<&str>) -> Result<(), String> {
+        let transform = actor
+            .map(|actor| self.speaker_transform_file(actor))
+            .filter(|file| file.is_file());
+
+        // the decoder is reinitialized only to drop an applied transform
+        if transform == self.speaker_transform && !self.speaker_transform_replaced {
+            return Ok(());
+        }
+
+        match &transform {
+            // a new transform replaces the applied one
+            Some(file) => {
+                debug!(
+                    "> pocketsphinx: applying speaker adaptation [{}]",
+                    file.display()
+                );
+                self.decoder
+                    .update_mllr(&file.to_string_lossy())
+                    .map_err(|err| {
+                        format!("pocketsphinx: failed to apply speaker adaptation: {err}")
+                    })?;
+            }
+            None => {
+                // reloads the speaker independent acoustic model
+                debug!("> pocketsphinx: resetting speaker adaptation");
+                let config = self.decoder.get_config();
+                self.decoder.reinit(&config).map_err(|err| {
+                    format!("pocketsphinx: failed to reset speaker adaptation: {err}")
+                })?;
+            }
+        }
+        self.speaker_transform = transform;
+        self.speaker_transform_replaced = false;
+        Ok(())
+    }
+    // ------------------------------------------------------------------------
+    fn adapt_speaker(&mut self, actor: &str, corpus: &AdaptationCorpus) -> Result<PathBuf, String> {
+        use std::fs;
+
+        let model_dir = Path::new(&self.model_dir);
+        let dir = corpus.dir.to_string_lossy().to_string();
+        let fileids = corpus.fileids.to_string_lossy().to_string();
+        let transcription = corpus.transcription.to_string_lossy().to_string();
+        let model_file = |name: &str| model_dir.join(name).to_string_lossy().to_string();
+
+        // baum-welch requires the model definition in text format
+        let mdef = model_file("mdef.txt");
+        if !Path::new(&mdef).is_file() {
+            return Err(format!(
+                "speaker adaptation: model definition [{mdef}] not found. convert it with: \
+                 pocketsphinx_mdef_convert -text {} {mdef}",
+                model_file("mdef")
+            ));
+        }
+
+        let params = self.feature_params()?;
+        let model_type = params
+            .iter()
+            .find(|(key, _)| key == "model")
+            .map(|(_, value)| format!(".{value}."))
+            .unwrap_or_else(|| String::from(".cont."));
+
+        let output = self.speaker_transform_file(actor);
+        let outputfile = output.to_string_lossy().to_string();
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent).map_err(|err| {
+                format!(
+                    "speaker adaptation: failed to create {}: {err}",
+                    parent.display()
+                )
+            })?;
+        }
+
+        info!(
+            "> {actor}: extracting acoustic features of #{} lines...",
+            corpus.lines
+        );
+        self.run_adaptation_tool(
+            "sphinx_fe",
+            &[
+                ("argfile", model_file("feat.params").as_str()),
+                ("samprate", "16000"),
+                ("c", fileids.as_str()),
+                ("di", dir.as_str()),
+                ("do", dir.as_str()),
+                ("ei", "wav"),
+                ("eo", "mfc"),
+                ("mswav", "yes"),
+            ],
+        )?;
+
+        info!("> {actor}: accumulating observation statistics...");
+        let mut options = vec![
+            ("hmmdir", self.model_dir.as_str()),
+            ("moddeffn", mdef.as_str()),
+            ("ts2cbfn", model_type.as_str()),
+            ("dictfn", self.phoneme_dictionary_path.as_str()),
+            ("fdictfn", self.noise_dictionary_path.as_str()),
+            ("ctlfn", fileids.as_str()),
+            ("lsnfn", transcription.as_str()),
+            ("cepdir", dir.as_str()),
+            ("accumdir", dir.as_str()),
+        ];
+        options.extend(
+            params
+                .iter()
+                .filter(|(key, _)| ADAPTATION_FEATURE_PARAMS.contains(&key.as_str()))
+                .map(|(key, value)| (key.as_str(), value.as_str())),
+        );
+        self.run_adaptation_tool("bw", &options)?;
+
+        info!("> {actor}: estimating transform...");
+        self.run_adaptation_tool(
+            "mllr_solve",
+            &[
+                ("meanfn", model_file("means").as_str()),
+                ("varfn", model_file("variances").as_str()),
+                ("outmllrfn", outputfile.as_str()),
+                ("accumdir", dir.as_str()),
+            ],
+        )?;
+
+        // reload a replaced transform on the next speaker selection
+        if self.speaker_transform.as_ref() == Some(&output) {
+            self.speaker_transform_replaced = true;
+        }
+        Ok(output)
+    }
+    // ------------------------------------------------------------------------
 }
 // ----------------------------------------------------------------------------
 impl Translator {
@@ -587,21 +1397,7 @@ impl Translator {
             // .inspect(|w| println!("word: {w}"))
             .map(|word| {
                 let word = word.trim();
-                self.custom_dict
-                    .get(word)
-                    .inspect(|w| {
-                        info!("pocketsphinx: > using custom dictionary word: {word} -> {w}")
-                    })
-                    .map(|w| w.to_string())
-                    .or_else(|| self.pocketsphinx.lookup_word(word).ok().flatten())
-                    // translate word: text -> ipa -> pocketsphinx-phoneme
-                    .or_else(|| {
-                        phonetizer
-                            .phonetize(word)
-                            .inspect(|w| {
-                                info!("pocketsphinx: > phonetizing IPA word translation: {word} -> {w}")
-                            })
-                    })
+                self.lookup_pronunciation(word, phonetizer)
                     // last fallback: text chars -> pocketsphinx-phonemes
                     .unwrap_or_else(|| {
                         let mut chars = word.replace('\'', "").chars().collect::<Vec<_>>();
@@ -622,6 +1418,79 @@ impl Translator {
             .replace("[[TEXTLINE_GRAMMER]]", &grammer.replace("  ", " "))
     }
     // ------------------------------------------------------------------------
+    /// grammer accepting only the given phoneme sequence without pauses
+    fn generate_phoneme_grammer(&self, phonemes: &str) -> String {
+        let grammer = format!("[SIL] {} [SIL]", phonemes.trim());
+
+        self.grammer_template
+            .replace("[[TEXTLINE_GRAMMER]]", &grammer)
+    }
+    // ------------------------------------------------------------------------
+    /// phoneme sequence of every word of text without any alternatives. None if
+    /// at least one word cannot be transcribed without character fallback
+    fn generate_transcription(
+        &self,
+        text: &str,
+        phonetizer: &WordPhonetizer,
+    ) -> Option<Vec<String>> {
+        let input = text
+            .to_lowercase()
+            .replace(self.cleanup_chars.as_slice(), " ");
+
+        let mut transcription = Vec::new();
+        for word in input.split(' ').map(str::trim).filter(|w| !w.is_empty()) {
+            let pronunciation = self.lookup_pronunciation(word, phonetizer)?;
+
+            // custom dictionary entries may contain grammar rules
+            if pronunciation.contains(['|', '(', ')', '[', ']']) {
+                debug!("pocketsphinx: > no unique transcription for: {word} -> {pronunciation}");
+                return None;
+            }
+            transcription.push(pronunciation);
+        }
+
+        if transcription.is_empty() {
+            None
+        } else {
+            Some(transcription)
+        }
+    }
+    // ------------------------------------------------------------------------
+    fn lookup_pronunciation(&self, word: &str, phonetizer: &WordPhonetizer) -> Option<String> {
+        self.custom_dict
+            .get(word)
+            .inspect(|w| info!("pocketsphinx: > using custom dictionary word: {word} -> {w}"))
+            .map(|w| w.to_string())
+            .or_else(|| self.pocketsphinx.lookup_word(word).ok().flatten())
+            // translate word: text -> ipa -> pocketsphinx-phoneme
+            .or_else(|| {
+                phonetizer.phonetize(word).inspect(|w| {
+                    info!("pocketsphinx: > phonetizing IPA word translation: {word} -> {w}")
+                })
+            })
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+/// sorted set of blank separated phonemes
+fn parse_phoneme_set(value: &str) -> Vec<String> {
+    let mut phonemes = value
+        .trim()
+        .split(' ')
+        .map(|a| a.trim().to_string())
+        .filter(|a| !a.is_empty())
+        .collect::<Vec<_>>();
+    phonemes.sort();
+    phonemes.dedup();
+    phonemes
+}
+// ----------------------------------------------------------------------------
+/// (option, value) from a DECODER-<option> setting
+fn parse_decoder_option(key: &str, value: &str) -> (String, String) {
+    (
+        key.trim_start_matches(DECODER_OPTION_PREFIX).to_owned(),
+        value.trim().to_owned(),
+    )
 }
 // ----------------------------------------------------------------------------
 fn check_dir(dir: &str) -> Result<String, String> {
@@ -642,3 +1511,131 @@ fn check_file(file: &str) -> Result<String, String> {
     }
 }
 // ----------------------------------------------------------------------------
+/// normalized acoustic confidence (0..1) for every segment. uses the posterior
+/// probability if the search computed it. otherwise the acoustic likelihood
+/// per frame is rated relative to the median segment of the utterance.
+fn acoustic_confidences(logmath: &LogMath, scores: &[AcousticScore]) -> Vec<f32> {
+    if scores.iter().any(|s| s.posterior < 0) {
+        return scores
+            .iter()
+            .map(|s| logmath.exp(s.posterior).clamp(0.0, 1.0) as f32)
+            .collect();
+    }
+
+    let likelihoods = scores
+        .iter()
+        .map(|s| logmath.log_to_ln(s.ascr) / s.frames.max(1) as f64)
+        .collect::<Vec<_>>();
+
+    let mut speech = scores
+        .iter()
+        .zip(likelihoods.iter())
+        .filter(|(s, _)| !s.silence)
+        .map(|(_, l)| *l)
+        .collect::<Vec<_>>();
+
+    if speech.is_empty() {
+        return vec![1.0; scores.len()];
+    }
+    speech.sort_by(|a, b| a.total_cmp(b));
+    let median = speech[speech.len() / 2];
+
+    scores
+        .iter()
+        .zip(likelihoods)
+        .map(|(s, likelihood)| {
+            if s.silence {
+                1.0
+            } else {
+                ((likelihood - median) / ACOUSTIC_CONFIDENCE_SCALE)
+                    .exp()
+                    .min(1.0) as f32
+            }
+        })
+        .collect()
+}
+// ----------------------------------------------------------------------------
+/// silence and noise segments of the decoder
+#[inline]
+fn is_filler(name: &str) -> bool {
+    matches!(name, "<s>" | "<sil>" | "</s>" | "(NULL)" | "SIL") || name.starts_with('+')
+}
+// ----------------------------------------------------------------------------
+/// index of the first phoneme of every word in the concatenated transcription
+fn word_start_indices(words: &[String]) -> Vec<usize> {
+    words
+        .iter()
+        .scan(0, |index, word| {
+            let start = *index;
+            *index += word.split_whitespace().count();
+            Some(start)
+        })
+        .collect()
+}
+// ----------------------------------------------------------------------------
+/// marks the (non filler) phoneme starting closest to every word start if it is
+/// within the tolerance
+fn mark_word_starts(phonemes: &mut [PhonemeSegment], word_starts: &[u32]) {
+    for &word_start in word_starts {
+        let closest = phonemes
+            .iter_mut()
+            .filter(|p| !is_filler(&p.phoneme))
+            .min_by_key(|p| p.start.abs_diff(word_start));
+
+        if let Some(phoneme) = closest {
+            if phoneme.start.abs_diff(word_start) <= WORD_START_TOLERANCE_MS {
+                phoneme.word_start = true;
+            }
+        }
+    }
+}
+// ----------------------------------------------------------------------------
+// ----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::{mark_word_starts, word_start_indices, PhonemeSegment};
+
+    fn segment(phoneme: &str, start: u32, end: u32) -> PhonemeSegment {
+        PhonemeSegment {
+            phoneme: phoneme.to_owned(),
+            start,
+            end,
+            active: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_word_start_indices() {
+        let words = ["HH AH", "W ER L D", "AY"]
+            .iter()
+            .map(|w| (*w).to_owned())
+            .collect::<Vec<_>>();
+
+        assert_eq!(word_start_indices(&words), [0, 2, 6]);
+        assert!(word_start_indices(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_mark_word_starts() {
+        let mut phonemes = vec![
+            segment("SIL", 0, 100),
+            segment("HH", 100, 150),
+            segment("AH", 150, 220),
+            segment("W", 220, 270),
+            segment("ER", 270, 400),
+            segment("+NSN+", 400, 450),
+            segment("L", 450, 500),
+        ];
+        // fillers are never marked, word starts beyond the tolerance are
+        // ignored
+        mark_word_starts(&mut phonemes, &[0, 110, 255, 420, 600]);
+
+        let marked = phonemes
+            .iter()
+            .filter(|p| p.word_start)
+            .map(|p| p.phoneme.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(marked, ["HH", "ER", "L"]);
+    }
+}
@@ -31,6 +31,8 @@ pub struct Task {
     phonemefile: Option<String>,
     renaming_required: bool,
     full_rename: bool,
+    /// range (in ms) to re-align on next processing
+    realign_range: Option<(u32, u32)>,
     state: ProcessingState,
     quality: QualityAssessment,
 }
@@ -42,6 +44,8 @@ pub struct TaskId(usize);
 pub enum TaskOperation {
     Rename(bool),
     Extract,
+    /// re-align words within range (in ms) of existing phoneme track
+    Realign(u32, u32),
 }
 // ----------------------------------------------------------------------------
 pub struct TaskData {
@@ -104,6 +108,8 @@ impl Task {
             audiofile: self.audiofile.clone(),
             operation: if self.renaming_required {
                 TaskOperation::Rename(self.full_rename)
+            } else if let Some((start, end)) = self.realign_range {
+                TaskOperation::Realign(start, end)
             } else {
                 TaskOperation::Extract
             },
@@ -115,7 +121,10 @@ impl Task {
         match self.state {
             ProcessingState::Processing => {
                 self.state = match result {
-                    TaskResult::Error(_, err) => ProcessingState::Error(err),
+                    TaskResult::Error(_, err) => {
+                        self.realign_range = None;
+                        ProcessingState::Error(err)
+                    }
                     TaskResult::Renamed(_, new_audiofile) => {
                         self.renaming_required = false;
                         self.audiofile = new_audiofile;
@@ -130,6 +139,7 @@ impl Task {
                         }
                     }
                     TaskResult::Finished(_, phoneme_file) => {
+                        self.realign_range = None;
                         self.quality = ::phonemes::load(self.lineid, &phoneme_file)
                             .map(|track| track.assessed_quality())
                             .unwrap_or(QualityAssessment::Unknown);
@@ -259,6 +269,7 @@ impl ProcessingQueue {
                         phonemes.insert(id, filepath.to_owned());
                     }
                 }
+                FileInfo::Alignment(_, _) => {}
             }
         }
 
@@ -287,6 +298,7 @@ impl ProcessingQueue {
                         phonemefile: Some(phonemes),
                         renaming_required,
                         full_rename: force_rename,
+                        realign_range: None,
                         state,
                         quality,
                     }
@@ -298,6 +310,7 @@ impl ProcessingQueue {
                     phonemefile: None,
                     renaming_required: !has_duration || force_rename,
                     full_rename: force_rename,
+                    realign_range: None,
                     state: ProcessingState::Waiting,
                     quality: QualityAssessment::Unknown,
                 },
@@ -315,6 +328,7 @@ impl ProcessingQueue {
                 phonemefile: None,
                 renaming_required: true,
                 full_rename: false,
+                realign_range: None,
                 state: ProcessingState::UnassignedId,
                 quality: QualityAssessment::Unknown,
             });
@@ -393,6 +407,7 @@ impl ProcessingQueue {
                 phonemefile: None,
                 renaming_required: false,
                 full_rename: false,
+                realign_range: None,
                 state: ProcessingState::Waiting,
                 quality: QualityAssessment::Unknown,
             });
@@ -437,6 +452,28 @@ impl ProcessingQueue {
         }
     }
     // ------------------------------------------------------------------------
+    /// queues re-alignment of words within range (in ms) of a finished task
+    pub fn realign_task(&mut self, slot: usize, range: (u32, u32)) -> Result<(), String> {
+        let task = self
+            .tasks
+            .get_mut(slot)
+            .ok_or_else(|| format!("no task in slot {slot}"))?;
+
+        match task.state {
+            ProcessingState::Finished if task.phonemefile.is_some() => {
+                // subtract stats
+                self.finished -= 1;
+
+                task.realign_range = Some(range);
+                task.state = ProcessingState::Waiting;
+                Ok(())
+            }
+            _ => Err(String::from(
+                "re-alignment requires a finished task with phoneme timings.",
+            )),
+        }
+    }
+    // ------------------------------------------------------------------------
     pub fn update_taskresult(&mut self, result: TaskResult) -> Result<&Task, String> {
         match self.tasks.iter_mut().find(|t| t.id.0 == result.id().0) {
             Some(processed_task) => {
@@ -0,0 +1,350 @@
+//
+// localized second-pass re-alignment of words
+//
+
+// ----------------------------------------------------------------------------
+// external interface
+// ----------------------------------------------------------------------------
+/// audio (in ms) added before and after a word for re-alignment
+pub const REALIGN_PADDING_MS: u32 = 150;
+// ----------------------------------------------------------------------------
+impl<S> Processor<S>
+where
+    S: StringsProvider,
+{
+    // ------------------------------------------------------------------------
+    /// enables a second extraction pass which re-aligns every word flagged by
+    /// the quality assessment
+    pub fn set_word_realignment(&mut self, enabled: bool) {
+        self.realign_words = enabled;
+    }
+    // ------------------------------------------------------------------------
+    /// re-aligns all words of the stored phoneme track of task overlapping the
+    /// range (in ms). returns the phoneme file.
+    pub(super) fn realign_range(
+        &mut self,
+        task: &TaskData,
+        range: (u32, u32),
+    ) -> Result<String, String> {
+        let lineid = task.lineid();
+
+        let mut outputdir = PathBuf::from(task.audiofile());
+        outputdir.pop();
+        let phonemefile = outputdir.join(format!("{lineid:010}.phonemes"));
+
+        let mut phonemetrack = phonemes::load(lineid, &phonemefile)?;
+
+        let mut dataprovider = DataProvider::new_from_taskdata(task, &self.datadir);
+        dataprovider.load()?;
+        let audiodata = dataprovider.get_rawaudio(16000, true)?;
+
+        let actor = self.strings.get_actor(lineid);
+        self.extractor.set_speaker(actor.map(String::as_str))?;
+
+        let words = word_slots(phonemetrack.phonemes())
+            .into_iter()
+            .filter(
+                |word| match word_span(&phonemetrack.phonemes()[word.clone()]) {
+                    Some((start, end)) => start < range.1 && end > range.0,
+                    None => false,
+                },
+            )
+            .collect::<Vec<_>>();
+
+        info!(
+            "id {lineid:010}: re-aligning #{} words within [{}..{}]ms...",
+            words.len(),
+            range.0,
+            range.1
+        );
+
+        let phonetizer = WordPhonetizer::new(
+            self.matcher.phoneme_pairing_alternatives(),
+            &self.translator,
+        );
+        let mut realigner = WordRealigner {
+            extractor: &mut *self.extractor,
+            matcher: &self.matcher,
+            phonetizer: &phonetizer,
+        };
+
+        let realigned = realigner.realign(lineid, &audiodata, &mut phonemetrack, &words, true);
+        if realigned == 0 {
+            info!("id {lineid:010}: > no word re-aligned. keeping phoneme timings.");
+            return Ok(phonemefile.to_string_lossy().to_string());
+        }
+
+        // mark as edited
+        phonemetrack.set_version(phonemetrack.version() + 1);
+        phonemetrack.assess_quality();
+
+        let phonemefile = phonemes::store(&outputdir, phonemetrack)?;
+        info!("id {lineid:010}: > re-aligned #{realigned} words. stored in [{phonemefile}]");
+
+        Ok(phonemefile)
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+// internals
+// ----------------------------------------------------------------------------
+use std::ops::Range;
+use std::path::PathBuf;
+
+use phonemes::{self, PhonemeResult, PhonemeSegment, PhonemeTrack, QualityWarning};
+use queue::TaskData;
+
+use super::{
+    AudioPhonemeExtractor, DataProvider, PhonemeSequenceMatcher, Processor, StringsProvider,
+    WordPhonetizer,
+};
+// ----------------------------------------------------------------------------
+const SAMPLES_PER_MS: usize = 16;
+// ----------------------------------------------------------------------------
+/// re-decodes single words within a padded audio window constrained to the
+/// word phonemes and splices the refined segments back into the track
+pub(super) struct WordRealigner<'a, 'p> {
+    pub extractor: &'a mut dyn AudioPhonemeExtractor,
+    pub matcher: &'a PhonemeSequenceMatcher,
+    pub phonetizer: &'a WordPhonetizer<'p, 'p>,
+}
+// ----------------------------------------------------------------------------
+impl<'a, 'p> WordRealigner<'a, 'p> {
+    // ------------------------------------------------------------------------
+    /// re-aligns the words (segment slot ranges) of the (assessed) track in
+    /// (16kHz) audio. a refined word is accepted only if it has less warnings
+    /// than before (or not more if forced). returns number of changed words.
+    pub fn realign(
+        &mut self,
+        lineid: u32,
+        raw_audio_data: &[i16],
+        track: &mut PhonemeTrack<PhonemeSegment>,
+        words: &[Range<usize>],
+        force: bool,
+    ) -> usize {
+        let mut realigned = 0;
+
+        // backwards so that slots of the remaining words stay valid
+        for word in words.iter().rev() {
+            let (candidate, refined) =
+                match self.realign_word(lineid, raw_audio_data, track, word.clone()) {
+                    Ok(result) => result,
+                    Err(why) => {
+                        debug!("id {lineid:010}: > word re-alignment failed: {why}");
+                        continue;
+                    }
+                };
+
+            let before = count_warnings(&track.phonemes()[word.clone()]);
+            let after = count_warnings(&candidate.phonemes()[refined]);
+
+            if after < before || (force && after <= before) {
+                debug!(
+                    "id {lineid:010}: > re-aligned word at slot {} (warnings: {before} -> {after})",
+                    word.start
+                );
+                *track = candidate;
+                realigned += 1;
+            }
+        }
+        realigned
+    }
+    // ------------------------------------------------------------------------
+    /// returns the updated and assessed track and the slots of the refined word
+    fn realign_word(
+        &mut self,
+        lineid: u32,
+        raw_audio_data: &[i16],
+        track: &PhonemeTrack<PhonemeSegment>,
+        word: Range<usize>,
+    ) -> Result<(PhonemeTrack<PhonemeSegment>, Range<usize>), String> {
+        let duration = (raw_audio_data.len() / SAMPLES_PER_MS) as u32;
+        let segments = &track.phonemes()[word.clone()];
+
+        let phonemes = self
+            .phonetizer
+            .map_phonemes(
+                segments
+                    .iter()
+                    .map(|s| s.phoneme.as_str())
+                    .filter(|p| *p != "_"),
+            )
+            .filter(|phonemes| !phonemes.is_empty())
+            .ok_or_else(|| String::from("no extractor phonemes for word."))?;
+
+        // refined segments must not overlap with neighboring words
+        let lower = track.phonemes()[..word.start]
+            .iter()
+            .filter(|s| s.active)
+            .map(|s| s.end)
+            .max()
+            .unwrap_or(0);
+        let upper = track.phonemes()[word.end..]
+            .iter()
+            .filter(|s| s.active)
+            .map(|s| s.start)
+            .min()
+            .unwrap_or(duration)
+            .min(duration);
+        if lower >= upper {
+            return Err(format!("no room for word between {lower}ms and {upper}ms."));
+        }
+
+        let (start, end) = word_span(segments).unwrap_or((lower, upper));
+        let from = start.saturating_sub(REALIGN_PADDING_MS);
+        let to = (end + REALIGN_PADDING_MS).min(duration);
+        if from >= to {
+            return Err(String::from("empty audio window."));
+        }
+        let window = &raw_audio_data[from as usize * SAMPLES_PER_MS..to as usize * SAMPLES_PER_MS];
+
+        let mut audio = self.extractor.realign_phonemes(lineid, window, &phonemes)?;
+        for segment in &mut audio.phonemes {
+            segment.start += from;
+            segment.end += from;
+        }
+
+        // gaps have no similarity scores
+        let text = PhonemeResult {
+            hypothesis: None,
+            phonemes: segments
+                .iter()
+                .filter(|s| s.phoneme != "_")
+                .cloned()
+                .collect(),
+        };
+        let mut refined = self
+            .matcher
+            .calculate_matching(lineid, &audio, &text)?
+            .phonemes;
+
+        for segment in &mut refined {
+            segment.start = segment.start.clamp(lower, upper);
+            segment.end = segment.end.clamp(segment.start, upper);
+        }
+        if let Some(first) = refined.first_mut() {
+            first.word_start = segments[0].word_start;
+        }
+
+        let mut candidate = track.clone();
+        let refined_slots = word.start..word.start + refined.len();
+        candidate.phonemes_mut().splice(word, refined);
+
+        phonemes::auto_close_gaps(duration, &mut candidate);
+        candidate.assess_quality();
+
+        Ok((candidate, refined_slots))
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+/// slot ranges of all words in phonemes
+pub(super) fn word_slots(phonemes: &[PhonemeSegment]) -> Vec<Range<usize>> {
+    let mut words = Vec::new();
+    let mut start = 0;
+    for (i, segment) in phonemes.iter().enumerate() {
+        if segment.word_start && i > start {
+            words.push(start..i);
+            start = i;
+        }
+    }
+    if start < phonemes.len() {
+        words.push(start..phonemes.len());
+    }
+    words
+}
+// ----------------------------------------------------------------------------
+/// slot ranges of all words of the (assessed) track with gaps, inactive
+/// segments or low scores
+pub(super) fn flagged_words(track: &PhonemeTrack<PhonemeSegment>) -> Vec<Range<usize>> {
+    word_slots(track.phonemes())
+        .into_iter()
+        .filter(|word| count_warnings(&track.phonemes()[word.clone()]) > 0)
+        .collect()
+}
+// ----------------------------------------------------------------------------
+/// start of first and end of last active segment
+fn word_span(segments: &[PhonemeSegment]) -> Option<(u32, u32)> {
+    let mut active = segments.iter().filter(|s| s.active);
+    let first = active.next()?;
+    let last = active.next_back().unwrap_or(first);
+
+    Some((first.start, last.end))
+}
+// ----------------------------------------------------------------------------
+fn count_warnings(segments: &[PhonemeSegment]) -> usize {
+    use self::QualityWarning::*;
+
+    segments
+        .iter()
+        .flat_map(|s| s.warnings.iter())
+        .filter(|w| {
+            matches!(
+                w,
+                PhonemeGapInWord | InactiveSegmentsInWord(_) | HighAmountOfLowScoreSegments(..)
+            )
+        })
+        .count()
+}
+// ----------------------------------------------------------------------------
+// ----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::super::phonemes::QualityWarning::*;
+    use super::super::phonemes::{PhonemeSegment, PhonemeTrack, QualityWarning};
+    use super::{count_warnings, flagged_words, word_slots};
+
+    fn segment(phoneme: &str, word_start: bool, warnings: Vec<QualityWarning>) -> PhonemeSegment {
+        PhonemeSegment {
+            phoneme: phoneme.to_owned(),
+            word_start,
+            active: phoneme != "_",
+            warnings,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_word_slots() {
+        let phonemes = vec![
+            segment("_", false, Vec::new()),
+            segment("h", true, Vec::new()),
+            segment("aɪ", false, Vec::new()),
+            segment("j", true, Vec::new()),
+            segment("_", false, Vec::new()),
+            segment("u", false, Vec::new()),
+        ];
+        // leading segments before the first word start form a word
+        assert_eq!(word_slots(&phonemes), [0..1, 1..3, 3..6]);
+        assert_eq!(word_slots(&phonemes[1..]), [0..2, 2..5]);
+        assert!(word_slots(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_count_warnings() {
+        let segments = [
+            segment("h", true, vec![PhonemeGapInWord, UnusualDuration(600)]),
+            segment("aɪ", false, vec![InactiveSegmentsInWord(1)]),
+            segment("j", false, vec![HighAmountOfLowScoreSegments(30, 0.1)]),
+            segment("u", false, vec![AudioClipping(2), AudioTruncatedEnd]),
+        ];
+        // only alignment warnings within words are counted
+        assert_eq!(count_warnings(&segments), 3);
+        assert_eq!(count_warnings(&segments[3..]), 0);
+    }
+
+    #[test]
+    fn test_flagged_words() {
+        let phonemes = vec![
+            segment("h", true, Vec::new()),
+            segment("aɪ", false, vec![UnusualDuration(600)]),
+            segment("j", true, Vec::new()),
+            segment("_", false, vec![PhonemeGapInWord]),
+            segment("u", false, Vec::new()),
+            segment("d", true, vec![InactiveSegmentsInWord(1)]),
+        ];
+        let track = PhonemeTrack::new(1, "en", "hi you do", "haɪ ju d", None, None, phonemes);
+
+        assert_eq!(flagged_words(&track), [2..5, 5..6]);
+    }
+}
@@ -0,0 +1,282 @@
+//
+// alignment scoring profile
+//
+
+// ----------------------------------------------------------------------------
+// external interface
+// ----------------------------------------------------------------------------
+/// language specific scores and penalties of the sequence alignment that do not
+/// depend on the similarity of phonemes
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScoringProfile {
+    /// penalty for the first phoneme of a gap (deleted audio or inserted text
+    /// phonemes)
+    pub gap_open: f32,
+    /// penalty for every following phoneme of the same gap
+    pub gap_extend: f32,
+    /// penalty for the first phoneme of a gap starting at a word boundary (text
+    /// word start or audio phoneme following a silence)
+    pub word_boundary_gap_open: f32,
+    /// penalty for matches, merges and splits across detected audio gaps within
+    /// a word
+    pub gap_within_word: f32,
+    /// weight of the phoneme similarity for alignments across detected audio
+    /// gaps within a word
+    pub gap_within_word_similarity: f32,
+    /// bonus for aligning a text word start with an audio word start detected
+    /// by the word level recognition (0 skips the word level recognition)
+    pub word_start_anchor: f32,
+    /// score reductions so a match is always preferred to merges and splits
+    pub merge_left_bias: f32,
+    pub merge_right_bias: f32,
+    pub split_left_bias: f32,
+    pub split_right_bias: f32,
+    /// min similarity score of audio phonemes used as pairing alternatives of a
+    /// text phoneme
+    pub alternatives_min_score: f32,
+    /// scaling of all similarity scores relative to the gap penalties
+    pub similarity_scale: f32,
+}
+// ----------------------------------------------------------------------------
+/// keys of all settings in profile files
+pub const SCORING_SETTINGS: &[&str] = &[
+    "gap-open",
+    "gap-extend",
+    "word-boundary-gap-open",
+    "gap-within-word",
+    "gap-within-word-similarity",
+    "word-start-anchor",
+    "merge-left-bias",
+    "merge-right-bias",
+    "split-left-bias",
+    "split-right-bias",
+    "alternatives-min-score",
+    "similarity-scale",
+];
+// ----------------------------------------------------------------------------
+// internals
+// ----------------------------------------------------------------------------
+use std::fs;
+use std::path::Path;
+
+use text::{CsvWriter, SimpleCsvWriter};
+// ----------------------------------------------------------------------------
+const DEFAULT_GAP_PENALTY: f32 = -1.0;
+// ----------------------------------------------------------------------------
+impl Default for ScoringProfile {
+    fn default() -> ScoringProfile {
+        ScoringProfile {
+            gap_open: DEFAULT_GAP_PENALTY,
+            gap_extend: DEFAULT_GAP_PENALTY,
+            word_boundary_gap_open: DEFAULT_GAP_PENALTY,
+            gap_within_word: DEFAULT_GAP_PENALTY * 1.5,
+            gap_within_word_similarity: 0.5,
+            word_start_anchor: 0.25,
+            merge_left_bias: 0.01,
+            merge_right_bias: 0.01,
+            split_left_bias: 0.01,
+            split_right_bias: 0.01,
+            alternatives_min_score: 0.1,
+            similarity_scale: 1.0,
+        }
+    }
+}
+// ----------------------------------------------------------------------------
+impl ScoringProfile {
+    // ------------------------------------------------------------------------
+    /// profile with a single gap penalty for all kinds of gaps
+    pub fn with_gap_penalty(gap_penalty: f32) -> ScoringProfile {
+        ScoringProfile {
+            gap_open: gap_penalty,
+            gap_extend: gap_penalty,
+            word_boundary_gap_open: gap_penalty,
+            gap_within_word: gap_penalty * 1.5,
+            ..Default::default()
+        }
+    }
+    // ------------------------------------------------------------------------
+    /// loads profile settings (KEY = value). undefined settings use the
+    /// defaults.
+    pub fn load(file: &Path) -> Result<ScoringProfile, String> {
+        let cfg_name = file.display();
+        let conf =
+            fs::read_to_string(file).map_err(|err| format!("failed to load {cfg_name}: {err}"))?;
+
+        let mut profile = ScoringProfile::default();
+
+        for (i, line) in conf
+            .lines()
+            .enumerate()
+            .filter(|(_i, line)| !line.trim().is_empty() && !line.starts_with(';'))
+        {
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("{cfg_name}:{} failed to parse: {line}", i + 1))?;
+
+            let value = value
+                .trim()
+                .parse::<f32>()
+                .map_err(|err| format!("{cfg_name}:{} invalid value for {key}: {err}", i + 1))?;
+
+            profile
+                .set(&key.trim().to_lowercase(), value)
+                .map_err(|why| format!("{cfg_name}:{} {why}", i + 1))?;
+        }
+        Ok(profile)
+    }
+    // ------------------------------------------------------------------------
+    /// stores all settings in the format of the loader
+    pub fn store(&self, file: &Path, comments: &[String]) -> Result<(), String> {
+        let mut writer = SimpleCsvWriter::create(&file.to_path_buf())
+            .map_err(|err| format!("failed to create {}: {err}", file.display()))?;
+
+        for comment in comments {
+            writer.write_comment(&format!(" {comment}"));
+        }
+        for key in SCORING_SETTINGS {
+            if let Some(value) = self.get(key) {
+                writer.writeln(&format!("{} = {value}", key.to_uppercase()));
+            }
+        }
+        Ok(())
+    }
+    // ------------------------------------------------------------------------
+    /// value of a setting (lowercased key of profile files)
+    pub fn get(&self, key: &str) -> Option<f32> {
+        let value = match key {
+            "gap-open" => self.gap_open,
+            "gap-extend" => self.gap_extend,
+            "word-boundary-gap-open" => self.word_boundary_gap_open,
+            "gap-within-word" => self.gap_within_word,
+            "gap-within-word-similarity" => self.gap_within_word_similarity,
+            "word-start-anchor" => self.word_start_anchor,
+            "merge-left-bias" => self.merge_left_bias,
+            "merge-right-bias" => self.merge_right_bias,
+            "split-left-bias" => self.split_left_bias,
+            "split-right-bias" => self.split_right_bias,
+            "alternatives-min-score" => self.alternatives_min_score,
+            "similarity-scale" => self.similarity_scale,
+            _ => return None,
+        };
+        Some(value)
+    }
+    // ------------------------------------------------------------------------
+    /// sets a setting (lowercased key of profile files) after validating the
+    /// value
+    pub fn set(&mut self, key: &str, value: f32) -> Result<(), String> {
+        let (setting, is_penalty) = self
+            .setting_mut(key)
+            .ok_or_else(|| format!("unknown scoring setting {}", key.to_uppercase()))?;
+
+        // penalties reduce the score, everything else is a weight, bias or
+        // threshold
+        if is_penalty && value > 0.0 {
+            return Err(format!(
+                "{} must not be positive. found: {value}",
+                key.to_uppercase()
+            ));
+        }
+        if !is_penalty && value < 0.0 {
+            return Err(format!(
+                "{} must not be negative. found: {value}",
+                key.to_uppercase()
+            ));
+        }
+        *setting = value;
+        Ok(())
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl ScoringProfile {
+    // ------------------------------------------------------------------------
+    /// setting and flag if it is a penalty
+    fn setting_mut(&mut self, key: &str) -> Option<(&mut f32, bool)> {
+        let setting = match key {
+            "gap-open" => (&mut self.gap_open, true),
+            "gap-extend" => (&mut self.gap_extend, true),
+            "word-boundary-gap-open" => (&mut self.word_boundary_gap_open, true),
+            "gap-within-word" => (&mut self.gap_within_word, true),
+            "gap-within-word-similarity" => (&mut self.gap_within_word_similarity, false),
+            "word-start-anchor" => (&mut self.word_start_anchor, false),
+            "merge-left-bias" => (&mut self.merge_left_bias, false),
+            "merge-right-bias" => (&mut self.merge_right_bias, false),
+            "split-left-bias" => (&mut self.split_left_bias, false),
+            "split-right-bias" => (&mut self.split_right_bias, false),
+            "alternatives-min-score" => (&mut self.alternatives_min_score, false),
+            "similarity-scale" => (&mut self.similarity_scale, false),
+            _ => return None,
+        };
+        Some(setting)
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+// ----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::Path;
+
+    use super::{ScoringProfile, SCORING_SETTINGS};
+
+    const PROFILE_FILE: &str = "data/en.phoneme.scoring.cfg";
+
+    #[test]
+    fn test_load_profile() {
+        let file = Path::new(env!("CARGO_MANIFEST_DIR")).join(PROFILE_FILE);
+        assert_eq!(ScoringProfile::load(&file), Ok(ScoringProfile::default()));
+
+        // documented defaults are the defaults
+        let conf = fs::read_to_string(&file).unwrap();
+        let uncommented = conf
+            .lines()
+            .map(|line| match line.strip_prefix(';') {
+                Some(setting) if setting.contains('=') => setting,
+                _ => line,
+            })
+            .collect::<Vec<_>>();
+        let settings = uncommented.iter().filter(|line| line.contains('=')).count();
+        assert_eq!(settings, SCORING_SETTINGS.len(), "all settings documented");
+
+        let tmpfile = env::temp_dir().join("w3phonemetools-test-scoring-profile.cfg");
+        fs::write(&tmpfile, uncommented.join("\n")).unwrap();
+        assert_eq!(
+            ScoringProfile::load(&tmpfile),
+            Ok(ScoringProfile::default())
+        );
+
+        fs::write(&tmpfile, "GAP-OPEN = -2.0\n gap-extend=-0.5 \n").unwrap();
+        let profile = ScoringProfile::load(&tmpfile).unwrap();
+        assert_eq!((profile.gap_open, profile.gap_extend), (-2.0, -0.5));
+        assert_eq!(profile.similarity_scale, 1.0);
+
+        fs::remove_file(&tmpfile).ok();
+    }
+
+    #[test]
+    fn test_load_profile_rejects_invalid_values() {
+        let tmpfile = env::temp_dir().join("w3phonemetools-test-scoring-profile-invalid.cfg");
+
+        for (conf, error) in [
+            ("GAP-OPEN = 0.5", "must not be positive"),
+            ("MERGE-LEFT-BIAS = -0.1", "must not be negative"),
+            ("GAP-EXTEND = high", "invalid value"),
+            ("GAP-CLOSE = -1.0", "unknown scoring setting"),
+            ("GAP-OPEN -1.0", "failed to parse"),
+        ]
+        .iter()
+        {
+            fs::write(&tmpfile, format!("; comment\n\n{conf}\n")).unwrap();
+            let result = ScoringProfile::load(&tmpfile);
+            assert!(
+                matches!(&result, Err(why) if why.contains(":3 ") && why.contains(error)),
+                "{}: {:?}",
+                conf,
+                result
+            );
+        }
+        fs::remove_file(&tmpfile).ok();
+    }
+}
@@ -0,0 +1,577 @@
+//
+// alignment scoring profile tuning against corrected phoneme tracks
+//
+
+// ----------------------------------------------------------------------------
+// external interface
+// ----------------------------------------------------------------------------
+/// tunes the scoring profile of the sequence matcher so the alignments of the
+/// recognized audio phonemes stored in edited tracks reproduce the manually
+/// corrected phoneme boundaries
+pub struct ScoringTuner {
+    matcher: SequenceMatcher,
+    translator: TextPhonemeConverter,
+    training: Vec<GoldAlignment>,
+    heldout: Vec<GoldAlignment>,
+}
+// ----------------------------------------------------------------------------
+/// accuracy of aligned phoneme boundaries compared to corrected tracks
+#[derive(Clone, Debug, Default)]
+pub struct BoundaryMetrics {
+    /// number of evaluated lines
+    pub lines: usize,
+    /// number of lines that could not be aligned (all their boundaries are
+    /// counted as missed)
+    pub failed: usize,
+    /// number of compared phoneme boundaries
+    pub boundaries: usize,
+    /// number of boundaries within the tolerance
+    pub accurate: usize,
+    /// sum of all absolute boundary errors in ms
+    pub total_error: u64,
+}
+// ----------------------------------------------------------------------------
+/// initial and tuned profile with their metrics on the training and held-out
+/// lines
+pub struct TuningResult {
+    pub initial: ScoringProfile,
+    pub tuned: ScoringProfile,
+    /// metrics of initial and tuned profile
+    pub training: (BoundaryMetrics, BoundaryMetrics),
+    pub heldout: (BoundaryMetrics, BoundaryMetrics),
+    /// number of evaluated profiles
+    pub evaluations: usize,
+}
+// ----------------------------------------------------------------------------
+/// max deviation of an aligned boundary from the corrected one in ms
+pub const BOUNDARY_TOLERANCE_MS: u32 = 20;
+// ----------------------------------------------------------------------------
+impl ScoringTuner {
+    // ------------------------------------------------------------------------
+    /// profile of the similarity matrix is the starting point of the tuning.
+    /// translator provides the expected phoneme durations.
+    pub fn new(
+        similarity_matrix: SimilarityMatrix,
+        translator: TextPhonemeConverter,
+    ) -> ScoringTuner {
+        ScoringTuner {
+            matcher: SequenceMatcher::new(similarity_matrix),
+            translator,
+            training: Vec::default(),
+            heldout: Vec::default(),
+        }
+    }
+    // ------------------------------------------------------------------------
+    /// adds all edited phoneme tracks found in inputdir. every n-th line is
+    /// held out from tuning. returns number of used and skipped tracks.
+    pub fn add_tracks(&mut self, inputdir: PathBuf) -> Result<(usize, usize), String> {
+        let mut scanner = FilesScanner::new(inputdir)?;
+
+        let mut used = 0;
+        let mut skipped = 0;
+        for file in scanner.scan()? {
+            let FileInfo::Phonemes(lineid, phonemefile) = file else {
+                continue;
+            };
+            let track = match phonemes::load(lineid, &phonemefile) {
+                Ok(track) => track,
+                Err(why) => {
+                    warn!("id {lineid:010}: {why} skipping...");
+                    skipped += 1;
+                    continue;
+                }
+            };
+            // only manually corrected tracks are reliable
+            if track.version() <= 1 {
+                continue;
+            }
+            match self.add_track(&track) {
+                Ok(boundaries) => {
+                    debug!("id {lineid:010}: > collected #{boundaries} phoneme boundaries");
+                    used += 1;
+                }
+                Err(why) => {
+                    warn!("id {lineid:010}: {why} skipping...");
+                    skipped += 1;
+                }
+            }
+        }
+        Ok((used, skipped))
+    }
+    // ------------------------------------------------------------------------
+    /// restores the recognized audio phonemes from the matching info of the
+    /// track and uses its corrected (active) phonemes as gold alignment.
+    /// returns number of gold boundaries.
+    pub fn add_track(&mut self, track: &PhonemeTrack<PhonemeSegment>) -> Result<usize, String> {
+        let alignment = GoldAlignment::new(track, &self.translator)?;
+        let boundaries = alignment.gold.iter().flatten().count() * 2;
+
+        if track.id() % HELDOUT_EVERY_NTH_LINE == 0 {
+            self.heldout.push(alignment);
+        } else {
+            self.training.push(alignment);
+        }
+        Ok(boundaries)
+    }
+    // ------------------------------------------------------------------------
+    /// number of training and held-out lines
+    pub fn lines(&self) -> (usize, usize) {
+        (self.training.len(), self.heldout.len())
+    }
+    // ------------------------------------------------------------------------
+    /// hill-climbs the profile settings (coordinate search with shrinking step
+    /// sizes) to maximise the boundary accuracy on the training lines
+    pub fn tune(&mut self) -> Result<TuningResult, String> {
+        if self.training.is_empty() {
+            return Err(String::from("no training lines available."));
+        }
+        let initial = self.matcher.similarity().profile().clone();
+        let initial_training = self.evaluate(&self.training);
+        let initial_heldout = self.evaluate(&self.heldout);
+        info!(
+            "initial profile: training accuracy {:.1}% (mean error {:.1} ms)",
+            initial_training.accuracy() * 100.0,
+            initial_training.mean_error()
+        );
+
+        let mut best = initial.clone();
+        let mut best_metrics = initial_training.clone();
+        let mut evaluations = 1;
+        let mut step_scale = 1.0;
+
+        for round in 1..=MAX_TUNING_ROUNDS {
+            let mut improved = false;
+            for &(key, step) in TUNED_SETTINGS {
+                let value = best.get(key).unwrap_or_default();
+                for delta in [step * step_scale, -step * step_scale] {
+                    let mut candidate = best.clone();
+                    // rounding prevents drifting values due to float errors
+                    let new_value = ((value + delta) * 1000.0).round() / 1000.0;
+                    if candidate.set(key, new_value).is_err() {
+                        continue;
+                    }
+                    self.matcher.set_profile(candidate.clone());
+                    let metrics = self.evaluate(&self.training);
+                    evaluations += 1;
+
+                    if metrics.is_better(&best_metrics) {
+                        debug!(
+                            "round {round}: {} = {new_value}: accuracy {:.1}%",
+                            key.to_uppercase(),
+                            metrics.accuracy() * 100.0
+                        );
+                        best = candidate;
+                        best_metrics = metrics;
+                        improved = true;
+                        break;
+                    }
+                }
+            }
+            info!(
+                "round {round}: training accuracy {:.1}% (mean error {:.1} ms)",
+                best_metrics.accuracy() * 100.0,
+                best_metrics.mean_error()
+            );
+            if !improved {
+                if step_scale <= MIN_STEP_SCALE {
+                    break;
+                }
+                step_scale /= 2.0;
+            }
+        }
+
+        self.matcher.set_profile(best.clone());
+        let tuned_heldout = self.evaluate(&self.heldout);
+
+        Ok(TuningResult {
+            initial,
+            tuned: best,
+            training: (initial_training, best_metrics),
+            heldout: (initial_heldout, tuned_heldout),
+            evaluations,
+        })
+    }
+    // ------------------------------------------------------------------------
+    /// stores metrics of initial and tuned profile and all changed settings
+    pub fn store_report(result: &TuningResult, file: &Path) -> Result<(), String> {
+        let mut writer = SimpleCsvWriter::create(&file.to_path_buf())
+            .map_err(|err| format!("failed to create {}: {err}", file.display()))?;
+
+        writer.write_comment(&format!(
+            " boundary accuracy (tolerance {BOUNDARY_TOLERANCE_MS} ms) after #{} \
+             evaluated profiles",
+            result.evaluations
+        ));
+        writer.write_comment(" set|profile|lines|failed|boundaries|accuracy|mean error (ms)");
+        for (set, (initial, tuned)) in
+            [("training", &result.training), ("heldout", &result.heldout)]
+        {
+            for (profile, metrics) in [("initial", initial), ("tuned", tuned)] {
+                writer.writeln(&format!(
+                    "{set}|{profile}|{}|{}|{}|{:.3}|{:.1}",
+                    metrics.lines,
+                    metrics.failed,
+                    metrics.boundaries,
+                    metrics.accuracy(),
+                    metrics.mean_error()
+                ));
+            }
+        }
+        writer.write_comment(" changed settings");
+        writer.write_comment(" setting|initial|tuned");
+        for key in SCORING_SETTINGS {
+            let initial = result.initial.get(key).unwrap_or_default();
+            let tuned = result.tuned.get(key).unwrap_or_default();
+            if initial != tuned {
+                writer.writeln(&format!("{}|{initial}|{tuned}", key.to_uppercase()));
+            }
+        }
+        Ok(())
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl BoundaryMetrics {
+    // ------------------------------------------------------------------------
+    /// share of boundaries within the tolerance
+    pub fn accuracy(&self) -> f32 {
+        if self.boundaries > 0 {
+            self.accurate as f32 / self.boundaries as f32
+        } else {
+            0.0
+        }
+    }
+    // ------------------------------------------------------------------------
+    /// mean absolute boundary error in ms
+    pub fn mean_error(&self) -> f32 {
+        if self.boundaries > 0 {
+            self.total_error as f32 / self.boundaries as f32
+        } else {
+            0.0
+        }
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+// internals
+// ----------------------------------------------------------------------------
+use std::path::{Path, PathBuf};
+
+use espeak::TextPhonemeTranslator;
+use file_scanner::{FileInfo, FilesScanner};
+use phonemes::{self, PhonemeResult, PhonemeSegment, PhonemeTrack};
+use scoring_profile::{ScoringProfile, SCORING_SETTINGS};
+use sequence_matcher::SequenceMatcher;
+use similarity_matrix::SimilarityMatrix;
+use text::{CsvWriter, SimpleCsvWriter};
+
+use super::{TextPhonemeConverter, REGEXP_CLEANUP};
+// ----------------------------------------------------------------------------
+/// lines with an id divisible by this are not used for tuning
+const HELDOUT_EVERY_NTH_LINE: u32 = 5;
+const MAX_TUNING_ROUNDS: usize = 20;
+/// min fraction of the initial step sizes
+const MIN_STEP_SCALE: f32 = 0.125;
+/// tuned settings with initial step sizes. the pairing alternatives threshold
+/// does not influence the alignment.
+const TUNED_SETTINGS: &[(&str, f32)] = &[
+    ("gap-open", 0.2),
+    ("gap-extend", 0.2),
+    ("word-boundary-gap-open", 0.2),
+    ("gap-within-word", 0.2),
+    ("gap-within-word-similarity", 0.1),
+    ("similarity-scale", 0.1),
+    ("merge-left-bias", 0.01),
+    ("merge-right-bias", 0.01),
+    ("split-left-bias", 0.01),
+    ("split-right-bias", 0.01),
+];
+/// fallback duration of text phonemes if the translation does not match the
+/// corrected phonemes
+const UNIFORM_PHONEME_DURATION: u32 = 100;
+/// cap of boundary errors in the mean error so missing phonemes do not
+/// dominate it
+const MAX_BOUNDARY_ERROR_MS: u32 = 1000;
+// ----------------------------------------------------------------------------
+/// recognized audio phonemes and corrected phonemes of one line
+struct GoldAlignment {
+    lineid: u32,
+    audio: PhonemeResult,
+    text: PhonemeResult,
+    /// corrected timings of all text phonemes (none if deactivated)
+    gold: Vec<Option<(u32, u32)>>,
+}
+// ----------------------------------------------------------------------------
+impl GoldAlignment {
+    // ------------------------------------------------------------------------
+    fn new(
+        track: &PhonemeTrack<PhonemeSegment>,
+        translator: &TextPhonemeConverter,
+    ) -> Result<Self, String> {
+        let audio = SequenceMatcher::matched_audio_phonemes(track);
+        if audio.is_empty() {
+            return Err(String::from("no matching info stored."));
+        }
+
+        let corrected = track
+            .phonemes()
+            .iter()
+            .filter(|s| s.phoneme != "_")
+            .collect::<Vec<_>>();
+
+        if corrected.is_empty() {
+            return Err(String::from("no phonemes found."));
+        }
+
+        let durations = Self::expected_durations(track, &corrected, translator);
+
+        let text = corrected
+            .iter()
+            .zip(durations)
+            .map(|(s, (start, end))| PhonemeSegment {
+                phoneme: s.phoneme.clone(),
+                word_start: s.word_start,
+                start,
+                end,
+                weight: s.weight,
+                score: 1.0,
+                active: true,
+                ..Default::default()
+            })
+            .collect();
+
+        let gold = corrected
+            .iter()
+            .map(|s| {
+                if s.active {
+                    Some((s.start, s.end))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        Ok(GoldAlignment {
+            lineid: track.id(),
+            audio: PhonemeResult {
+                hypothesis: None,
+                phonemes: audio,
+            },
+            text: PhonemeResult {
+                hypothesis: None,
+                phonemes: text,
+            },
+            gold,
+        })
+    }
+    // ------------------------------------------------------------------------
+    /// relative durations of the translation if it matches the corrected
+    /// phonemes (edits may have changed them)
+    fn expected_durations(
+        track: &PhonemeTrack<PhonemeSegment>,
+        corrected: &[&PhonemeSegment],
+        translator: &TextPhonemeConverter,
+    ) -> Vec<(u32, u32)> {
+        // remove all non-spoken textual hints framed by *
+        let text = &*REGEXP_CLEANUP.replace_all(track.input_text(), "");
+
+        if let Ok(translation) = translator.translate(text) {
+            let translated = translation
+                .phonemes
+                .iter()
+                .filter(|p| p.phoneme != "_")
+                .collect::<Vec<_>>();
+
+            if translated.len() == corrected.len()
+                && translated
+                    .iter()
+                    .zip(corrected)
+                    .all(|(a, b)| a.phoneme.to_lowercase() == b.phoneme.to_lowercase())
+            {
+                return translated.iter().map(|p| (p.start, p.end)).collect();
+            }
+        }
+        debug!(
+            "id {:010}: translation differs from corrected phonemes. using uniform durations.",
+            track.id()
+        );
+        (0..corrected.len() as u32)
+            .map(|i| {
+                (
+                    i * UNIFORM_PHONEME_DURATION,
+                    (i + 1) * UNIFORM_PHONEME_DURATION,
+                )
+            })
+            .collect()
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl ScoringTuner {
+    // ------------------------------------------------------------------------
+    /// aligns all lines with the current profile of the matcher
+    fn evaluate(&self, alignments: &[GoldAlignment]) -> BoundaryMetrics {
+        let mut metrics = BoundaryMetrics::default();
+
+        for alignment in alignments {
+            metrics.lines += 1;
+
+            let matching = match self
+                .matcher
+                .calculate_matching_unchecked(&alignment.audio, &alignment.text)
+            {
+                Ok(matching) => matching,
+                Err(why) => {
+                    debug!("id {:010}: {why}", alignment.lineid);
+                    metrics.add_failed(&alignment.gold);
+                    continue;
+                }
+            };
+            // deleted audio phonemes are inactive gaps without text phoneme
+            let aligned = matching
+                .phonemes
+                .iter()
+                .filter(|p| p.phoneme != "_")
+                .collect::<Vec<_>>();
+
+            if aligned.len() != alignment.gold.len() {
+                debug!(
+                    "id {:010}: aligned phonemes do not match text phonemes",
+                    alignment.lineid
+                );
+                metrics.add_failed(&alignment.gold);
+                continue;
+            }
+
+            for (segment, gold) in aligned.iter().zip(alignment.gold.iter()) {
+                let Some((gold_start, gold_end)) = gold else {
+                    continue;
+                };
+                // phonemes not aligned to audio miss both boundaries
+                for (boundary, gold_boundary) in
+                    [(segment.start, *gold_start), (segment.end, *gold_end)]
+                {
+                    let error = if segment.active {
+                        boundary.abs_diff(gold_boundary)
+                    } else {
+                        u32::MAX
+                    };
+                    metrics.boundaries += 1;
+                    if error <= BOUNDARY_TOLERANCE_MS {
+                        metrics.accurate += 1;
+                    }
+                    metrics.total_error += u64::from(error.min(MAX_BOUNDARY_ERROR_MS));
+                }
+            }
+        }
+        metrics
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl BoundaryMetrics {
+    // ------------------------------------------------------------------------
+    /// all gold boundaries of a line that could not be aligned are missed (so
+    /// profiles cannot improve by failing difficult lines)
+    fn add_failed(&mut self, gold: &[Option<(u32, u32)>]) {
+        let boundaries = gold.iter().flatten().count() * 2;
+
+        self.failed += 1;
+        self.boundaries += boundaries;
+        self.total_error += boundaries as u64 * u64::from(MAX_BOUNDARY_ERROR_MS);
+    }
+    // ------------------------------------------------------------------------
+    /// higher accuracy or same accuracy with lower mean error
+    fn is_better(&self, other: &BoundaryMetrics) -> bool {
+        let (accuracy, other_accuracy) = (self.accuracy(), other.accuracy());
+
+        accuracy > other_accuracy
+            || (accuracy == other_accuracy && self.mean_error() < other.mean_error())
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+// ----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::super::phonemes::{PhonemeResult, PhonemeSegment};
+    use super::super::similarity_matrix::SimilarityMatrix;
+    use super::super::TextPhonemeConverter;
+    use super::{BoundaryMetrics, GoldAlignment, ScoringTuner, MAX_BOUNDARY_ERROR_MS};
+
+    fn segments(phonemes: &[(&str, u32, u32)]) -> PhonemeResult {
+        PhonemeResult {
+            hypothesis: None,
+            phonemes: phonemes
+                .iter()
+                .map(|&(phoneme, start, end)| PhonemeSegment {
+                    phoneme: phoneme.to_owned(),
+                    start,
+                    end,
+                    weight: 1.0,
+                    score: 1.0,
+                    active: true,
+                    ..Default::default()
+                })
+                .collect(),
+        }
+    }
+
+    fn gold_alignment(lineid: u32, gold: Vec<Option<(u32, u32)>>) -> GoldAlignment {
+        GoldAlignment {
+            lineid,
+            audio: segments(&[("a", 0, 100), ("b", 100, 200), ("c", 200, 300)]),
+            text: segments(&[("a", 0, 100), ("b", 100, 200), ("c", 200, 300)]),
+            gold,
+        }
+    }
+
+    fn metrics(lines: usize, failed: usize, accurate: usize, total_error: u64) -> BoundaryMetrics {
+        BoundaryMetrics {
+            lines,
+            failed,
+            boundaries: 12,
+            accurate,
+            total_error,
+        }
+    }
+
+    #[test]
+    fn test_evaluate_counts_failed_lines_as_missed() {
+        let sim_matrix = SimilarityMatrix::init_from_str("a;b;c", "a;b;c", -1.0, 1.0);
+        let tuner = ScoringTuner::new(sim_matrix, TextPhonemeConverter::new(""));
+
+        let aligned = gold_alignment(1, vec![Some((0, 100)), Some((100, 210)), None]);
+        let metrics = tuner.evaluate(&[aligned]);
+        assert_eq!((metrics.lines, metrics.failed), (1, 0));
+        assert_eq!((metrics.boundaries, metrics.accurate), (4, 4));
+        assert_eq!(metrics.total_error, 10);
+
+        // gold phonemes differ from the aligned text phonemes
+        let aligned = gold_alignment(1, vec![Some((0, 100)), Some((100, 210)), None]);
+        let failed = gold_alignment(2, vec![Some((0, 50)), Some((50, 100)), None, None]);
+        let metrics = tuner.evaluate(&[aligned, failed]);
+        assert_eq!((metrics.lines, metrics.failed), (2, 1));
+        assert_eq!((metrics.boundaries, metrics.accurate), (8, 4));
+        assert_eq!(
+            metrics.total_error,
+            10 + 4 * u64::from(MAX_BOUNDARY_ERROR_MS)
+        );
+        assert_eq!(metrics.accuracy(), 0.5);
+    }
+
+    #[test]
+    fn test_metrics_is_better() {
+        let base = metrics(2, 0, 6, 600);
+
+        assert!(metrics(2, 0, 7, 600).is_better(&base), "higher accuracy");
+        assert!(metrics(2, 0, 6, 500).is_better(&base), "lower mean error");
+        assert!(!metrics(2, 0, 6, 600).is_better(&base), "same metrics");
+        assert!(!metrics(2, 0, 5, 100).is_better(&base), "lower accuracy");
+
+        // line with only accurate boundaries fails: its boundaries are missed
+        let failed = metrics(2, 1, 6, 6 * u64::from(MAX_BOUNDARY_ERROR_MS));
+        assert!(!failed.is_better(&base));
+        assert!(base.is_better(&failed));
+    }
+}
@@ -13,26 +13,46 @@ pub struct SequenceMatcher {
     mapping: HashMap<String, String>,
 }
 // ----------------------------------------------------------------------------
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum AlignmentOperation {
+    Match = 0,
+    Delete = 1,
+    MergeLeft = 2,
+    MergeRight = 3,
+    Insert = 4,
+    SplitLeft = 5,
+    SplitRight = 6,
+}
+// ----------------------------------------------------------------------------
+/// score matrix of an alignment with the traceback of the best path for
+/// inspection. columns are audio phonemes, rows text phonemes (both with an
+/// additional first gap column/row).
+pub struct AlignmentMatrix {
+    pub audio: Vec<PhonemeSegment>,
+    pub text: Vec<PhonemeSegment>,
+    /// cells (audio, text) of the chosen operations from start to end
+    pub path: Vec<(usize, usize)>,
+    scores: ScoreMatrix,
+}
+// ----------------------------------------------------------------------------
 // internals
 // ----------------------------------------------------------------------------
 use std::f32;
 use std::fmt;
 use std::collections::HashMap;
+use std::ops::Index;
+
+use lazy_static::lazy_static;
+use regex::Regex;
 
-use matrix::{DebugMatrix2D, Matrix2D};
-use phonemes::{PhonemeResult, PhonemeSegment};
+use matrix::{BandedMatrix2D, DebugMatrix2D, Dimension2D, Matrix2D, MAX_MATRIX_SIZE};
+use phonemes::{PhonemeResult, PhonemeSegment, PhonemeTrack};
+use scoring_profile::ScoringProfile;
 use similarity_matrix::SimilarityMatrix;
 // ----------------------------------------------------------------------------
-
-#[derive(PartialEq, Clone, Copy, Debug)]
-enum AlignmentOperation {
-    Match = 0,
-    Delete = 1,
-    MergeLeft = 2,
-    //    MergeRight = 3,
-    Insert = 4,
-    SplitLeft = 5,
-    //    SplitRight = 6,
+lazy_static! {
+    // audio phoneme with timing in matching info, e.g. "a  [   50 -  100]"
+    static ref REGEXP_AUDIO_TIMING: Regex = Regex::new(r"(\S+)\s*\[\s*(\d+)\s*-\s*(\d+)\]").unwrap();
 }
 // ----------------------------------------------------------------------------
 #[derive(Clone, Copy)]
@@ -40,7 +60,81 @@ struct Score {
     score: f32,
     total: f32,
     op: Option<AlignmentOperation>,
-    _debug: [f32; 7],
+    /// score of the merged/split phoneme of MergeRight and SplitRight (the
+    /// remaining score is the match of the next phoneme)
+    partial: f32,
+}
+// ----------------------------------------------------------------------------
+/// best gap (deleted audio or inserted text phonemes) ending in a cell. gaps
+/// are either opened after the best path into the previous cell or extend the
+/// best gap ending in the previous cell (Gotoh)
+#[derive(Clone, Copy)]
+struct GapScore {
+    score: f32,
+    total: f32,
+    /// gap extends the gap ending in the previous cell
+    extended: bool,
+}
+// ----------------------------------------------------------------------------
+/// cells (audio, text) of the best path and the scores of their operations
+type Traceback = Vec<((usize, usize), Score)>;
+// ----------------------------------------------------------------------------
+/// best operation of a cell and the scores of all evaluated operations
+struct CellScores {
+    best: Score,
+    /// indexed by alignment operation
+    ops: [f32; 7],
+}
+// ----------------------------------------------------------------------------
+/// score matrix of an alignment which stores only the cells within a band of
+/// audio columns for every text row
+struct ScoreMatrix {
+    scores: BandedMatrix2D<Score>,
+    /// best gaps of deleted audio phonemes ending in every cell
+    deletes: BandedMatrix2D<GapScore>,
+    /// best gaps of inserted text phonemes ending in every cell
+    inserts: BandedMatrix2D<GapScore>,
+    /// scores of all evaluated operations for every cell (only for inspection)
+    op_scores: Option<Matrix2D<[f32; 7]>>,
+}
+// ----------------------------------------------------------------------------
+impl AlignmentOperation {
+    // ------------------------------------------------------------------------
+    pub const ALL: [AlignmentOperation; 7] = [
+        AlignmentOperation::Match,
+        AlignmentOperation::Delete,
+        AlignmentOperation::MergeLeft,
+        AlignmentOperation::MergeRight,
+        AlignmentOperation::Insert,
+        AlignmentOperation::SplitLeft,
+        AlignmentOperation::SplitRight,
+    ];
+    // ------------------------------------------------------------------------
+    pub fn name(&self) -> &'static str {
+        match *self {
+            AlignmentOperation::Match => "match",
+            AlignmentOperation::Delete => "delete",
+            AlignmentOperation::MergeLeft => "merge left",
+            AlignmentOperation::MergeRight => "merge right",
+            AlignmentOperation::Insert => "insert",
+            AlignmentOperation::SplitLeft => "split left",
+            AlignmentOperation::SplitRight => "split right",
+        }
+    }
+    // ------------------------------------------------------------------------
+    /// number of consumed (audio, text) phonemes
+    fn steps(&self) -> (usize, usize) {
+        match *self {
+            AlignmentOperation::Match => (1, 1),
+            AlignmentOperation::Delete => (1, 0),
+            AlignmentOperation::MergeLeft => (1, 0),
+            AlignmentOperation::MergeRight => (2, 1),
+            AlignmentOperation::Insert => (0, 1),
+            AlignmentOperation::SplitLeft => (0, 1),
+            AlignmentOperation::SplitRight => (1, 2),
+        }
+    }
+    // ------------------------------------------------------------------------
 }
 // ----------------------------------------------------------------------------
 impl Default for Score {
@@ -49,20 +143,184 @@ impl Default for Score {
             score: f32::MIN,
             total: f32::MIN,
             op: None,
-            _debug: [f32::MIN; 7],
+            partial: 0.0,
         }
     }
 }
 // ----------------------------------------------------------------------------
 impl Score {
+    // ------------------------------------------------------------------------
+    /// match part of MergeRight and SplitRight
+    fn matched_part(&self) -> Score {
+        Score {
+            score: self.score - self.partial,
+            op: Some(AlignmentOperation::Match),
+            ..*self
+        }
+    }
+    // ------------------------------------------------------------------------
+    /// previous operation ended with a match which may be extended by a merge
+    /// or split
+    fn ends_with_match(&self) -> bool {
+        matches!(
+            self.op,
+            Some(AlignmentOperation::Match)
+                | Some(AlignmentOperation::MergeRight)
+                | Some(AlignmentOperation::SplitRight)
+        )
+    }
+}
+// ----------------------------------------------------------------------------
+impl Default for GapScore {
+    fn default() -> GapScore {
+        GapScore {
+            score: f32::MIN,
+            total: f32::MIN,
+            extended: false,
+        }
+    }
+}
+// ----------------------------------------------------------------------------
+impl GapScore {
+    // ------------------------------------------------------------------------
+    /// opens a new gap after the best path into the previous cell or extends
+    /// the gap ending in the previous cell, whichever is better
+    fn new(open: f32, prev_total: f32, extend: f32, prev_gap: &GapScore) -> GapScore {
+        if prev_gap.total + extend > prev_total + open {
+            GapScore {
+                score: extend,
+                total: prev_gap.total + extend,
+                extended: true,
+            }
+        } else {
+            GapScore {
+                score: open,
+                total: prev_total + open,
+                extended: false,
+            }
+        }
+    }
+    // ------------------------------------------------------------------------
+    /// score of the gap operation within the alignment
+    fn as_score(&self, op: AlignmentOperation) -> Score {
+        Score {
+            score: self.score,
+            total: self.total,
+            op: Some(op),
+            partial: 0.0,
+        }
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl Default for CellScores {
+    fn default() -> CellScores {
+        CellScores {
+            best: Score::default(),
+            ops: [f32::MIN; 7],
+        }
+    }
+}
+// ----------------------------------------------------------------------------
+impl CellScores {
     fn set(&mut self, op: AlignmentOperation, score: f32, prev_total: f32) {
-        if prev_total + score > self.total {
-            self.total = prev_total + score;
-            self.score = score;
-            self.op = Some(op);
+        if prev_total + score > self.best.total {
+            self.best.total = prev_total + score;
+            self.best.score = score;
+            self.best.op = Some(op);
         }
-        self._debug[op as usize] = score;
+        self.ops[op as usize] = score;
+    }
+    // ------------------------------------------------------------------------
+    /// gap operations: the total is the total of the best gap ending in the cell
+    fn set_gap(&mut self, op: AlignmentOperation, gap: &GapScore) {
+        if gap.total > self.best.total {
+            self.best.total = gap.total;
+            self.best.score = gap.score;
+            self.best.op = Some(op);
+        }
+        self.ops[op as usize] = gap.score;
+    }
+    // ------------------------------------------------------------------------
+    /// operations spanning two phonemes: merged/split phoneme + match
+    fn set_two_step(
+        &mut self,
+        op: AlignmentOperation,
+        partial: f32,
+        matched: f32,
+        prev_total: f32,
+    ) {
+        let score = partial + matched;
+        if prev_total + score > self.best.total {
+            self.best.total = prev_total + score;
+            self.best.score = score;
+            self.best.op = Some(op);
+            self.best.partial = partial;
+        }
+        self.ops[op as usize] = score;
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl ScoreMatrix {
+    // ------------------------------------------------------------------------
+    fn new(width: usize, band: Vec<(usize, usize)>, with_op_scores: bool) -> ScoreMatrix {
+        let height = band.len();
+        ScoreMatrix {
+            deletes: BandedMatrix2D::new(width, band.clone()),
+            inserts: BandedMatrix2D::new(width, band.clone()),
+            scores: BandedMatrix2D::new(width, band),
+            op_scores: if with_op_scores {
+                Some(Matrix2D::new_with_default(width, height, [f32::MIN; 7]))
+            } else {
+                None
+            },
+        }
+    }
+    // ------------------------------------------------------------------------
+    fn set(&mut self, index: (usize, usize), cell: CellScores) {
+        self.scores[index] = cell.best;
+        if let Some(ref mut op_scores) = self.op_scores {
+            op_scores[index] = cell.ops;
+        }
+    }
+    // ------------------------------------------------------------------------
+    /// total score of the best alignment
+    fn total(&self) -> f32 {
+        self.scores[(self.width() - 1, self.height() - 1)].total
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl Index<(usize, usize)> for ScoreMatrix {
+    type Output = Score;
+    // ------------------------------------------------------------------------
+    fn index(&self, index: (usize, usize)) -> &Score {
+        &self.scores[index]
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl Dimension2D for ScoreMatrix {
+    // ------------------------------------------------------------------------
+    fn width(&self) -> usize {
+        self.scores.width
     }
+    // ------------------------------------------------------------------------
+    fn height(&self) -> usize {
+        self.scores.height
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+struct Matching {
+    phonemes: Vec<PhonemeSegment>,
+    min_score: f32,
+    has_gaps: bool,
+    /// total alignment score
+    total: f32,
+    /// number of alignment operations
+    path_length: usize,
 }
 // ----------------------------------------------------------------------------
 #[derive(Default)]
@@ -118,6 +376,18 @@ impl SequenceAlignmentString {
                         prev.0, prev.1, info.1, prev.2, info.2
                     )
                 }
+                // match part of a MergeRight
+                AlignmentOperation::Match
+                    if self.traceback.len() > 1
+                        && self.traceback[self.traceback.len() - 2].1
+                            == AlignmentOperation::MergeRight =>
+                {
+                    let prev = &self.traceback[self.traceback.len() - 2];
+                    format!(
+                        " {: <2} {}{} {} + {}",
+                        info.0, prev.1, info.1, prev.2, info.2
+                    )
+                }
                 op => format!(" {: <2} {}  {}", info.0, op, info.2),
             },
             None => "".to_owned(),
@@ -142,11 +412,31 @@ impl fmt::Display for SequenceAlignmentString {
 }
 // ----------------------------------------------------------------------------
 const MAX_PHONEME_ALTERNATIVES: usize = 25;
+/// half width of the diagonal band of the score matrix in audio phonemes
+/// (ratio of all audio phonemes clamped to min and max)
+const BAND_RADIUS_RATIO: f32 = 0.1;
+const BAND_RADIUS_MIN: usize = 25;
+const BAND_RADIUS_MAX: usize = 100;
+/// max cells of a full score matrix as fallback for failed banded alignments
+const MAX_FULL_MATRIX_CELLS: usize = MAX_MATRIX_SIZE * MAX_MATRIX_SIZE;
 // ----------------------------------------------------------------------------
 impl SequenceMatcher {
     // ------------------------------------------------------------------------
     pub fn new(similarity_matrix: SimilarityMatrix) -> SequenceMatcher {
-        let mapping = similarity_matrix
+        SequenceMatcher {
+            mapping: Self::pairing_alternatives(&similarity_matrix),
+            similarity: similarity_matrix,
+        }
+    }
+    // ------------------------------------------------------------------------
+    /// replaces the scoring profile of the similarity matrix
+    pub fn set_profile(&mut self, profile: ScoringProfile) {
+        self.similarity.set_profile(profile);
+        self.mapping = Self::pairing_alternatives(&self.similarity);
+    }
+    // ------------------------------------------------------------------------
+    fn pairing_alternatives(similarity_matrix: &SimilarityMatrix) -> HashMap<String, String> {
+        similarity_matrix
             .sorted_scores()
             .drain(..)
             .map(|(key, mut scores)| {
@@ -161,65 +451,122 @@ impl SequenceMatcher {
                     (key, scores.join(""))
                 }
             })
-            .collect();
-
-        SequenceMatcher {
-            similarity: similarity_matrix,
-            mapping,
-        }
+            .collect()
     }
     // ------------------------------------------------------------------------
+    /// score matrix within a diagonal band guided by the phoneme timings. falls
+    /// back to the full matrix if the best path reaches the border of the band
+    /// (a better path outside of the band may exist).
     fn calculate_score_matrix(
         &self,
         audio: &[PhonemeSegment],
         text: &[PhonemeSegment],
-    ) -> Matrix2D<Score> {
-        // reserve space (include additional first row & col)
-        let a_len = audio.len();
-        let t_len = text.len();
-        let width = a_len + 1;
-        let height = t_len + 1;
-        let mut matrix: Matrix2D<Score> = Matrix2D::new(width, height);
-        // println!("a.len {}", a_len);
-        // for a in audio {
-        // println!("AUDIO PHONEMES {:?}", &a.phoneme);
-        // }
-        // small sequences, performance not that important
-        // top left score must be zero
-        matrix[(0, 0)].score = 0.0;
-        matrix[(0, 0)].total = 0.0;
+    ) -> Result<ScoreMatrix, String> {
+        let band = Self::timing_band(audio, text);
+        let matrix = self.calculate_banded_score_matrix(audio, text, band, false);
 
-        // special case: first row + col
-        for a in 1..width {
-            let prev_idx = (a - 1, 0);
-            let prev_total = matrix[prev_idx].total;
-            let p_a = &audio[a - 1].phoneme;
+        if matrix.scores.is_full() || Self::is_path_within_band(&matrix) {
+            return Ok(matrix);
+        }
 
-            let current = &mut matrix[(a, 0)];
-            current.set(
-                AlignmentOperation::Delete,
-                self.similarity.get_delete_score(p_a),
-                prev_total,
-            );
+        if matrix.width() * matrix.height() > MAX_FULL_MATRIX_CELLS {
+            return Err(format!(
+                "best alignment path reaches border of score matrix band and sequences \
+                 are too long for a full alignment ({} audio and {} text phonemes, max \
+                 {} cells).",
+                audio.len(),
+                text.len(),
+                MAX_FULL_MATRIX_CELLS
+            ));
         }
+        debug!("> best alignment path reaches border of score matrix band. using full matrix.");
 
-        for t in 1..height {
-            let prev_idx = (0, t - 1);
-            let prev_total = matrix[prev_idx].total;
-            let p_t = &text[t - 1].phoneme;
-
-            let current = &mut matrix[(0, t)];
-            current.set(
-                AlignmentOperation::Insert,
-                self.similarity.get_insert_score(p_t),
-                prev_total,
-            );
+        let band = vec![(0, audio.len()); text.len() + 1];
+        Ok(self.calculate_banded_score_matrix(audio, text, band, false))
+    }
+    // ------------------------------------------------------------------------
+    /// first and last audio column of every text row within a band around the
+    /// diagonal expected from the phoneme timings. text timings are relative
+    /// (expected durations) and are scaled to the audio timings.
+    fn timing_band(audio: &[PhonemeSegment], text: &[PhonemeSegment]) -> Vec<(usize, usize)> {
+        let width = audio.len() + 1;
+        // no timings to guide the band: single row or column is the full matrix
+        if audio.is_empty() || text.is_empty() {
+            return vec![(0, audio.len()); text.len() + 1];
+        }
+        let radius = ((audio.len() as f32 * BAND_RADIUS_RATIO) as usize)
+            .clamp(BAND_RADIUS_MIN, BAND_RADIUS_MAX);
+
+        let audio_start = audio[0].start;
+        let audio_span = audio[audio.len() - 1].end.saturating_sub(audio_start);
+        let text_start = text[0].start;
+        let text_span = text[text.len() - 1].end.saturating_sub(text_start);
+
+        let mut band = Vec::with_capacity(text.len() + 1);
+        let mut prev = (0, 0);
+        for t in 0..=text.len() {
+            // audio phonemes expected to be consumed after t text phonemes
+            let center = if t == 0 {
+                0
+            } else if audio_span > 0 && text_span > 0 {
+                let ratio = text[t - 1].end.saturating_sub(text_start) as f32 / text_span as f32;
+                let time = audio_start as f32 + ratio * audio_span as f32;
+                audio.partition_point(|p| p.end as f32 <= time)
+            } else {
+                t * audio.len() / text.len()
+            };
+
+            // band must be monotonic and overlap with the previous row to
+            // make every cell reachable
+            let first = center.saturating_sub(radius).max(prev.0).min(prev.1);
+            let last = (center + radius).min(width - 1).max(prev.1);
+
+            band.push((first, last));
+            prev = (first, last);
+        }
+        // alignment ends in the bottom right cell
+        if let Some(last_row) = band.last_mut() {
+            last_row.1 = width - 1;
+        }
+        band
+    }
+    // ------------------------------------------------------------------------
+    /// best path does not touch a border of the band which is not also a border
+    /// of the matrix
+    fn is_path_within_band(matrix: &ScoreMatrix) -> bool {
+        let last_column = matrix.width() - 1;
+
+        match Self::calculate_traceback(matrix) {
+            Ok(path) => path.iter().all(|&((a, t), _)| {
+                let (first, last) = matrix.scores.columns(t);
+                (a > first || first == 0) && (a < last || last == last_column)
+            }),
+            Err(_) => false,
         }
+    }
+    // ------------------------------------------------------------------------
+    /// score matrix with cells calculated only within the band of columns for
+    /// every row. optionally stores the scores of all evaluated operations.
+    fn calculate_banded_score_matrix(
+        &self,
+        audio: &[PhonemeSegment],
+        text: &[PhonemeSegment],
+        band: Vec<(usize, usize)>,
+        with_op_scores: bool,
+    ) -> ScoreMatrix {
+        // include additional first row & col
+        let width = audio.len() + 1;
+        let height = text.len() + 1;
+        let mut matrix = ScoreMatrix::new(width, band, with_op_scores);
+
+        // top left score must be zero
+        matrix.scores[(0, 0)].score = 0.0;
+        matrix.scores[(0, 0)].total = 0.0;
 
         // -- addittional alignment constraints if audio gaps (silence) are detected
 
         // as this is only for detecting gaps within words ignore starting gap
-        let mut last_end = audio[0].end as i32;
+        let mut last_end = audio.first().map_or(0, |p| p.end as i32);
         let preceding_audio_gaps = audio
             .iter()
             .map(|p| {
@@ -229,9 +576,49 @@ impl SequenceMatcher {
             })
             .collect::<Vec<_>>();
 
+        // special case: first row + col (as far as within the band)
+        for a in 1..=matrix.scores.columns(0).1 {
+            let prev_idx = (a - 1, 0);
+            let p_a = &audio[a - 1].phoneme;
+            let word_boundary = a == 1 || preceding_audio_gaps[a - 1];
+
+            let delete = GapScore::new(
+                self.similarity.get_delete_score(p_a, false, word_boundary),
+                matrix[prev_idx].total,
+                self.similarity.get_delete_score(p_a, true, word_boundary),
+                &matrix.deletes[prev_idx],
+            );
+            let mut current = CellScores::default();
+            current.set_gap(AlignmentOperation::Delete, &delete);
+            matrix.deletes[(a, 0)] = delete;
+            matrix.set((a, 0), current);
+        }
+
+        let first_column_height = (0..height)
+            .take_while(|t| matrix.scores.columns(*t).0 == 0)
+            .count();
+        for t in 1..first_column_height {
+            let prev_idx = (0, t - 1);
+            let p_t = &text[t - 1];
+
+            let insert = GapScore::new(
+                self.similarity
+                    .get_insert_score(&p_t.phoneme, false, p_t.word_start),
+                matrix[prev_idx].total,
+                self.similarity
+                    .get_insert_score(&p_t.phoneme, true, p_t.word_start),
+                &matrix.inserts[prev_idx],
+            );
+            let mut current = CellScores::default();
+            current.set_gap(AlignmentOperation::Insert, &insert);
+            matrix.inserts[(0, t)] = insert;
+            matrix.set((0, t), current);
+        }
+
         for t in 1..height {
-            for a in 1..width {
-                let mut score = Score::default();
+            let (first, last) = matrix.scores.columns(t);
+            for a in first.max(1)..=last {
+                let mut score = CellScores::default();
 
                 // zero based index
                 let text_idx = t - 1;
@@ -276,13 +663,20 @@ impl SequenceMatcher {
                 // audio word ends with a gap but text word started
                 let flag_audio_word_end_misaligned_with_text_word_start =
                     flag_audio_word_end_gap_follows && flag_text_word_with_multiple_phonemes_start;
+
+                // text word starts at a word start from the word level recognition. the bonus
+                // is added to the total only so phoneme scores still reflect similarity.
+                let word_start_anchor = self.similarity.get_word_start_anchor_score(
+                    audio[audio_idx].word_start,
+                    text[text_idx].word_start,
+                );
                 // --------------------------------------------------------------------------------
 
                 // --- match
                 let prev_idx = (a - 1, t - 1);
 
                 // penalty for matches in the vicinity of detected audio gaps
-                let op_score = if !flag_text_single_phoneme_word
+                let match_score = if !flag_text_single_phoneme_word
                     && (flag_audio_gap_but_still_within_word
                         || flag_audio_word_end_misaligned_with_text_word_start)
                 {
@@ -295,22 +689,31 @@ impl SequenceMatcher {
                     //  t: ACB
                     self.similarity.get_score(p_a, p_t)
                 };
-                score.set(AlignmentOperation::Match, op_score, matrix[prev_idx].total);
+                score.set(
+                    AlignmentOperation::Match,
+                    match_score,
+                    matrix[prev_idx].total + word_start_anchor,
+                );
 
                 // --- deletes
                 //  a: ACB
                 //  t: A_B
                 let prev_idx = (a - 1, t);
-                score.set(
-                    AlignmentOperation::Delete,
-                    self.similarity.get_delete_score(p_a),
+                let delete = GapScore::new(
+                    self.similarity
+                        .get_delete_score(p_a, false, flag_preceding_gap),
                     matrix[prev_idx].total,
+                    self.similarity
+                        .get_delete_score(p_a, true, flag_preceding_gap),
+                    &matrix.deletes[prev_idx],
                 );
+                score.set_gap(AlignmentOperation::Delete, &delete);
+                matrix.deletes[(a, t)] = delete;
 
                 // instead of making gaps favor extension of previous text
                 // phoneme - but only if previous is not already a gap/gap
                 // filler
-                if matrix[prev_idx].op == Some(AlignmentOperation::Match) {
+                if matrix[prev_idx].ends_with_match() {
                     // do not match + merge over audio gaps (phonemes most probably belong to
                     // different words with a silence in between)
                     let op_score = if flag_preceding_gap {
@@ -329,33 +732,64 @@ impl SequenceMatcher {
                         matrix[prev_idx].total,
                     );
                 }
-                // FIXME: MergeRight needs a more sophisticated/different
-                // apply_alignment to allow for prefetching and merging of
-                // text phoneme. also how to test for gap extension? (multipass?).
-                // Maybe later...
-                //
-                // if t < t_len {
-                //     //  a: ACB    ACB
-                //     //  t: A_B -> AbB
-                //     let next_t = &text[t].phoneme;
-                //     score.set(AlignmentOperation::MergeRight,
-                //         self.similarity.get_merge_right_score(p_a, next_t),
-                //         matrix[prev_idx].total);
-                // }
+                // or extension of current text phoneme: previous audio phoneme
+                // is merged into the current text phoneme which is matched
+                // with the current audio phoneme
+                if a > 1 {
+                    let p_merged = &audio[audio_idx - 1].phoneme;
+
+                    // merged phoneme follows a silence but text phoneme is
+                    // within a word (same constraint as for matches)
+                    let flag_merged_after_gap_within_word =
+                        preceding_audio_gaps[audio_idx - 1] && !text[text_idx].word_start;
+
+                    // do not merge over audio gaps (merged phoneme most probably
+                    // belongs to another word)
+                    let op_score = if flag_preceding_gap || flag_merged_after_gap_within_word {
+                        //  a: AC|B   AC|B    A|CB   A|CB
+                        //  t: A_B -> A_|B    AA_B -> AA|_B
+                        self.similarity
+                            .get_merge_right_score_over_gap(p_merged, p_t)
+                    } else {
+                        //  a: ACB    ACB
+                        //  t: A_B -> AbB
+                        self.similarity.get_merge_right_score(p_merged, p_t)
+                    };
+
+                    // only an alternative to a gap if it is more similar
+                    if op_score > self.similarity.get_delete_score(p_merged, false, false) {
+                        // merged phoneme starts the text phoneme
+                        let word_start_anchor = self.similarity.get_word_start_anchor_score(
+                            audio[audio_idx - 1].word_start,
+                            text[text_idx].word_start,
+                        );
+                        score.set_two_step(
+                            AlignmentOperation::MergeRight,
+                            op_score,
+                            match_score,
+                            matrix[(a - 2, t - 1)].total + word_start_anchor,
+                        );
+                    }
+                }
 
                 // --- inserts
                 //  a: A_B
                 //  t: ACB
                 let prev_idx = (a, t - 1);
-                score.set(
-                    AlignmentOperation::Insert,
-                    self.similarity.get_insert_score(p_t),
+                let insert = GapScore::new(
+                    self.similarity
+                        .get_insert_score(p_t, false, text[text_idx].word_start),
                     matrix[prev_idx].total,
+                    self.similarity
+                        .get_insert_score(p_t, true, text[text_idx].word_start),
+                    &matrix.inserts[prev_idx],
                 );
+                score.set_gap(AlignmentOperation::Insert, &insert);
+                matrix.inserts[(a, t)] = insert;
 
                 // instead of making insert favor split of previous/next audio
                 // phoneme - but only if previous is not already a gap/gap filler
-                if matrix[prev_idx].op == Some(AlignmentOperation::Match) {
+                if matrix[prev_idx].ends_with_match() {
 
                     // do not split phonemes over audio gaps
                     let op_score = if flag_text_word_does_not_end_yet && flag_audio_next_phoneme_after_gap {
@@ -374,9 +808,53 @@ impl SequenceMatcher {
                     );
                 }
 
+                // or split of current audio phoneme: previous text phoneme
+                // takes the first part of the current audio phoneme which is
+                // matched with the current text phoneme
+                if t > 1 {
+                    let p_split = &text[text_idx - 1];
+
+                    // do not split phonemes over audio gaps (previous text word
+                    // started before the gap)
+                    let op_score = if !p_split.word_start && flag_preceding_gap {
+                        //  a: A|_B   A|_B
+                        //  t: ACB -> ACB
+                        self.similarity
+                            .get_split_right_gap_within_word_score(p_a, &p_split.phoneme)
+                    } else {
+                        //  a: A_B    AbB
+                        //  t: ACB -> ACB
+                        self.similarity.get_split_right_score(p_a, &p_split.phoneme)
+                    };
+                    // only an alternative to a gap if it is more similar and
+                    // only if the audio phoneme is rather the matched than the
+                    // split phoneme (otherwise match + split left is the same
+                    // alignment with the better matching phoneme)
+                    let gap_score =
+                        self.similarity
+                            .get_insert_score(&p_split.phoneme, false, false);
+                    let is_matched_phoneme = self.similarity.get_score(p_a, p_t)
+                        >= self.similarity.get_score(p_a, &p_split.phoneme);
+
+                    if op_score > gap_score && is_matched_phoneme {
+                        // split phoneme starts at the audio phoneme
+                        let word_start_anchor = self.similarity.get_word_start_anchor_score(
+                            audio[audio_idx].word_start,
+                            p_split.word_start,
+                        );
+                        // matched part has the same audio gap constraints as a match
+                        score.set_two_step(
+                            AlignmentOperation::SplitRight,
+                            op_score,
+                            match_score,
+                            matrix[(a - 1, t - 2)].total + word_start_anchor,
+                        );
+                    }
+                }
+
                 //println!("{}x{} {:?}", a, t, &score);
 
-                matrix[(a, t)] = score;
+                matrix.set((a, t), score);
             }
         }
         trace!(
@@ -387,36 +865,19 @@ impl SequenceMatcher {
         matrix
     }
     // ------------------------------------------------------------------------
-    fn calculate_alignment(&self, score_matrix: Matrix2D<Score>) -> Result<Vec<Score>, String> {
-        let mut ops = Vec::with_capacity(score_matrix.height);
-
-        // backtrace (one!) best path from (width, height) to (0,0)
-        let mut a = score_matrix.width - 1;
-        let mut t = score_matrix.height - 1;
+    /// cells (audio, text) and scores of the (one!) best path backtraced from
+    /// (width, height) to (0, 0) in reversed order (without (0, 0)). gaps are
+    /// traced back within the gap matrices until the opening of the gap.
+    fn calculate_traceback(score_matrix: &ScoreMatrix) -> Result<Traceback, String> {
+        let mut path = Vec::with_capacity(score_matrix.height());
+
+        let mut a = score_matrix.width() - 1;
+        let mut t = score_matrix.height() - 1;
+        // gap operation if the path is within a gap
+        let mut gap = None;
         while a > 0 || t > 0 {
-            let score = score_matrix[(a, t)];
-            //warn!("a,t: ({},{}) {:?}:{}", a, t, score.op.unwrap(), score.score);
-            match score.op {
-                Some(ref op) => match *op {
-                    AlignmentOperation::Match => {
-                        a -= 1;
-                        t -= 1;
-                    }
-                    AlignmentOperation::Delete => {
-                        a -= 1;
-                    }
-                    AlignmentOperation::MergeLeft => {
-                        a -= 1;
-                    }
-                    //AlignmentOperation::MergeRight => { a -= 1; },
-                    AlignmentOperation::Insert => {
-                        t -= 1;
-                    }
-                    AlignmentOperation::SplitLeft => {
-                        t -= 1;
-                    }
-                    //AlignmentOperation::SplitRight => { t -= 1; },
-                },
+            let op = match gap.or(score_matrix[(a, t)].op) {
+                Some(op) => op,
                 None => {
                     return Err(format!(
                         "found score matrix element without alignment \
@@ -424,13 +885,35 @@ impl SequenceMatcher {
                         a, t
                     ))
                 }
-            }
+            };
+            let score = match op {
+                AlignmentOperation::Delete | AlignmentOperation::Insert => {
+                    let gap_score = if op == AlignmentOperation::Delete {
+                        &score_matrix.deletes[(a, t)]
+                    } else {
+                        &score_matrix.inserts[(a, t)]
+                    };
+                    gap = if gap_score.extended { Some(op) } else { None };
+                    gap_score.as_score(op)
+                }
+                _ => score_matrix[(a, t)],
+            };
+            path.push(((a, t), score));
 
-            ops.push(score);
+            let (steps_a, steps_t) = op.steps();
+            a -= steps_a;
+            t -= steps_t;
         }
 
-        ops.reverse();
-        Ok(ops)
+        path.reverse();
+        Ok(path)
+    }
+    // ------------------------------------------------------------------------
+    fn calculate_alignment(&self, score_matrix: ScoreMatrix) -> Result<Vec<Score>, String> {
+        Ok(Self::calculate_traceback(&score_matrix)?
+            .into_iter()
+            .map(|(_, score)| score)
+            .collect())
     }
     // ------------------------------------------------------------------------
     fn apply_alignment(
@@ -445,6 +928,8 @@ impl SequenceMatcher {
         let mut t = 0;
         let mut has_gaps = false;
         let mut min_score = f32::MAX;
+        // expected durations of the text phonemes in result (0 for gaps)
+        let mut expected = Vec::new();
 
         for (i, align_info) in align_ops.iter().enumerate() {
 
@@ -502,6 +987,7 @@ impl SequenceMatcher {
                         let p_a = &audio[a];
                         // extend previous text phoneme to include this audio phoneme
                         let prev_t: PhonemeSegment = result.pop().unwrap();
+                        expected.pop();
 
                         seq.push(Some(p_a), Some(&prev_t), align_info);
                         a += 1;
@@ -518,12 +1004,30 @@ impl SequenceMatcher {
                             true,
                         )
                     }
-                    // AlignmentOperation::MergeRight => {
-                    //     seq.push(&p_a.phoneme, &p_t.phoneme, op, score);
-                    //     a += 1;
-                    //     // FIXME
-                    //     unimplemented!();
-                    // },
+                    AlignmentOperation::MergeRight => {
+                        let p_merged = &audio[a];
+                        let p_a = &audio[a + 1];
+                        let p_t = &text[t];
+
+                        // text phoneme is extended to include the preceding
+                        // audio phoneme
+                        seq.push(Some(p_merged), Some(p_t), align_info);
+                        seq.push(Some(p_a), Some(p_t), &align_info.matched_part());
+                        a += 2;
+                        t += 1;
+
+                        // score is adjusted as it contains "two" scores
+                        score = align_info.score / 2.0;
+
+                        (
+                            p_t.phoneme.clone(),
+                            p_t.word_start,
+                            p_t.weight,
+                            p_merged.start,
+                            p_a.end,
+                            true,
+                        )
+                    }
                     AlignmentOperation::Insert => {
                         let p_t = &text[t];
                         has_gaps = true;
@@ -551,7 +1055,8 @@ impl SequenceMatcher {
                         t += 1;
 
                         // take half the timing slot of previous audio phoneme
-                        // adjust previous phoneme end time
+                        // adjust previous phoneme end time (final split point
+                        // is set by distribute_shared_slots)
                         let duration = prev_audio.end - prev_audio.start;
                         let half_time = prev_audio.start + duration / 2;
 
@@ -567,21 +1072,49 @@ impl SequenceMatcher {
                             true,
                         )
                     }
-                    // AlignmentOperation::SplitRight => {
-                    //     let p_a = &audio[a];
-                    //     let p_t = &text[t];
-
-                    //     seq.push(Some(p_a), Some(p_t), align_info);
-                    //     t += 1;
-                    //     // take half the timing slot of "next" audio phoneme
-                    //     //TODO adjust next textphoneme?
-                    //     let duration = p_a.end - p_a.start;
-                    //     (p_t.phoneme.clone(), p_t.weight,
-                    //         p_a.start + duration / 2, p_a.end)
-                    // },
+                    AlignmentOperation::SplitRight => {
+                        let p_a = &audio[a];
+                        let p_split = &text[t];
+                        let p_t = &text[t + 1];
 
-                    //_ => unimplemented!(),
+                        seq.push(Some(p_a), Some(p_split), align_info);
+                        a += 1;
+                        t += 2;
+
+                        // split text phoneme takes the first half of the timing
+                        // slot, the matched text phoneme the second half (final
+                        // split point is set by distribute_shared_slots)
+                        let duration = p_a.end - p_a.start;
+                        let half_time = p_a.start + duration / 2;
+
+                        result.push(PhonemeSegment {
+                            phoneme: p_split.phoneme.clone(),
+                            word_start: p_split.word_start,
+                            start: p_a.start,
+                            end: half_time,
+                            weight: p_split.weight,
+                            score: align_info.partial,
+                            matching_info: Some(seq.get_last_traceback()),
+                            traceback: None,
+                            active: true,
+                            warnings: Vec::default(),
+                        });
+                        expected.push(self.expected_duration(p_split));
+                        min_score = min_score.min(align_info.partial);
+
+                        let matched = align_info.matched_part();
+                        seq.push(Some(p_a), Some(p_t), &matched);
+                        score = matched.score;
 
+                        (
+                            p_t.phoneme.clone(),
+                            p_t.word_start,
+                            p_t.weight,
+                            half_time,
+                            p_a.end,
+                            true,
+                        )
+                    }
                 },
                 None => return Err(format!("found alignment op without op at {}!", i)),
             };
@@ -601,47 +1134,106 @@ impl SequenceMatcher {
                 active,
                 warnings: Vec::default(),
             });
+            let expected_text_duration = if active {
+                self.expected_duration(&text[t - 1])
+            } else {
+                0
+            };
+            expected.push(expected_text_duration);
             // required for suggesting manual adjustments below a threshold
             min_score = min_score.min(score);
         }
 
+        Self::distribute_shared_slots(audio, &mut result, &expected);
+
         debug!("> Aligned sequences:{}", seq);
 
         Ok((result, min_score, has_gaps, seq))
     }
     // ------------------------------------------------------------------------
-    pub fn phoneme_pairing_alternatives(&self) -> &HashMap<String, String> {
-        &self.mapping
+    /// expected (relative) duration of a text phoneme: typical duration of its
+    /// phoneme class scaled by its translated length. the translator derives
+    /// the timings from eSpeak's length markers (e.g. long and shortened
+    /// phonemes).
+    #[inline]
+    fn expected_duration(&self, phoneme: &PhonemeSegment) -> u32 {
+        let length = phoneme.end.saturating_sub(phoneme.start).max(1);
+        self.similarity.durations().of(&phoneme.phoneme) * length
     }
     // ------------------------------------------------------------------------
-    pub fn calculate_matching(
+    /// text phonemes sharing an audio phoneme (splits) get a part of the shared
+    /// timing slot proportional to their expected durations. the shared slot
+    /// includes all audio phonemes merged into these text phonemes.
+    fn distribute_shared_slots(
+        audio: &[PhonemeSegment],
+        segments: &mut [PhonemeSegment],
+        expected: &[u32],
+    ) {
+        // split points are the only segment boundaries within audio phonemes
+        let split_points = segments
+            .iter()
+            .map(|s| {
+                let i = audio.partition_point(|p_a| p_a.end <= s.start);
+                matches!(audio.get(i), Some(p_a) if p_a.start < s.start)
+            })
+            .collect::<Vec<_>>();
+
+        let mut first = 0;
+        for i in 1..=segments.len() {
+            let shared = i < segments.len()
+                && segments[i - 1].active
+                && segments[i].active
+                && expected[i - 1] > 0
+                && expected[i] > 0
+                && segments[i - 1].end == segments[i].start
+                && split_points[i];
+
+            if !shared {
+                if i - first > 1 {
+                    distribute_slot(&mut segments[first..i], &expected[first..i]);
+                }
+                first = i;
+            }
+        }
+    }
+    // ------------------------------------------------------------------------
+    fn match_sequences(
         &self,
-        lineid: u32,
         audio: &PhonemeResult,
         text: &PhonemeResult,
-    ) -> Result<PhonemeResult, String> {
+    ) -> Result<Matching, String> {
         // "optimal" global alignment based on Needleman-Wunsch
         // https://en.wikipedia.org/wiki/Needleman%E2%80%93Wunsch_algorithm
-        let score_matrix = self.calculate_score_matrix(&audio.phonemes, &text.phonemes);
-        let ops = match self.calculate_alignment(score_matrix) {
-            Ok(ops) => ops,
-            Err(why) => return Err(why),
-        };
+        let score_matrix = self.calculate_score_matrix(&audio.phonemes, &text.phonemes)?;
+        let total = score_matrix.total();
 
-        let (phonemes, min_score, has_gaps, _) =
-            match self.apply_alignment(&audio.phonemes, &ops, &text.phonemes) {
-                Ok(result) => result,
-                Err(why) => return Err(why),
-            };
+        let ops = self.calculate_alignment(score_matrix)?;
+        let path_length = ops.len();
+
+        let (mut phonemes, min_score, has_gaps, _) =
+            self.apply_alignment(&audio.phonemes, &ops, &text.phonemes)?;
 
-        if has_gaps {
+        // final score reflects phoneme similarity and how well the audio matched
+        Self::apply_acoustic_confidence(&audio.phonemes, &mut phonemes);
+
+        Ok(Matching {
+            phonemes,
+            min_score,
+            has_gaps,
+            total,
+            path_length,
+        })
+    }
+    // ------------------------------------------------------------------------
+    fn check_matching(lineid: u32, matching: &Matching) {
+        if matching.has_gaps {
             warn!(
                 "id {lineid:010}: automatic sequence alignment failed and created matching \
                  with gaps! manual adjustment in phoneme file is highly \
                  recommended!"
             );
         }
-        if min_score < WARN_MATCHING_SCORE_MIN {
+        if matching.min_score < WARN_MATCHING_SCORE_MIN {
             warn!(
                 "id {lineid:010}: automatic sequence alignment matched some phonemes with \
                  scores below {}. manual adjustment in phoneme file is highly \
@@ -649,13 +1241,373 @@ impl SequenceMatcher {
                 WARN_MATCHING_SCORE_MIN
             );
         }
-
-        Ok(PhonemeResult {
-            hypothesis: text.hypothesis.clone(),
-            phonemes,
-        })
     }
     // ------------------------------------------------------------------------
+    /// scales positive similarity scores of matched segments with the acoustic
+    /// confidence of the overlapped audio segments (weighted by duration).
+    /// gap penalties are kept as they are.
+    fn apply_acoustic_confidence(audio: &[PhonemeSegment], matched: &mut [PhonemeSegment]) {
+        for segment in matched
+            .iter_mut()
+            .filter(|s| s.active && s.end > s.start && s.score > 0.0)
+        {
+            let (weighted, duration) = audio
+                .iter()
+                .map(|p_a| {
+                    let overlap = p_a
+                        .end
+                        .min(segment.end)
+                        .saturating_sub(p_a.start.max(segment.start));
+                    (p_a.score * overlap as f32, overlap)
+                })
+                .fold((0.0, 0), |(w, d), (score, overlap)| {
+                    (w + score, d + overlap)
+                });
+
+            if duration > 0 {
+                segment.score *= weighted / duration as f32;
+            }
+        }
+    }
+    // ------------------------------------------------------------------------
+    pub fn phoneme_pairing_alternatives(&self) -> &HashMap<String, String> {
+        &self.mapping
+    }
+    // ------------------------------------------------------------------------
+    /// average alignment score per phoneme of audio and text phonemes (ignoring
+    /// silence and noise in audio). fails if sequences are empty or too long
+    /// for an alignment.
+    pub fn calculate_similarity(
+        &self,
+        audio: &PhonemeResult,
+        text: &PhonemeResult,
+    ) -> Result<f32, String> {
+        let audio = Self::without_silence(audio);
+
+        let len = audio.len().max(text.phonemes.len());
+        if audio.is_empty() || text.phonemes.is_empty() {
+            return Err(String::from("no audio or text phonemes to compare."));
+        }
+
+        let score_matrix = self.calculate_score_matrix(&audio, &text.phonemes)?;
+        let total = score_matrix.total();
+
+        Ok(total / len as f32)
+    }
+    // ------------------------------------------------------------------------
+    /// (audio, text) phoneme pairs of the optimal alignment (matches, merges
+    /// and splits) ignoring silence and noise in audio. None if sequences are
+    /// empty.
+    pub fn calculate_phoneme_pairs(
+        &self,
+        audio: &PhonemeResult,
+        text: &PhonemeResult,
+    ) -> Option<Vec<(String, String)>> {
+        let audio = Self::without_silence(audio);
+        let text = &text.phonemes;
+
+        if audio.is_empty() || text.is_empty() {
+            return None;
+        }
+
+        let score_matrix = self.calculate_score_matrix(&audio, text).ok()?;
+        let ops = self.calculate_alignment(score_matrix).ok()?;
+
+        let mut pairs = Vec::new();
+        let mut a = 0;
+        let mut t = 0;
+        for op in ops.iter().filter_map(|score| score.op) {
+            let matched: &[(usize, usize)] = match op {
+                AlignmentOperation::Match => {
+                    a += 1;
+                    t += 1;
+                    &[(a - 1, t - 1)]
+                }
+                AlignmentOperation::MergeLeft => {
+                    a += 1;
+                    &[(a - 1, t - 1)]
+                }
+                AlignmentOperation::MergeRight => {
+                    a += 2;
+                    t += 1;
+                    &[(a - 2, t - 1), (a - 1, t - 1)]
+                }
+                AlignmentOperation::SplitLeft => {
+                    t += 1;
+                    &[(a - 1, t - 1)]
+                }
+                AlignmentOperation::SplitRight => {
+                    a += 1;
+                    t += 2;
+                    &[(a - 1, t - 2), (a - 1, t - 1)]
+                }
+                AlignmentOperation::Delete => {
+                    a += 1;
+                    continue;
+                }
+                AlignmentOperation::Insert => {
+                    t += 1;
+                    continue;
+                }
+            };
+            pairs.extend(
+                matched
+                    .iter()
+                    .map(|(a, t)| (audio[*a].phoneme.clone(), text[*t].phoneme.clone())),
+            );
+        }
+        Some(pairs)
+    }
+    // ------------------------------------------------------------------------
+    pub fn similarity(&self) -> &SimilarityMatrix {
+        &self.similarity
+    }
+    // ------------------------------------------------------------------------
+    /// recognized audio phonemes without silence and noise
+    pub fn without_silence(audio: &PhonemeResult) -> Vec<PhonemeSegment> {
+        audio
+            .phonemes
+            .iter()
+            .filter(|p| !matches!(p.phoneme.to_lowercase().as_str(), "sil" | "+nsn+" | "+spn+"))
+            .cloned()
+            .collect()
+    }
+    // ------------------------------------------------------------------------
+    pub fn calculate_matching(
+        &self,
+        lineid: u32,
+        audio: &PhonemeResult,
+        text: &PhonemeResult,
+    ) -> Result<PhonemeResult, String> {
+        let matching = self.match_sequences(audio, text)?;
+
+        Self::check_matching(lineid, &matching);
+
+        Ok(PhonemeResult {
+            hypothesis: text.hypothesis.clone(),
+            phonemes: matching.phonemes,
+        })
+    }
+    // ------------------------------------------------------------------------
+    /// matching without checks and warnings about its quality (e.g. for
+    /// evaluations of many alignments)
+    pub fn calculate_matching_unchecked(
+        &self,
+        audio: &PhonemeResult,
+        text: &PhonemeResult,
+    ) -> Result<PhonemeResult, String> {
+        let matching = self.match_sequences(audio, text)?;
+
+        Ok(PhonemeResult {
+            hypothesis: text.hypothesis.clone(),
+            phonemes: matching.phonemes,
+        })
+    }
+    // ------------------------------------------------------------------------
+    /// matches every audio phoneme candidate (e.g. n-best hypotheses) with the
+    /// text phonemes. returns the index of the candidate with the highest
+    /// alignment score per alignment operation, its matching and the scores of
+    /// all candidates (None if the candidate could not be aligned). fails only
+    /// if no candidate could be aligned.
+    pub fn calculate_best_matching(
+        &self,
+        lineid: u32,
+        candidates: &[PhonemeResult],
+        text: &PhonemeResult,
+    ) -> Result<(usize, PhonemeResult, Vec<Option<f32>>), String> {
+        let mut best: Option<(usize, Matching, f32)> = None;
+        let mut scores = Vec::with_capacity(candidates.len());
+        let mut last_error = None;
+
+        for (i, audio) in candidates.iter().enumerate() {
+            let matching = match self.match_sequences(audio, text) {
+                Ok(matching) => matching,
+                Err(why) => {
+                    warn!("id {lineid:010}: > {why} skipping audio phoneme candidate #{i}...");
+                    scores.push(None);
+                    last_error = Some(why);
+                    continue;
+                }
+            };
+            // candidates differ in the number of audio phonemes: longer paths
+            // accumulate more (merge) scores without being a better alignment
+            let score = matching.total / matching.path_length.max(1) as f32;
+            scores.push(Some(score));
+
+            let is_better = match best {
+                Some((_, _, best_score)) => score > best_score,
+                None => true,
+            };
+            if is_better {
+                best = Some((i, matching, score));
+            }
+        }
+
+        let (best, matching, _) = best.ok_or_else(|| {
+            last_error.unwrap_or_else(|| String::from("no audio phoneme candidates to match."))
+        })?;
+
+        Self::check_matching(lineid, &matching);
+
+        Ok((
+            best,
+            PhonemeResult {
+                hypothesis: text.hypothesis.clone(),
+                phonemes: matching.phonemes,
+            },
+            scores,
+        ))
+    }
+    // ------------------------------------------------------------------------
+    /// score matrix and best path of the alignment of audio and text phonemes
+    pub fn calculate_alignment_matrix(
+        &self,
+        audio: &PhonemeResult,
+        text: &PhonemeResult,
+    ) -> Result<AlignmentMatrix, String> {
+        let audio = &audio.phonemes;
+        let text = &text.phonemes;
+
+        let len = audio.len().max(text.len());
+        if audio.is_empty() || text.is_empty() || len >= MAX_MATRIX_SIZE {
+            return Err(format!(
+                "alignment matrix requires 1 to {} audio and text phonemes.",
+                MAX_MATRIX_SIZE - 1
+            ));
+        }
+
+        // full matrix: all cells are inspectable
+        let band = vec![(0, audio.len()); text.len() + 1];
+        let scores = self.calculate_banded_score_matrix(audio, text, band, true);
+        let path = Self::calculate_traceback(&scores)?
+            .into_iter()
+            .map(|(cell, _)| cell)
+            .collect();
+
+        Ok(AlignmentMatrix {
+            audio: audio.clone(),
+            text: text.clone(),
+            path,
+            scores,
+        })
+    }
+    // ------------------------------------------------------------------------
+    /// recalculates the alignment matrix of a stored track. audio phonemes are
+    /// taken from the matching info, text phonemes are the (possibly edited)
+    /// phonemes of the track.
+    pub fn calculate_track_alignment_matrix(
+        &self,
+        track: &PhonemeTrack<PhonemeSegment>,
+    ) -> Result<AlignmentMatrix, String> {
+        let audio = Self::matched_audio_phonemes(track);
+        if audio.is_empty() {
+            return Err(String::from("no matching info stored."));
+        }
+
+        let text = track
+            .phonemes()
+            .iter()
+            .filter(|s| s.phoneme != "_")
+            .cloned()
+            .collect();
+
+        self.calculate_alignment_matrix(
+            &PhonemeResult {
+                hypothesis: None,
+                phonemes: audio,
+            },
+            &PhonemeResult {
+                hypothesis: None,
+                phonemes: text,
+            },
+        )
+    }
+    // ------------------------------------------------------------------------
+    /// recognized audio phonemes with timings from the matching info of all
+    /// segments (silence is not included)
+    pub fn matched_audio_phonemes(track: &PhonemeTrack<PhonemeSegment>) -> Vec<PhonemeSegment> {
+        let mut audio = Vec::new();
+        for segment in track.phonemes() {
+            let Some(info) = segment.matching_info.as_ref() else {
+                continue;
+            };
+            for timing in REGEXP_AUDIO_TIMING.captures_iter(info) {
+                audio.push(PhonemeSegment {
+                    phoneme: timing[1].to_owned(),
+                    start: timing[2].parse::<u32>().unwrap_or_default(),
+                    end: timing[3].parse::<u32>().unwrap_or_default(),
+                    weight: 1.0,
+                    score: 1.0,
+                    active: true,
+                    ..Default::default()
+                });
+            }
+        }
+        // merges and splits refer to the same audio phoneme more than once
+        audio.sort_by_key(|p| (p.start, p.end));
+        audio.dedup_by(|a, b| a.start == b.start && a.end == b.end && a.phoneme == b.phoneme);
+        audio
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+impl AlignmentMatrix {
+    // ------------------------------------------------------------------------
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.scores.width()
+    }
+    // ------------------------------------------------------------------------
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.scores.height()
+    }
+    // ------------------------------------------------------------------------
+    /// accumulated score of the best operation of the cell. None if no
+    /// operation is possible.
+    pub fn total(&self, a: usize, t: usize) -> Option<f32> {
+        let total = self.scores[(a, t)].total;
+        if total > f32::MIN {
+            Some(total)
+        } else {
+            None
+        }
+    }
+    // ------------------------------------------------------------------------
+    /// best operation of the cell
+    pub fn operation(&self, a: usize, t: usize) -> Option<AlignmentOperation> {
+        self.scores[(a, t)].op
+    }
+    // ------------------------------------------------------------------------
+    /// scores of all operations which were evaluated for the cell
+    pub fn operation_scores(&self, a: usize, t: usize) -> Vec<(AlignmentOperation, f32)> {
+        match self.scores.op_scores {
+            Some(ref op_scores) => AlignmentOperation::ALL
+                .iter()
+                .zip(op_scores[(a, t)].iter())
+                .filter(|(_, score)| **score > f32::MIN)
+                .map(|(op, score)| (*op, *score))
+                .collect(),
+            None => Vec::default(),
+        }
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+/// splits the timing slot spanned by all segments proportional to the expected
+/// durations
+fn distribute_slot(segments: &mut [PhonemeSegment], expected: &[u32]) {
+    let start = segments[0].start;
+    let slot = u64::from(segments[segments.len() - 1].end - start);
+    let total: u64 = expected.iter().map(|&d| u64::from(d)).sum();
+
+    let mut elapsed = 0;
+    for i in 1..segments.len() {
+        elapsed += u64::from(expected[i - 1]);
+        let split_point = start + (slot * elapsed / total) as u32;
+        segments[i - 1].end = split_point;
+        segments[i].start = split_point;
+    }
 }
 // ----------------------------------------------------------------------------
 // ----------------------------------------------------------------------------
@@ -669,10 +1621,10 @@ impl fmt::Display for AlignmentOperation {
                 AlignmentOperation::Match => "~",
                 AlignmentOperation::Delete => "d",
                 AlignmentOperation::MergeLeft => ">",
-                //AlignmentOperation::MergeRight => "<",
+                AlignmentOperation::MergeRight => "<",
                 AlignmentOperation::Insert => "i",
                 AlignmentOperation::SplitLeft => "\\",
-                //AlignmentOperation::SplitRight => "/",
+                AlignmentOperation::SplitRight => "/",
             }
         )
     }
@@ -681,27 +1633,14 @@ impl fmt::Display for AlignmentOperation {
 // ----------------------------------------------------------------------------
 #[cfg(test)]
 #[allow(unused_must_use)]
-fn fmt_debug_opscore(f: &mut fmt::Formatter, op: AlignmentOperation, score: f32) {
-    if score > f32::MIN {
-        write!(f, " {}:{:4.1} ", op, score);
-    } else {
-        write!(f, " {}:     ", op);
-    }
-}
-#[cfg(test)]
-#[allow(unused_must_use)]
 impl fmt::Debug for Score {
     // ------------------------------------------------------------------------
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // all scores
-        fmt_debug_opscore(f, AlignmentOperation::Match, self._debug[0]);
-        fmt_debug_opscore(f, AlignmentOperation::Delete, self._debug[1]);
-        fmt_debug_opscore(f, AlignmentOperation::MergeLeft, self._debug[2]);
-        //fmt_debug_opscore(f, AlignmentOperation::MergeRight, self._debug[3]);
-        fmt_debug_opscore(f, AlignmentOperation::Insert, self._debug[4]);
-        fmt_debug_opscore(f, AlignmentOperation::SplitLeft, self._debug[5]);
-        //fmt_debug_opscore(f, AlignmentOperation::SplitRight, self._debug[6]);
-
+        if self.score > f32::MIN {
+            write!(f, " {:4.1} ", self.score);
+        } else {
+            write!(f, "      ");
+        }
         write!(f, " {:?}", self.op);
         if self.total > f32::MIN {
             write!(f, " total: {:4.1}", self.total);
@@ -743,6 +1682,8 @@ impl fmt::Display for PhonemeSegment {
 #[cfg(test)]
 mod tests {
     use super::super::matrix::DebugMatrix2D;
+    use super::super::phonemes::{PhonemeTrack, WORD_CONFIDENCE_ERROR, WORD_CONFIDENCE_WARN};
+    use super::super::scoring_profile::ScoringProfile;
     use super::super::similarity_matrix::SimilarityMatrix;
     use super::{PhonemeResult as PSeq, PhonemeSegment as P};
 
@@ -772,6 +1713,16 @@ mod tests {
             }
         }
 
+        /// audio phonemes: | marks a preceding silence but no word start
+        /// (only available from word level recognition)
+        fn from_audio_str(phonemes: &str) -> PSeq {
+            let mut pseq = PSeq::from_str(phonemes);
+            for p in &mut pseq.phonemes {
+                p.word_start = false;
+            }
+            pseq
+        }
+
         fn from_array(mut phonemes: Vec<(&str, u32, u32)>) -> PSeq {
             PSeq {
                 hypothesis: None,
@@ -871,7 +1822,7 @@ mod tests {
         let a = PSeq::from_str("a;d");
         let t = PSeq::from_str("a;d");
         let alignment = matcher
-            .calculate_alignment(matcher.calculate_score_matrix(&a.phonemes, &t.phonemes))
+            .calculate_alignment(matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap())
             .unwrap();
 
         let result = generate_result_matching(&matcher, &a, &alignment, &t, true);
@@ -899,7 +1850,7 @@ mod tests {
         let a = PSeq::from_str("a;d");
         let t = PSeq::from_str("x;a;d;e");
 
-        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes);
+        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap();
         println!(
             "> ScoreMatrix: {}",
             DebugMatrix2D::new(&matrix, &a.phonemes, &t.phonemes)
@@ -930,7 +1881,7 @@ mod tests {
 
         let a = PSeq::from_str("a;d");
         let t = PSeq::from_str("a;e;d");
-        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes);
+        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap();
         println!(
             "> ScoreMatrix: {}",
             DebugMatrix2D::new(&matrix, &a.phonemes, &t.phonemes)
@@ -957,7 +1908,7 @@ mod tests {
 
         let a = PSeq::from_str("a;d");
         let t = PSeq::from_str("a;e;b;c;d");
-        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes);
+        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap();
         println!(
             "> ScoreMatrix: {}",
             DebugMatrix2D::new(&matrix, &a.phonemes, &t.phonemes)
@@ -1001,7 +1952,7 @@ mod tests {
 
         let a = PSeq::from_str("x;a;d;t");
         let t = PSeq::from_str("a;d");
-        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes);
+        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap();
         //println!("> ScoreMatrix: {}", DebugMatrix2D::new(&matrix, &a.phonemes, &t.phonemes));
         let alignment = matcher.calculate_alignment(matrix).unwrap();
 
@@ -1017,7 +1968,7 @@ mod tests {
         // NO MergeLeft as similarity x != a
         let a = PSeq::from_str("a;x;d");
         let t = PSeq::from_str("a;d");
-        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes);
+        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap();
         //println!("> ScoreMatrix: {}", DebugMatrix2D::new(&matrix, &a.phonemes, &t.phonemes));
         let alignment = matcher.calculate_alignment(matrix).unwrap();
 
@@ -1033,7 +1984,7 @@ mod tests {
         // MergeLeft as similarity e ~ a
         let a = PSeq::from_str("a;e;d");
         let t = PSeq::from_str("a;d");
-        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes);
+        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap();
         //println!("> ScoreMatrix: {}", DebugMatrix2D::new(&matrix, &a.phonemes, &t.phonemes));
         let alignment = matcher.calculate_alignment(matrix).unwrap();
 
@@ -1043,7 +1994,7 @@ mod tests {
         // prefer Match, MergeLeft over Match, Delete
         let a = PSeq::from_str("a;e;a;c;d");
         let t = PSeq::from_str("a;d");
-        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes);
+        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap();
         //println!("> ScoreMatrix: {}", DebugMatrix2D::new(&matrix, &a.phonemes, &t.phonemes));
         let alignment = matcher.calculate_alignment(matrix).unwrap();
 
@@ -1077,7 +2028,7 @@ mod tests {
         // split over words without audio gap
         let a = PSeq::from_str("A;B;C;E;F");
         let t = PSeq::from_str("a;b;c;|c;e;f");
-        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes);
+        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap();
         //println!("> ScoreMatrix: {}", DebugMatrix2D::new(&matrix, &a.phonemes, &t.phonemes));
         let alignment = matcher.calculate_alignment(matrix).unwrap();
 
@@ -1095,9 +2046,9 @@ mod tests {
 
 
         // matched gap + word start
-        let a = PSeq::from_str("A;B;C;|C;E;F");
+        let a = PSeq::from_audio_str("A;B;C;|C;E;F");
         let t = PSeq::from_str("a;b;c;|c;e;f");
-        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes);
+        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap();
         //println!("> ScoreMatrix: {}", DebugMatrix2D::new(&matrix, &a.phonemes, &t.phonemes));
         let alignment = matcher.calculate_alignment(matrix).unwrap();
 
@@ -1126,9 +2077,9 @@ mod tests {
 
         let matcher = SequenceMatcher::new(sim_matrix);
         // prevent audio gap within word on last phoneme
-        let a = PSeq::from_str("A;B;|C;E;F");
+        let a = PSeq::from_audio_str("A;B;|C;E;F");
         let t = PSeq::from_str("a;b;c;|c;e;f");
-        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes);
+        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap();
         println!("> ScoreMatrix: {}", DebugMatrix2D::new(&matrix, &a.phonemes, &t.phonemes));
         let alignment = matcher.calculate_alignment(matrix).unwrap();
 
@@ -1157,9 +2108,9 @@ mod tests {
 
         let matcher = SequenceMatcher::new(sim_matrix);
 
-        let a = PSeq::from_str("A;B;C;|E;F");
+        let a = PSeq::from_audio_str("A;B;C;|E;F");
         let t = PSeq::from_str("a;b;c;|c;e;f");
-        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes);
+        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap();
         println!("> ScoreMatrix: {}", DebugMatrix2D::new(&matrix, &a.phonemes, &t.phonemes));
         let alignment = matcher.calculate_alignment(matrix).unwrap();
 
@@ -1189,9 +2140,9 @@ mod tests {
 
         let matcher = SequenceMatcher::new(sim_matrix);
 
-        let a = PSeq::from_str("A;B;C;|B;E;F");
+        let a = PSeq::from_audio_str("A;B;C;|B;E;F");
         let t = PSeq::from_str("a;b;c;|e;f");
-        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes);
+        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap();
         println!("> ScoreMatrix: {}", DebugMatrix2D::new(&matrix, &a.phonemes, &t.phonemes));
         let alignment = matcher.calculate_alignment(matrix).unwrap();
 
@@ -1208,6 +2159,249 @@ mod tests {
         assert_eq!(scores_to_ops(alignment), [Match, Match, Match, Delete, Match, Match]);
     }
 
+    #[test]
+    fn test_alignment_merge_right() {
+        let mut sim_matrix = SimilarityMatrix::init_from_str("A;B;C;E", "a;b;c;e", -1.0, 1.0);
+        sim_matrix.set_score("E", "a", -0.5).set_score("E", "b", 0.5);
+
+        let matcher = SequenceMatcher::new(sim_matrix);
+
+        // word start in audio begins with a similar phoneme
+        let a = PSeq::from_audio_str("A;B;|E;B");
+        let t = PSeq::from_str("a;|b");
+        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap();
+        println!("> ScoreMatrix: {}", DebugMatrix2D::new(&matrix, &a.phonemes, &t.phonemes));
+        let alignment = matcher.calculate_alignment(matrix).unwrap();
+
+        let result = generate_result_matching(&matcher, &a, &alignment, &t, true);
+
+        assert_eq!(result, vec![
+            "a  ~  a  [   50 -  100] | 1.00",
+            "_  d  b  [  100 -  150] | -1.00",
+            "b  <~ e  [  350 -  400] + b  [  400 -  450] | 0.75",
+        ], "merge right at word start");
+        assert_eq!(scores_to_ops(alignment), [Match, Delete, MergeRight]);
+
+        // merge into previous and next text phoneme
+        let a = PSeq::from_str("A;E;E;B;C");
+        let t = PSeq::from_str("a;b;c");
+        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap();
+        println!("> ScoreMatrix: {}", DebugMatrix2D::new(&matrix, &a.phonemes, &t.phonemes));
+        let alignment = matcher.calculate_alignment(matrix).unwrap();
+
+        let result = generate_result_matching(&matcher, &a, &alignment, &t, true);
+
+        assert_eq!(result, vec![
+            "a  ~> a  [   50 -  100] + e  [  100 -  150] | 0.25",
+            "b  <~ e  [  150 -  200] + b  [  200 -  250] | 0.75",
+            "c  ~  c  [  250 -  300] | 1.00",
+        ], "merge into previous and next text phoneme");
+        assert_eq!(scores_to_ops(alignment), [Match, MergeLeft, MergeRight, Match]);
+    }
+
+    #[test]
+    fn test_alignment_split_right_at_word_start() {
+        let mut sim_matrix = SimilarityMatrix::init_from_str("A;B;C;E", "a;b;c;e", -1.0, 1.0);
+        sim_matrix.set_score("C", "b", 0.5);
+
+        let matcher = SequenceMatcher::new(sim_matrix);
+
+        // (a preceding audio gap would penalize the match of c within the word)
+        let a = PSeq::from_audio_str("A;C;|E");
+        let t = PSeq::from_str("|a;|b;c;|e");
+        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap();
+        println!("> ScoreMatrix: {}", DebugMatrix2D::new(&matrix, &a.phonemes, &t.phonemes));
+        let alignment = matcher.calculate_alignment(matrix).unwrap();
+
+        let result = generate_result_matching(&matcher, &a, &alignment, &t, true);
+
+        assert_eq!(result, vec![
+            "a  ~  a  [   50 -  100] | 1.00",
+            "b  /  c  [  100 -  150] | 0.49",
+            "c  ~  c  [  100 -  150] | 1.00",
+            "e  ~  e  [  350 -  400] | 1.00",
+        ], "split right at word start");
+        assert_eq!(scores_to_ops(alignment), [Match, SplitRight, Match]);
+    }
+
+    #[test]
+    fn test_alignment_split_right_matched_part_after_audio_gap() {
+        let mut sim_matrix = SimilarityMatrix::init_from_str("A;B;C;E", "a;b;c;e", -1.0, 1.0);
+        sim_matrix.set_score("C", "b", 0.5);
+
+        let matcher = SequenceMatcher::new(sim_matrix);
+
+        // matched part c is within the word but follows an audio gap
+        let a = PSeq::from_audio_str("A;|C;|E");
+        let t = PSeq::from_str("|a;|b;c;|e");
+        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap();
+        println!("> ScoreMatrix: {}", DebugMatrix2D::new(&matrix, &a.phonemes, &t.phonemes));
+        let alignment = matcher.calculate_alignment(matrix).unwrap();
+
+        let result = generate_result_matching(&matcher, &a, &alignment, &t, true);
+
+        assert_eq!(result, vec![
+            "a  ~  a  [   50 -  100] | 1.00",
+            "b  ~  c  [  300 -  350] | -1.25",
+            "c  \\  c  [  300 -  350] | 0.99",
+            "e  ~  e  [  550 -  600] | 1.00",
+        ], "same audio gap penalty as for a match");
+        assert_eq!(scores_to_ops(alignment), [Match, Match, SplitLeft, Match]);
+    }
+
+    #[test]
+    fn test_alignment_no_merge_right_on_word_gaps() {
+        let mut sim_matrix = SimilarityMatrix::init_from_str("A;B;C;D", "a;b;c;d", -1.0, 1.0);
+        sim_matrix.set_score("B", "c", 0.7);
+
+        let matcher = SequenceMatcher::new(sim_matrix);
+
+        let a = PSeq::from_audio_str("A;B;|C;D");
+        let t = PSeq::from_str("|a;|c;d");
+        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap();
+        println!("> ScoreMatrix: {}", DebugMatrix2D::new(&matrix, &a.phonemes, &t.phonemes));
+        let alignment = matcher.calculate_alignment(matrix).unwrap();
+
+        let result = generate_result_matching(&matcher, &a, &alignment, &t, true);
+
+        assert_eq!(result, vec![
+            "a  ~  a  [   50 -  100] | 1.00",
+            "_  d  b  [  100 -  150] | -1.00",
+            "c  ~  c  [  350 -  400] | 1.00",
+            "d  ~  d  [  400 -  450] | 1.00",
+        ], "no merge right on word gaps");
+        assert_eq!(scores_to_ops(alignment), [Match, Delete, Match, Match]);
+    }
+
+    #[test]
+    fn test_alignment_splits_proportional_to_expected_durations() {
+        let mut sim_matrix = SimilarityMatrix::init_from_str("A;B;C", "a;b;c", -1.0, 1.0);
+        sim_matrix.set_score("A", "b", 0.5);
+
+        let matcher = SequenceMatcher::new(sim_matrix);
+
+        // long text phoneme followed by a shortened one
+        let a = PSeq::from_array(vec![("A", 0, 300), ("C", 300, 400)]);
+        let t = PSeq::from_array(vec![("a", 0, 100), ("b", 100, 125), ("c", 125, 225)]);
+        let alignment = matcher
+            .calculate_alignment(matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap())
+            .unwrap();
+        let (result, _, _, _) = matcher
+            .apply_alignment(&a.phonemes, &alignment, &t.phonemes)
+            .unwrap();
+
+        assert_eq!(
+            result.iter().map(|p| (p.phoneme.as_str(), p.start, p.end)).collect::<Vec<_>>(),
+            vec![("a", 0, 246), ("b", 246, 300), ("c", 300, 400)],
+            "split left"
+        );
+        assert_eq!(scores_to_ops(alignment), [Match, SplitLeft, Match]);
+
+        // diphthong next to a short vowel with the same translated length
+        let mut sim_matrix = SimilarityMatrix::init_from_str("AY;K", "aɪ;ə;k", -1.0, 1.0);
+        sim_matrix.set_score("AY", "aɪ", 1.0);
+        sim_matrix.set_score("AY", "ə", 0.5);
+
+        let matcher = SequenceMatcher::new(sim_matrix);
+
+        let a = PSeq::from_array(vec![("AY", 0, 230), ("K", 230, 300)]);
+        let t = PSeq::from_array(vec![("aɪ", 0, 100), ("ə", 100, 200), ("k", 200, 300)]);
+        let alignment = matcher
+            .calculate_alignment(matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap())
+            .unwrap();
+        let (result, _, _, _) = matcher
+            .apply_alignment(&a.phonemes, &alignment, &t.phonemes)
+            .unwrap();
+
+        assert_eq!(
+            result.iter().map(|p| (p.phoneme.as_str(), p.start, p.end)).collect::<Vec<_>>(),
+            vec![("aɪ", 0, 150), ("ə", 150, 230), ("k", 230, 300)],
+            "diphthong split"
+        );
+        assert_eq!(scores_to_ops(alignment), [Match, SplitLeft, Match]);
+
+        // shared slot includes merged audio phonemes
+        let a = PSeq::from_array(vec![("A", 0, 100), ("C", 100, 200), ("B", 200, 300)]);
+        let mut result = PSeq::from_array(vec![("a", 0, 100), ("b", 100, 150), ("c", 150, 300)]);
+        for p in result.phonemes.iter_mut() {
+            p.active = true;
+        }
+        SequenceMatcher::distribute_shared_slots(&a.phonemes, &mut result.phonemes, &[100, 100, 50]);
+        assert_eq!(
+            result.phonemes.iter().map(|p| (p.phoneme.as_str(), p.start, p.end)).collect::<Vec<_>>(),
+            vec![("a", 0, 100), ("b", 100, 233), ("c", 233, 300)],
+            "split and merge"
+        );
+    }
+
+    #[test]
+    fn test_alignment_affine_gaps() {
+        let mut sim_matrix = SimilarityMatrix::init_from_str("A;B;C;D;X;Y;Z", "a;b;c;d;x;y;z", -1.5, 1.0);
+        let mut profile = ScoringProfile::with_gap_penalty(-1.0);
+        sim_matrix.set_profile(profile.clone());
+
+        let a = PSeq::from_str("A;X;Y;Z;B");
+        let t = PSeq::from_str("a;c;d;b");
+
+        // constant gap penalty: mismatches are cheaper than gaps
+        let matcher = SequenceMatcher::new(sim_matrix);
+        let alignment = matcher
+            .calculate_alignment(matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap())
+            .unwrap();
+        assert_eq!(scores_to_ops(alignment), [Match, Delete, Match, Match, Match]);
+
+        // cheap gap extension: one long gap instead of mismatches
+        let mut sim_matrix = SimilarityMatrix::init_from_str("A;B;C;D;X;Y;Z", "a;b;c;d;x;y;z", -1.5, 1.0);
+        profile.gap_extend = -0.2;
+        sim_matrix.set_profile(profile);
+
+        let matcher = SequenceMatcher::new(sim_matrix);
+        let alignment = matcher
+            .calculate_alignment(matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap())
+            .unwrap();
+
+        let result = generate_result_matching(&matcher, &a, &alignment, &t, true);
+
+        assert_eq!(result, vec![
+            "a  ~  a  [   50 -  100] | 1.00",
+            "_  d  x  [  100 -  150] | -1.00",
+            "_  d  y  [  150 -  200] | -0.20",
+            "_  d  z  [  200 -  250] | -0.20",
+            "c  i | -1.00",
+            "d  i | -0.20",
+            "b  ~  b  [  250 -  300] | 1.00",
+        ]);
+        assert_eq!(scores_to_ops(alignment), [Match, Delete, Delete, Delete, Insert, Insert, Match]);
+    }
+
+    #[test]
+    fn test_alignment_affine_gaps_extend_worse_path() {
+        let mut sim_matrix = SimilarityMatrix::init_from_str("A;B;W;X;Y;Z", "a;b", -1.5, 1.0);
+        sim_matrix.set_profile(ScoringProfile {
+            gap_open: -3.0,
+            gap_extend: -0.1,
+            word_boundary_gap_open: -3.0,
+            ..ScoringProfile::with_gap_penalty(-3.0)
+        });
+        let matcher = SequenceMatcher::new(sim_matrix);
+
+        let a = PSeq::from_str("A;W;X;Y;Z;B");
+        let t = PSeq::from_str("a;b");
+
+        // after the first audio phoneme the merge into the matched phoneme is
+        // better than opening a gap but the single long gap is the best path
+        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap();
+        assert_eq!(matrix[(2, 1)].op, Some(MergeLeft));
+        assert!((matrix.total() - (2.0 - 3.0 - 3.0 * 0.1)).abs() < 0.001, "{}", matrix.total());
+
+        let alignment = matcher.calculate_alignment(matrix).unwrap();
+        assert_eq!(
+            alignment.iter().map(|score| score.score).collect::<Vec<_>>(),
+            [1.0, -3.0, -0.1, -0.1, -0.1, 1.0]
+        );
+        assert_eq!(scores_to_ops(alignment), [Match, Delete, Delete, Delete, Delete, Match]);
+    }
+
     #[test]
     fn test_alignment_problem() {
         let mut sim_matrix =
@@ -1234,7 +2428,7 @@ mod tests {
             ]
         );
         let t = PSeq::from_str("|a;|b;c;b;c");
-        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes);
+        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap();
         println!("> ScoreMatrix: {}", DebugMatrix2D::new(&matrix, &a.phonemes, &t.phonemes));
         let alignment = matcher.calculate_alignment(matrix).unwrap();
 
@@ -1275,7 +2469,7 @@ mod tests {
             ]
         );
         let t = PSeq::from_str("|a;b;|c;d;x;|e");
-        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes);
+        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap();
         println!("> ScoreMatrix: {}", DebugMatrix2D::new(&matrix, &a.phonemes, &t.phonemes));
         let alignment = matcher.calculate_alignment(matrix).unwrap();
 
@@ -1317,7 +2511,7 @@ mod tests {
             ]
         );
         let t = PSeq::from_str("|a;b;c;|d;x;|e;f");
-        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes);
+        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap();
         println!("> ScoreMatrix: {}", DebugMatrix2D::new(&matrix, &a.phonemes, &t.phonemes));
         let alignment = matcher.calculate_alignment(matrix).unwrap();
 
@@ -1354,7 +2548,7 @@ mod tests {
             ]
         );
         let t = PSeq::from_str("|a;|a");
-        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes);
+        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap();
         println!("> ScoreMatrix: {}", DebugMatrix2D::new(&matrix, &a.phonemes, &t.phonemes));
         let alignment = matcher.calculate_alignment(matrix).unwrap();
 
@@ -1366,4 +2560,316 @@ mod tests {
         ], "do not ignore last single phoneme match");
         assert_eq!(scores_to_ops(alignment), [Delete, Match, Delete, Match, Delete]);
     }
+
+    #[test]
+    fn test_acoustic_confidence_scales_positive_scores() {
+        let mut a = PSeq::from_array(vec![("A", 0, 100), ("B", 100, 200), ("C", 200, 300)]);
+        a.phonemes[0].score = 1.0;
+        a.phonemes[1].score = 0.5;
+        a.phonemes[2].score = 0.0;
+
+        let mut matched = PSeq::from_array(vec![("a", 0, 100), ("b", 100, 200), ("c", 200, 300)]);
+        for (segment, score) in matched.phonemes.iter_mut().zip([2.0, 2.0, -1.0]) {
+            segment.active = true;
+            segment.score = score;
+        }
+        // spans two audio segments with different confidence
+        let mut merged = PSeq::from_array(vec![("ab", 50, 150)]);
+        merged.phonemes[0].active = true;
+        merged.phonemes[0].score = 2.0;
+
+        SequenceMatcher::apply_acoustic_confidence(&a.phonemes, &mut matched.phonemes);
+        SequenceMatcher::apply_acoustic_confidence(&a.phonemes, &mut merged.phonemes);
+
+        let scores = matched.phonemes.iter().map(|p| p.score).collect::<Vec<_>>();
+        assert_eq!(scores, vec![2.0, 1.0, -1.0], "gap penalties are not scaled");
+        assert_eq!(
+            merged.phonemes[0].score, 1.5,
+            "duration weighted confidence"
+        );
+    }
+
+    #[test]
+    fn test_phoneme_pairs_include_merges_and_ignore_silence() {
+        let sim_matrix = SimilarityMatrix::init_from_str("A;B;C", "a;b;c", -1.0, 2.0);
+        let matcher = SequenceMatcher::new(sim_matrix);
+
+        let a = PSeq::from_str("SIL;A;B;B;C;SIL");
+        let t = PSeq::from_str("a;b;c");
+
+        let pairs = matcher.calculate_phoneme_pairs(&a, &t).unwrap();
+        let pairs = pairs
+            .iter()
+            .map(|(a, t)| format!("{a}~{t}"))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            pairs,
+            vec!["a~a", "b~b", "b~b", "c~c"],
+            "merged audio phoneme is paired"
+        );
+    }
+
+    #[test]
+    fn test_similarity_fails_without_phonemes() {
+        let sim_matrix = SimilarityMatrix::init_from_str("a;b;c", "a;b;c", -1.0, 1.0);
+        let matcher = SequenceMatcher::new(sim_matrix);
+
+        let a = PSeq::from_str("SIL;A;B;C;SIL");
+        let t = PSeq::from_str("a;b;c");
+        assert_eq!(matcher.calculate_similarity(&a, &t), Ok(1.0));
+
+        let silence = PSeq::from_str("SIL;+NSN+");
+        assert!(matcher.calculate_similarity(&silence, &t).is_err());
+    }
+
+    #[test]
+    fn test_alignment_matrix_traceback() {
+        let mut sim_matrix =
+            SimilarityMatrix::init_from_str("e;a;b;c;d;x;t", "d;a;b;c;e;x", -1.0, 1.0);
+        sim_matrix.set_score("t", "d", 0.5).set_score("e", "a", 0.5);
+
+        let matcher = SequenceMatcher::new(sim_matrix);
+
+        let a = PSeq::from_str("x;a;d;t");
+        let t = PSeq::from_str("a;d");
+        let matrix = matcher.calculate_alignment_matrix(&a, &t).unwrap();
+
+        assert_eq!((matrix.width(), matrix.height()), (5, 3));
+        assert_eq!(matrix.path, [(1, 0), (2, 1), (3, 2), (4, 2)]);
+
+        let ops = matrix
+            .path
+            .iter()
+            .map(|(a, t)| matrix.operation(*a, *t).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(ops, [Delete, Match, Match, MergeLeft]);
+
+        assert_eq!(matrix.total(0, 0), Some(0.0));
+        assert_eq!(matrix.operation(0, 0), None);
+        assert!(
+            matrix
+                .operation_scores(2, 1)
+                .contains(&(AlignmentOperation::Match, 1.0)),
+            "per operation scores of the cell"
+        );
+    }
+
+    fn repeated_phonemes(phonemes: &[&str], count: usize) -> String {
+        phonemes
+            .iter()
+            .cycle()
+            .take(count)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+
+    #[test]
+    fn test_banded_alignment_long_sequence() {
+        let sim_matrix = SimilarityMatrix::init_from_str("a;b;c;d", "a;b;c;d", -1.0, 1.0);
+        let matcher = SequenceMatcher::new(sim_matrix);
+
+        // too long for a full score matrix
+        let phonemes = repeated_phonemes(&["a", "b", "c", "d"], 800);
+        let a = PSeq::from_str(&phonemes);
+        let t = PSeq::from_str(&phonemes);
+
+        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap();
+        assert!(!matrix.scores.is_full(), "banded score matrix");
+
+        let alignment = matcher.calculate_alignment(matrix).unwrap();
+        assert_eq!(alignment.len(), 800);
+        assert!(alignment.iter().all(|score| score.op == Some(Match)));
+    }
+
+    #[test]
+    fn test_banded_alignment_fallback() {
+        let sim_matrix = SimilarityMatrix::init_from_str("a;b;c;d;x", "a;b;c;d;x", -1.0, 1.0);
+        let matcher = SequenceMatcher::new(sim_matrix);
+
+        // leading noise in audio shifts the best path away from the diagonal
+        // expected from the timings
+        let text = repeated_phonemes(&["a", "b", "c", "d"], 240);
+        let a = PSeq::from_str(&format!("{};{}", repeated_phonemes(&["x"], 60), text));
+        let t = PSeq::from_str(&text);
+
+        let band = SequenceMatcher::timing_band(&a.phonemes, &t.phonemes);
+        let banded = matcher.calculate_banded_score_matrix(&a.phonemes, &t.phonemes, band, false);
+        assert!(!SequenceMatcher::is_path_within_band(&banded));
+
+        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap();
+        assert!(matrix.scores.is_full(), "fallback to full score matrix");
+        assert!(matrix.total() > banded.total());
+
+        let ops = scores_to_ops(matcher.calculate_alignment(matrix).unwrap());
+        assert!(ops[..60].iter().all(|op| *op == Delete));
+        assert!(ops[60..].iter().all(|op| *op == Match));
+    }
+
+    #[test]
+    fn test_banded_alignment_fallback_too_long() {
+        let sim_matrix = SimilarityMatrix::init_from_str("a;b;c;d;x", "a;b;c;d;x", -1.0, 1.0);
+        let matcher = SequenceMatcher::new(sim_matrix);
+
+        // path leaves the band but sequences are too long for a full matrix
+        let text = repeated_phonemes(&["a", "b", "c", "d"], 600);
+        let a = PSeq::from_str(&format!("{};{}", repeated_phonemes(&["x"], 100), text));
+        let t = PSeq::from_str(&text);
+
+        assert!(matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).is_err());
+        assert!(matcher.calculate_matching_unchecked(&a, &t).is_err());
+    }
+
+    #[test]
+    fn test_best_matching_prefers_alternative() {
+        let sim_matrix = SimilarityMatrix::init_from_str("a;b;c;d;x", "a;b;c;d;x", -1.0, 1.0);
+        let matcher = SequenceMatcher::new(sim_matrix);
+
+        let t = PSeq::from_str("a;b;c;d");
+        let candidates = [
+            PSeq::from_str("a;x;c;d"),
+            PSeq::from_str("a;b;c;d"),
+            // higher total by merges (but same score per operation)
+            PSeq::from_str("a;b;b;c;c;d"),
+        ];
+
+        let (best, result, scores) = matcher.calculate_best_matching(1, &candidates, &t).unwrap();
+        assert_eq!(best, 1, "alternative hypothesis");
+        assert_eq!(
+            result.phonemes.iter().map(|p| p.phoneme.as_str()).collect::<Vec<_>>(),
+            ["a", "b", "c", "d"]
+        );
+        assert_eq!(scores[1], Some(1.0));
+    }
+
+    #[test]
+    fn test_best_matching_skips_failing_candidates() {
+        let sim_matrix = SimilarityMatrix::init_from_str("a;b;c;d;x", "a;b;c;d;x", -1.0, 1.0);
+        let matcher = SequenceMatcher::new(sim_matrix);
+
+        // too long for a full alignment after leaving the band
+        let text = repeated_phonemes(&["a", "b", "c", "d"], 600);
+        let failing = PSeq::from_str(&format!("{};{}", repeated_phonemes(&["x"], 100), text));
+        let t = PSeq::from_str(&text);
+
+        let candidates = [failing, PSeq::from_str(&text)];
+        let (best, _, scores) = matcher.calculate_best_matching(1, &candidates, &t).unwrap();
+        assert_eq!(best, 1);
+        assert_eq!(scores[0], None);
+
+        assert!(matcher
+            .calculate_best_matching(1, &candidates[..1], &t)
+            .is_err());
+    }
+
+    #[test]
+    fn test_alignment_empty_audio() {
+        let sim_matrix = SimilarityMatrix::init_from_str("a;b;c;d", "a;b;c;d", -1.0, 1.0);
+        let matcher = SequenceMatcher::new(sim_matrix);
+
+        let a = PSeq { hypothesis: None, phonemes: Vec::new() };
+        let t = PSeq::from_str("a;b");
+
+        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap();
+        assert!(matrix.scores.is_full());
+        // all text phonemes inserted
+        let matching = matcher.match_sequences(&a, &t).unwrap();
+        let phonemes = matching.phonemes.iter().map(|p| p.phoneme.as_str());
+        assert_eq!(phonemes.collect::<Vec<_>>(), ["a", "b"]);
+        assert!(matching.phonemes.iter().all(|p| !p.active));
+    }
+
+    #[test]
+    fn test_alignment_empty_text() {
+        let sim_matrix = SimilarityMatrix::init_from_str("a;b;c;d", "a;b;c;d", -1.0, 1.0);
+        let matcher = SequenceMatcher::new(sim_matrix);
+
+        let a = PSeq::from_str("a;b");
+        let t = PSeq { hypothesis: None, phonemes: Vec::new() };
+
+        let matrix = matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap();
+        assert!(matrix.scores.is_full());
+        // all audio phonemes deleted
+        let matching = matcher.match_sequences(&a, &t).unwrap();
+        let phonemes = matching.phonemes.iter().map(|p| p.phoneme.as_str());
+        assert_eq!(phonemes.collect::<Vec<_>>(), ["_", "_"]);
+        assert!(matching.phonemes.iter().all(|p| !p.active));
+    }
+
+    #[test]
+    fn test_alignment_word_start_anchors() {
+        let mut sim_matrix = SimilarityMatrix::init_from_str("a;b;c;d", "a;b;c;d", -1.0, 1.0);
+        sim_matrix.set_score("a", "b", 0.9);
+        let mut matcher = SequenceMatcher::new(sim_matrix);
+
+        // word start from word level recognition without an audio gap
+        let a = PSeq::from_array(vec![("a", 0, 50), ("|b", 50, 100), ("c", 100, 150)]);
+        let t = PSeq::from_str("a;b;|b;c");
+
+        let alignment = matcher
+            .calculate_alignment(matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap())
+            .unwrap();
+        let result = generate_result_matching(&matcher, &a, &alignment, &t, false);
+
+        assert_eq!(result, vec![
+            "a  ~  a  [    0 -   50]",
+            "b  \\  a  [    0 -   50]",
+            "b  ~  b  [   50 -  100]",
+            "c  ~  c  [  100 -  150]",
+        ], "text word start aligned with audio word start");
+        assert_eq!(scores_to_ops(alignment), [Match, SplitLeft, Match, Match]);
+
+        // without anchor the more similar split of the audio word start wins
+        matcher.set_profile(ScoringProfile {
+            word_start_anchor: 0.0,
+            ..ScoringProfile::with_gap_penalty(-1.0)
+        });
+        let alignment = matcher
+            .calculate_alignment(matcher.calculate_score_matrix(&a.phonemes, &t.phonemes).unwrap())
+            .unwrap();
+        assert_eq!(scores_to_ops(alignment), [Match, Match, SplitLeft, Match]);
+    }
+
+    #[test]
+    fn test_word_confidences_of_matched_track() {
+        let sim_matrix = SimilarityMatrix::init_from_str("a;b;c;d", "a;b;c;d", -1.0, 2.0);
+        let matcher = SequenceMatcher::new(sim_matrix);
+
+        let mut a = PSeq::from_array(vec![
+            ("a", 0, 100), ("b", 100, 200), ("c", 200, 300), ("d", 300, 400),
+        ]);
+        // acoustic confidence of the recognized phonemes
+        for (segment, score) in a.phonemes.iter_mut().zip([1.0, 1.0, 0.2, 0.2]) {
+            segment.score = score;
+        }
+        let t = PSeq::from_str("a;b;|c;d");
+
+        let matching = matcher.match_sequences(&a, &t).unwrap();
+        let mut track =
+            PhonemeTrack::new(1, "en", "ab cd", "a;b;|c;d", None, None, matching.phonemes);
+        track.assess_quality();
+
+        let words = track.word_confidences().clone();
+        assert_eq!(words.len(), 2);
+        assert_eq!((words[0].segments, words[1].segments), ((0, 1), (2, 3)));
+        assert!(words[0].confidence >= WORD_CONFIDENCE_WARN, "{:?}", words);
+        assert!(
+            words[1].confidence < WORD_CONFIDENCE_ERROR,
+            "low acoustic confidence: {:?}",
+            words
+        );
+        assert_eq!((words[1].durations, words[1].inactive), (1.0, 0.0));
+        assert_eq!(words[1].timings(track.phonemes()), Some((200, 400)));
+        assert_eq!(words[1].phonemes(track.phonemes()), "c d");
+
+        // missing part of the word
+        track.phonemes_mut()[1].active = false;
+        track.assess_quality();
+
+        let words = track.word_confidences();
+        assert_eq!(words[0].inactive, 0.5);
+        assert!(words[0].confidence < WORD_CONFIDENCE_WARN, "{:?}", words);
+        assert_eq!(words[0].timings(track.phonemes()), Some((0, 100)));
+    }
 }
@@ -7,13 +7,32 @@
 // ----------------------------------------------------------------------------
 pub struct SimilarityMatrix {
     scores: Matrix2D<f32>,
-    gap_penalty: f32,
+    profile: ScoringProfile,
+    durations: PhonemeDurations,
     audio_idx: HashMap<String, usize>,
     text_idx: HashMap<String, usize>,
     audio_names: Vec<String>,
     text_names: Vec<String>,
 }
 // ----------------------------------------------------------------------------
+/// max score of a phoneme pair (identical phonemes score 2.0)
+pub const SIMILARITY_SCORE_MAX: f32 = 2.1;
+// ----------------------------------------------------------------------------
+/// number of observed co-occurrences of (lowercased) text and audio phonemes
+pub type PhonemePairCounts = HashMap<(String, String), usize>;
+// ----------------------------------------------------------------------------
+/// changed cell of a learned similarity matrix
+pub struct ScoreChange {
+    pub text: String,
+    pub audio: String,
+    pub old: f32,
+    pub new: f32,
+    /// co-occurrences of the pair
+    pub count: usize,
+    /// all observations of the text phoneme
+    pub total: usize,
+}
+// ----------------------------------------------------------------------------
 // internals
 // ----------------------------------------------------------------------------
 use std::collections::HashMap;
@@ -21,15 +40,72 @@ use std::io::BufRead;
 use std::path::Path;
 
 use super::matrix::{DebugMatrix2D, Matrix2D};
-use super::CsvLoader;
+use super::{CsvLoader, CsvWriter, SimpleCsvWriter};
+use phoneme_durations::PhonemeDurations;
+use scoring_profile::ScoringProfile;
 // ----------------------------------------------------------------------------
-const SIMILARITY_SCORE_MAX: f32 = 2.1;
 const SIMILARITY_SCORE_MIN: f32 = -2.1;
 const SIMILARITY_SCORE_DEFAULT: f32 = -1.00;
-const SIMILARITY_SCORE_GAP_PENALTY: f32 = SIMILARITY_SCORE_DEFAULT;
-const SIMILARITY_ALTERNATIVES_MIN_SCORE: f32 = 0.1;
+/// score of the most frequent audio phoneme of a text phoneme
+const LEARNED_SCORE_MAX: f32 = 2.0;
+/// text phonemes with less observations keep their scores
+const LEARN_MIN_TEXT_OBSERVATIONS: usize = 20;
+/// pairs with less co-occurrences (absolute and relative to all observations of
+/// the text phoneme) are considered noise and learn the default score
+const LEARN_MIN_PAIR_COUNT: usize = 2;
+const LEARN_MIN_PAIR_SHARE: f32 = 0.02;
+/// min difference of a score to be reported as changed
+const LEARN_MIN_CHANGE: f32 = 0.005;
 // ----------------------------------------------------------------------------
 impl SimilarityMatrix {
+    // ------------------------------------------------------------------------
+    /// matrix from rows of scores (one row per text phoneme with one score
+    /// per audio phoneme). undefined scores use the default score.
+    pub fn new(
+        audio_names: Vec<String>,
+        text_names: Vec<String>,
+        rows: Vec<Vec<Option<f32>>>,
+    ) -> Result<SimilarityMatrix, String> {
+        let mut scores = Matrix2D {
+            width: audio_names.len(),
+            height: 0,
+            data: Vec::with_capacity(audio_names.len() * text_names.len()),
+        };
+        for (text, row) in text_names.iter().zip(rows) {
+            let mut row = row
+                .into_iter()
+                .map(|score| score.unwrap_or(SIMILARITY_SCORE_DEFAULT))
+                .collect();
+            scores
+                .add_row(&mut row)
+                .map_err(|why| format!("invalid scores for text phoneme {text}: {why}"))?;
+        }
+        if scores.height != text_names.len() {
+            return Err(format!(
+                "expected scores for {} text phonemes. found: {}",
+                text_names.len(),
+                scores.height
+            ));
+        }
+
+        Ok(SimilarityMatrix {
+            scores,
+            profile: ScoringProfile::default(),
+            durations: PhonemeDurations::default(),
+            audio_idx: audio_names
+                .iter()
+                .enumerate()
+                .map(|(i, p)| (p.to_lowercase(), i))
+                .collect(),
+            text_idx: text_names
+                .iter()
+                .enumerate()
+                .map(|(i, p)| (p.to_lowercase(), i))
+                .collect(),
+            audio_names,
+            text_names,
+        })
+    }
     // ------------------------------------------------------------------------
     pub fn sorted_scores(&self) -> Vec<(String, Vec<String>)> {
         let mut mapping = Vec::with_capacity(self.scores.height);
@@ -39,7 +115,7 @@ impl SimilarityMatrix {
                 .row(row)
                 .copied()
                 .enumerate()
-                .filter(|(_i, v)| *v >= SIMILARITY_ALTERNATIVES_MIN_SCORE)
+                .filter(|(_i, v)| *v >= self.profile.alternatives_min_score)
                 .collect::<Vec<_>>();
 
             scores.sort_by(|(_, a), (_, b)| b.total_cmp(a));
@@ -56,6 +132,116 @@ impl SimilarityMatrix {
         mapping
     }
     // ------------------------------------------------------------------------
+    /// learns scores from observed co-occurrences of text and audio phonemes
+    /// (relative to the most frequent audio phoneme of every text phoneme) and
+    /// blends them with the current scores (0: current only, 1: learned only).
+    /// unobserved (or rarely observed) pairs of text phonemes with enough
+    /// observations learn the default score (-1), i.e. their current scores
+    /// (including hand-tuned ones) are pulled toward it. returns the updated
+    /// matrix and all changed cells.
+    pub fn learn(
+        &self,
+        counts: &PhonemePairCounts,
+        blend: f32,
+    ) -> (SimilarityMatrix, Vec<ScoreChange>) {
+        let mut scores = Matrix2D {
+            width: self.scores.width,
+            height: self.scores.height,
+            data: self.scores.data.clone(),
+        };
+        let mut changes = Vec::new();
+
+        for (t, text) in self.text_names.iter().enumerate() {
+            // counts use lowercased names like the score lookups
+            let text_key = text.to_lowercase();
+            let observed = self
+                .audio_names
+                .iter()
+                .map(|audio| {
+                    counts
+                        .get(&(text_key.clone(), audio.to_lowercase()))
+                        .copied()
+                        .unwrap_or(0)
+                })
+                .collect::<Vec<_>>();
+
+            let total = observed.iter().sum::<usize>();
+            let max = observed.iter().copied().max().unwrap_or(0);
+            if total < LEARN_MIN_TEXT_OBSERVATIONS {
+                continue;
+            }
+
+            for (a, count) in observed.into_iter().enumerate() {
+                let learned = if count >= LEARN_MIN_PAIR_COUNT
+                    && count as f32 / total as f32 >= LEARN_MIN_PAIR_SHARE
+                {
+                    LEARNED_SCORE_MAX * count as f32 / max as f32
+                } else {
+                    SIMILARITY_SCORE_DEFAULT
+                };
+
+                let old = self.scores[(a, t)];
+                let new = ((old + blend * (learned - old)) * 100.0).round() / 100.0;
+
+                if (new - old).abs() >= LEARN_MIN_CHANGE {
+                    scores[(a, t)] = new;
+                    changes.push(ScoreChange {
+                        text: text.to_owned(),
+                        audio: self.audio_names[a].to_owned(),
+                        old,
+                        new,
+                        count,
+                        total,
+                    });
+                }
+            }
+        }
+
+        let learned = SimilarityMatrix {
+            scores,
+            profile: self.profile.clone(),
+            durations: self.durations.clone(),
+            audio_idx: self.audio_idx.clone(),
+            text_idx: self.text_idx.clone(),
+            audio_names: self.audio_names.clone(),
+            text_names: self.text_names.clone(),
+        };
+        (learned, changes)
+    }
+    // ------------------------------------------------------------------------
+    /// stores matrix in the format of the loader. default scores are omitted.
+    pub fn store(&self, file: &Path, comments: &[String]) -> Result<(), String> {
+        let mut writer = SimpleCsvWriter::create(&file.to_path_buf())
+            .map_err(|err| format!("failed to create {}: {err}", file.display()))?;
+
+        for comment in comments {
+            writer.write_comment(&format!(" {comment}"));
+        }
+
+        let header = self
+            .audio_names
+            .iter()
+            .map(|name| format!("{name: <5}"))
+            .collect::<Vec<_>>();
+        writer.writeln(&format!("[T\\A]|{}", header.join("|")));
+
+        for (t, text) in self.text_names.iter().enumerate() {
+            let scores = self
+                .scores
+                .row(t)
+                .map(|score| {
+                    if *score == SIMILARITY_SCORE_DEFAULT {
+                        String::from("     ")
+                    } else {
+                        format!(" {: <4}", format_score(*score))
+                    }
+                })
+                .collect::<Vec<_>>();
+            writer.writeln(&format!("{text: <5}|{}", scores.join("|")));
+        }
+        Ok(())
+    }
+    // ------------------------------------------------------------------------
     pub fn get_score(&self, audio: &str, text: &str) -> f32 {
         let audio = &audio.to_lowercase();
         let text = &text.to_lowercase();
@@ -70,14 +256,14 @@ impl SimilarityMatrix {
 
             _ => match self.audio_idx.get(audio) {
                 Some(a_idx) => match self.text_idx.get(text) {
-                    Some(t_idx) => self.scores[(*a_idx, *t_idx)],
+                    Some(t_idx) => self.scores[(*a_idx, *t_idx)] * self.profile.similarity_scale,
                     None => {
                         warn!(
                             "missing similarity scores for text phoneme [{}]! \
                              using default gap score!",
                             text
                         );
-                        self.gap_penalty
+                        self.profile.gap_open
                     }
                 },
                 None => {
@@ -86,67 +272,123 @@ impl SimilarityMatrix {
                          using default gap score!",
                         audio
                     );
-                    self.gap_penalty
+                    self.profile.gap_open
                 }
             },
         }
     }
     // ------------------------------------------------------------------------
-    pub fn get_delete_score(&self, audio: &str) -> f32 {
+    /// extends_gap: previous phoneme of the same sequence is a gap, too
+    /// word_boundary: audio phoneme follows a silence
+    pub fn get_delete_score(&self, audio: &str, extends_gap: bool, word_boundary: bool) -> f32 {
         // special case: SIL does not match anything and must be removed
         // -> favor it
         if audio.to_lowercase() == "sil" {
             SIMILARITY_SCORE_MAX + 0.01
         } else {
-            self.gap_penalty
+            self.get_gap_score(extends_gap, word_boundary)
         }
     }
     // ------------------------------------------------------------------------
     pub fn get_merge_left_score(&self, audio: &str, text: &str) -> f32 {
         // reduce similarity so a match is always preferred
-        self.get_score(audio, text) - 0.01
+        self.get_score(audio, text) - self.profile.merge_left_bias
     }
     // ------------------------------------------------------------------------
     pub fn get_merge_right_score(&self, audio: &str, text: &str) -> f32 {
         // reduce similarity so a match is always preferred
-        self.get_score(audio, text) - 0.01
+        self.get_score(audio, text) - self.profile.merge_right_bias
     }
     // ------------------------------------------------------------------------
-    pub fn get_insert_score(&self, _text: &str) -> f32 {
-        //TODO some extra modifying factor?
-        self.gap_penalty
+    /// extends_gap: previous phoneme of the same sequence is a gap, too
+    /// word_boundary: text phoneme starts a word
+    pub fn get_insert_score(&self, _text: &str, extends_gap: bool, word_boundary: bool) -> f32 {
+        self.get_gap_score(extends_gap, word_boundary)
     }
     // ------------------------------------------------------------------------
     pub fn get_gap_within_word_score(&self, audio: &str, text: &str) -> f32 {
-        self.gap_penalty * 1.5 + self.get_score(audio, text) * 0.5
-        // self.word_gap_penalty
+        self.get_gap_within_word_similarity(audio, text)
     }
     // ------------------------------------------------------------------------
     pub fn get_merge_left_score_over_gap(&self, audio: &str, text: &str) -> f32 {
         // drastically reduce similarity if it would span a gap
-        self.gap_penalty * 1.5 + self.get_score(audio, text) * 0.5
+        self.get_gap_within_word_similarity(audio, text)
+    }
+    // ------------------------------------------------------------------------
+    pub fn get_merge_right_score_over_gap(&self, audio: &str, text: &str) -> f32 {
+        // drastically reduce similarity if it would span a gap
+        self.get_gap_within_word_similarity(audio, text)
     }
     // ------------------------------------------------------------------------
     pub fn get_split_left_gap_within_word_score(&self, audio: &str, text: &str) -> f32 {
-        self.gap_penalty * 1.5 + self.get_score(audio, text) * 0.5
-        // self.word_gap_penalty
+        self.get_gap_within_word_similarity(audio, text)
+    }
+    // ------------------------------------------------------------------------
+    pub fn get_split_right_gap_within_word_score(&self, audio: &str, text: &str) -> f32 {
+        self.get_gap_within_word_similarity(audio, text)
+    }
+    // ------------------------------------------------------------------------
+    /// bonus if audio and text phoneme both start a word
+    pub fn get_word_start_anchor_score(
+        &self,
+        audio_word_start: bool,
+        text_word_start: bool,
+    ) -> f32 {
+        if audio_word_start && text_word_start {
+            self.profile.word_start_anchor
+        } else {
+            0.0
+        }
     }
     // ------------------------------------------------------------------------
     pub fn get_split_left_score(&self, audio: &str, text: &str) -> f32 {
         // reduce similarity so a match is always preferred
         // prefer left split than match
-        self.get_score(audio, text) - 0.01
+        self.get_score(audio, text) - self.profile.split_left_bias
     }
     // ------------------------------------------------------------------------
     pub fn get_split_right_score(&self, audio: &str, text: &str) -> f32 {
         // reduce similarity so a match is always preferred
         // prefer match then right split
-        self.get_score(audio, text) - 0.01
+        self.get_score(audio, text) - self.profile.split_right_bias
+    }
+    // ------------------------------------------------------------------------
+    pub fn profile(&self) -> &ScoringProfile {
+        &self.profile
+    }
+    // ------------------------------------------------------------------------
+    pub fn set_profile(&mut self, profile: ScoringProfile) {
+        self.profile = profile;
+    }
+    // ------------------------------------------------------------------------
+    /// typical durations of text phonemes for splitting shared audio phonemes
+    pub fn durations(&self) -> &PhonemeDurations {
+        &self.durations
+    }
+    // ------------------------------------------------------------------------
+    pub fn set_durations(&mut self, durations: PhonemeDurations) {
+        self.durations = durations;
     }
     // ------------------------------------------------------------------------
 }
 // ----------------------------------------------------------------------------
 impl SimilarityMatrix {
+    // ------------------------------------------------------------------------
+    /// affine gap penalty
+    fn get_gap_score(&self, extends_gap: bool, word_boundary: bool) -> f32 {
+        if extends_gap {
+            self.profile.gap_extend
+        } else if word_boundary {
+            self.profile.word_boundary_gap_open
+        } else {
+            self.profile.gap_open
+        }
+    }
+    // ------------------------------------------------------------------------
+    fn get_gap_within_word_similarity(&self, audio: &str, text: &str) -> f32 {
+        self.profile.gap_within_word
+            + self.get_score(audio, text) * self.profile.gap_within_word_similarity
+    }
     // ------------------------------------------------------------------------
     fn extract_audiophonemes_line(textline: &str) -> Result<Vec<String>, String> {
         if textline.starts_with("[T\\A]") {
@@ -297,7 +539,8 @@ impl CsvLoader<SimilarityMatrix> for SimilarityMatrix {
 
         let m = SimilarityMatrix {
             scores: score_matrix,
-            gap_penalty: SIMILARITY_SCORE_GAP_PENALTY,
+            profile: ScoringProfile::default(),
+            durations: PhonemeDurations::default(),
             audio_idx: audio_lut,
             text_idx: text_lut,
             audio_names,
@@ -310,6 +553,15 @@ impl CsvLoader<SimilarityMatrix> for SimilarityMatrix {
     // ------------------------------------------------------------------------
 }
 // ----------------------------------------------------------------------------
+/// score with at least one and at most two decimals
+fn format_score(score: f32) -> String {
+    let formatted = format!("{score:.2}");
+    match formatted.strip_suffix('0') {
+        Some(shortened) => shortened.to_owned(),
+        None => formatted,
+    }
+}
+// ----------------------------------------------------------------------------
 // ----------------------------------------------------------------------------
 use std::fmt;
 
@@ -370,7 +622,8 @@ impl SimilarityMatrix {
 
         SimilarityMatrix {
             scores: m,
-            gap_penalty: default_score,
+            profile: ScoringProfile::with_gap_penalty(default_score),
+            durations: PhonemeDurations::default(),
             audio_idx: audio,
             text_idx: text,
             audio_names,
@@ -0,0 +1,253 @@
+//
+// similarity matrix training from corrected phoneme tracks
+//
+
+// ----------------------------------------------------------------------------
+// external interface
+// ----------------------------------------------------------------------------
+/// collects co-occurrences of recognized audio phonemes and corrected text
+/// phonemes of edited tracks
+pub struct SimilarityTrainer {
+    matcher: SequenceMatcher,
+    counts: PhonemePairCounts,
+    pairs: usize,
+}
+// ----------------------------------------------------------------------------
+impl SimilarityTrainer {
+    // ------------------------------------------------------------------------
+    /// current matrix is used to align the sequences and as base for learning
+    pub fn new(similarity_matrix: SimilarityMatrix) -> SimilarityTrainer {
+        SimilarityTrainer {
+            matcher: SequenceMatcher::new(similarity_matrix),
+            counts: PhonemePairCounts::default(),
+            pairs: 0,
+        }
+    }
+    // ------------------------------------------------------------------------
+    /// adds all edited phoneme tracks found in inputdir. returns number of used
+    /// and skipped tracks.
+    pub fn add_tracks(&mut self, inputdir: PathBuf) -> Result<(usize, usize), String> {
+        let mut scanner = FilesScanner::new(inputdir)?;
+
+        let mut used = 0;
+        let mut skipped = 0;
+        for file in scanner.scan()? {
+            let FileInfo::Phonemes(lineid, phonemefile) = file else {
+                continue;
+            };
+            let track = match phonemes::load(lineid, &phonemefile) {
+                Ok(track) => track,
+                Err(why) => {
+                    warn!("id {lineid:010}: {why} skipping...");
+                    skipped += 1;
+                    continue;
+                }
+            };
+            // only manually corrected tracks are reliable
+            if track.version() <= 1 {
+                continue;
+            }
+            match self.add_track(&track) {
+                Ok(pairs) => {
+                    debug!("id {lineid:010}: > collected #{pairs} phoneme pairs");
+                    used += 1;
+                }
+                Err(why) => {
+                    warn!("id {lineid:010}: {why} skipping...");
+                    skipped += 1;
+                }
+            }
+        }
+        Ok((used, skipped))
+    }
+    // ------------------------------------------------------------------------
+    /// aligns the stored audio hypothesis with the corrected (active) phonemes
+    /// of the track. returns number of collected phoneme pairs.
+    pub fn add_track(&mut self, track: &PhonemeTrack<PhonemeSegment>) -> Result<usize, String> {
+        let hypothesis = track
+            .audio_hypothesis()
+            .as_ref()
+            .ok_or_else(|| String::from("no audio hypothesis stored."))?;
+
+        // timings are irrelevant but must not indicate gaps
+        let audio = hypothesis
+            .split_whitespace()
+            .enumerate()
+            .map(|(i, phoneme)| PhonemeSegment {
+                phoneme: phoneme.to_owned(),
+                start: i as u32 * 10,
+                end: (i as u32 + 1) * 10,
+                weight: 1.0,
+                score: 1.0,
+                active: true,
+                ..Default::default()
+            })
+            .collect();
+        let text = track
+            .phonemes()
+            .iter()
+            .filter(|s| s.active && s.phoneme != "_")
+            .cloned()
+            .collect();
+
+        let pairs = self
+            .matcher
+            .calculate_phoneme_pairs(
+                &PhonemeResult {
+                    hypothesis: None,
+                    phonemes: audio,
+                },
+                &PhonemeResult {
+                    hypothesis: None,
+                    phonemes: text,
+                },
+            )
+            .ok_or_else(|| String::from("failed to align audio hypothesis and phonemes."))?;
+
+        for (audio, text) in &pairs {
+            *self
+                .counts
+                .entry((text.to_lowercase(), audio.to_lowercase()))
+                .or_default() += 1;
+        }
+        self.pairs += pairs.len();
+        Ok(pairs.len())
+    }
+    // ------------------------------------------------------------------------
+    /// number of all collected phoneme pairs
+    pub fn pairs(&self) -> usize {
+        self.pairs
+    }
+    // ------------------------------------------------------------------------
+    /// blends the scores learned from all collected pairs with the current
+    /// matrix (0: current only, 1: learned only). note: scores of pairs never
+    /// observed for a frequent text phoneme are pulled toward -1, even if they
+    /// were hand-tuned. returns the updated matrix and all changed cells.
+    pub fn learn(&self, blend: f32) -> (SimilarityMatrix, Vec<ScoreChange>) {
+        self.matcher.similarity().learn(&self.counts, blend)
+    }
+    // ------------------------------------------------------------------------
+    /// stores changed cells ordered by their absolute change
+    pub fn store_report(changes: &[ScoreChange], file: &Path) -> Result<(), String> {
+        let mut changes = changes.iter().collect::<Vec<_>>();
+        changes.sort_by(|a, b| (b.new - b.old).abs().total_cmp(&(a.new - a.old).abs()));
+
+        let mut writer = SimpleCsvWriter::create(&file.to_path_buf())
+            .map_err(|err| format!("failed to create {}: {err}", file.display()))?;
+
+        writer.write_comment(" changed similarity scores");
+        writer.write_comment(" text|audio|old|new|change|pair observations|text observations");
+        for change in changes {
+            writer.writeln(&format!(
+                "{}|{}|{:.2}|{:.2}|{:+.2}|{}|{}",
+                change.text,
+                change.audio,
+                change.old,
+                change.new,
+                change.new - change.old,
+                change.count,
+                change.total
+            ));
+        }
+        Ok(())
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+// internals
+// ----------------------------------------------------------------------------
+use std::path::{Path, PathBuf};
+
+use file_scanner::{FileInfo, FilesScanner};
+use phonemes::{self, PhonemeResult, PhonemeSegment, PhonemeTrack};
+use sequence_matcher::SequenceMatcher;
+use similarity_matrix::{PhonemePairCounts, ScoreChange, SimilarityMatrix};
+use text::{CsvWriter, SimpleCsvWriter};
+// ----------------------------------------------------------------------------
+// ----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use super::super::CsvLoader;
+    use super::{SimilarityMatrix, SimilarityTrainer};
+
+    #[test]
+    fn test_learned_matrix_round_trip() {
+        let mut matrix = SimilarityMatrix::init_from_str("a;b;c", "a;b;c", -1.0, 1.0);
+        // hand-tuned score
+        matrix.set_score("b", "a", 1.5);
+
+        let mut trainer = SimilarityTrainer::new(matrix);
+        for (text, audio, count) in [("a", "a", 30), ("a", "c", 15), ("b", "b", 5)].iter() {
+            trainer
+                .counts
+                .insert(((*text).to_owned(), (*audio).to_owned()), *count);
+        }
+
+        let (learned, changes) = trainer.learn(0.5);
+
+        let mut changes = changes
+            .iter()
+            .map(|c| (c.text.as_str(), c.audio.as_str(), c.old, c.new))
+            .collect::<Vec<_>>();
+        changes.sort_by(|a, b| a.1.cmp(b.1));
+        // text phoneme b has too few observations. unobserved pair a/b is pulled
+        // toward -1 despite its hand-tuned score
+        assert_eq!(
+            changes,
+            [
+                ("a", "a", 1.0, 1.5),
+                ("a", "b", 1.5, 0.25),
+                ("a", "c", -1.0, 0.0)
+            ]
+        );
+
+        let file = env::temp_dir().join("w3phonemetools-test-similarity-learned.csv");
+        learned.store(&file, &[String::from("learned")]).unwrap();
+        let loaded = SimilarityMatrix::load(&file);
+        fs::remove_file(&file).ok();
+        let loaded = loaded.unwrap();
+
+        for audio in ["a", "b", "c"].iter() {
+            for text in ["a", "b", "c"].iter() {
+                assert_eq!(
+                    loaded.get_score(audio, text),
+                    learned.get_score(audio, text),
+                    "{} {}",
+                    audio,
+                    text
+                );
+            }
+        }
+        assert_eq!(loaded.get_score("b", "a"), 0.25);
+    }
+
+    #[test]
+    fn test_learn_mixed_case_phonemes() {
+        let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        let matrix = SimilarityMatrix::new(
+            names(&["A", "b"]),
+            names(&["a", "B"]),
+            vec![vec![Some(1.0), None], vec![None, Some(1.0)]],
+        )
+        .unwrap();
+
+        // the trainer collects counts with lowercased names
+        let mut trainer = SimilarityTrainer::new(matrix);
+        for (text, audio, count) in [("a", "b", 30), ("b", "a", 30)].iter() {
+            trainer
+                .counts
+                .insert(((*text).to_owned(), (*audio).to_owned()), *count);
+        }
+
+        let (learned, changes) = trainer.learn(1.0);
+
+        assert_eq!(changes.len(), 4);
+        assert_eq!(learned.get_score("b", "a"), 2.0);
+        assert_eq!(learned.get_score("a", "b"), 2.0);
+        assert_eq!(learned.get_score("a", "a"), -1.0);
+        assert_eq!(learned.get_score("b", "b"), -1.0);
+    }
+}
@@ -0,0 +1,520 @@
+//
+// recording session splitter
+//
+
+// ----------------------------------------------------------------------------
+// external interface
+// ----------------------------------------------------------------------------
+/// min average alignment score per phoneme for an accepted assignment
+pub const SPLIT_MIN_CONFIDENCE: f32 = 0.5;
+/// assignments with a second best candidate within this margin are ambiguous
+pub const SPLIT_AMBIGUITY_MARGIN: f32 = 0.1;
+// ----------------------------------------------------------------------------
+impl<S> Processor<S>
+where
+    S: StringsProvider,
+{
+    // ------------------------------------------------------------------------
+    /// segments a long recording into utterances, assigns every utterance to
+    /// the best matching textline (optionally restricted to an actor and/or an
+    /// id range) and writes every assigned utterance as <id>[duration]...wav
+    /// clip into outputdir. all low confidence, unmatched and alternative
+    /// takes are listed in a review csv next to the clips.
+    /// returns number of written clips.
+    pub fn split_recording(
+        &mut self,
+        recording: &Path,
+        actor: Option<&str>,
+        ids: Option<(u32, u32)>,
+        outputdir: &Path,
+    ) -> Result<usize, String> {
+        let candidates = self.collect_split_candidates(actor, ids)?;
+        info!("> matching against #{} textlines", candidates.len());
+
+        let mut dataprovider = DataProvider::new(&recording.to_string_lossy(), &self.datadir);
+        dataprovider.load()?;
+
+        let audiodata = dataprovider.get_rawaudio(REQUIRED_SAMPLE_RATE, false)?;
+        // pocketsphinx requires the audiodata to be 16khz
+        let recognition_data = dataprovider.get_rawaudio(16000, true)?;
+
+        info!("> detecting utterances...");
+        let utterances = PocketSphinx::detect_utterances(&recognition_data)?;
+        info!("> found #{} utterances", utterances.len());
+
+        let mut segments = self.assign_utterances(&utterances, &recognition_data, &candidates);
+        mark_alternative_takes(&mut segments);
+
+        let mut written = 0;
+        for (i, segment) in segments.iter().enumerate() {
+            if let Some(clipname) = self.write_clip(segment, &audiodata, outputdir)? {
+                info!("> segment #{:>3}: stored as [{}]", i + 1, clipname);
+                written += 1;
+            }
+        }
+
+        let reviewfile = outputdir.join(format!(
+            "{}.split-review.csv",
+            recording.file_stem().unwrap_or_default().to_string_lossy()
+        ));
+        let review_count = self.store_review(&reviewfile, recording, &segments)?;
+
+        info!("> stored #{written} clips in {}", outputdir.display());
+        if review_count > 0 {
+            warn!(
+                "> #{review_count} segments require manual review. see {}",
+                reviewfile.display()
+            );
+        }
+        Ok(written)
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+// internals
+// ----------------------------------------------------------------------------
+use std::collections::HashMap;
+use std::path::Path;
+
+use audio::AudioWriter;
+use pocketsphinx::PocketSphinx;
+use sequence_matcher::SequenceMatcher;
+use text::{CsvWriter, SimpleCsvWriter};
+
+use super::{
+    audiofile_name, escape_textline, DataProvider, PhonemeResult, Processor, StringsProvider,
+    REGEXP_CLEANUP, REQUIRED_SAMPLE_RATE,
+};
+// ----------------------------------------------------------------------------
+/// audio added before and after a clip (clamped to recording boundaries)
+const CLIP_PADDING_MS: u32 = 50;
+/// utterances separated by longer pauses are never merged into one line
+const MAX_MERGE_GAP_MS: u32 = 2000;
+// ----------------------------------------------------------------------------
+struct Candidate {
+    lineid: u32,
+    phonemes: PhonemeResult,
+}
+// ----------------------------------------------------------------------------
+#[derive(Debug, PartialEq)]
+enum SplitStatus {
+    Ok,
+    LowConfidence,
+    Ambiguous,
+    Unmatched,
+    AlternativeTake,
+}
+// ----------------------------------------------------------------------------
+struct SplitSegment {
+    start: u32,
+    end: u32,
+    hypothesis: String,
+    /// best matching lineid and its score
+    best: Option<(u32, f32)>,
+    second_score: Option<f32>,
+    status: SplitStatus,
+}
+// ----------------------------------------------------------------------------
+impl<S> Processor<S>
+where
+    S: StringsProvider,
+{
+    // ------------------------------------------------------------------------
+    fn collect_split_candidates(
+        &self,
+        actor: Option<&str>,
+        ids: Option<(u32, u32)>,
+    ) -> Result<Vec<Candidate>, String> {
+        // actors are stored uppercased
+        let actor = actor.map(str::to_uppercase);
+
+        let mut candidates = Vec::new();
+        for (lineid, (text, line_actor)) in self.strings.get_all_lines() {
+            if let Some((from, to)) = ids {
+                if *lineid < from || *lineid > to {
+                    continue;
+                }
+            }
+            if actor.is_some() && line_actor.as_ref() != actor.as_ref() {
+                continue;
+            }
+
+            // remove all non-spoken textual hints framed by *
+            let text = &*REGEXP_CLEANUP.replace_all(text, "");
+
+            match self.translator.translate(text) {
+                Ok(phonemes) => candidates.push(Candidate {
+                    lineid: *lineid,
+                    phonemes,
+                }),
+                Err(why) => warn!("id {lineid:010}: {why} skipping line as candidate..."),
+            }
+        }
+
+        if candidates.is_empty() {
+            return Err(String::from(
+                "no textlines found for splitting recording (check actor and id range).",
+            ));
+        }
+        Ok(candidates)
+    }
+    // ------------------------------------------------------------------------
+    /// recognizes phonemes of the audio range and finds best matching
+    /// candidate. failed recognitions and utterances without any phonemes
+    /// besides silence and noise are unmatched.
+    fn match_utterance(
+        &mut self,
+        start: u32,
+        end: u32,
+        audiodata: &[i16],
+        candidates: &[Candidate],
+    ) -> SplitSegment {
+        let from = (start as usize * 16).min(audiodata.len());
+        let to = (end as usize * 16).min(audiodata.len());
+
+        let unmatched = |hypothesis| SplitSegment {
+            start,
+            end,
+            hypothesis,
+            best: None,
+            second_score: None,
+            status: SplitStatus::Unmatched,
+        };
+
+        let recognized = match self.extractor.recognize_phonemes(0, &audiodata[from..to]) {
+            Ok(recognized) => recognized,
+            Err(why) => {
+                warn!("> utterance at {start}ms: {why}");
+                return unmatched(format!("recognition failed: {why}"));
+            }
+        };
+        if SequenceMatcher::without_silence(&recognized).is_empty() {
+            warn!("> utterance at {start}ms: no phonemes recognized");
+            return unmatched(recognized.hypothesis.unwrap_or_default());
+        }
+
+        let mut best: Option<(u32, f32)> = None;
+        let mut second_score: Option<f32> = None;
+
+        for candidate in candidates {
+            let score = match self
+                .matcher
+                .calculate_similarity(&recognized, &candidate.phonemes)
+            {
+                Ok(score) => score,
+                Err(why) => {
+                    warn!(
+                        "id {:010}: {why} skipping line as candidate...",
+                        candidate.lineid
+                    );
+                    continue;
+                }
+            };
+            match best {
+                Some((_, best_score)) if score <= best_score => {
+                    if second_score.map_or(true, |second| score > second) {
+                        second_score = Some(score);
+                    }
+                }
+                _ => {
+                    second_score = best.map(|(_, best_score)| best_score);
+                    best = Some((candidate.lineid, score));
+                }
+            }
+        }
+
+        SplitSegment {
+            start,
+            end,
+            hypothesis: recognized.hypothesis.unwrap_or_default(),
+            best,
+            second_score,
+            status: split_status(best, second_score),
+        }
+    }
+    // ------------------------------------------------------------------------
+    /// assigns utterances to candidates
+    fn assign_utterances(
+        &mut self,
+        utterances: &[(u32, u32)],
+        audiodata: &[i16],
+        candidates: &[Candidate],
+    ) -> Vec<SplitSegment> {
+        merge_utterances(utterances, |start, end| {
+            self.match_utterance(start, end, audiodata, candidates)
+        })
+    }
+    // ------------------------------------------------------------------------
+    /// writes clip for an assigned segment. returns the clip filename
+    fn write_clip(
+        &self,
+        segment: &SplitSegment,
+        audiodata: &[i16],
+        outputdir: &Path,
+    ) -> Result<Option<String>, String> {
+        let lineid = match (&segment.status, segment.best) {
+            (SplitStatus::Unmatched, _) | (SplitStatus::AlternativeTake, _) | (_, None) => {
+                return Ok(None)
+            }
+            (_, Some((lineid, _))) => lineid,
+        };
+        let samples_per_ms = REQUIRED_SAMPLE_RATE as usize / 1000;
+
+        let from = (segment.start.saturating_sub(CLIP_PADDING_MS) as usize * samples_per_ms)
+            .min(audiodata.len());
+        let to = ((segment.end + CLIP_PADDING_MS) as usize * samples_per_ms).min(audiodata.len());
+        let clip = &audiodata[from..to];
+
+        let duration = clip.len() as f32 / REQUIRED_SAMPLE_RATE as f32;
+        let line = self.strings.get_line(lineid)?;
+        let clipname = audiofile_name(
+            lineid,
+            duration,
+            self.strings.get_actor(lineid),
+            &format!("{}.wav", escape_textline(line)?),
+        )?;
+
+        let clipfile = outputdir.join(&clipname);
+        if clipfile.exists() {
+            warn!(
+                "id {lineid:010}: overwriting existing {}",
+                clipfile.display()
+            );
+        }
+        AudioWriter::write_wav(&clipfile, clip, REQUIRED_SAMPLE_RATE)?;
+
+        Ok(Some(clipname))
+    }
+    // ------------------------------------------------------------------------
+    /// writes all segments which require a manual check into a review csv.
+    /// returns number of listed segments
+    fn store_review(
+        &self,
+        reviewfile: &Path,
+        recording: &Path,
+        segments: &[SplitSegment],
+    ) -> Result<usize, String> {
+        let mut writer = SimpleCsvWriter::create(&reviewfile.to_path_buf())?;
+
+        writer.write_comment("");
+        writer.write_comment(&format!(
+            " segments of {} requiring manual review",
+            recording.file_name().unwrap_or_default().to_string_lossy()
+        ));
+        writer.write_comment("");
+        writer.write_header(
+            "segment|start|  end|        id| score|second| status         |text|hypothesis",
+        );
+
+        let mut count = 0;
+        for (i, segment) in segments.iter().enumerate() {
+            let status = match segment.status {
+                SplitStatus::Ok => continue,
+                SplitStatus::LowConfidence => "low confidence",
+                SplitStatus::Ambiguous => "ambiguous",
+                SplitStatus::Unmatched => "unmatched",
+                SplitStatus::AlternativeTake => "alternative take",
+            };
+            let (lineid, score, text) = match segment.best {
+                Some((lineid, score)) => (
+                    format!("{:0>10}", lineid),
+                    format!("{:>6.2}", score),
+                    self.strings.get_line(lineid)?.as_str(),
+                ),
+                None => (format!("{:>10}", ""), format!("{:>6}", ""), ""),
+            };
+            let second = segment
+                .second_score
+                .map(|second| format!("{:>6.2}", second))
+                .unwrap_or_else(|| format!("{:>6}", ""));
+
+            writer.writeln(&format!(
+                "{:>7}|{:>5}|{:>5}|{}|{}|{}| {:<15}|{}|{}",
+                i + 1,
+                segment.start,
+                segment.end,
+                lineid,
+                score,
+                second,
+                status,
+                text,
+                segment.hypothesis
+            ));
+            count += 1;
+        }
+        Ok(count)
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+/// classifies the best matching candidate by its score and the distance to the
+/// second best candidate
+fn split_status(best: Option<(u32, f32)>, second_score: Option<f32>) -> SplitStatus {
+    match best {
+        None => SplitStatus::Unmatched,
+        Some((_, score)) if score < SPLIT_MIN_CONFIDENCE => SplitStatus::LowConfidence,
+        Some((_, score))
+            if second_score.is_some_and(|second| score - second < SPLIT_AMBIGUITY_MARGIN) =>
+        {
+            SplitStatus::Ambiguous
+        }
+        Some(_) => SplitStatus::Ok,
+    }
+}
+// ----------------------------------------------------------------------------
+/// matches every utterance with match_range. as the endpointer splits lines
+/// with longer pauses consecutive utterances are merged as long as the match
+/// with the best candidate improves
+fn merge_utterances<F>(utterances: &[(u32, u32)], mut match_range: F) -> Vec<SplitSegment>
+where
+    F: FnMut(u32, u32) -> SplitSegment,
+{
+    let score = |segment: &SplitSegment| segment.best.map(|(_, score)| score);
+
+    let mut segments = Vec::new();
+    let mut i = 0;
+    while i < utterances.len() {
+        let (start, mut end) = utterances[i];
+        let mut segment = match_range(start, end);
+
+        while let Some(&(next_start, next_end)) = utterances.get(i + 1) {
+            if next_start.saturating_sub(end) > MAX_MERGE_GAP_MS {
+                break;
+            }
+            let merged = match_range(start, next_end);
+            if score(&merged) > score(&segment) {
+                debug!("> merging utterances at {}ms and {}ms", start, next_start);
+                segment = merged;
+                end = next_end;
+                i += 1;
+            } else {
+                break;
+            }
+        }
+
+        segments.push(segment);
+        i += 1;
+    }
+    segments
+}
+// ----------------------------------------------------------------------------
+/// keeps only the best scoring take for every lineid
+fn mark_alternative_takes(segments: &mut [SplitSegment]) {
+    let mut best_takes: HashMap<u32, (usize, f32)> = HashMap::new();
+
+    for (i, segment) in segments.iter().enumerate() {
+        if segment.status == SplitStatus::Unmatched {
+            continue;
+        }
+        if let Some((lineid, score)) = segment.best {
+            let best = best_takes.entry(lineid).or_insert((i, score));
+            if score > best.1 {
+                *best = (i, score);
+            }
+        }
+    }
+
+    for (i, segment) in segments.iter_mut().enumerate() {
+        if segment.status == SplitStatus::Unmatched {
+            continue;
+        }
+        if let Some((lineid, _)) = segment.best {
+            if best_takes.get(&lineid).is_some_and(|(best, _)| *best != i) {
+                segment.status = SplitStatus::AlternativeTake;
+            }
+        }
+    }
+}
+// ----------------------------------------------------------------------------
+// ----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::{mark_alternative_takes, merge_utterances, split_status};
+    use super::{SplitSegment, SplitStatus};
+
+    fn segment(start: u32, end: u32, best: Option<(u32, f32)>) -> SplitSegment {
+        SplitSegment {
+            start,
+            end,
+            hypothesis: String::default(),
+            best,
+            second_score: None,
+            status: split_status(best, None),
+        }
+    }
+
+    #[test]
+    fn test_split_status() {
+        assert_eq!(split_status(None, None), SplitStatus::Unmatched);
+        assert_eq!(
+            split_status(Some((1, 0.4)), None),
+            SplitStatus::LowConfidence
+        );
+        assert_eq!(
+            split_status(Some((1, 0.4)), Some(0.35)),
+            SplitStatus::LowConfidence
+        );
+        assert_eq!(
+            split_status(Some((1, 0.8)), Some(0.75)),
+            SplitStatus::Ambiguous
+        );
+        assert_eq!(split_status(Some((1, 0.8)), Some(0.6)), SplitStatus::Ok);
+        assert_eq!(split_status(Some((1, 0.5)), None), SplitStatus::Ok);
+    }
+
+    #[test]
+    fn test_mark_alternative_takes() {
+        let mut segments = vec![
+            segment(0, 100, Some((1, 0.7))),
+            segment(200, 300, Some((2, 0.9))),
+            segment(400, 500, Some((1, 0.8))),
+            segment(600, 700, None),
+            segment(800, 900, Some((1, 0.6))),
+        ];
+        mark_alternative_takes(&mut segments);
+
+        let status = segments.iter().map(|s| &s.status).collect::<Vec<_>>();
+        assert_eq!(
+            status,
+            [
+                &SplitStatus::AlternativeTake,
+                &SplitStatus::Ok,
+                &SplitStatus::Ok,
+                &SplitStatus::Unmatched,
+                &SplitStatus::AlternativeTake,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_utterances() {
+        // first line spans two utterances, second line starts after a long
+        // pause and does not improve if merged with the next utterance
+        let utterances = [(0, 500), (900, 1500), (4000, 4500), (4600, 5000)];
+        let score = |start, end| match (start, end) {
+            (0, 500) => Some((1, 0.4)),
+            (0, 1500) => Some((1, 0.9)),
+            (4000, 4500) => Some((2, 0.8)),
+            (4000, 5000) => Some((2, 0.7)),
+            (4600, 5000) => Some((3, 0.6)),
+            _ => Some((9, 0.1)),
+        };
+
+        let mut matched = Vec::new();
+        let segments = merge_utterances(&utterances, |start, end| {
+            matched.push((start, end));
+            segment(start, end, score(start, end))
+        });
+
+        let ranges = segments
+            .iter()
+            .map(|s| (s.start, s.end))
+            .collect::<Vec<_>>();
+        assert_eq!(ranges, [(0, 1500), (4000, 4500), (4600, 5000)]);
+        assert_eq!(segments[0].best, Some((1, 0.9)));
+
+        // no merge across the pause of more than 2s
+        assert!(!matched.contains(&(0, 4500)), "{:?}", matched);
+        assert!(matched.contains(&(4000, 5000)), "{:?}", matched);
+    }
+}
@@ -0,0 +1,155 @@
+//
+// synthetic benchmark corpus generated with eSpeak
+//
+
+// ----------------------------------------------------------------------------
+// external interface
+// ----------------------------------------------------------------------------
+/// synthesizes audio for strings lines together with the exact phoneme timings
+/// of the synthesizer as reference alignment. the result is a deterministic
+/// corpus for running the extraction end to end and evaluating it.
+pub struct SyntheticCorpus {
+    translator: TextPhonemeConverter,
+    outputdir: PathBuf,
+}
+// ----------------------------------------------------------------------------
+impl SyntheticCorpus {
+    // ------------------------------------------------------------------------
+    pub fn new(translator: TextPhonemeConverter, outputdir: PathBuf) -> SyntheticCorpus {
+        SyntheticCorpus {
+            translator,
+            outputdir,
+        }
+    }
+    // ------------------------------------------------------------------------
+    /// stores synthesized audio as <id>.wav and the phoneme timings as
+    /// <id>.alignment.json reference alignment. returns number of phonemes.
+    pub fn generate(&self, id: u32, text: &str) -> Result<usize, String> {
+        // remove all non-spoken textual hints framed by *
+        let text = &*REGEXP_CLEANUP.replace_all(text, "");
+
+        let speech = self.translator.synthesize(text)?;
+
+        store_speech(&self.outputdir, id, &speech)
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+// internals
+// ----------------------------------------------------------------------------
+use std::path::{Path, PathBuf};
+
+use audio::{AudioResampler, AudioWriter};
+use espeak::SynthesizedSpeech;
+use evaluation::{store_reference, ReferenceSegment};
+
+use super::{TextPhonemeConverter, REGEXP_CLEANUP, REQUIRED_SAMPLE_RATE};
+// ----------------------------------------------------------------------------
+/// stores speech resampled to the sample rate required for extraction (eSpeak
+/// synthesizes with its native rate, usually 22050 Hz) and the reference
+/// alignment. returns number of phonemes.
+fn store_speech(outputdir: &Path, id: u32, speech: &SynthesizedSpeech) -> Result<usize, String> {
+    if speech.samples.is_empty() || speech.phonemes.is_empty() {
+        return Err(String::from("eSpeak synthesized no speech."));
+    }
+
+    let samples = if speech.sample_rate == REQUIRED_SAMPLE_RATE {
+        speech.samples.clone()
+    } else {
+        AudioResampler::resample(
+            &speech.samples,
+            speech.sample_rate,
+            REQUIRED_SAMPLE_RATE,
+            false,
+        )?
+    };
+    let duration = (samples.len() as u64 * 1000 / u64::from(REQUIRED_SAMPLE_RATE)) as u32;
+
+    // phoneme timings are in ms and do not depend on the sample rate but the
+    // resampled audio may be slightly shorter
+    let reference = speech
+        .phonemes
+        .iter()
+        .map(|p| ReferenceSegment {
+            // same normalization as for translated phonemes
+            phoneme: p.phoneme.replace(['ˈ', 'ˌ', '"', '^', 'ː', '-'], ""),
+            start: p.start.min(duration),
+            end: p.end.min(duration),
+        })
+        .filter(|p| !p.phoneme.is_empty())
+        .collect::<Vec<_>>();
+
+    let audiofile = outputdir.join(format!("{id:010}.wav"));
+    let referencefile = outputdir.join(format!("{id:010}.alignment.json"));
+
+    AudioWriter::write_wav(&audiofile, &samples, REQUIRED_SAMPLE_RATE)?;
+    store_reference(&referencefile, &reference)?;
+
+    debug!(
+        "id {id:010}: stored {duration} ms of synthesized speech in {}",
+        audiofile.display()
+    );
+    Ok(reference.len())
+}
+// ----------------------------------------------------------------------------
+// ----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::f32::consts::PI;
+    use std::fs;
+
+    use super::super::audio::AudioLoader;
+    use super::super::espeak::{SynthesizedPhoneme, SynthesizedSpeech};
+    use super::super::evaluation::load_reference;
+    use super::{store_speech, REQUIRED_SAMPLE_RATE};
+
+    fn phoneme(phoneme: &str, start: u32, end: u32) -> SynthesizedPhoneme {
+        SynthesizedPhoneme {
+            phoneme: phoneme.to_owned(),
+            word_start: start == 0,
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn test_stored_speech_has_required_sample_rate() {
+        let outputdir = env::temp_dir().join("w3phonemetools-test-synthetic-corpus");
+        fs::create_dir_all(&outputdir).unwrap();
+
+        // 500 ms at the native sample rate of eSpeak
+        let speech = SynthesizedSpeech {
+            sample_rate: 22050,
+            samples: (0..11025)
+                .map(|i| ((i as f32 * 2.0 * PI * 220.0 / 22050.0).sin() * 8000.0) as i16)
+                .collect(),
+            phonemes: vec![phoneme("h", 0, 150), phoneme("ˈaɪ", 150, 480)],
+        };
+
+        assert_eq!(store_speech(&outputdir, 1, &speech), Ok(2));
+
+        let audiofile = outputdir.join("0000000001.wav");
+        let audio = AudioLoader::load(
+            &audiofile.to_string_lossy(),
+            REQUIRED_SAMPLE_RATE,
+            &outputdir,
+        );
+        let audio = audio.unwrap();
+        assert!(!audio.resampled, "stored with required sample rate");
+        assert!(
+            (audio.values.len() as i64 - 22050).abs() < 100,
+            "same duration"
+        );
+
+        let referencefile = outputdir.join("0000000001.alignment.json");
+        let reference = load_reference(1, &referencefile.to_string_lossy()).unwrap();
+        let reference = reference
+            .iter()
+            .map(|p| (p.phoneme.as_str(), p.start, p.end))
+            .collect::<Vec<_>>();
+        assert_eq!(reference, [("h", 0, 150), ("aɪ", 150, 480)]);
+
+        fs::remove_dir_all(&outputdir).ok();
+    }
+}
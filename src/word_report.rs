@@ -0,0 +1,232 @@
+//
+// word level quality report of assessed phoneme tracks
+//
+
+// ----------------------------------------------------------------------------
+// external interface
+// ----------------------------------------------------------------------------
+/// collects the words with a confidence below WORD_CONFIDENCE_WARN of all
+/// assessed phoneme tracks so reviewers can check the worst words first
+#[derive(Default)]
+pub struct WordQualityReport {
+    lines: usize,
+    words: usize,
+    flagged: Vec<FlaggedWord>,
+}
+// ----------------------------------------------------------------------------
+/// low confidence word of a phoneme track
+#[derive(Clone, Debug)]
+pub struct FlaggedWord {
+    pub lineid: u32,
+    pub phonemefile: String,
+    /// start and end of the active segments in ms
+    pub timings: (u32, u32),
+    pub phonemes: String,
+    pub confidence: WordConfidence,
+}
+// ----------------------------------------------------------------------------
+impl WordQualityReport {
+    // ------------------------------------------------------------------------
+    /// adds the phonemes files of all finished tasks of the queue
+    pub fn add_queue(&mut self, queue: &ProcessingQueue) {
+        for task in queue.iter() {
+            let (ProcessingState::Finished, Some(phonemefile)) = (task.state(), task.phonemefile())
+            else {
+                continue;
+            };
+            match phonemes::load(task.lineid(), phonemefile) {
+                Ok(track) => self.add_track(&track, phonemefile),
+                Err(why) => warn!("id {:010}: {why} skipping...", task.lineid()),
+            }
+        }
+    }
+    // ------------------------------------------------------------------------
+    /// adds the low confidence words of an assessed track
+    pub fn add_track(&mut self, track: &PhonemeTrack<PhonemeSegment>, phonemefile: &str) {
+        let phonemes = track.phonemes();
+
+        for word in track.word_confidences() {
+            if word.confidence < WORD_CONFIDENCE_WARN {
+                self.flagged.push(FlaggedWord {
+                    lineid: track.id(),
+                    phonemefile: phonemefile.to_owned(),
+                    timings: word.timings(phonemes).unwrap_or_default(),
+                    phonemes: word.phonemes(phonemes),
+                    confidence: word.clone(),
+                });
+            }
+        }
+        self.words += track.word_confidences().len();
+        self.lines += 1;
+    }
+    // ------------------------------------------------------------------------
+    /// low confidence words sorted by confidence (worst first)
+    pub fn flagged(&self) -> Vec<&FlaggedWord> {
+        let mut flagged = self.flagged.iter().collect::<Vec<_>>();
+        flagged.sort_by(|a, b| {
+            a.confidence
+                .confidence
+                .total_cmp(&b.confidence.confidence)
+                .then(a.lineid.cmp(&b.lineid))
+                .then(a.timings.cmp(&b.timings))
+        });
+        flagged
+    }
+    // ------------------------------------------------------------------------
+    /// human readable summary of all assessed words
+    pub fn summary(&self) -> Vec<String> {
+        let errors = self
+            .flagged
+            .iter()
+            .filter(|w| w.confidence.confidence < WORD_CONFIDENCE_ERROR)
+            .count();
+        let lines = self
+            .flagged
+            .iter()
+            .map(|w| w.lineid)
+            .collect::<BTreeSet<_>>()
+            .len();
+
+        vec![
+            format!("assessed words: {} in #{} lines", self.words, self.lines),
+            format!(
+                "words below confidence {WORD_CONFIDENCE_WARN}: {} in #{lines} lines \
+                 (below {WORD_CONFIDENCE_ERROR}: {errors})",
+                self.flagged.len()
+            ),
+        ]
+    }
+    // ------------------------------------------------------------------------
+    /// stores the low confidence words (worst first) as '|' separated table
+    pub fn store(&self, file: &Path) -> Result<(), String> {
+        let mut writer = SimpleCsvWriter::create(&file.to_path_buf())
+            .map_err(|err| format!("failed to create {}: {err}", file.display()))?;
+
+        for line in self.summary() {
+            writer.write_comment(&format!(" {line}"));
+        }
+        writer.write_comment("");
+        writer.write_header(
+            "lineid|start|  end|confidence| score|durations|inactive| phonemes | file",
+        );
+
+        for word in self.flagged() {
+            let confidence = &word.confidence;
+            writer.writeln(&format!(
+                "{:010}|{:>5}|{:>5}|{:>10.2}|{:>6.2}|{:>9.2}|{:>8.2}| {} | {}",
+                word.lineid,
+                word.timings.0,
+                word.timings.1,
+                confidence.confidence,
+                confidence.score,
+                confidence.durations,
+                confidence.inactive,
+                word.phonemes,
+                word.phonemefile
+            ));
+        }
+        Ok(())
+    }
+    // ------------------------------------------------------------------------
+}
+// ----------------------------------------------------------------------------
+// internals
+// ----------------------------------------------------------------------------
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use phonemes::{self, PhonemeSegment, PhonemeTrack, WordConfidence};
+use phonemes::{WORD_CONFIDENCE_ERROR, WORD_CONFIDENCE_WARN};
+use queue::{ProcessingQueue, ProcessingState};
+use text::{CsvWriter, SimpleCsvWriter};
+// ----------------------------------------------------------------------------
+// ----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::super::phonemes::{PhonemeSegment, PhonemeTrack, WordConfidence};
+    use super::WordQualityReport;
+
+    fn word(segments: (usize, usize), confidence: f32) -> WordConfidence {
+        WordConfidence {
+            segments,
+            score: confidence,
+            durations: 1.0,
+            inactive: 0.0,
+            confidence,
+        }
+    }
+
+    fn track(id: u32, words: Vec<WordConfidence>) -> PhonemeTrack<PhonemeSegment> {
+        let phonemes = [
+            ("_", 0, 100, false),
+            ("h", 100, 180, true),
+            ("aɪ", 180, 350, true),
+            ("_", 350, 400, false),
+            ("j", 400, 450, false),
+            ("u", 450, 600, true),
+        ]
+        .iter()
+        .map(|&(phoneme, start, end, active)| PhonemeSegment {
+            phoneme: phoneme.to_owned(),
+            start,
+            end,
+            active,
+            ..Default::default()
+        })
+        .collect();
+
+        let mut track = PhonemeTrack::new(id, "en", "hi you", "haɪ ju", None, None, phonemes);
+        track.set_word_confidences(words);
+        track
+    }
+
+    #[test]
+    fn test_add_track() {
+        let mut report = WordQualityReport::default();
+        report.add_track(
+            &track(1, vec![word((1, 2), 0.5), word((3, 5), 0.2)]),
+            "1.phonemes",
+        );
+
+        // only words below the warn level are flagged
+        let flagged = report.flagged();
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].lineid, 1);
+        assert_eq!(flagged[0].phonemefile, "1.phonemes");
+        // timings ignore inactive segments. phonemes include inactive segments,
+        // exclude gaps
+        assert_eq!(flagged[0].timings, (450, 600));
+        assert_eq!(flagged[0].phonemes, "j u");
+        assert_eq!(flagged[0].confidence, word((3, 5), 0.2));
+
+        assert_eq!(
+            report.summary(),
+            [
+                "assessed words: 2 in #1 lines",
+                "words below confidence 0.5: 1 in #1 lines (below 0.25: 1)"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_flagged_order() {
+        let mut report = WordQualityReport::default();
+        report.add_track(
+            &track(2, vec![word((1, 2), 0.3), word((3, 5), 0.3)]),
+            "2.phonemes",
+        );
+        report.add_track(
+            &track(1, vec![word((3, 5), 0.3), word((1, 2), 0.3)]),
+            "1.phonemes",
+        );
+        report.add_track(&track(3, vec![word((1, 5), 0.1)]), "3.phonemes");
+
+        // worst first, equal confidences ordered by line and start
+        let flagged = report
+            .flagged()
+            .iter()
+            .map(|w| (w.lineid, w.timings.0))
+            .collect::<Vec<_>>();
+        assert_eq!(flagged, [(3, 100), (1, 100), (1, 450), (2, 100), (2, 450)]);
+    }
+}